@@ -0,0 +1,55 @@
+"""Writes the ONNX model of the nn method test, assets/mel_mean.onnx.
+
+The model averages its input of shape (1, frames, n_mels) over the mel bands, so it returns
+one activation per frame. It is encoded directly as protobuf, so no packages are needed.
+"""
+import os
+
+
+def varint(value):
+    out = bytearray()
+    while True:
+        byte = value & 0x7f
+        value >>= 7
+        if value:
+            out.append(byte | 0x80)
+        else:
+            out.append(byte)
+            return bytes(out)
+
+
+def field(number, payload):
+    # length-delimited field: strings, bytes, and embedded messages
+    return varint(number << 3 | 2) + varint(len(payload)) + payload
+
+
+def integer(number, value):
+    return varint(number << 3) + varint(value)
+
+
+def string(number, text):
+    return field(number, text.encode())
+
+
+def tensor_value_info(name, dims):
+    # ValueInfoProto with a float tensor type of symbolic dimensions
+    shape = b''.join(field(1, string(2, dim)) for dim in dims)
+    tensor_type = integer(1, 1) + field(2, shape)
+    return string(1, name) + field(2, field(1, tensor_type))
+
+
+if __name__ == '__main__':
+    axes = string(1, 'axes') + integer(8, 2) + integer(20, 7)
+    keepdims = string(1, 'keepdims') + integer(3, 0) + integer(20, 2)
+    node = string(1, 'mel') + string(2, 'activation') + string(3, 'mean') + string(4, 'ReduceMean') + field(5, axes) + field(5, keepdims)
+    graph = (
+        field(1, node)
+        + string(2, 'mel_mean')
+        + field(11, tensor_value_info('mel', ['batch', 'frames', 'bands']))
+        + field(12, tensor_value_info('activation', ['batch', 'frames']))
+    )
+    model = integer(1, 7) + string(2, 'novelty_rust') + field(7, graph) + field(8, string(1, '') + integer(2, 11))
+
+    file_dir = os.path.dirname(os.path.realpath(__file__))
+    with open(f'{file_dir}/../assets/mel_mean.onnx', 'wb') as f:
+        f.write(model)
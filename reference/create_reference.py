@@ -12,7 +12,7 @@ if __name__ == '__main__':
     x, Fs = sf.read(f'{file_dir}/../assets/LJ037-0171.wav')
     novelty_energy, Fs_feature = compute_novelty_energy(x, Fs=Fs, N=2048, H=128, gamma=10.0, norm=True)
 
-    time = np.arange(len(novelty_energy)) * Fs_feature / Fs
+    time = np.arange(len(novelty_energy)) / Fs_feature
 
     df = pd.DataFrame(zip(time, novelty_energy), columns=['time', 'novelty'])
     df.to_csv('LJ037-0171.csv', sep=',', float_format='%.5f', index=False)
\ No newline at end of file
@@ -0,0 +1,18 @@
+import os
+
+import numpy as np
+import pandas as pd
+import soundfile as sf
+
+from libfmp.c6 import compute_novelty_spectrum
+
+
+if __name__ == '__main__':
+    file_dir = os.path.dirname(os.path.realpath(__file__))
+    x, Fs = sf.read(f'{file_dir}/../assets/LJ037-0171.wav')
+    novelty_spectrum, Fs_feature = compute_novelty_spectrum(x, Fs=Fs, N=1024, H=256, gamma=100.0, M=10, norm=True)
+
+    time = np.arange(len(novelty_spectrum)) / Fs_feature
+
+    df = pd.DataFrame(zip(time, novelty_spectrum), columns=['time', 'novelty'])
+    df.to_csv('LJ037-0171_fmp_spectrum.csv', sep=',', float_format='%.5f', index=False)
@@ -0,0 +1,17 @@
+import os
+
+import librosa
+import numpy as np
+import pandas as pd
+import soundfile as sf
+
+
+if __name__ == '__main__':
+    file_dir = os.path.dirname(os.path.realpath(__file__))
+    x, Fs = sf.read(f'{file_dir}/../assets/LJ037-0171.wav')
+    onset_strength = librosa.onset.onset_strength(y=x, sr=Fs, n_fft=2048, hop_length=512, n_mels=128)
+
+    time = np.arange(len(onset_strength)) * 512 / Fs
+
+    df = pd.DataFrame(zip(time, onset_strength), columns=['time', 'novelty'])
+    df.to_csv('LJ037-0171_librosa.csv', sep=',', float_format='%.5f', index=False)
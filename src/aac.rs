@@ -0,0 +1,97 @@
+use std::fs::File;
+use std::path::Path;
+
+use ndarray::Array2;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_AAC};
+use symphonia::core::errors::Error;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use crate::riff::{deinterleave, AudioInfo};
+
+/// Reads an AAC file, in an MP4/M4A container or as ADTS stream, and returns the samples of
+/// all channels, along with the sampling rate.
+///
+/// Decoding is done with symphonia. The encoder delay and padding stored in MP4 files are
+/// trimmed where present, so onset times match the original recording. Packets that fail
+/// to decode, e.g. in a damaged recording, are skipped.
+///
+/// # Returns
+/// - The samples with shape (channels, samples) and the sampling rate
+///
+/// # Errors
+/// Returns an error if the file can't be read or doesn't contain an AAC track.
+pub fn read_aac(path: &Path) -> anyhow::Result<(Array2<f32>, u32)> {
+    let source = MediaSourceStream::new(Box::new(File::open(path)?), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|extension| extension.to_str()) {
+        hint.with_extension(extension);
+    }
+    let format_options = FormatOptions { enable_gapless: true, ..Default::default() };
+    let mut format = symphonia::default::get_probe().format(&hint, source, &format_options, &MetadataOptions::default())?.format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec == CODEC_TYPE_AAC)
+        .ok_or_else(|| anyhow::anyhow!("No AAC track in {}", path.display()))?;
+    let track_id = track.id;
+    let fs = track.codec_params.sample_rate.ok_or_else(|| anyhow::anyhow!("Unknown sampling rate in {}", path.display()))?;
+    let mut decoder = symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+
+    let mut samples = Vec::new();
+    let mut channels = track.codec_params.channels.map_or(0, |channels| channels.count());
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(Error::IoError(error)) if error.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(error) => return Err(error.into()),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            Err(Error::DecodeError(_)) => continue,
+            Err(error) => return Err(error.into()),
+        };
+
+        let spec = *decoded.spec();
+        channels = spec.channels.count();
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        samples.extend_from_slice(buffer.samples());
+    }
+
+    Ok((deinterleave(samples, channels)?, fs))
+}
+
+/// Reads the properties of an AAC file from its container, without decoding the samples.
+///
+/// The number of samples is unknown for ADTS streams, which don't store it.
+///
+/// # Errors
+/// Returns an error if the file can't be read or doesn't contain an AAC track.
+pub fn read_aac_info(path: &Path) -> anyhow::Result<AudioInfo> {
+    let source = MediaSourceStream::new(Box::new(File::open(path)?), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|extension| extension.to_str()) {
+        hint.with_extension(extension);
+    }
+    let format = symphonia::default::get_probe().format(&hint, source, &FormatOptions::default(), &MetadataOptions::default())?.format;
+
+    let params = &format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec == CODEC_TYPE_AAC)
+        .ok_or_else(|| anyhow::anyhow!("No AAC track in {}", path.display()))?
+        .codec_params;
+    Ok(AudioInfo {
+        channels: params.channels.map_or(0, |channels| channels.count() as u16),
+        fs: params.sample_rate.ok_or_else(|| anyhow::anyhow!("Unknown sampling rate in {}", path.display()))?,
+        frames: params.n_frames,
+    })
+}
@@ -0,0 +1,148 @@
+use ndarray::Array1;
+
+use crate::novelty::rms_envelope;
+use crate::postprocess::{attack_release, AttackRelease};
+
+/// Parameters of [`detect_activity`].
+///
+/// Levels are in dB relative to the loudest frame of the smoothed envelope, so they don't
+/// depend on the recording gain.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ActivityThresholds {
+    /// Level above which an inactive recording becomes active
+    pub high: f32,
+    /// Level below which an active recording becomes inactive, at most `high`
+    pub low: f32,
+    /// Smoothing of the RMS envelope before thresholding
+    pub smoothing: AttackRelease,
+    /// Shortest active segment in seconds; shorter ones, e.g. clicks, become inactive
+    pub min_active: f32,
+    /// Shortest inactive segment in seconds; shorter ones, e.g. pauses between words, become
+    /// active
+    pub min_inactive: f32,
+}
+
+impl Default for ActivityThresholds {
+    /// Returns thresholds suited to speech: active above -30 dB and inactive below -40 dB,
+    /// with an attack of 10 ms, a release of 100 ms, and segments of at least 0.1 seconds of
+    /// activity and 0.3 seconds of inactivity.
+    fn default() -> Self {
+        ActivityThresholds {
+            high: -30.0,
+            low: -40.0,
+            smoothing: AttackRelease { attack: 0.01, release: 0.1 },
+            min_active: 0.1,
+            min_inactive: 0.3,
+        }
+    }
+}
+
+/// A section of a recording that is active or inactive, see [`detect_activity`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ActivitySegment {
+    /// Start of the segment in seconds
+    pub start: f32,
+    /// End of the segment in seconds
+    pub end: f32,
+    /// Whether there is sound, e.g. speech or music, rather than silence or background noise
+    pub active: bool,
+}
+
+/// Splits a recording into active and inactive segments, e.g. to trim silence or to cut long
+/// recordings into utterances before novelty analysis.
+///
+/// The RMS envelope is computed with the framing of [`rms_envelope`] and smoothed with
+/// [`attack_release`]. A frame becomes active once the envelope rises above the high level
+/// and stays active until it falls below the low level, so the decision doesn't flicker while
+/// the level hovers around a single threshold. Afterwards, inactive segments shorter than
+/// `min_inactive` are merged into their neighbors, and then active segments shorter than
+/// `min_active` are dropped.
+///
+/// # Returns
+/// - Alternating segments covering the whole recording, empty for an empty recording
+///
+/// # Errors
+/// Returns an error if the low level is above the high level, if a duration is negative, or
+/// if the envelope computation fails.
+pub fn detect_activity(
+    audio_array: Array1<f32>,
+    fs: u32,
+    window_length: u32,
+    hop_length: u32,
+    thresholds: &ActivityThresholds,
+) -> anyhow::Result<Vec<ActivitySegment>> {
+    if thresholds.low > thresholds.high {
+        anyhow::bail!("The low level must not be above the high level!");
+    }
+    if thresholds.min_active < 0.0 || thresholds.min_inactive < 0.0 {
+        anyhow::bail!("Minimum segment durations must not be negative!");
+    }
+    let duration = audio_array.len() as f32 / fs as f32;
+    if audio_array.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let (envelope, fs_feature) = rms_envelope(audio_array, fs, window_length, hop_length, false)?;
+    let envelope = attack_release(&envelope, fs_feature, thresholds.smoothing);
+    let max_value = envelope.iter().fold(0.0_f32, |acc, &v| acc.max(v));
+    let level = |v: f32| if max_value > 0.0 { 20.0 * (v / max_value).max(1e-10).log10() } else { f32::NEG_INFINITY };
+
+    let mut active = false;
+    let frames: Vec<bool> = envelope
+        .iter()
+        .map(|&v| {
+            let level = level(v);
+            active = if active { level >= thresholds.low } else { level > thresholds.high };
+            active
+        })
+        .collect();
+
+    // runs of equal frames as (first frame, active)
+    let mut runs: Vec<(usize, bool)> = Vec::new();
+    for (n, &active) in frames.iter().enumerate() {
+        if runs.last().map_or(true, |&(_, previous)| previous != active) {
+            runs.push((n, active));
+        }
+    }
+    let time = |frame: usize| (frame as f32 / fs_feature).min(duration);
+    let mut segments: Vec<ActivitySegment> = runs
+        .iter()
+        .enumerate()
+        .map(|(i, &(first, active))| ActivitySegment {
+            start: time(first),
+            end: runs.get(i + 1).map_or(duration, |&(next, _)| time(next)),
+            active,
+        })
+        .collect();
+
+    // short pauses inside activity belong to it, but silence at the edges stays inactive
+    let last = segments.len().saturating_sub(1);
+    for (i, segment) in segments.iter_mut().enumerate() {
+        if !segment.active && i > 0 && i < last && segment.end - segment.start < thresholds.min_inactive {
+            segment.active = true;
+        }
+    }
+    let segments = merge_segments(segments);
+    let segments = segments
+        .into_iter()
+        .map(|segment| ActivitySegment {
+            active: segment.active && segment.end - segment.start >= thresholds.min_active,
+            ..segment
+        })
+        .collect();
+    Ok(merge_segments(segments))
+}
+
+/// Merges consecutive segments with the same activity.
+fn merge_segments(segments: Vec<ActivitySegment>) -> Vec<ActivitySegment> {
+    let mut merged: Vec<ActivitySegment> = Vec::with_capacity(segments.len());
+    for segment in segments {
+        match merged.last_mut() {
+            Some(previous) if previous.active == segment.active => previous.end = segment.end,
+            _ => merged.push(segment),
+        }
+    }
+    merged
+}
@@ -0,0 +1,121 @@
+use std::io::Read;
+
+use ndarray::Array2;
+
+use crate::riff::{decode_samples_with_order, deinterleave, read_header_chunk, AudioInfo, ByteOrder, SampleFormat};
+
+/// Reads an AIFF or AIFF-C file from a stream and returns the samples of all channels,
+/// along with the sampling rate.
+///
+/// Like [`crate::read_wav`], the stream is read in order, so the `COMM` chunk must precede
+/// the `SSND` chunk, as written by all common tools. AIFF stores big-endian integer PCM
+/// with 8 to 32 bits. Of the AIFF-C compression types, uncompressed integer PCM in either
+/// byte order (`NONE`, `twos`, `sowt`) and 32 or 64-bit floats (`fl32`, `fl64`) are
+/// supported.
+///
+/// # Returns
+/// - The samples with shape (channels, samples) and the sampling rate
+///
+/// # Errors
+/// Returns an error if the stream can't be read, is not an AIFF file, or uses an
+/// unsupported compression type or sample format.
+pub fn read_aiff<R: Read>(mut reader: R) -> anyhow::Result<(Array2<f32>, u32)> {
+    let (sample_format, order, info) = read_aiff_header(&mut reader)?;
+
+    let length = info.frames.unwrap_or(0) * info.channels as u64 * sample_format.bytes() as u64;
+    let mut bytes = Vec::new();
+    reader.take(length).read_to_end(&mut bytes)?;
+
+    // AIFF stores 8-bit samples signed, unlike WAV
+    if sample_format == SampleFormat::Int(8) {
+        bytes.iter_mut().for_each(|b| *b ^= 0x80);
+    }
+    let samples = decode_samples_with_order(&bytes, sample_format, order)?;
+    Ok((deinterleave(samples, info.channels as usize)?, info.fs))
+}
+
+/// Reads the header of an AIFF or AIFF-C file from a stream up to the start of the samples.
+///
+/// # Returns
+/// - The sample format, its byte order, and the properties of the file
+///
+/// # Errors
+/// Returns an error if the stream can't be read, is not an AIFF file, or uses an
+/// unsupported compression type.
+pub fn read_aiff_header<R: Read>(reader: &mut R) -> anyhow::Result<(SampleFormat, ByteOrder, AudioInfo)> {
+    let mut header = [0u8; 12];
+    reader.read_exact(&mut header)?;
+    let is_aifc = match &header[8..12] {
+        b"AIFF" => false,
+        b"AIFC" => true,
+        _ => anyhow::bail!("Input is not an AIFF file"),
+    };
+    if &header[0..4] != b"FORM" {
+        anyhow::bail!("Input is not an AIFF file");
+    }
+
+    let mut format = None;
+    loop {
+        let mut chunk_header = [0u8; 8];
+        reader.read_exact(&mut chunk_header).map_err(|_| anyhow::anyhow!("AIFF stream ended before the SSND chunk"))?;
+        let size = u32::from_be_bytes([chunk_header[4], chunk_header[5], chunk_header[6], chunk_header[7]]) as usize;
+
+        match &chunk_header[0..4] {
+            b"COMM" => {
+                let chunk = read_header_chunk(reader, (size + (size & 1)) as u64, "AIFF COMM")?;
+                format = Some(parse_comm(&chunk, is_aifc)?);
+            }
+            b"SSND" => {
+                let (sample_format, order, info) = format.ok_or_else(|| anyhow::anyhow!("AIFF SSND chunk precedes the COMM chunk"))?;
+
+                // the samples start after the offset and block size fields and the offset
+                let mut fields = [0u8; 8];
+                reader.read_exact(&mut fields)?;
+                let offset = u32::from_be_bytes([fields[0], fields[1], fields[2], fields[3]]) as u64;
+                std::io::copy(&mut (&mut *reader).take(offset), &mut std::io::sink())?;
+
+                return Ok((sample_format, order, info));
+            }
+            _ => {
+                // skip other chunks, which are padded to an even size
+                let skip = size as u64 + (size as u64 & 1);
+                std::io::copy(&mut (&mut *reader).take(skip), &mut std::io::sink())?;
+            }
+        }
+    }
+}
+
+/// Parses a `COMM` chunk into the sample format, byte order, and the properties of the file.
+fn parse_comm(chunk: &[u8], is_aifc: bool) -> anyhow::Result<(SampleFormat, ByteOrder, AudioInfo)> {
+    if chunk.len() < 18 || (is_aifc && chunk.len() < 22) {
+        anyhow::bail!("AIFF COMM chunk is too short");
+    }
+    let channels = u16::from_be_bytes([chunk[0], chunk[1]]);
+    let frames = u32::from_be_bytes([chunk[2], chunk[3], chunk[4], chunk[5]]);
+    let bits = u16::from_be_bytes([chunk[6], chunk[7]]);
+    let fs = extended_to_f64(chunk[8..18].try_into().expect("Slice of 10 bytes")).round() as u32;
+
+    let compression = if is_aifc { &chunk[18..22] } else { &b"NONE"[..] };
+    let (sample_format, order) = match compression {
+        b"NONE" | b"twos" => (SampleFormat::Int(bits), ByteOrder::Big),
+        b"sowt" => (SampleFormat::Int(bits), ByteOrder::Little),
+        b"fl32" | b"FL32" => (SampleFormat::Float(32), ByteOrder::Big),
+        b"fl64" | b"FL64" => (SampleFormat::Float(64), ByteOrder::Big),
+        compression => anyhow::bail!("Unsupported AIFF-C compression type {}", String::from_utf8_lossy(compression)),
+    };
+
+    Ok((sample_format, order, AudioInfo { channels, fs, frames: Some(frames as u64) }))
+}
+
+/// Converts an 80-bit IEEE 754 extended precision number, in which AIFF stores the sampling
+/// rate, to a double.
+fn extended_to_f64(bytes: [u8; 10]) -> f64 {
+    let sign = if bytes[0] & 0x80 != 0 { -1.0 } else { 1.0 };
+    let exponent = (u16::from_be_bytes([bytes[0], bytes[1]]) & 0x7FFF) as i32;
+    let mantissa = u64::from_be_bytes(bytes[2..10].try_into().expect("Slice of 8 bytes"));
+    if exponent == 0 && mantissa == 0 {
+        return 0.0;
+    }
+    // the mantissa has an explicit integer bit, so it is scaled by 2^-63
+    sign * mantissa as f64 * 2.0_f64.powi(exponent - 16383 - 63)
+}
@@ -0,0 +1,109 @@
+use crate::input::Annotation;
+
+/// Default tolerance in seconds for a detection to match an annotation, as commonly used in
+/// onset detection evaluation.
+pub const DEFAULT_ALIGNMENT_TOLERANCE: f32 = 0.05;
+
+/// An annotation with its matching detection, an annotation without a detection (a missed
+/// event), or a detection without an annotation (an extra event), see [`align_onsets`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Alignment {
+    /// Annotated event, `None` for an extra detection
+    pub annotation: Option<Annotation>,
+    /// Time of the detected event in seconds, `None` for a missed annotation
+    pub detection: Option<f32>,
+}
+
+impl Alignment {
+    /// Time of the detection relative to the annotation in seconds, if both exist.
+    pub fn deviation(&self) -> Option<f32> {
+        Some(self.detection? - self.annotation.as_ref()?.time)
+    }
+
+    /// Time of the annotation, or of the detection if there is no annotation.
+    pub fn time(&self) -> f32 {
+        self.annotation.as_ref().map_or(self.detection.unwrap_or(0.0), |annotation| annotation.time)
+    }
+}
+
+/// Aligns detected onsets with annotations, e.g. to check detections against hand labels.
+///
+/// Pairs of a detection and an annotation within `tolerance` seconds are matched greedily,
+/// closest first, so each detection and annotation is matched at most once. The result
+/// holds the matched pairs, the missed annotations, and the extra detections, sorted by
+/// time. Counting them gives the usual precision and recall, but the list itself shows where
+/// the detector and the annotations disagree.
+///
+/// # Arguments
+/// - `detections`: Detected onset times in seconds
+/// - `annotations`: Annotated onsets
+/// - `tolerance`: Maximum time difference in seconds of a match, e.g.
+///   [`DEFAULT_ALIGNMENT_TOLERANCE`]
+pub fn align_onsets(detections: &[f32], annotations: &[Annotation], tolerance: f32) -> Vec<Alignment> {
+    let mut pairs: Vec<(usize, usize, f32)> = Vec::new();
+    for (i, &detection) in detections.iter().enumerate() {
+        for (j, annotation) in annotations.iter().enumerate() {
+            let distance = (detection - annotation.time).abs();
+            if distance <= tolerance {
+                pairs.push((i, j, distance));
+            }
+        }
+    }
+    pairs.sort_by(|a, b| a.2.total_cmp(&b.2));
+
+    let mut detection_match = vec![None; detections.len()];
+    let mut annotation_matched = vec![false; annotations.len()];
+    for (i, j, _) in pairs {
+        if detection_match[i].is_none() && !annotation_matched[j] {
+            detection_match[i] = Some(j);
+            annotation_matched[j] = true;
+        }
+    }
+
+    let mut alignments: Vec<Alignment> = detections
+        .iter()
+        .zip(&detection_match)
+        .map(|(&detection, matched)| Alignment {
+            annotation: matched.map(|j| annotations[j].clone()),
+            detection: Some(detection),
+        })
+        .collect();
+    let missed = annotations.iter().zip(&annotation_matched).filter(|(_, &matched)| !matched);
+    alignments.extend(missed.map(|(annotation, _)| Alignment {
+        annotation: Some(annotation.clone()),
+        detection: None,
+    }));
+    alignments.sort_by(|a, b| a.time().total_cmp(&b.time()));
+    alignments
+}
+
+/// Snaps rough annotations to the nearest detected onset, e.g. to clean hand-made labels that
+/// were placed by ear or on a coarse grid.
+///
+/// Unlike [`align_onsets`], each annotation is treated on its own, so two annotations may snap
+/// to the same onset, and onsets without an annotation are ignored. The result holds one
+/// alignment per annotation in the given order, with the nearest onset within `tolerance`
+/// seconds as detection, or without a detection if there is none. The corrected time of an
+/// annotation is [`Alignment::detection`] if present and its original time otherwise.
+///
+/// # Arguments
+/// - `peaks`: Onset times in seconds, usually all peaks of the novelty function
+/// - `annotations`: Annotated onsets
+/// - `tolerance`: Maximum distance in seconds an annotation is moved
+pub fn snap_annotations(peaks: &[f32], annotations: &[Annotation], tolerance: f32) -> Vec<Alignment> {
+    annotations
+        .iter()
+        .map(|annotation| {
+            let nearest = peaks
+                .iter()
+                .copied()
+                .filter(|peak| (peak - annotation.time).abs() <= tolerance)
+                .min_by(|a, b| (a - annotation.time).abs().total_cmp(&(b - annotation.time).abs()));
+            Alignment {
+                annotation: Some(annotation.clone()),
+                detection: nearest,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,64 @@
+use ndarray::Array1;
+
+use crate::artifacts::{detect_artifacts, Artifact};
+use crate::config::NoveltyConfig;
+use crate::novelty::NoveltyCurve;
+use crate::onsets::{detect_onsets, Onset};
+use crate::stats::{signal_stats, SignalStats};
+use crate::summary::{summarize, Summary};
+use crate::tempo::{default_tempogram, estimate_tempo, tempo_candidates, TempoCandidate, DEFAULT_NUM_CANDIDATES};
+
+/// Combined result of all analysis stages for one recording.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Analysis {
+    /// Configuration used for the novelty function
+    pub config: NoveltyConfig,
+    /// Basic statistics of the audio signal
+    pub stats: SignalStats,
+    /// Clipped regions and dropouts in the audio signal
+    pub artifacts: Vec<Artifact>,
+    /// Estimated global tempo in BPM
+    pub tempo: Option<f32>,
+    /// Alternative tempo hypotheses with confidences, best first
+    pub tempo_candidates: Vec<TempoCandidate>,
+    /// Onsets detected in the novelty function
+    pub onsets: Vec<Onset>,
+    /// Aggregate statistics of the novelty function and onsets
+    pub summary: Summary,
+    /// The novelty function
+    pub novelty: NoveltyCurve,
+}
+
+/// Runs novelty computation, onset detection, tempo estimation, artifact detection, and signal
+/// statistics in one pass.
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples
+/// - `fs`: Sampling rate of the audio
+/// - `config`: Configuration of the novelty function
+/// - `onset_threshold`: Relative peak threshold, see [`detect_onsets`]
+///
+/// # Errors
+/// Returns an error if the novelty computation fails.
+pub fn analyze(audio_array: Array1<f32>, fs: u32, config: &NoveltyConfig, onset_threshold: f32) -> anyhow::Result<Analysis> {
+    let stats = signal_stats(&audio_array, fs);
+    let artifacts = detect_artifacts(&audio_array, fs);
+    let novelty = config.compute(audio_array, fs)?;
+    let onsets = detect_onsets(&novelty, onset_threshold);
+    let tempogram = default_tempogram(&novelty);
+    let tempo = estimate_tempo(&tempogram);
+    let tempo_candidates = tempo_candidates(&tempogram, DEFAULT_NUM_CANDIDATES);
+    let summary = summarize(&novelty, &onsets, tempo).with_artifacts(&artifacts);
+
+    Ok(Analysis {
+        config: config.clone(),
+        stats,
+        artifacts,
+        tempo,
+        tempo_candidates,
+        onsets,
+        summary,
+        novelty,
+    })
+}
@@ -0,0 +1,123 @@
+use ndarray::Array1;
+
+use crate::novelty::NoveltyCurve;
+
+/// Absolute sample value from which a sample counts as full scale. 16-bit audio reaches
+/// 32767 / 32768 at most in the positive direction.
+pub const CLIPPING_LEVEL: f32 = 0.999;
+
+/// Smallest number of consecutive full-scale samples that count as clipping.
+pub const MIN_CLIPPING_RUN: usize = 3;
+
+/// Shortest run of exactly zero samples in seconds that counts as a dropout.
+pub const MIN_DROPOUT_DURATION: f32 = 0.005;
+
+/// Kind of a decoding artifact.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum ArtifactKind {
+    /// Consecutive full-scale samples, where the waveform was cut off by the converter or a
+    /// gain stage
+    Clipping,
+    /// Exactly zero samples within the signal, where samples were lost in transmission or
+    /// by a buffer underrun
+    Dropout,
+}
+
+impl std::fmt::Display for ArtifactKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArtifactKind::Clipping => write!(f, "clipping"),
+            ArtifactKind::Dropout => write!(f, "dropout"),
+        }
+    }
+}
+
+/// A clipped region or dropout of a recording, see [`detect_artifacts`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Artifact {
+    /// Kind of the artifact
+    pub kind: ArtifactKind,
+    /// Start of the artifact in seconds
+    pub start: f32,
+    /// End of the artifact in seconds
+    pub end: f32,
+}
+
+impl Artifact {
+    /// Duration of the artifact in seconds.
+    pub fn duration(&self) -> f32 {
+        self.end - self.start
+    }
+}
+
+/// Finds clipped regions and digital dropouts in decoded audio.
+///
+/// Clipping is a run of at least [`MIN_CLIPPING_RUN`] samples of the same sign at or above
+/// [`CLIPPING_LEVEL`]. A dropout is a run of exactly zero samples of at least
+/// [`MIN_DROPOUT_DURATION`] seconds, since analog and dithered silence is never exactly
+/// zero for long. Runs at the start or end of the recording are digital silence from
+/// editing rather than dropouts and are skipped.
+///
+/// # Returns
+/// - The artifacts in temporal order
+pub fn detect_artifacts(audio_array: &Array1<f32>, fs: u32) -> Vec<Artifact> {
+    let min_dropout = ((MIN_DROPOUT_DURATION * fs as f32).round() as usize).max(1);
+    let time = |n: usize| n as f32 / fs as f32;
+
+    // the kind of artifact each sample may be part of, with the sign for clipping
+    let class = |v: f32| {
+        if v >= CLIPPING_LEVEL {
+            Some((ArtifactKind::Clipping, 1))
+        } else if v <= -CLIPPING_LEVEL {
+            Some((ArtifactKind::Clipping, -1))
+        } else if v == 0.0 {
+            Some((ArtifactKind::Dropout, 0))
+        } else {
+            None
+        }
+    };
+
+    let mut artifacts = Vec::new();
+    let mut start = 0;
+    while start < audio_array.len() {
+        let current = class(audio_array[start]);
+        let end = (start + 1..audio_array.len()).find(|&n| class(audio_array[n]) != current).unwrap_or(audio_array.len());
+        let length = end - start;
+        match current {
+            Some((ArtifactKind::Clipping, _)) if length >= MIN_CLIPPING_RUN => {
+                artifacts.push(Artifact { kind: ArtifactKind::Clipping, start: time(start), end: time(end) })
+            }
+            Some((ArtifactKind::Dropout, _)) if length >= min_dropout && start > 0 && end < audio_array.len() => {
+                artifacts.push(Artifact { kind: ArtifactKind::Dropout, start: time(start), end: time(end) })
+            }
+            _ => {}
+        }
+        start = end;
+    }
+    artifacts
+}
+
+/// Normalizes a novelty function to a maximum of 1 outside of artifacts, so the strong
+/// novelty at the edges of clipped regions and dropouts doesn't dominate the curve.
+///
+/// Frames within `margin` seconds of an artifact are excluded from the maximum and clipped
+/// to 1 afterwards. Without frames outside of artifacts, the novelty function is left
+/// unchanged.
+pub fn normalize_excluding(novelty: &mut NoveltyCurve, artifacts: &[Artifact], margin: f32) {
+    let num_frames = novelty.values.len();
+    let mut affected = vec![false; num_frames];
+    for artifact in artifacts {
+        let first = ((artifact.start - margin) * novelty.fs_feature).ceil().max(0.0) as usize;
+        let last = ((artifact.end + margin) * novelty.fs_feature).floor().max(0.0) as usize;
+        for flag in affected.iter_mut().take(last.saturating_add(1)).skip(first) {
+            *flag = true;
+        }
+    }
+    let max_value = novelty.values.iter().zip(&affected).filter(|&(_, &excluded)| !excluded).fold(0.0_f32, |acc, (&v, _)| acc.max(v));
+    if max_value > 0.0 {
+        novelty.values.mapv_inplace(|v| (v / max_value).min(1.0));
+    }
+}
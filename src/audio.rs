@@ -0,0 +1,203 @@
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read};
+use std::path::{Path, PathBuf};
+
+use ndarray::{Array, Array1, Array2, Axis};
+use wavers::{Wav, Samples, read, write};
+
+use crate::aiff::{read_aiff, read_aiff_header};
+use crate::caf::{read_caf, read_caf_header};
+use crate::riff::{deinterleave, read_wav_channels, read_wav_header, read_wav_lenient, AudioInfo};
+use crate::segments::Segment;
+
+/// Reads a mono audio file from the given path and returns the audio samples as a 1D array,
+/// along with the sampling rate.
+///
+/// WAV files are read by extension, as are AAC files (`.m4a`, `.mp4`, `.aac`) with the
+/// `aac` feature. Other files, and RF64 files beyond 4 GB despite their `.wav` extension,
+/// are detected by their content with [`read_audio_channels`], which supports AIFF and CAF.
+/// The path `-` reads a WAV, AIFF, or CAF stream from standard input.
+///
+/// # Errors
+/// Returns an error if the file can't be read or if it is not mono.
+pub fn audio_path_to_array(path: &str) -> anyhow::Result<(Array1<f32>, u32)> {
+    if path == "-" || !has_extension(path, &["wav", "wave"]) || is_rf64(path) {
+        let (channels, fs) = audio_path_to_channels(path)?;
+        if channels.nrows() != 1 {
+            anyhow::bail!("Can only handle mono files currently. Please convert input audio file to mono.");
+        }
+        return Ok((channels.index_axis_move(Axis(0), 0), fs));
+    }
+
+    let reader: Wav<i16> = Wav::from_path(path)?;
+
+    if reader.n_channels() != 1 {
+        anyhow::bail!("Can only handle mono files currently. Please convert input audio file to mono.");
+    }
+
+    let (samples, sample_rate): (Samples<i16>, i32) = read::<i16, _>(path)?;
+    let samples: Vec<f32> = samples.convert().to_vec();
+    let audio_array = Array::from_vec(samples);
+
+    Ok((audio_array, sample_rate as u32))
+}
+
+/// Reads an audio file with any number of channels from the given path, like
+/// [`audio_path_to_array`].
+///
+/// # Returns
+/// - The samples with shape (channels, samples) and the sampling rate
+///
+/// # Errors
+/// Returns an error if the file can't be read.
+pub fn audio_path_to_channels(path: &str) -> anyhow::Result<(Array2<f32>, u32)> {
+    if path == "-" {
+        return read_audio_channels(std::io::stdin().lock());
+    }
+    #[cfg(feature = "aac")]
+    if has_extension(path, &["m4a", "mp4", "aac"]) {
+        return crate::aac::read_aac(Path::new(path));
+    }
+    if !has_extension(path, &["wav", "wave"]) || is_rf64(path) {
+        return read_audio_channels(BufReader::new(File::open(path)?));
+    }
+
+    let reader: Wav<i16> = Wav::from_path(path)?;
+    let channels = reader.n_channels() as usize;
+
+    let (samples, sample_rate): (Samples<i16>, i32) = read::<i16, _>(path)?;
+    let samples: Vec<f32> = samples.convert().to_vec();
+
+    Ok((deinterleave(samples, channels)?, sample_rate as u32))
+}
+
+/// Reads an audio file with any number of channels like [`audio_path_to_channels`], but
+/// recovers the samples of malformed WAV files with [`read_wav_lenient`] instead of failing.
+///
+/// # Returns
+/// - The samples with shape (channels, samples), the sampling rate, and a description of
+///   each malformation that was worked around
+///
+/// # Errors
+/// Returns an error if the file can't be read or no samples can be recovered.
+pub fn audio_path_to_channels_lenient(path: &str) -> anyhow::Result<(Array2<f32>, u32, Vec<String>)> {
+    #[cfg(feature = "aac")]
+    if has_extension(path, &["m4a", "mp4", "aac"]) {
+        let (channels, fs) = crate::aac::read_aac(Path::new(path))?;
+        return Ok((channels, fs, Vec::new()));
+    }
+
+    let mut bytes = Vec::new();
+    if path == "-" {
+        std::io::stdin().lock().read_to_end(&mut bytes)?;
+    } else {
+        File::open(path)?.read_to_end(&mut bytes)?;
+    }
+    let is_wav = [b"RIFF", b"RF64", b"BW64"].iter().any(|magic| bytes.starts_with(*magic));
+    if is_wav || has_extension(path, &["wav", "wave"]) {
+        return read_wav_lenient(&bytes);
+    }
+    let (channels, fs) = read_audio_channels(Cursor::new(bytes))?;
+    Ok((channels, fs, Vec::new()))
+}
+
+/// Reads an audio stream with any number of channels, detecting the container from its
+/// first bytes: WAV, RF64, and BW64 with [`read_wav_channels`], AIFF and AIFF-C with [`read_aiff`], or CAF
+/// with [`read_caf`].
+///
+/// # Returns
+/// - The samples with shape (channels, samples) and the sampling rate
+///
+/// # Errors
+/// Returns an error if the stream can't be read or its format is not supported.
+pub fn read_audio_channels<R: Read>(mut reader: R) -> anyhow::Result<(Array2<f32>, u32)> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    let reader = Cursor::new(magic).chain(reader);
+    match &magic {
+        b"RIFF" | b"RF64" | b"BW64" => read_wav_channels(reader),
+        b"FORM" => read_aiff(reader),
+        b"caff" => read_caf(reader),
+        _ => anyhow::bail!("Unsupported audio format, expected WAV, AIFF, or CAF"),
+    }
+}
+
+/// Reads the properties of an audio file from its header, without decoding the samples.
+///
+/// This supports the same formats as [`audio_path_to_channels`], except for standard
+/// input, which can't be read twice.
+///
+/// # Errors
+/// Returns an error if the file can't be read or its format is not supported.
+pub fn audio_path_info(path: &str) -> anyhow::Result<AudioInfo> {
+    #[cfg(feature = "aac")]
+    if has_extension(path, &["m4a", "mp4", "aac"]) {
+        return crate::aac::read_aac_info(Path::new(path));
+    }
+
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    let mut reader = Cursor::new(magic).chain(reader);
+    let info = match &magic {
+        b"RIFF" | b"RF64" | b"BW64" => read_wav_header(&mut reader)?.1,
+        b"FORM" => read_aiff_header(&mut reader)?.2,
+        b"caff" => read_caf_header(&mut reader)?.2,
+        _ => anyhow::bail!("Unsupported audio format, expected WAV, AIFF, or CAF"),
+    };
+    Ok(info)
+}
+
+/// Returns whether a file starts with the magic bytes of RF64 or BW64, the WAV variants for
+/// files over 4 GB.
+fn is_rf64(path: &str) -> bool {
+    let mut magic = [0u8; 4];
+    File::open(path).and_then(|mut file| file.read_exact(&mut magic)).is_ok() && matches!(&magic, b"RF64" | b"BW64")
+}
+
+/// Returns whether a path has one of the given file extensions, ignoring case.
+fn has_extension(path: &str, extensions: &[&str]) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extensions.iter().any(|candidate| extension.eq_ignore_ascii_case(candidate)))
+}
+
+/// Writes a mono 16-bit WAV file from samples in the range -1 to 1.
+///
+/// Samples outside this range are clipped.
+///
+/// # Errors
+/// Returns an error if the file can't be written.
+pub fn write_wav(path: &str, samples: &Array1<f32>, fs: u32) -> anyhow::Result<()> {
+    let samples: Vec<i16> = samples.iter().map(|v| (v.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16).collect();
+    write(path, &samples, fs as i32, 1)?;
+    Ok(())
+}
+
+/// Writes each segment as a mono 16-bit WAV file to `dir`, which is created if needed.
+///
+/// The files are named `<stem>_<index>.wav` with the index starting at 1 and zero-padded to
+/// at least three digits, so they sort in temporal order.
+///
+/// # Returns
+/// - The paths of the written files
+///
+/// # Errors
+/// Returns an error if one of the files already exists or can't be written. Existing files
+/// are checked before anything is written.
+pub fn write_segments(dir: &Path, stem: &str, segments: &[Segment], fs: u32) -> anyhow::Result<Vec<PathBuf>> {
+    let digits = segments.len().to_string().len().max(3);
+    let paths: Vec<PathBuf> = (1..=segments.len()).map(|index| dir.join(format!("{}_{:0digits$}.wav", stem, index))).collect();
+    if let Some(existing) = paths.iter().find(|path| path.exists()) {
+        anyhow::bail!("Segment file {} already exists!", existing.display());
+    }
+
+    std::fs::create_dir_all(dir)?;
+    for (path, segment) in paths.iter().zip(segments) {
+        let path = path.to_str().ok_or_else(|| anyhow::anyhow!("Invalid segment path {}", path.display()))?;
+        write_wav(path, &segment.samples, fs)?;
+    }
+
+    Ok(paths)
+}
@@ -0,0 +1,153 @@
+use ndarray::{Array1, Array2};
+
+use crate::compression::Compression;
+use crate::mel::{band_flux, mel_filterbank};
+use crate::novelty::normalize_max;
+use crate::spectral::magnitude_spectrogram;
+
+/// Frequency scale of the filterbank used for band-wise novelty.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum BandScale {
+    /// Slaney mel scale with triangular filters, see [`mel_filterbank`]
+    Mel,
+    /// Bark scale with triangular filters, see [`bark_filterbank`]
+    Bark,
+    /// ERB-rate scale with gammatone-shaped filters, see [`erb_filterbank`]
+    Erb,
+}
+
+/// Converts a frequency in Hz to the Bark scale after Traunmüller (1990).
+pub fn hz_to_bark(frequency: f64) -> f64 {
+    26.81 * frequency / (1960.0 + frequency) - 0.53
+}
+
+/// Converts a value on the Bark scale to a frequency in Hz, the inverse of [`hz_to_bark`].
+pub fn bark_to_hz(bark: f64) -> f64 {
+    1960.0 * (bark + 0.53) / (26.28 - bark)
+}
+
+/// Converts a frequency in Hz to the ERB-rate scale after Glasberg and Moore (1990), i.e. the
+/// number of equivalent rectangular bandwidths below the frequency.
+pub fn hz_to_erb_rate(frequency: f64) -> f64 {
+    21.4 * (1.0 + 0.00437 * frequency).log10()
+}
+
+/// Converts a value on the ERB-rate scale to a frequency in Hz, the inverse of [`hz_to_erb_rate`].
+pub fn erb_rate_to_hz(erb_rate: f64) -> f64 {
+    (10.0_f64.powf(erb_rate / 21.4) - 1.0) / 0.00437
+}
+
+/// Computes a Bark filterbank with triangular filters of unit area.
+///
+/// The band edges are equally spaced on the Bark scale, as the band edges of
+/// [`mel_filterbank`] are on the mel scale.
+///
+/// # Arguments
+/// - `fs`: Sampling rate of the audio
+/// - `n_fft`: FFT size of the spectrogram the filterbank is applied to
+/// - `n_bands`: Number of bands
+/// - `fmin`: Lowest frequency in Hz
+/// - `fmax`: Highest frequency in Hz
+///
+/// # Returns
+/// - Filter weights with shape (`n_bands`, `n_fft / 2 + 1`)
+pub fn bark_filterbank(fs: u32, n_fft: usize, n_bands: usize, fmin: f32, fmax: f32) -> Array2<f32> {
+    let num_bins = n_fft / 2 + 1;
+    let fft_frequencies: Vec<f64> = (0..num_bins).map(|k| k as f64 * fs as f64 / n_fft as f64).collect();
+
+    let min_bark = hz_to_bark(fmin as f64);
+    let max_bark = hz_to_bark(fmax as f64);
+    let edges: Vec<f64> = (0..n_bands + 2)
+        .map(|i| bark_to_hz(min_bark + (max_bark - min_bark) * i as f64 / (n_bands + 1) as f64))
+        .collect();
+
+    Array2::from_shape_fn((n_bands, num_bins), |(i, k)| {
+        let lower = (fft_frequencies[k] - edges[i]) / (edges[i + 1] - edges[i]);
+        let upper = (edges[i + 2] - fft_frequencies[k]) / (edges[i + 2] - edges[i + 1]);
+        let enorm = 2.0 / (edges[i + 2] - edges[i]);
+        (lower.min(upper).max(0.0) * enorm) as f32
+    })
+}
+
+/// Computes an ERB filterbank approximating a gammatone filterbank in the frequency domain.
+///
+/// The center frequencies are equally spaced on the ERB-rate scale. Each filter has the
+/// magnitude response of a fourth-order gammatone filter,
+/// `(1 + ((f - fc) / b)^2)^(-2)` with `b = 1.019 * ERB(fc)` and
+/// `ERB(fc) = 24.7 * (4.37 * fc / 1000 + 1)`, and is scaled to unit sum.
+///
+/// # Arguments
+/// - `fs`: Sampling rate of the audio
+/// - `n_fft`: FFT size of the spectrogram the filterbank is applied to
+/// - `n_bands`: Number of bands
+/// - `fmin`: Lowest center frequency in Hz
+/// - `fmax`: Highest center frequency in Hz
+///
+/// # Returns
+/// - Filter weights with shape (`n_bands`, `n_fft / 2 + 1`)
+pub fn erb_filterbank(fs: u32, n_fft: usize, n_bands: usize, fmin: f32, fmax: f32) -> Array2<f32> {
+    let num_bins = n_fft / 2 + 1;
+    let fft_frequencies: Vec<f64> = (0..num_bins).map(|k| k as f64 * fs as f64 / n_fft as f64).collect();
+
+    let min_erb = hz_to_erb_rate(fmin as f64);
+    let max_erb = hz_to_erb_rate(fmax as f64);
+    let steps = n_bands.saturating_sub(1).max(1) as f64;
+
+    let mut filterbank = Array2::zeros((n_bands, num_bins));
+    for (i, mut filter) in filterbank.rows_mut().into_iter().enumerate() {
+        let center = erb_rate_to_hz(min_erb + (max_erb - min_erb) * i as f64 / steps);
+        let bandwidth = 1.019 * 24.7 * (4.37 * center / 1000.0 + 1.0);
+        for (weight, frequency) in filter.iter_mut().zip(&fft_frequencies) {
+            *weight = (1.0 + ((frequency - center) / bandwidth).powi(2)).powi(-2) as f32;
+        }
+        let sum = filter.sum();
+        if sum > 0.0 {
+            filter.mapv_inplace(|w| w / sum);
+        }
+    }
+    filterbank
+}
+
+/// Computes a novelty function from the increase of band energies over time.
+///
+/// This is [`crate::novelty_mel`] with the mel filterbank replaced by a filterbank on the
+/// given frequency scale, covering the range from 0 Hz to the Nyquist frequency.
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples
+/// - `fs`: Sampling rate of the audio
+/// - `window_length`: Size of the analysis window and FFT
+/// - `hop_length`: Step size between successive frames
+/// - `scale`: Frequency scale of the filterbank
+/// - `n_bands`: Number of bands
+/// - `compression`: Compression function applied to the band energies
+/// - `gamma`: Parameter of the compression function
+/// - `norm`: Whether to normalize the output between 0 and 1
+///
+/// # Returns
+/// - A tuple of the novelty function and its effective sampling rate
+///
+/// # Errors
+/// Returns an error if normalization fails.
+#[allow(clippy::too_many_arguments)]
+pub fn novelty_bands(audio_array: &Array1<f32>, fs: u32, window_length: u32, hop_length: u32, scale: BandScale, n_bands: u32, compression: Compression, gamma: f32, norm: bool) -> anyhow::Result<(Array1<f32>, f32)> {
+    let fs_feature = (fs as f32) / (hop_length as f32);
+
+    let (n_fft, n_bands, fmax) = (window_length as usize, n_bands as usize, fs as f32 / 2.0);
+    let filterbank = match scale {
+        BandScale::Mel => mel_filterbank(fs, n_fft, n_bands, 0.0, fmax),
+        BandScale::Bark => bark_filterbank(fs, n_fft, n_bands, 0.0, fmax),
+        BandScale::Erb => erb_filterbank(fs, n_fft, n_bands, 0.0, fmax),
+    };
+
+    let spectrogram = magnitude_spectrogram(audio_array, n_fft, hop_length as usize);
+    let mut novelty = band_flux(&filterbank, &spectrogram, compression, gamma);
+
+    if norm {
+        normalize_max(&mut novelty)?;
+    }
+
+    Ok((novelty, fs_feature))
+}
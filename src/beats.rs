@@ -0,0 +1,150 @@
+use crate::novelty::NoveltyCurve;
+
+/// A beat position estimated by [`track_beats`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Beat {
+    /// Frame index in the novelty function
+    pub frame: usize,
+    /// Time of the beat in seconds
+    pub time: f32,
+    /// Position of the beat in its bar starting at 1 for the downbeat, if estimated with
+    /// [`crate::track_downbeats`]
+    pub position_in_bar: Option<usize>,
+}
+
+/// Tracks beats in a novelty function by dynamic programming.
+///
+/// This follows the FMP notebooks: the score of a beat sequence is the sum of the novelty
+/// values at its beats plus a penalty of `-tightness * log2(delta / beat_period)^2` for each
+/// inter-beat interval `delta`. To keep the runtime linear in the signal length, only
+/// predecessors between half and twice the beat period are considered.
+///
+/// # Arguments
+/// - `novelty`: Novelty function to track
+/// - `tempo`: Expected tempo in BPM
+/// - `tightness`: Weight of the tempo penalty relative to the novelty values
+pub fn track_beats(novelty: &NoveltyCurve, tempo: f32, tightness: f32) -> Vec<Beat> {
+    let values = &novelty.values;
+    let num_frames = values.len();
+    if num_frames == 0 || tempo <= 0.0 {
+        return Vec::new();
+    }
+
+    let beat_period = novelty.fs_feature * 60.0 / tempo;
+    let min_delta = ((beat_period / 2.0).round() as usize).max(1);
+    let max_delta = ((beat_period * 2.0).round() as usize).max(min_delta);
+    let penalty = |delta: usize| -tightness * (delta as f32 / beat_period).log2().powi(2);
+
+    // accumulated score and best predecessor of a beat at each frame
+    let mut score = vec![0.0_f32; num_frames];
+    let mut predecessor: Vec<Option<usize>> = vec![None; num_frames];
+
+    for n in 0..num_frames {
+        let best = (min_delta..=max_delta.min(n))
+            .map(|delta| (n - delta, score[n - delta] + penalty(delta)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        score[n] = values[n];
+        if let Some((m, best_score)) = best {
+            if best_score > 0.0 {
+                score[n] += best_score;
+                predecessor[n] = Some(m);
+            }
+        }
+    }
+
+    // backtrack from the best final score
+    let mut frame = (0..num_frames).max_by(|&a, &b| score[a].total_cmp(&score[b])).unwrap_or(0);
+    let mut frames = vec![frame];
+    while let Some(previous) = predecessor[frame] {
+        frame = previous;
+        frames.push(frame);
+    }
+    frames.reverse();
+
+    frames
+        .into_iter()
+        .map(|frame| Beat {
+            frame,
+            time: frame as f32 / novelty.fs_feature,
+            position_in_bar: None,
+        })
+        .collect()
+}
+
+/// Default tolerance in seconds for a beat to match a constant beat grid.
+pub const DEFAULT_GRID_TOLERANCE: f32 = 0.02;
+
+/// Statistics of tracked beats relative to a constant beat grid, see [`beat_grid_stats`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridStats {
+    /// Tempo of the constant grid in BPM
+    pub bpm: f32,
+    /// Mean inter-beat interval in seconds
+    pub ibi_mean: f32,
+    /// Standard deviation of the inter-beat intervals in seconds
+    pub ibi_std: f32,
+    /// Largest deviation of a beat from the grid in seconds
+    pub max_drift: f32,
+    /// Root mean square deviation of the beats from the grid in seconds
+    pub rms_drift: f32,
+    /// Fraction of beats within the tolerance of the grid, from 0 to 1
+    pub matching: f32,
+}
+
+/// Compares tracked beats with a constant beat grid, to judge whether a recording is
+/// quantized or played live.
+///
+/// The `k`-th beat is compared with the grid position `offset + k * 60 / bpm`, so the beats
+/// must be consecutive, as returned by [`track_beats`]. Without a given tempo, the tempo and
+/// offset of the grid are fitted to the beats by least squares; with a given tempo, only the
+/// offset is fitted. Quantized music has a small interval deviation and drift with nearly
+/// all beats matching the grid, while live recordings drift away from any constant grid as
+/// the tempo varies.
+///
+/// # Arguments
+/// - `beats`: Consecutive beats
+/// - `bpm`: Tempo of the grid in BPM, or `None` to fit it
+/// - `tolerance`: Maximum deviation in seconds for a beat to match the grid, e.g.
+///   [`DEFAULT_GRID_TOLERANCE`]
+///
+/// # Returns
+/// - The statistics, or `None` for fewer than three beats
+pub fn beat_grid_stats(beats: &[Beat], bpm: Option<f32>, tolerance: f32) -> Option<GridStats> {
+    if beats.len() < 3 {
+        return None;
+    }
+    let num_beats = beats.len() as f64;
+    let times: Vec<f64> = beats.iter().map(|beat| beat.time as f64).collect();
+
+    let intervals: Vec<f64> = times.windows(2).map(|pair| pair[1] - pair[0]).collect();
+    let ibi_mean = intervals.iter().sum::<f64>() / intervals.len() as f64;
+    let ibi_var = intervals.iter().map(|ibi| (ibi - ibi_mean).powi(2)).sum::<f64>() / intervals.len() as f64;
+
+    // least-squares fit of time = offset + k * period
+    let k_mean = (num_beats - 1.0) / 2.0;
+    let t_mean = times.iter().sum::<f64>() / num_beats;
+    let period = match bpm {
+        Some(bpm) => 60.0 / bpm as f64,
+        None => {
+            let covariance: f64 = times.iter().enumerate().map(|(k, t)| (k as f64 - k_mean) * (t - t_mean)).sum();
+            let variance: f64 = (0..beats.len()).map(|k| (k as f64 - k_mean).powi(2)).sum();
+            covariance / variance
+        }
+    };
+    let offset = t_mean - k_mean * period;
+
+    let drifts: Vec<f64> = times.iter().enumerate().map(|(k, t)| (t - offset - k as f64 * period).abs()).collect();
+    let matching = drifts.iter().filter(|&&drift| drift <= tolerance as f64).count();
+
+    Some(GridStats {
+        bpm: (60.0 / period) as f32,
+        ibi_mean: ibi_mean as f32,
+        ibi_std: ibi_var.sqrt() as f32,
+        max_drift: drifts.iter().copied().fold(0.0, f64::max) as f32,
+        rms_drift: (drifts.iter().map(|drift| drift * drift).sum::<f64>() / num_beats).sqrt() as f32,
+        matching: matching as f32 / num_beats as f32,
+    })
+}
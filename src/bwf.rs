@@ -0,0 +1,125 @@
+use std::io::{Read, Seek, SeekFrom};
+
+/// Broadcast Wave (BWF) metadata of a WAV file from its `bext` and `iXML` chunks, which
+/// place a recording on the timeline of a production.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BroadcastInfo {
+    /// Free-text description of the recording
+    pub description: String,
+    /// Name of the device or software that created the file
+    pub originator: String,
+    /// Date the recording started, as `yyyy-mm-dd`
+    pub origination_date: String,
+    /// Time of day the recording started, as `hh:mm:ss` or `hh-mm-ss`
+    pub origination_time: String,
+    /// Position of the first sample on the production timeline in samples since midnight
+    pub time_reference: Option<u64>,
+    /// Frame rate of the timecode from iXML, e.g. `25/1` or `30000/1001`
+    pub timecode_rate: Option<String>,
+    /// Project name from iXML
+    pub project: Option<String>,
+    /// Scene from iXML
+    pub scene: Option<String>,
+    /// Take from iXML
+    pub take: Option<String>,
+}
+
+impl BroadcastInfo {
+    /// Returns the position of the first sample on the production timeline in seconds since
+    /// midnight, if the file states a time reference.
+    pub fn timecode_offset(&self, fs: u32) -> Option<f64> {
+        self.time_reference.map(|samples| samples as f64 / fs as f64)
+    }
+}
+
+/// Reads the Broadcast Wave metadata of a WAV, RF64, or BW64 file.
+///
+/// All chunks are visited, since recorders often write `iXML` after the samples, which are
+/// skipped by seeking. The time reference is taken from `bext`, or from the
+/// `BWF_TIME_REFERENCE_LOW` and `BWF_TIME_REFERENCE_HIGH` elements of `iXML` without one.
+///
+/// # Returns
+/// - The metadata, or `None` for files without `bext` and `iXML` chunks and other formats
+///
+/// # Errors
+/// Returns an error if the file can't be read or seeked.
+pub fn read_broadcast_info<R: Read + Seek>(reader: &mut R) -> anyhow::Result<Option<BroadcastInfo>> {
+    let mut header = [0u8; 12];
+    if reader.read_exact(&mut header).is_err()
+        || !matches!(&header[0..4], b"RIFF" | b"RF64" | b"BW64")
+        || &header[8..12] != b"WAVE"
+    {
+        return Ok(None);
+    }
+
+    let mut bext = None;
+    let mut ixml = None;
+    let mut data_size = None;
+    loop {
+        let mut chunk_header = [0u8; 8];
+        if reader.read_exact(&mut chunk_header).is_err() {
+            break;
+        }
+        let size = u32::from_le_bytes([chunk_header[4], chunk_header[5], chunk_header[6], chunk_header[7]]);
+        let size = match (&chunk_header[0..4], data_size) {
+            (b"data", Some(data_size)) if size == u32::MAX => data_size,
+            // a data chunk of unknown size extends to the end of the file
+            (b"data", None) if size == u32::MAX => break,
+            _ => size as u64,
+        };
+
+        if matches!(&chunk_header[0..4], b"bext" | b"iXML" | b"ds64") {
+            let mut chunk = Vec::new();
+            (&mut *reader).take(size).read_to_end(&mut chunk)?;
+            match &chunk_header[0..4] {
+                b"bext" => bext = Some(chunk),
+                b"iXML" => ixml = Some(String::from_utf8_lossy(&chunk).into_owned()),
+                _ if chunk.len() >= 16 => data_size = Some(u64::from_le_bytes(chunk[8..16].try_into().expect("Slice of 8 bytes"))),
+                _ => {}
+            }
+            reader.seek(SeekFrom::Current((size & 1) as i64))?;
+        } else {
+            // skip other chunks, which are padded to an even size
+            reader.seek(SeekFrom::Current((size + (size & 1)) as i64))?;
+        }
+    }
+    if bext.is_none() && ixml.is_none() {
+        return Ok(None);
+    }
+
+    let mut info = BroadcastInfo::default();
+    // the bext fields up to the time reference take 346 bytes, with text padded by zeros
+    if let Some(bext) = bext.filter(|bext| bext.len() >= 346) {
+        let text = |range: std::ops::Range<usize>| {
+            let bytes = &bext[range];
+            let end = bytes.iter().position(|&byte| byte == 0).unwrap_or(bytes.len());
+            String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+        };
+        info.description = text(0..256);
+        info.originator = text(256..288);
+        info.origination_date = text(320..330);
+        info.origination_time = text(330..338);
+        info.time_reference = Some(u64::from_le_bytes(bext[338..346].try_into().expect("Slice of 8 bytes")));
+    }
+    if let Some(ixml) = ixml {
+        info.timecode_rate = xml_element(&ixml, "TIMECODE_RATE");
+        info.project = xml_element(&ixml, "PROJECT");
+        info.scene = xml_element(&ixml, "SCENE");
+        info.take = xml_element(&ixml, "TAKE");
+        if info.time_reference.is_none() {
+            let low = xml_element(&ixml, "BWF_TIME_REFERENCE_LOW").and_then(|low| low.parse::<u64>().ok());
+            let high = xml_element(&ixml, "BWF_TIME_REFERENCE_HIGH").and_then(|high| high.parse::<u64>().ok());
+            info.time_reference = low.map(|low| (high.unwrap_or(0) << 32) | low);
+        }
+    }
+    Ok(Some(info))
+}
+
+/// Returns the trimmed text of the first element with the given name, ignoring nesting, or
+/// `None` if it is missing or empty.
+fn xml_element(xml: &str, name: &str) -> Option<String> {
+    let start = xml.find(&format!("<{}>", name))? + name.len() + 2;
+    let end = start + xml[start..].find(&format!("</{}>", name))?;
+    Some(xml[start..end].trim().to_string()).filter(|text| !text.is_empty())
+}
@@ -0,0 +1,105 @@
+use std::io::Read;
+
+use ndarray::Array2;
+
+use crate::riff::{decode_samples_with_order, deinterleave, read_header_chunk, AudioInfo, ByteOrder, SampleFormat};
+
+/// Reads an Apple Core Audio Format (CAF) file from a stream and returns the samples of all
+/// channels, along with the sampling rate.
+///
+/// Like [`crate::read_wav`], the stream is read in order, so the `desc` chunk must precede
+/// the `data` chunk, which the format requires anyway. A `data` chunk with a size of -1,
+/// as written while recording, is read until the end of the stream. Only linear PCM
+/// (`lpcm`) is supported, as integers with 8 to 32 bits or 32 or 64-bit floats in either
+/// byte order.
+///
+/// # Returns
+/// - The samples with shape (channels, samples) and the sampling rate
+///
+/// # Errors
+/// Returns an error if the stream can't be read, is not a CAF file, or uses an unsupported
+/// format.
+pub fn read_caf<R: Read>(mut reader: R) -> anyhow::Result<(Array2<f32>, u32)> {
+    let (sample_format, order, info) = read_caf_header(&mut reader)?;
+
+    let mut bytes = Vec::new();
+    match info.frames {
+        Some(frames) => reader.take(frames * info.channels as u64 * sample_format.bytes() as u64).read_to_end(&mut bytes)?,
+        None => reader.read_to_end(&mut bytes)?,
+    };
+
+    // CAF stores 8-bit samples signed, unlike WAV
+    if sample_format == SampleFormat::Int(8) {
+        bytes.iter_mut().for_each(|b| *b ^= 0x80);
+    }
+    let samples = decode_samples_with_order(&bytes, sample_format, order)?;
+    Ok((deinterleave(samples, info.channels as usize)?, info.fs))
+}
+
+/// Reads the header of a CAF file from a stream up to the start of the samples.
+///
+/// The number of samples is unknown for a `data` chunk with a size of -1, see [`read_caf`].
+///
+/// # Returns
+/// - The sample format, its byte order, and the properties of the file
+///
+/// # Errors
+/// Returns an error if the stream can't be read, is not a CAF file, or uses an unsupported
+/// format.
+pub fn read_caf_header<R: Read>(reader: &mut R) -> anyhow::Result<(SampleFormat, ByteOrder, AudioInfo)> {
+    let mut header = [0u8; 8];
+    reader.read_exact(&mut header)?;
+    if &header[0..4] != b"caff" {
+        anyhow::bail!("Input is not a CAF file");
+    }
+
+    let mut format = None;
+    loop {
+        let mut chunk_header = [0u8; 12];
+        reader.read_exact(&mut chunk_header).map_err(|_| anyhow::anyhow!("CAF stream ended before the data chunk"))?;
+        let size = i64::from_be_bytes(chunk_header[4..12].try_into().expect("Slice of 8 bytes"));
+
+        match &chunk_header[0..4] {
+            b"desc" => {
+                let chunk = read_header_chunk(reader, size.max(0) as u64, "CAF desc")?;
+                format = Some(parse_desc(&chunk)?);
+            }
+            b"data" => {
+                let (sample_format, order, channels, fs) = format.ok_or_else(|| anyhow::anyhow!("CAF data chunk precedes the desc chunk"))?;
+
+                // the audio data follows a 4-byte edit count
+                let mut edit_count = [0u8; 4];
+                reader.read_exact(&mut edit_count)?;
+
+                let frame_bytes = (channels as u64 * sample_format.bytes() as u64).max(1);
+                let frames = (size >= 0).then(|| (size as u64).saturating_sub(4) / frame_bytes);
+                return Ok((sample_format, order, AudioInfo { channels, fs, frames }));
+            }
+            _ => {
+                std::io::copy(&mut (&mut *reader).take(size.max(0) as u64), &mut std::io::sink())?;
+            }
+        }
+    }
+}
+
+/// Parses a `desc` chunk into the sample format, byte order, number of channels, and
+/// sampling rate.
+fn parse_desc(chunk: &[u8]) -> anyhow::Result<(SampleFormat, ByteOrder, u16, u32)> {
+    if chunk.len() < 32 {
+        anyhow::bail!("CAF desc chunk is too short");
+    }
+    let u32_at = |i: usize| u32::from_be_bytes([chunk[i], chunk[i + 1], chunk[i + 2], chunk[i + 3]]);
+    let fs = f64::from_be_bytes(chunk[0..8].try_into().expect("Slice of 8 bytes")).round() as u32;
+    if &chunk[8..12] != b"lpcm" {
+        anyhow::bail!("Unsupported CAF format {}, only linear PCM is supported", String::from_utf8_lossy(&chunk[8..12]));
+    }
+    let flags = u32_at(12);
+    let channels = u32_at(24) as u16;
+    let bits = u32_at(28) as u16;
+
+    // bit 0 of the flags marks floats, bit 1 little-endian samples
+    let sample_format = if flags & 1 != 0 { SampleFormat::Float(bits) } else { SampleFormat::Int(bits) };
+    let order = if flags & 2 != 0 { ByteOrder::Little } else { ByteOrder::Big };
+
+    Ok((sample_format, order, channels, fs))
+}
@@ -0,0 +1,90 @@
+use ndarray::{Array1, Array2, Axis};
+
+use crate::onsets::Onset;
+
+/// Signal of a stereo recording that the novelty function is computed on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum StereoMode {
+    /// Mid channel, i.e. the mono downmix `(L + R) / 2`
+    Mid,
+    /// Side channel, i.e. the difference `(L - R) / 2`, which only contains what differs
+    /// between the channels, such as panned or wide events
+    Side,
+    /// Mid and side channel separately
+    MidSide,
+}
+
+/// Splits a stereo signal into its mid and side channels.
+///
+/// Events panned hard to one side appear in both, while events that cancel in a mono
+/// downmix, e.g. out-of-phase material, only appear in the side channel.
+///
+/// # Arguments
+/// - `channels`: Samples with shape (2, samples), left channel first
+///
+/// # Returns
+/// - A tuple of the mid channel `(L + R) / 2` and the side channel `(L - R) / 2`
+///
+/// # Errors
+/// Returns an error if the signal doesn't have exactly two channels.
+pub fn mid_side(channels: &Array2<f32>) -> anyhow::Result<(Array1<f32>, Array1<f32>)> {
+    if channels.nrows() != 2 {
+        anyhow::bail!("Mid/side processing requires a stereo input, got {} channels", channels.nrows());
+    }
+    let (left, right) = (channels.index_axis(Axis(0), 0), channels.index_axis(Axis(0), 1));
+    Ok(((&left + &right) / 2.0, (&left - &right) / 2.0))
+}
+
+/// An onset merged from the detections of several channels.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FusedOnset {
+    /// Time of the earliest detection in seconds
+    pub time: f32,
+    /// Largest strength of the detections
+    pub strength: f32,
+    /// Zero-based indices of the channels that triggered, in ascending order
+    pub channels: Vec<usize>,
+}
+
+/// Merges onsets detected separately per channel into one list of onsets.
+///
+/// Detections are grouped in temporal order: a group starts at the earliest remaining
+/// detection and takes all later detections within `tolerance` seconds of it. Each group
+/// becomes one onset at the time of its earliest detection, which for multi-microphone
+/// recordings is the microphone closest to the source, so the sound's propagation delay to
+/// the other microphones doesn't blur the onset.
+///
+/// # Arguments
+/// - `per_channel`: Onsets of each channel, in channel order
+/// - `tolerance`: Maximum time in seconds between the first and last detection of a group
+///
+/// # Returns
+/// - The fused onsets in temporal order
+pub fn fuse_onsets(per_channel: &[Vec<Onset>], tolerance: f32) -> Vec<FusedOnset> {
+    let mut detections: Vec<(f32, f32, usize)> = per_channel
+        .iter()
+        .enumerate()
+        .flat_map(|(channel, onsets)| onsets.iter().map(move |onset| (onset.time, onset.strength, channel)))
+        .collect();
+    detections.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut fused: Vec<FusedOnset> = Vec::new();
+    for (time, strength, channel) in detections {
+        match fused.last_mut() {
+            Some(onset) if time - onset.time <= tolerance => {
+                onset.strength = onset.strength.max(strength);
+                if !onset.channels.contains(&channel) {
+                    onset.channels.push(channel);
+                    onset.channels.sort_unstable();
+                }
+            }
+            _ => {
+                fused.push(FusedOnset { time, strength, channels: vec![channel] });
+            }
+        }
+    }
+    fused
+}
@@ -0,0 +1,388 @@
+use hann_rs::get_hann_window;
+use ndarray::{Array1, Axis};
+
+use crate::descriptors::{spectral_flatness, zero_crossing_rate};
+use crate::onsets::Onset;
+use crate::spectral::{magnitude_spectrogram, stft_magnitude};
+
+/// Attack time in seconds up to which an onset is classified as hard.
+pub const HARD_ATTACK_TIME: f32 = 0.02;
+
+/// Brightness from which an onset is classified as hard regardless of its attack time.
+pub const HARD_BRIGHTNESS: f32 = 0.3;
+
+/// Upper edge in Hz of the low band of [`classify_drums`], which holds the body of a kick drum.
+pub const KICK_MAX_FREQUENCY: f32 = 150.0;
+
+/// Lower edge in Hz of the high band of [`classify_drums`], which holds the noise of a hi-hat.
+pub const HAT_MIN_FREQUENCY: f32 = 5000.0;
+
+/// Length in seconds of the RMS windows of the energy envelope of [`onset_attacks`].
+pub const ATTACK_ENVELOPE_WINDOW: f32 = 0.005;
+
+/// Longest attack in seconds after an onset that [`onset_attacks`] searches for a peak.
+pub const MAX_ATTACK_TIME: f32 = 0.2;
+
+/// Low-energy ratio from which a segment may be speech, see [`classify_content`].
+pub const SPEECH_LOW_ENERGY_RATIO: f32 = 0.15;
+
+/// High zero-crossing rate ratio from which a segment may be speech, see
+/// [`classify_content`].
+pub const SPEECH_HIGH_ZCR_RATIO: f32 = 0.05;
+
+/// Mean spectral flatness from which a segment is classified as noise-like other content.
+pub const NOISE_FLATNESS: f32 = 0.3;
+
+/// Character of an onset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum OnsetKind {
+    /// Percussive onset with an abrupt, broadband attack, e.g. a drum hit or a pluck
+    Hard,
+    /// Tonal onset with a gradual or narrowband attack, e.g. a bowed or sung note
+    Soft,
+}
+
+impl std::fmt::Display for OnsetKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OnsetKind::Hard => write!(f, "hard"),
+            OnsetKind::Soft => write!(f, "soft"),
+        }
+    }
+}
+
+/// Local characteristics of the signal at an onset, see [`classify_onsets`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OnsetCharacter {
+    /// Classification of the onset
+    pub kind: OnsetKind,
+    /// Time in seconds the amplitude envelope takes to rise from 10% to 90% of its peak
+    pub attack_time: f32,
+    /// High-frequency content of the spectral increase relative to its flux, from 0 (all
+    /// of the increase at 0 Hz) to 1 (all at the Nyquist frequency)
+    pub brightness: f32,
+}
+
+/// Classifies onsets as hard (percussive) or soft (tonal) from the signal around them.
+///
+/// Two characteristics are measured per onset:
+/// - The attack time, from the amplitude envelope (RMS over 1 ms) between 50 ms before and
+///   100 ms after the onset: the time from the last crossing of 10% of the rise before the
+///   envelope peak to the first crossing of 90% of it.
+/// - The brightness, from the power spectra of a window ending at the onset and a window
+///   starting shortly before it: the high-frequency content (HFC) of the positive spectral
+///   difference divided by its flux and the number of bins, i.e. the normalized centroid
+///   of the increase.
+///
+/// An onset is hard if its attack time is at most [`HARD_ATTACK_TIME`] or its brightness
+/// is at least [`HARD_BRIGHTNESS`], since broadband increases point to noise-like
+/// transients even when the envelope rises slowly, and soft otherwise.
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples the onsets were detected in
+/// - `fs`: Sampling rate of the audio
+/// - `onsets`: Detected onsets
+/// - `window_length`: Length of the spectral windows before and after each onset
+///
+/// # Returns
+/// - The characteristics of each onset, in the order of `onsets`
+pub fn classify_onsets(audio_array: &Array1<f32>, fs: u32, onsets: &[Onset], window_length: usize) -> Vec<OnsetCharacter> {
+    let window = get_hann_window(window_length.max(2)).expect("Failed to get the Hann window");
+    let sample_at = |n: isize| if n < 0 { 0.0 } else { audio_array.get(n as usize).copied().unwrap_or(0.0) };
+
+    onsets
+        .iter()
+        .map(|onset| {
+            let center = (onset.time * fs as f32).round() as isize;
+            let attack_time = attack_time(&sample_at, center, fs);
+
+            let increase = spectral_increase(&sample_at, center, &window);
+            let flux = increase.sum();
+            let hfc: f32 = increase.iter().enumerate().map(|(k, v)| k as f32 * v).sum();
+            let brightness = if flux > 0.0 { hfc / flux / (increase.len() - 1).max(1) as f32 } else { 0.0 };
+
+            let kind = if attack_time <= HARD_ATTACK_TIME || brightness >= HARD_BRIGHTNESS { OnsetKind::Hard } else { OnsetKind::Soft };
+            OnsetCharacter { kind, attack_time, brightness }
+        })
+        .collect()
+}
+
+/// Returns the positive difference between the power spectra of a window starting shortly
+/// before a sample and a window ending at it.
+fn spectral_increase(sample_at: &impl Fn(isize) -> f32, center: isize, window: &[f32]) -> Array1<f32> {
+    let window_length = window.len();
+    // in a local spectrogram with a hop of a quarter window, frame 2 ends at the onset and
+    // frame 5 starts a quarter window before it, so the attack isn't faded out by the window
+    let local = Array1::from_shape_fn(2 * window_length, |j| sample_at(center - window_length as isize + j as isize));
+    let spectrogram = stft_magnitude(&local, window, (window_length / 4).max(1), 6);
+    (&spectrogram.column(5).mapv(|v| v * v) - &spectrogram.column(2).mapv(|v| v * v)).mapv(|v| v.max(0.0))
+}
+
+/// Measures the 10% to 90% rise time of the amplitude envelope around a sample.
+fn attack_time(sample_at: &impl Fn(isize) -> f32, center: isize, fs: u32) -> f32 {
+    let smoothing = (fs as isize / 1000).max(1);
+    let (start, end) = (center - fs as isize / 20, center + fs as isize / 10);
+    let envelope: Vec<f32> = (start..end)
+        .map(|n| ((n - smoothing + 1..=n).map(|m| sample_at(m).powi(2)).sum::<f32>() / smoothing as f32).sqrt())
+        .collect();
+
+    let Some((peak_index, &peak)) = envelope.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)) else {
+        return 0.0;
+    };
+    let base = envelope[..=peak_index].iter().copied().fold(peak, f32::min);
+    if peak <= base {
+        return 0.0;
+    }
+    let (low, high) = (base + 0.1 * (peak - base), base + 0.9 * (peak - base));
+    let low_index = envelope[..=peak_index].iter().rposition(|&v| v <= low).unwrap_or(0);
+    let high_index = envelope[low_index..=peak_index].iter().position(|&v| v >= high).map_or(peak_index, |i| low_index + i);
+    (high_index - low_index) as f32 / fs as f32
+}
+
+/// Attack of an onset, see [`onset_attacks`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Attack {
+    /// Start of the attack in seconds, at the local energy minimum before the peak
+    pub start: f32,
+    /// Time in seconds from the local energy minimum to the energy peak
+    pub duration: f32,
+}
+
+/// Estimates the attack of each onset as the time from the local energy minimum to the energy
+/// peak, e.g. to characterize instruments or to trim samples just before the attack.
+///
+/// The energy envelope is the mean square over [`ATTACK_ENVELOPE_WINDOW`] seconds every
+/// millisecond. The peak is the maximum of the envelope from the onset up to
+/// [`MAX_ATTACK_TIME`] seconds later, and the minimum is searched from 50 ms before the onset
+/// up to the peak. Both searches stop at the neighboring onsets, so fast repetitions don't
+/// share an attack. Unlike the 10% to 90% rise time of [`classify_onsets`], the duration
+/// covers the whole rise, including a slow start.
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples the onsets were detected in
+/// - `fs`: Sampling rate of the audio
+/// - `onsets`: Detected onsets, sorted by time
+///
+/// # Returns
+/// - The attack of each onset, in the order of `onsets`
+pub fn onset_attacks(audio_array: &Array1<f32>, fs: u32, onsets: &[Onset]) -> Vec<Attack> {
+    let hop = (fs as usize / 1000).max(1);
+    let half = ((ATTACK_ENVELOPE_WINDOW * fs as f32 / 2.0).round() as usize).max(1);
+    let mut cumulative = Vec::with_capacity(audio_array.len() + 1);
+    cumulative.push(0.0_f64);
+    for &v in audio_array {
+        cumulative.push(cumulative[cumulative.len() - 1] + (v as f64).powi(2));
+    }
+    let envelope: Vec<f64> = (0..audio_array.len().div_ceil(hop))
+        .map(|k| {
+            let (start, end) = ((k * hop).saturating_sub(half), (k * hop + half).min(audio_array.len()));
+            (cumulative[end] - cumulative[start]) / (end - start).max(1) as f64
+        })
+        .collect();
+    let index = |time: f32| ((time.max(0.0) * fs as f32 / hop as f32).round() as usize).min(envelope.len().saturating_sub(1));
+    let to_seconds = |k: usize| (k * hop) as f32 / fs as f32;
+
+    onsets
+        .iter()
+        .enumerate()
+        .map(|(i, onset)| {
+            if envelope.is_empty() {
+                return Attack { start: onset.time, duration: 0.0 };
+            }
+            let center = index(onset.time);
+            let previous = if i > 0 { index(onsets[i - 1].time) } else { 0 };
+            let next = onsets.get(i + 1).map_or(envelope.len() - 1, |next| index(next.time).max(center));
+            let lower = index(onset.time - 0.05).max(previous).min(center);
+            let upper = index(onset.time + MAX_ATTACK_TIME).min(next);
+
+            // the first maximum and the last minimum before it, so a plateau or silence
+            // before the attack doesn't count towards it
+            let peak = (center..=upper).rev().max_by(|&a, &b| envelope[a].total_cmp(&envelope[b])).unwrap_or(center);
+            let start = (lower..=peak).rev().min_by(|&a, &b| envelope[a].total_cmp(&envelope[b])).unwrap_or(peak);
+            Attack { start: to_seconds(start), duration: to_seconds(peak - start) }
+        })
+        .collect()
+}
+
+/// Drum voice of an onset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum DrumVoice {
+    /// Kick drum, with most of the attack energy in the low band
+    Kick,
+    /// Snare drum, with most of the attack energy in the mid band
+    Snare,
+    /// Hi-hat or cymbal, with most of the attack energy in the high band
+    Hat,
+}
+
+impl std::fmt::Display for DrumVoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DrumVoice::Kick => write!(f, "kick"),
+            DrumVoice::Snare => write!(f, "snare"),
+            DrumVoice::Hat => write!(f, "hat"),
+        }
+    }
+}
+
+/// Band energies of the attack of an onset and its drum voice, see [`classify_drums`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DrumHit {
+    /// Drum voice of the onset
+    pub voice: DrumVoice,
+    /// Fraction of the spectral increase below [`KICK_MAX_FREQUENCY`]
+    pub low: f32,
+    /// Fraction of the spectral increase between [`KICK_MAX_FREQUENCY`] and
+    /// [`HAT_MIN_FREQUENCY`]
+    pub mid: f32,
+    /// Fraction of the spectral increase above [`HAT_MIN_FREQUENCY`]
+    pub high: f32,
+}
+
+/// Classifies onsets as kick drum, snare drum, or hi-hat from the distribution of the energy
+/// of their attack over three bands, a lightweight drum transcription.
+///
+/// The energy of the attack is the positive difference between the power spectra of a window
+/// starting shortly before the onset and a window ending at it, as for the brightness of
+/// [`classify_onsets`], so a ringing kick drum doesn't mask a following hi-hat. It is split
+/// into a low band below [`KICK_MAX_FREQUENCY`], a mid band, and a high band above
+/// [`HAT_MIN_FREQUENCY`], and the onset is assigned the voice of the band with the largest
+/// fraction. Onsets without any increase are counted as snare drum.
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples the onsets were detected in
+/// - `fs`: Sampling rate of the audio
+/// - `onsets`: Detected onsets
+/// - `window_length`: Length of the spectral windows, long enough to resolve the low band
+///
+/// # Returns
+/// - The band energies and voice of each onset, in the order of `onsets`
+pub fn classify_drums(audio_array: &Array1<f32>, fs: u32, onsets: &[Onset], window_length: usize) -> Vec<DrumHit> {
+    let window = get_hann_window(window_length.max(2)).expect("Failed to get the Hann window");
+    let bin_width = fs as f32 / window.len() as f32;
+    let sample_at = |n: isize| if n < 0 { 0.0 } else { audio_array.get(n as usize).copied().unwrap_or(0.0) };
+
+    onsets
+        .iter()
+        .map(|onset| {
+            let center = (onset.time * fs as f32).round() as isize;
+            let increase = spectral_increase(&sample_at, center, &window);
+            let (mut low, mut mid, mut high) = (0.0, 0.0, 0.0);
+            for (k, &v) in increase.iter().enumerate() {
+                match k as f32 * bin_width {
+                    frequency if frequency < KICK_MAX_FREQUENCY => low += v,
+                    frequency if frequency < HAT_MIN_FREQUENCY => mid += v,
+                    _ => high += v,
+                }
+            }
+            let total = low + mid + high;
+            if total <= 0.0 {
+                return DrumHit { voice: DrumVoice::Snare, low: 0.0, mid: 0.0, high: 0.0 };
+            }
+            let (low, mid, high) = (low / total, mid / total, high / total);
+            let voice = if low >= mid && low >= high {
+                DrumVoice::Kick
+            } else if high > mid {
+                DrumVoice::Hat
+            } else {
+                DrumVoice::Snare
+            };
+            DrumHit { voice, low, mid, high }
+        })
+        .collect()
+}
+
+/// Kind of content of a segment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum ContentClass {
+    /// Speech, with syllables separated by short dips in energy
+    Speech,
+    /// Music, with sustained tonal energy
+    Music,
+    /// Noise-like or otherwise unclear content, e.g. applause, traffic, or sound effects
+    Other,
+}
+
+impl std::fmt::Display for ContentClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContentClass::Speech => write!(f, "speech"),
+            ContentClass::Music => write!(f, "music"),
+            ContentClass::Other => write!(f, "other"),
+        }
+    }
+}
+
+/// Features of a segment and its content class, see [`classify_content`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SegmentContent {
+    /// Classification of the segment
+    pub class: ContentClass,
+    /// Fraction of frames with less than half the mean energy of the segment
+    pub low_energy_ratio: f32,
+    /// Fraction of frames with a zero-crossing rate above 1.5 times the mean of the segment
+    pub high_zcr_ratio: f32,
+    /// Mean spectral flatness of the frames with at least 1% of the largest energy
+    pub flatness: f32,
+}
+
+/// Classifies a segment of a recording as speech, music, or other content, e.g. to index
+/// broadcast archives along with the novelty analysis.
+///
+/// This is a lightweight rule-based classifier on three features from the literature on
+/// speech/music discrimination:
+/// - The low-energy ratio, since speech alternates between syllables and short pauses while
+///   music sustains its energy.
+/// - The high zero-crossing rate ratio, since unvoiced consonants stand out from voiced
+///   speech by their many zero crossings.
+/// - The mean spectral flatness, which is high for noise-like sounds.
+///
+/// A segment is other content if its flatness is at least [`NOISE_FLATNESS`], speech if its
+/// low-energy ratio is at least [`SPEECH_LOW_ENERGY_RATIO`] and its high zero-crossing rate
+/// ratio at least [`SPEECH_HIGH_ZCR_RATIO`], music if its low-energy ratio is below
+/// [`SPEECH_LOW_ENERGY_RATIO`], and other content otherwise. The rules work best on segments
+/// of a few seconds, such as those of [`crate::detect_activity`].
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples of the segment
+/// - `window_length`: Size of the analysis window and FFT
+/// - `hop_length`: Step size between successive frames
+pub fn classify_content(audio_array: &Array1<f32>, window_length: usize, hop_length: usize) -> SegmentContent {
+    let spectrogram = magnitude_spectrogram(audio_array, window_length, hop_length);
+    let energy = spectrogram.map_axis(Axis(0), |frame| frame.dot(&frame));
+    let zcr = zero_crossing_rate(audio_array, window_length, hop_length);
+    let flatness = spectral_flatness(&spectrogram);
+
+    let fraction = |values: &Array1<f32>, predicate: &dyn Fn(f32) -> bool| {
+        values.iter().filter(|&&v| predicate(v)).count() as f32 / values.len().max(1) as f32
+    };
+    let mean_energy = energy.mean().unwrap_or(0.0);
+    let low_energy_ratio = fraction(&energy, &|v| v < 0.5 * mean_energy);
+    let mean_zcr = zcr.mean().unwrap_or(0.0);
+    let high_zcr_ratio = fraction(&zcr, &|v| v > 1.5 * mean_zcr);
+    // flatness is meaningless in near-silent frames
+    let max_energy = energy.iter().fold(0.0_f32, |acc, &v| acc.max(v));
+    let loud: Vec<f32> = flatness.iter().zip(&energy).filter(|&(_, &e)| e > 0.0 && e >= 0.01 * max_energy).map(|(&f, _)| f).collect();
+    let flatness = if loud.is_empty() { 1.0 } else { loud.iter().sum::<f32>() / loud.len() as f32 };
+
+    let class = if flatness >= NOISE_FLATNESS {
+        ContentClass::Other
+    } else if low_energy_ratio >= SPEECH_LOW_ENERGY_RATIO && high_zcr_ratio >= SPEECH_HIGH_ZCR_RATIO {
+        ContentClass::Speech
+    } else if low_energy_ratio < SPEECH_LOW_ENERGY_RATIO {
+        ContentClass::Music
+    } else {
+        ContentClass::Other
+    };
+    SegmentContent { class, low_energy_ratio, high_zcr_ratio, flatness }
+}
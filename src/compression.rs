@@ -0,0 +1,29 @@
+/// Compression function applied to local energies before differentiation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Compression {
+    /// Logarithmic compression `log(1 + gamma * x)`
+    #[default]
+    Log,
+    /// Hyperbolic tangent `tanh(gamma * x)`, which saturates for large values
+    Tanh,
+    /// Square root `sqrt(x)`
+    Sqrt,
+    /// No compression
+    None,
+}
+
+impl Compression {
+    /// Applies the compression function to a non-negative value.
+    ///
+    /// A `gamma` of zero disables logarithmic and hyperbolic tangent compression.
+    pub fn apply(&self, value: f32, gamma: f32) -> f32 {
+        match self {
+            Compression::Log if gamma != 0.0 => (1.0 + gamma * value).ln(),
+            Compression::Tanh if gamma != 0.0 => (gamma * value).tanh(),
+            Compression::Sqrt => value.max(0.0).sqrt(),
+            Compression::Log | Compression::Tanh | Compression::None => value,
+        }
+    }
+}
@@ -0,0 +1,590 @@
+use std::path::PathBuf;
+
+use ndarray::Array1;
+
+use crate::bands::{novelty_bands, BandScale};
+use crate::compression::Compression;
+use crate::denoise::spectral_gate;
+use crate::hpss::{hpss, Component, HPSS_KERNEL};
+use crate::mel::{novelty_mel, onset_strength_librosa};
+use crate::multires::{fuse_novelty, Fusion};
+#[cfg(feature = "nn")]
+use crate::nn::novelty_nn;
+#[cfg(feature = "plugins")]
+use crate::plugin::novelty_plugin;
+#[cfg(feature = "scripting")]
+use crate::script::PostScript;
+use crate::novelty::{normalize_max, novelty_energy, novelty_energy_aggregated, novelty_zcr, rms_envelope, Aggregation, NoveltyCurve};
+use crate::postprocess::{attack_release, median_filter, AttackRelease};
+use crate::spectral::{novelty_spectrum, novelty_spectrum_fmp};
+use crate::wavelet::novelty_wavelet;
+use crate::weighting::{apply_weighting, Weighting};
+
+/// The novelty detection method a [`NoveltyConfig`] computes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Method {
+    /// Energy-based novelty, see [`crate::novelty_energy`].
+    Energy,
+    /// RMS envelope, see [`rms_envelope`]. Ignores `compression` and `gamma`.
+    Rms,
+    /// Zero-crossing-rate novelty, see [`novelty_zcr`]. Ignores `compression` and `gamma`.
+    Zcr,
+    /// Mel-band spectral flux, see [`novelty_mel`].
+    Mel,
+    /// Spectral flux over linear frequency bins, see [`novelty_spectrum`].
+    Spectral,
+    /// Bark-band spectral flux, see [`novelty_bands`].
+    Bark,
+    /// ERB-band spectral flux with a gammatone-shaped filterbank, see [`novelty_bands`].
+    Erb,
+    /// Increase of the energies of octave bands of a discrete wavelet transform, see
+    /// [`novelty_wavelet`]. `n_mels` sets the number of decomposition levels.
+    Wavelet,
+    /// Onset activation of a neural network on the mel spectrogram, see `novelty_nn`.
+    /// Requires the `nn` feature and a `model`.
+    Nn,
+    /// Novelty function of a detector loaded from a shared library, see `Plugin`. Requires
+    /// the `plugins` feature and a `plugin`.
+    Plugin,
+}
+
+/// Reference implementations whose output a [`NoveltyConfig`] can reproduce numerically.
+///
+/// A compatibility mode replaces the framing, padding, compression, and aggregation of
+/// the method with those of the reference implementation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Compat {
+    /// `librosa.onset.onset_strength`, see [`onset_strength_librosa`]. Requires [`Method::Mel`].
+    Librosa,
+    /// The FMP notebooks (`libfmp.c6`), see [`novelty_spectrum_fmp`]. Requires [`Method::Energy`]
+    /// or [`Method::Spectral`].
+    Fmp,
+}
+
+/// Parameters of a novelty computation.
+///
+/// Use one of the method constructors to get a [`NoveltyConfigBuilder`]:
+///
+/// ```no_run
+/// use novelty_rust::NoveltyConfig;
+///
+/// let config = NoveltyConfig::energy().window(1024).hop(256).gamma(10.0).build()?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+///
+/// With the `serde` feature, missing fields take their [`Default`] values when deserializing.
+/// A deserialized configuration isn't validated until it is used, or by [`NoveltyConfig::validate`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct NoveltyConfig {
+    /// Novelty detection method
+    pub method: Method,
+    /// Window length in samples
+    pub window_length: u32,
+    /// Hop length in samples
+    pub hop_length: u32,
+    /// Aggregation of the windowed energy for [`Method::Energy`]
+    pub aggregation: Aggregation,
+    /// Compression function applied before differentiation
+    pub compression: Compression,
+    /// Compression parameter for logarithmic and tanh compression (0 disables compression)
+    pub gamma: f32,
+    /// Whether to normalize the novelty function between 0 and 1
+    pub norm: bool,
+    /// Number of bands for [`Method::Mel`], [`Method::Bark`], and [`Method::Erb`], or of
+    /// decomposition levels for [`Method::Wavelet`]
+    pub n_mels: u32,
+    /// Half length of the local average subtracted by [`Method::Spectral`] in frames (0 disables it)
+    pub local_average: u32,
+    /// Reference implementation to match numerically, if any
+    pub compat: Option<Compat>,
+    /// Frequency weighting applied to the signal before the novelty computation
+    pub weighting: Weighting,
+    /// Percentile of the frame magnitudes taken as per-bin noise floor and subtracted before
+    /// the novelty computation, see [`spectral_gate`] (`None` disables it)
+    pub noise_gate: Option<f32>,
+    /// Component of a harmonic-percussive separation the novelty function is computed on,
+    /// see [`hpss`] (`None` uses the whole signal)
+    pub hpss: Option<Component>,
+    /// Window lengths in samples to compute the novelty function at and fuse, see
+    /// [`fuse_novelty`], which is always normalized (empty computes it at `window_length` only)
+    pub resolutions: Vec<u32>,
+    /// Combination of the novelty functions of several `resolutions`
+    pub fusion: Fusion,
+    /// Path to the ONNX model of [`Method::Nn`]
+    pub model: Option<PathBuf>,
+    /// Path to the shared library of [`Method::Plugin`]
+    pub plugin: Option<PathBuf>,
+    /// Length in frames of the median filter applied to the novelty function, see
+    /// [`median_filter`] (0 disables it)
+    pub median_filter: u32,
+    /// Attack/release smoothing applied to the novelty function after the median filter,
+    /// see [`attack_release`] (`None` disables it)
+    pub smoothing: Option<AttackRelease>,
+    /// Rhai script whose `novelty` function transforms the novelty function after smoothing,
+    /// see `PostScript` (`None` disables it, requires the `scripting` feature)
+    pub post_script: Option<PathBuf>,
+}
+
+impl Default for NoveltyConfig {
+    fn default() -> Self {
+        NoveltyConfig {
+            method: Method::Energy,
+            window_length: 1024,
+            hop_length: 256,
+            aggregation: Aggregation::Sum,
+            compression: Compression::Log,
+            gamma: 10.0,
+            norm: true,
+            n_mels: 128,
+            local_average: 10,
+            compat: None,
+            weighting: Weighting::None,
+            noise_gate: None,
+            hpss: None,
+            resolutions: Vec::new(),
+            fusion: Fusion::Mean,
+            model: None,
+            plugin: None,
+            median_filter: 0,
+            smoothing: None,
+            post_script: None,
+        }
+    }
+}
+
+impl NoveltyConfig {
+    /// Starts building a configuration for energy-based novelty with default parameters.
+    pub fn energy() -> NoveltyConfigBuilder {
+        NoveltyConfigBuilder::new(Method::Energy)
+    }
+
+    /// Starts building a configuration for the RMS envelope with default parameters.
+    pub fn rms() -> NoveltyConfigBuilder {
+        NoveltyConfigBuilder::new(Method::Rms)
+    }
+
+    /// Starts building a configuration for zero-crossing-rate novelty with default parameters.
+    pub fn zcr() -> NoveltyConfigBuilder {
+        NoveltyConfigBuilder::new(Method::Zcr)
+    }
+
+    /// Starts building a configuration for mel-band spectral flux with default parameters.
+    pub fn mel() -> NoveltyConfigBuilder {
+        NoveltyConfigBuilder::new(Method::Mel)
+    }
+
+    /// Starts building a configuration for spectral flux with default parameters.
+    pub fn spectral() -> NoveltyConfigBuilder {
+        NoveltyConfigBuilder::new(Method::Spectral)
+    }
+
+    /// Starts building a configuration for Bark-band spectral flux with default parameters
+    /// and 24 bands.
+    pub fn bark() -> NoveltyConfigBuilder {
+        NoveltyConfigBuilder::new(Method::Bark)
+    }
+
+    /// Starts building a configuration for ERB-band spectral flux with default parameters
+    /// and 40 bands.
+    pub fn erb() -> NoveltyConfigBuilder {
+        NoveltyConfigBuilder::new(Method::Erb)
+    }
+
+    /// Starts building a configuration for wavelet novelty with default parameters and 8
+    /// decomposition levels.
+    pub fn wavelet() -> NoveltyConfigBuilder {
+        NoveltyConfigBuilder::new(Method::Wavelet)
+    }
+
+    /// Starts building a configuration for the onset activation of the given ONNX model
+    /// with default parameters.
+    pub fn nn(model: impl Into<PathBuf>) -> NoveltyConfigBuilder {
+        NoveltyConfigBuilder::new(Method::Nn).model(Some(model.into()))
+    }
+
+    /// Starts building a configuration for the detector of the given plugin library with
+    /// default parameters.
+    pub fn plugin(plugin: impl Into<PathBuf>) -> NoveltyConfigBuilder {
+        NoveltyConfigBuilder::new(Method::Plugin).plugin(Some(plugin.into()))
+    }
+
+    /// Starts building a configuration that matches `librosa.onset.onset_strength` with
+    /// librosa's default parameters (`n_fft=2048`, `hop_length=512`, `n_mels=128`, no normalization).
+    pub fn librosa() -> NoveltyConfigBuilder {
+        NoveltyConfigBuilder::new(Method::Mel)
+            .window(2048)
+            .hop(512)
+            .n_mels(128)
+            .norm(false)
+            .compat(Some(Compat::Librosa))
+    }
+
+    /// Starts building a configuration that matches the FMP notebooks with their default
+    /// parameters for the given method: `N=2048`, `H=128`, `gamma=10` for energy-based
+    /// novelty and `N=1024`, `H=256`, `gamma=100`, `M=10` for spectral novelty.
+    pub fn fmp(method: Method) -> NoveltyConfigBuilder {
+        let builder = NoveltyConfigBuilder::new(method).compat(Some(Compat::Fmp));
+        match method {
+            Method::Spectral => builder.window(1024).hop(256).gamma(100.0).local_average(10),
+            _ => builder.window(2048).hop(128).gamma(10.0),
+        }
+    }
+
+    /// Checks that the parameters are valid, which [`NoveltyConfigBuilder::build`] ensures
+    /// but a deserialized configuration may violate.
+    ///
+    /// # Errors
+    /// Returns an error describing the first invalid parameter.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.window_length == 0 {
+            anyhow::bail!("Window length must be greater than zero!");
+        }
+        if self.hop_length == 0 {
+            anyhow::bail!("Hop length must be greater than zero!");
+        }
+        if !self.gamma.is_finite() || self.gamma < 0.0 {
+            anyhow::bail!("Gamma must be a non-negative number!");
+        }
+        if self.n_mels == 0 {
+            anyhow::bail!("Number of mel bands must be greater than zero!");
+        }
+        if self.compat == Some(Compat::Librosa) && self.method != Method::Mel {
+            anyhow::bail!("The librosa compatibility mode requires the mel method!");
+        }
+        if self.compat == Some(Compat::Fmp) && !matches!(self.method, Method::Energy | Method::Spectral) {
+            anyhow::bail!("The FMP compatibility mode requires the energy or spectral method!");
+        }
+        if self.compat.is_some() && self.weighting != Weighting::None {
+            anyhow::bail!("Compatibility modes don't support frequency weighting!");
+        }
+        if let Some(percentile) = self.noise_gate {
+            if !(0.0..=100.0).contains(&percentile) {
+                anyhow::bail!("Noise gate percentile must be between 0 and 100!");
+            }
+            if self.compat.is_some() {
+                anyhow::bail!("Compatibility modes don't support the noise gate!");
+            }
+        }
+        if let Some(smoothing) = self.smoothing {
+            let valid = |time: f32| time.is_finite() && time >= 0.0;
+            if !valid(smoothing.attack) || !valid(smoothing.release) {
+                anyhow::bail!("Attack and release times must be non-negative numbers!");
+            }
+        }
+        if self.method == Method::Nn && self.model.is_none() {
+            anyhow::bail!("The nn method requires a model!");
+        }
+        if self.method == Method::Plugin && self.plugin.is_none() {
+            anyhow::bail!("The plugin method requires a plugin!");
+        }
+        if self.compat.is_some() && self.hpss.is_some() {
+            anyhow::bail!("Compatibility modes don't support harmonic-percussive separation!");
+        }
+        if !self.resolutions.is_empty() {
+            if self.resolutions.contains(&0) {
+                anyhow::bail!("Window lengths of all resolutions must be greater than zero!");
+            }
+            if self.compat.is_some() {
+                anyhow::bail!("Compatibility modes don't support several resolutions!");
+            }
+        }
+        Ok(())
+    }
+
+    /// Computes the configured novelty function for the given audio signal.
+    ///
+    /// # Errors
+    /// Returns an error if the configuration is invalid or the underlying novelty computation fails.
+    pub fn compute(&self, audio_array: Array1<f32>, fs: u32) -> anyhow::Result<NoveltyCurve> {
+        self.validate()?;
+        let mut novelty = self.compute_method(audio_array, fs)?;
+        if self.median_filter <= 1 && self.smoothing.is_none() && self.post_script.is_none() {
+            return Ok(novelty);
+        }
+        if self.median_filter > 1 {
+            novelty.values = median_filter(&novelty.values, self.median_filter as usize);
+        }
+        if let Some(smoothing) = self.smoothing {
+            novelty.values = attack_release(&novelty.values, novelty.fs_feature, smoothing);
+        }
+        #[cfg(feature = "scripting")]
+        if let Some(path) = &self.post_script {
+            novelty.values = PostScript::load(path)?.transform_novelty(&novelty.values, novelty.fs_feature)?;
+        }
+        #[cfg(not(feature = "scripting"))]
+        if self.post_script.is_some() {
+            anyhow::bail!("Post-processing scripts require the scripting feature!");
+        }
+        if self.norm {
+            normalize_max(&mut novelty.values)?;
+        }
+        Ok(novelty)
+    }
+
+    /// Computes the novelty function of the configured method, before post-processing.
+    fn compute_method(&self, audio_array: Array1<f32>, fs: u32) -> anyhow::Result<NoveltyCurve> {
+        if !self.resolutions.is_empty() {
+            // all resolutions share the hop length, so their frames are already aligned
+            let curves = self
+                .resolutions
+                .iter()
+                .map(|&window_length| {
+                    let config = NoveltyConfig { window_length, resolutions: Vec::new(), ..self.clone() };
+                    config.compute_method(audio_array.clone(), fs)
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            return fuse_novelty(&curves, self.fusion);
+        }
+
+        let audio_array = match self.weighting {
+            Weighting::None => audio_array,
+            weighting => apply_weighting(&audio_array, fs, weighting),
+        };
+        let audio_array = match self.noise_gate {
+            Some(percentile) => spectral_gate(&audio_array, self.window_length as usize, percentile),
+            None => audio_array,
+        };
+        let audio_array = match self.hpss {
+            Some(component) => {
+                let (harmonic, percussive) = hpss(&audio_array, self.window_length as usize, HPSS_KERNEL);
+                if component == Component::Percussive { percussive } else { harmonic }
+            }
+            None => audio_array,
+        };
+
+        if let Some(Compat::Librosa) = self.compat {
+            let (mut values, fs_feature) = onset_strength_librosa(&audio_array, fs, self.window_length, self.hop_length, self.n_mels);
+            if self.norm {
+                normalize_max(&mut values)?;
+            }
+            return Ok(NoveltyCurve { values, fs_feature });
+        }
+
+        if let Some(Compat::Fmp) = self.compat {
+            // the energy-based novelty function already matches libfmp, including its edge handling
+            let (values, fs_feature) = match self.method {
+                Method::Spectral => novelty_spectrum_fmp(&audio_array, fs, self.window_length, self.hop_length, self.gamma, self.local_average, self.norm)?,
+                _ => novelty_energy(audio_array, fs, self.window_length, self.hop_length, self.gamma, self.norm)?,
+            };
+            return Ok(NoveltyCurve { values, fs_feature });
+        }
+
+        let (values, fs_feature) = match self.method {
+            Method::Energy => novelty_energy_aggregated(audio_array, fs, self.window_length, self.hop_length, self.aggregation, self.compression, self.gamma, self.norm)?,
+            Method::Rms => rms_envelope(audio_array, fs, self.window_length, self.hop_length, self.norm)?,
+            Method::Zcr => novelty_zcr(audio_array, fs, self.window_length, self.hop_length, self.norm)?,
+            Method::Mel => novelty_mel(&audio_array, fs, self.window_length, self.hop_length, self.n_mels, self.compression, self.gamma, self.norm)?,
+            Method::Spectral => novelty_spectrum(&audio_array, fs, self.window_length, self.hop_length, self.compression, self.gamma, self.local_average, self.norm)?,
+            Method::Bark => novelty_bands(&audio_array, fs, self.window_length, self.hop_length, BandScale::Bark, self.n_mels, self.compression, self.gamma, self.norm)?,
+            Method::Erb => novelty_bands(&audio_array, fs, self.window_length, self.hop_length, BandScale::Erb, self.n_mels, self.compression, self.gamma, self.norm)?,
+            Method::Wavelet => novelty_wavelet(&audio_array, fs, self.window_length, self.hop_length, self.n_mels, self.compression, self.gamma, self.norm)?,
+            #[cfg(feature = "nn")]
+            Method::Nn => {
+                let model = self.model.as_deref().ok_or_else(|| anyhow::anyhow!("The nn method requires a model!"))?;
+                novelty_nn(&audio_array, fs, self.window_length, self.hop_length, self.n_mels, self.compression, self.gamma, model, self.norm)?
+            }
+            #[cfg(not(feature = "nn"))]
+            Method::Nn => anyhow::bail!("The nn method requires the nn feature!"),
+            #[cfg(feature = "plugins")]
+            Method::Plugin => {
+                let plugin = self.plugin.as_deref().ok_or_else(|| anyhow::anyhow!("The plugin method requires a plugin!"))?;
+                novelty_plugin(&audio_array, fs, self.window_length, self.hop_length, plugin, self.norm)?
+            }
+            #[cfg(not(feature = "plugins"))]
+            Method::Plugin => anyhow::bail!("The plugin method requires the plugins feature!"),
+        };
+        Ok(NoveltyCurve { values, fs_feature })
+    }
+}
+
+/// Builder for [`NoveltyConfig`], created by the method constructors such as [`NoveltyConfig::energy`].
+///
+/// The builder can also be created for a [`Method`] chosen at runtime via [`From`].
+#[derive(Clone, Debug)]
+pub struct NoveltyConfigBuilder {
+    config: NoveltyConfig,
+    /// Overlap of successive frames, resolved to a hop length in [`NoveltyConfigBuilder::build`]
+    overlap: Option<f32>,
+}
+
+impl From<Method> for NoveltyConfigBuilder {
+    fn from(method: Method) -> Self {
+        NoveltyConfigBuilder::new(method)
+    }
+}
+
+impl NoveltyConfigBuilder {
+    fn new(method: Method) -> Self {
+        // Bark and ERB scales have far fewer bands than the 128 common for mel spectrograms,
+        // and each wavelet level halves the frequency range
+        let n_mels = match method {
+            Method::Bark => 24,
+            Method::Erb => 40,
+            Method::Wavelet => 8,
+            _ => NoveltyConfig::default().n_mels,
+        };
+        NoveltyConfigBuilder {
+            config: NoveltyConfig { method, n_mels, ..NoveltyConfig::default() },
+            overlap: None,
+        }
+    }
+
+    /// Sets the window length in samples.
+    pub fn window(mut self, window_length: u32) -> Self {
+        self.config.window_length = window_length;
+        self
+    }
+
+    /// Sets the hop length in samples, replacing an overlap set before.
+    pub fn hop(mut self, hop_length: u32) -> Self {
+        self.config.hop_length = hop_length;
+        self.overlap = None;
+        self
+    }
+
+    /// Sets the hop length via the overlap of successive frames as a fraction of the window
+    /// length, e.g. 0.75 for a hop of a quarter window, replacing a hop length set before.
+    ///
+    /// The hop length is computed from the final window length when building, rounded to
+    /// the nearest sample and at least one sample.
+    pub fn overlap(mut self, overlap: f32) -> Self {
+        self.overlap = Some(overlap);
+        self
+    }
+
+    /// Sets the aggregation of the windowed energy.
+    pub fn aggregation(mut self, aggregation: Aggregation) -> Self {
+        self.config.aggregation = aggregation;
+        self
+    }
+
+    /// Sets the compression function.
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.config.compression = compression;
+        self
+    }
+
+    /// Sets the compression parameter.
+    pub fn gamma(mut self, gamma: f32) -> Self {
+        self.config.gamma = gamma;
+        self
+    }
+
+    /// Sets whether the novelty function is normalized between 0 and 1.
+    pub fn norm(mut self, norm: bool) -> Self {
+        self.config.norm = norm;
+        self
+    }
+
+    /// Sets the number of bands of the mel, Bark, or ERB filterbank, or the number of wavelet
+    /// decomposition levels.
+    pub fn n_mels(mut self, n_mels: u32) -> Self {
+        self.config.n_mels = n_mels;
+        self
+    }
+
+    /// Sets the half length of the local average in frames.
+    pub fn local_average(mut self, local_average: u32) -> Self {
+        self.config.local_average = local_average;
+        self
+    }
+
+    /// Sets the frequency weighting applied to the signal.
+    pub fn weighting(mut self, weighting: Weighting) -> Self {
+        self.config.weighting = weighting;
+        self
+    }
+
+    /// Sets the percentile of the frame magnitudes taken as noise floor, or disables the
+    /// noise gate with `None`.
+    pub fn noise_gate(mut self, percentile: Option<f32>) -> Self {
+        self.config.noise_gate = percentile;
+        self
+    }
+
+    /// Sets the component of a harmonic-percussive separation the novelty function is
+    /// computed on, or uses the whole signal with `None`.
+    pub fn hpss(mut self, component: Option<Component>) -> Self {
+        self.config.hpss = component;
+        self
+    }
+
+    /// Sets the window lengths to compute the novelty function at and fuse, e.g.
+    /// `vec![512, 2048, 8192]`, which replace the window length. An empty list disables
+    /// the fusion.
+    pub fn resolutions(mut self, resolutions: Vec<u32>) -> Self {
+        self.config.resolutions = resolutions;
+        self
+    }
+
+    /// Sets how the novelty functions of several resolutions are combined.
+    pub fn fusion(mut self, fusion: Fusion) -> Self {
+        self.config.fusion = fusion;
+        self
+    }
+
+    /// Sets the path to the ONNX model of the nn method.
+    pub fn model(mut self, model: Option<PathBuf>) -> Self {
+        self.config.model = model;
+        self
+    }
+
+    /// Sets the path to the shared library of the plugin method.
+    pub fn plugin(mut self, plugin: Option<PathBuf>) -> Self {
+        self.config.plugin = plugin;
+        self
+    }
+
+    /// Sets the length in frames of the median filter applied to the novelty function, or
+    /// disables it with 0.
+    pub fn median_filter(mut self, length: u32) -> Self {
+        self.config.median_filter = length;
+        self
+    }
+
+    /// Sets the attack/release smoothing of the novelty function, or disables it with `None`.
+    pub fn smoothing(mut self, smoothing: Option<AttackRelease>) -> Self {
+        self.config.smoothing = smoothing;
+        self
+    }
+
+    /// Sets the Rhai script that transforms the novelty function, or disables it with `None`.
+    pub fn post_script(mut self, post_script: Option<PathBuf>) -> Self {
+        self.config.post_script = post_script;
+        self
+    }
+
+    /// Sets the reference implementation to match numerically.
+    pub fn compat(mut self, compat: Option<Compat>) -> Self {
+        self.config.compat = compat;
+        self
+    }
+
+    /// Validates the parameters and returns the finished configuration.
+    ///
+    /// # Errors
+    /// Returns an error if the window, hop length, or number of mel bands is zero, if the
+    /// overlap is not in the range from 0 to 1 (exclusive), if gamma is negative or not
+    /// finite, if the noise gate percentile is not in the range from 0 to 100, or if the
+    /// compatibility mode doesn't support the method, a weighting, the noise gate, a
+    /// harmonic-percussive separation, or several resolutions, if a resolution is zero, if
+    /// the nn method has no model or the plugin method no plugin, or if a smoothing time
+    /// constant is negative or not finite.
+    pub fn build(mut self) -> anyhow::Result<NoveltyConfig> {
+        if let Some(overlap) = self.overlap {
+            if !(0.0..1.0).contains(&overlap) {
+                anyhow::bail!("Overlap must be at least 0 and less than 1!");
+            }
+            let hop_length = (self.config.window_length as f32 * (1.0 - overlap)).round() as u32;
+            self.config.hop_length = hop_length.max(1);
+        }
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
@@ -0,0 +1,106 @@
+use rusqlite::{params, Connection};
+
+use crate::novelty::NoveltyCurve;
+use crate::onsets::Onset;
+
+/// Schema of the results database. Tables are only created if they don't exist yet, so
+/// results of several runs are collected in one database.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS files (
+    id INTEGER PRIMARY KEY,
+    path TEXT NOT NULL,
+    parameters TEXT NOT NULL,
+    fs_feature REAL NOT NULL,
+    duration REAL NOT NULL,
+    onset_count INTEGER NOT NULL,
+    tempo REAL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE TABLE IF NOT EXISTS frames (
+    file_id INTEGER NOT NULL REFERENCES files (id),
+    frame INTEGER NOT NULL,
+    time REAL NOT NULL,
+    novelty REAL NOT NULL,
+    PRIMARY KEY (file_id, frame)
+);
+CREATE TABLE IF NOT EXISTS onsets (
+    file_id INTEGER NOT NULL REFERENCES files (id),
+    frame INTEGER NOT NULL,
+    time REAL NOT NULL,
+    strength REAL NOT NULL
+);
+";
+
+/// A SQLite database collecting novelty functions and onsets of many files, e.g. to query
+/// a whole corpus with SQL.
+///
+/// Each call of [`ResultsDatabase::insert`] adds a row to the `files` table, one row per
+/// frame to the `frames` table, and one row per onset to the `onsets` table, linked by
+/// `file_id`. Files are never replaced, so repeated analyses of the same file with
+/// different parameters can be compared via the `parameters` column.
+///
+/// ```no_run
+/// use novelty_rust::{detect_onsets, NoveltyCurve, ResultsDatabase};
+///
+/// let novelty = NoveltyCurve { values: ndarray::array![0.0, 1.0, 0.0], fs_feature: 100.0 };
+/// let onsets = detect_onsets(&novelty, 0.1);
+/// let mut database = ResultsDatabase::open("results.sqlite")?;
+/// database.insert("example.wav", "default", &novelty, &onsets, None)?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct ResultsDatabase {
+    connection: Connection,
+}
+
+impl ResultsDatabase {
+    /// Opens the database at `path`, creating the file and its tables if needed.
+    ///
+    /// # Errors
+    /// Returns an error if the database can't be opened or the tables can't be created.
+    pub fn open(path: &str) -> anyhow::Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    /// Adds the results of one file in a single transaction.
+    ///
+    /// # Arguments
+    /// - `path`: Path of the analyzed file
+    /// - `parameters`: Parameters the results were computed with, e.g. the configuration as JSON
+    /// - `novelty`: Novelty function of the file
+    /// - `onsets`: Onsets detected in the novelty function
+    /// - `tempo`: Estimated global tempo in BPM
+    ///
+    /// # Returns
+    /// - The `id` of the new row in the `files` table
+    ///
+    /// # Errors
+    /// Returns an error if writing to the database fails, in which case nothing is added.
+    pub fn insert(&mut self, path: &str, parameters: &str, novelty: &NoveltyCurve, onsets: &[Onset], tempo: Option<f32>) -> anyhow::Result<i64> {
+        let duration = novelty.values.len() as f64 / novelty.fs_feature as f64;
+
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "INSERT INTO files (path, parameters, fs_feature, duration, onset_count, tempo) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![path, parameters, novelty.fs_feature as f64, duration, onsets.len() as i64, tempo.map(|tempo| tempo as f64)],
+        )?;
+        let file_id = transaction.last_insert_rowid();
+
+        {
+            let mut statement = transaction.prepare("INSERT INTO frames (file_id, frame, time, novelty) VALUES (?1, ?2, ?3, ?4)")?;
+            for (frame, value) in novelty.values.iter().enumerate() {
+                let time = frame as f64 / novelty.fs_feature as f64;
+                statement.execute(params![file_id, frame as i64, time, *value as f64])?;
+            }
+
+            let mut statement = transaction.prepare("INSERT INTO onsets (file_id, frame, time, strength) VALUES (?1, ?2, ?3, ?4)")?;
+            for onset in onsets {
+                statement.execute(params![file_id, onset.frame as i64, onset.time as f64, onset.strength as f64])?;
+            }
+        }
+
+        transaction.commit()?;
+        Ok(file_id)
+    }
+}
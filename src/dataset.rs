@@ -0,0 +1,87 @@
+use ndarray::{Array1, Array2};
+
+use crate::config::NoveltyConfig;
+use crate::mel::mel_features;
+use crate::novelty::NoveltyCurve;
+use crate::npz::{write_npz, NpyArray, NpyData};
+
+/// Frame-aligned features, novelty, and onset labels of a recording for training onset
+/// models, see [`frame_features`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameFeatures {
+    /// Compressed mel spectrogram with shape (frames, bands), see [`mel_features`]
+    pub mel: Array2<f32>,
+    /// Novelty value per frame
+    pub novelty: Array1<f32>,
+    /// 1 for frames containing an onset and 0 otherwise
+    pub labels: Array1<u8>,
+    /// Sampling rate of the frames (frames per second)
+    pub fs_feature: f32,
+}
+
+impl FrameFeatures {
+    /// Writes the features to a NumPy `.npz` file with the arrays `mel` (frames, bands),
+    /// `novelty` (frames), `labels` (frames, `uint8`), and `times` (frames, in seconds).
+    ///
+    /// # Errors
+    /// Returns an error if writing to the file fails.
+    pub fn write_npz(&self, path: &str) -> anyhow::Result<()> {
+        let mel = self.mel.as_standard_layout();
+        let times: Vec<f32> = (0..self.novelty.len()).map(|m| m as f32 / self.fs_feature).collect();
+        let novelty = self.novelty.to_vec();
+        let labels = self.labels.to_vec();
+        let mel_data = mel.as_slice().expect("Array in standard layout");
+
+        write_npz(
+            path,
+            &[
+                ("mel", NpyArray { shape: mel.shape().to_vec(), data: NpyData::F32(mel_data) }),
+                ("novelty", NpyArray { shape: vec![novelty.len()], data: NpyData::F32(&novelty) }),
+                ("labels", NpyArray { shape: vec![labels.len()], data: NpyData::U8(&labels) }),
+                ("times", NpyArray { shape: vec![times.len()], data: NpyData::F32(&times) }),
+            ],
+        )
+    }
+}
+
+/// Computes the mel spectrogram, novelty function, and onset labels of a signal on the
+/// framing of the given configuration.
+///
+/// The mel spectrogram uses the window and hop length, number of bands, compression, and
+/// gamma of `config`, i.e. it is the input of [`crate::Method::Nn`]. The novelty function
+/// is passed in, so it can also be used to detect the onsets, and must have been computed
+/// with `config`. An onset is assigned to the frame nearest to its time; onsets outside
+/// the signal are ignored.
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples
+/// - `fs`: Sampling rate of the audio
+/// - `config`: Novelty configuration, which also sets the framing of the mel spectrogram
+/// - `novelty`: Novelty function of the signal computed with `config`
+/// - `onset_times`: Onset times in seconds, e.g. annotations or detected onsets
+///
+/// # Errors
+/// Returns an error if the frames of the novelty function don't match the mel spectrogram,
+/// as for the librosa compatibility mode.
+pub fn frame_features(audio_array: &Array1<f32>, fs: u32, config: &NoveltyConfig, novelty: NoveltyCurve, onset_times: &[f32]) -> anyhow::Result<FrameFeatures> {
+    let mel = mel_features(audio_array, fs, config.window_length, config.hop_length, config.n_mels, config.compression, config.gamma);
+    let num_frames = mel.ncols();
+    if novelty.values.len() != num_frames {
+        anyhow::bail!("The novelty function has {} frames, but the mel spectrogram has {}", novelty.values.len(), num_frames);
+    }
+
+    let mut labels = Array1::zeros(num_frames);
+    for &time in onset_times {
+        let frame = (time * novelty.fs_feature).round();
+        if frame >= 0.0 && (frame as usize) < num_frames {
+            labels[frame as usize] = 1;
+        }
+    }
+
+    Ok(FrameFeatures {
+        mel: mel.reversed_axes().as_standard_layout().into_owned(),
+        novelty: novelty.values,
+        labels,
+        fs_feature: novelty.fs_feature,
+    })
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use ndarray::Array1;
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+
+use crate::spectral::periodic_hann;
+
+/// Short-time Fourier transform for spectral processing with resynthesis.
+///
+/// The signal is analyzed with a periodic Hann window of `window_length` samples and a hop
+/// of a quarter window, with frame `m` centered on sample `m * hop_length`. Spectra are
+/// computed on demand, so processing that needs two passes over the signal doesn't have
+/// to keep all complex spectra in memory.
+pub(crate) struct Stft<'a> {
+    audio_array: &'a Array1<f32>,
+    window: Vec<f32>,
+    hop_length: usize,
+    fft: Arc<dyn Fft<f32>>,
+    ifft: Arc<dyn Fft<f32>>,
+    /// Number of frames covering the signal
+    pub(crate) num_frames: usize,
+}
+
+impl<'a> Stft<'a> {
+    /// Sets up the transform of a non-empty signal with a window of at least one sample.
+    pub(crate) fn new(audio_array: &'a Array1<f32>, window_length: usize) -> Self {
+        let hop_length = (window_length / 4).max(1);
+        let mut planner = FftPlanner::<f32>::new();
+        Stft {
+            audio_array,
+            window: periodic_hann(window_length),
+            hop_length,
+            fft: planner.plan_fft_forward(window_length),
+            ifft: planner.plan_fft_inverse(window_length),
+            num_frames: audio_array.len() / hop_length + 1,
+        }
+    }
+
+    /// Number of bins of the non-negative frequencies.
+    pub(crate) fn num_bins(&self) -> usize {
+        self.window.len() / 2 + 1
+    }
+
+    /// Complex spectrum of the given frame over all `window_length` bins.
+    pub(crate) fn spectrum(&self, frame: usize) -> Vec<Complex<f32>> {
+        let half_window = self.window.len() / 2;
+        let sample_at = |n: usize| n.checked_sub(half_window).and_then(|n| self.audio_array.get(n)).copied().unwrap_or(0.0);
+        let start = frame * self.hop_length;
+        let mut buffer: Vec<Complex<f32>> = self.window.iter().enumerate().map(|(j, w)| Complex::new(sample_at(start + j) * w, 0.0)).collect();
+        self.fft.process(&mut buffer);
+        buffer
+    }
+
+    /// Resynthesizes the signal from the spectra of all frames after `modify` changed them,
+    /// by weighted overlap-add with the analysis window as synthesis window.
+    ///
+    /// # Returns
+    /// - The resynthesized signal with the same length as the input
+    pub(crate) fn resynthesize(&self, mut modify: impl FnMut(usize, &mut [Complex<f32>])) -> Array1<f32> {
+        let (length, window_length) = (self.audio_array.len(), self.window.len());
+        let half_window = window_length / 2;
+        let mut output = vec![0.0_f32; length + window_length];
+        let mut window_sum = vec![0.0_f32; length + window_length];
+        for frame in 0..self.num_frames {
+            let mut spectrum = self.spectrum(frame);
+            modify(frame, &mut spectrum);
+            self.ifft.process(&mut spectrum);
+
+            let start = frame * self.hop_length;
+            for (j, (value, w)) in spectrum.iter().zip(&self.window).enumerate() {
+                output[start + j] += value.re / window_length as f32 * w;
+                window_sum[start + j] += w * w;
+            }
+        }
+
+        Array1::from_shape_fn(length, |n| {
+            let sum = window_sum[n + half_window];
+            if sum > 1e-6 { output[n + half_window] / sum } else { 0.0 }
+        })
+    }
+}
+
+/// Reduces stationary background noise by spectral subtraction of a per-bin noise floor.
+///
+/// The signal is analyzed with a short-time Fourier transform with a periodic Hann window
+/// of `window_length` samples and a hop of a quarter window. The noise floor of each
+/// frequency bin is the given `percentile` of its magnitudes over all frames, assuming that
+/// the quietest frames contain only noise. The floor is subtracted from the magnitudes,
+/// which gates bins at or below it entirely, and the signal is resynthesized by weighted
+/// overlap-add with the original phases.
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples
+/// - `window_length`: Size of the analysis window and FFT
+/// - `percentile`: Percentile of the frame magnitudes taken as noise floor, from 0 to 100
+///
+/// # Returns
+/// - The denoised signal with the same length as the input
+pub fn spectral_gate(audio_array: &Array1<f32>, window_length: usize, percentile: f32) -> Array1<f32> {
+    if audio_array.is_empty() || window_length == 0 {
+        return audio_array.clone();
+    }
+    let stft = Stft::new(audio_array, window_length);
+    let num_frames = stft.num_frames;
+
+    // per-bin noise floor as a percentile of the magnitudes over time; only the magnitudes
+    // of the non-negative frequencies are kept, the spectra are recomputed below
+    let mut magnitudes = vec![Vec::with_capacity(num_frames); stft.num_bins()];
+    for frame in 0..num_frames {
+        for (bin, value) in magnitudes.iter_mut().zip(stft.spectrum(frame)) {
+            bin.push(value.norm());
+        }
+    }
+    let rank = ((percentile.clamp(0.0, 100.0) / 100.0) * (num_frames - 1) as f32).round() as usize;
+    let floor_positive: Vec<f32> = magnitudes
+        .into_iter()
+        .map(|mut bin| {
+            bin.sort_by(|a, b| a.total_cmp(b));
+            bin[rank]
+        })
+        .collect();
+    let noise_floor: Vec<f32> = (0..window_length).map(|k| floor_positive[k.min(window_length - k)]).collect();
+
+    // subtract the floor and keep the phase
+    stft.resynthesize(|_, spectrum| {
+        for (value, floor) in spectrum.iter_mut().zip(&noise_floor) {
+            let magnitude = value.norm();
+            *value *= if magnitude > *floor { (magnitude - floor) / magnitude } else { 0.0 };
+        }
+    })
+}
@@ -0,0 +1,146 @@
+use ndarray::{Array1, Array2, Axis};
+
+use crate::loudness::{loudness, MOMENTARY_WINDOW, SHORT_TERM_WINDOW};
+use crate::spectral::{bin_frequencies, magnitude_spectrogram};
+
+/// Fraction of the spectral magnitude below the rolloff frequency.
+pub const ROLLOFF_PERCENT: f32 = 0.85;
+
+/// Framewise descriptors that can be computed alongside the novelty function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Descriptor {
+    /// Spectral centroid in Hz
+    Centroid,
+    /// Spectral rolloff frequency in Hz, see [`ROLLOFF_PERCENT`]
+    Rolloff,
+    /// Spectral flatness between 0 (tonal) and 1 (noise-like)
+    Flatness,
+    /// Zero-crossing rate, the fraction of sign changes per sample
+    Zcr,
+    /// Momentary loudness in LUFS over 400 ms, see [`loudness`]
+    Momentary,
+    /// Short-term loudness in LUFS over 3 s, see [`loudness`]
+    #[cfg_attr(feature = "serde", serde(rename = "short_term"))]
+    ShortTerm,
+}
+
+impl Descriptor {
+    /// Name of the descriptor, used as CSV column header.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Descriptor::Centroid => "centroid",
+            Descriptor::Rolloff => "rolloff",
+            Descriptor::Flatness => "flatness",
+            Descriptor::Zcr => "zcr",
+            Descriptor::Momentary => "momentary",
+            Descriptor::ShortTerm => "short_term",
+        }
+    }
+
+    /// Whether the descriptor is computed from the magnitude spectrogram.
+    pub fn is_spectral(&self) -> bool {
+        matches!(self, Descriptor::Centroid | Descriptor::Rolloff | Descriptor::Flatness)
+    }
+}
+
+/// Computes the spectral centroid of each frame of a magnitude spectrogram.
+///
+/// Silent frames get a centroid of zero.
+pub fn spectral_centroid(spectrogram: &Array2<f32>, frequencies: &Array1<f32>) -> Array1<f32> {
+    spectrogram.map_axis(Axis(0), |frame| {
+        let total = frame.sum();
+        if total > 0.0 {
+            frame.dot(frequencies) / total
+        } else {
+            0.0
+        }
+    })
+}
+
+/// Computes the frequency below which `roll_percent` of the magnitude of each frame lies.
+pub fn spectral_rolloff(spectrogram: &Array2<f32>, frequencies: &Array1<f32>, roll_percent: f32) -> Array1<f32> {
+    spectrogram.map_axis(Axis(0), |frame| {
+        let threshold = roll_percent * frame.sum();
+        let mut cumulative = 0.0;
+        for (value, frequency) in frame.iter().zip(frequencies.iter()) {
+            cumulative += value;
+            if cumulative >= threshold {
+                return *frequency;
+            }
+        }
+        0.0
+    })
+}
+
+/// Computes the spectral flatness of each frame, the ratio of the geometric to the
+/// arithmetic mean of the power spectrum.
+pub fn spectral_flatness(spectrogram: &Array2<f32>) -> Array1<f32> {
+    // lower bound of the power to avoid the logarithm of zero
+    let amin = 1e-10_f32;
+    spectrogram.map_axis(Axis(0), |frame| {
+        let power = frame.mapv(|v| (v * v).max(amin));
+        let geometric_mean = power.mapv(f32::ln).mean().unwrap_or(0.0).exp();
+        let arithmetic_mean = power.mean().unwrap_or(amin);
+        geometric_mean / arithmetic_mean
+    })
+}
+
+/// Computes the zero-crossing rate of each frame.
+///
+/// Frames are laid out as for the energy-based novelty function: frame `m` is centered on
+/// sample `m * hop_length` and spans `window_length` samples. Only sign changes between two
+/// samples inside the signal are counted, with zero treated as positive.
+pub fn zero_crossing_rate(audio_array: &Array1<f32>, window_length: usize, hop_length: usize) -> Array1<f32> {
+    let num_samples = audio_array.len();
+    let num_frames = num_samples.div_ceil(hop_length);
+    let half_window = window_length / 2;
+
+    Array1::from_shape_fn(num_frames, |frame| {
+        let center = frame * hop_length;
+        let start = center.saturating_sub(half_window).max(1);
+        let end = (center + window_length - half_window).min(num_samples);
+        let crossings = (start..end)
+            .filter(|&n| (audio_array[n - 1] >= 0.0) != (audio_array[n] >= 0.0))
+            .count();
+        crossings as f32 / window_length as f32
+    })
+}
+
+/// Computes the requested descriptors with the same framing as the novelty function.
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples
+/// - `fs`: Sampling rate of the audio
+/// - `window_length`: Size of the analysis window and FFT
+/// - `hop_length`: Step size between successive frames
+/// - `descriptors`: Descriptors to compute
+///
+/// # Returns
+/// - One array of framewise values per requested descriptor, in the requested order
+pub fn compute_descriptors(audio_array: &Array1<f32>, fs: u32, window_length: u32, hop_length: u32, descriptors: &[Descriptor]) -> Vec<Array1<f32>> {
+    if descriptors.is_empty() {
+        return Vec::new();
+    }
+
+    // the spectrogram is only needed for spectral descriptors
+    let spectrogram = if descriptors.iter().any(Descriptor::is_spectral) {
+        magnitude_spectrogram(audio_array, window_length as usize, hop_length as usize)
+    } else {
+        Array2::zeros((0, 0))
+    };
+    let frequencies = bin_frequencies(window_length as usize, fs);
+
+    descriptors
+        .iter()
+        .map(|descriptor| match descriptor {
+            Descriptor::Centroid => spectral_centroid(&spectrogram, &frequencies),
+            Descriptor::Rolloff => spectral_rolloff(&spectrogram, &frequencies, ROLLOFF_PERCENT),
+            Descriptor::Flatness => spectral_flatness(&spectrogram),
+            Descriptor::Zcr => zero_crossing_rate(audio_array, window_length as usize, hop_length as usize),
+            Descriptor::Momentary => loudness(audio_array, fs, hop_length as usize, MOMENTARY_WINDOW),
+            Descriptor::ShortTerm => loudness(audio_array, fs, hop_length as usize, SHORT_TERM_WINDOW),
+        })
+        .collect()
+}
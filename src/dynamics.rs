@@ -0,0 +1,143 @@
+use ndarray::Array1;
+
+use crate::loudness::{k_weighting, LOUDNESS_FLOOR, MOMENTARY_WINDOW, SHORT_TERM_WINDOW};
+
+/// Level in dBFS of silence, to which [`Dynamics::peak`] and [`Dynamics::rms`] are clipped.
+pub const LEVEL_FLOOR: f32 = -200.0;
+
+/// Step between the loudness blocks of [`DynamicsMeter`] in seconds, a 75% overlap of the
+/// momentary blocks as in ITU-R BS.1770.
+const BLOCK_STEP: f32 = 0.1;
+
+/// Dynamics profile of a recording or corpus, see [`DynamicsMeter`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dynamics {
+    /// Duration in seconds
+    pub duration: f32,
+    /// Sample peak level in dBFS
+    pub peak: f32,
+    /// RMS level in dBFS, where a full-scale sine has -3 dBFS
+    pub rms: f32,
+    /// Ratio of the peak to the RMS level in dB
+    pub crest_factor: f32,
+    /// Gated integrated loudness in LUFS per ITU-R BS.1770, if any block is above the
+    /// absolute gate
+    pub integrated_loudness: Option<f32>,
+    /// Loudness range in LU per EBU Tech 3342, the spread between the 10th and 95th
+    /// percentile of the gated short-term loudness
+    pub loudness_range: Option<f32>,
+    /// Ratio of the peak level to the integrated loudness in dB (PLR), a measure of how much
+    /// the peaks stand out after loudness normalization
+    pub peak_to_loudness: Option<f32>,
+    /// Number of onsets per minute
+    pub onset_density: f32,
+}
+
+/// Accumulates the dynamics of one or more recordings, so a corpus can be profiled as a
+/// whole without keeping its audio in memory.
+///
+/// Only the sample statistics and the energy of the K-weighted loudness blocks are kept:
+/// 400 ms momentary blocks for the integrated loudness and 3 s short-term blocks for the
+/// loudness range, both every 100 ms. Blocks don't span recordings, and recordings shorter
+/// than a block don't contribute to the loudness measures.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DynamicsMeter {
+    peak: f32,
+    sum_squares: f64,
+    num_samples: usize,
+    duration: f64,
+    onset_count: usize,
+    momentary: Vec<f64>,
+    short_term: Vec<f64>,
+}
+
+impl DynamicsMeter {
+    /// Creates a meter without any recording.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a mono recording and the number of onsets detected in it.
+    pub fn add(&mut self, audio_array: &Array1<f32>, fs: u32, onset_count: usize) {
+        self.peak = audio_array.iter().fold(self.peak, |acc, v| acc.max(v.abs()));
+        self.sum_squares += audio_array.iter().map(|&v| (v as f64).powi(2)).sum::<f64>();
+        self.num_samples += audio_array.len();
+        self.duration += audio_array.len() as f64 / fs as f64;
+        self.onset_count += onset_count;
+
+        let weighted = k_weighting(audio_array, fs);
+        let mut cumulative = Vec::with_capacity(weighted.len() + 1);
+        cumulative.push(0.0_f64);
+        for &x in weighted.iter() {
+            cumulative.push(cumulative[cumulative.len() - 1] + (x as f64).powi(2));
+        }
+        let step = ((BLOCK_STEP * fs as f32).round() as usize).max(1);
+        let blocks = |window: f32| {
+            let length = ((window * fs as f32).round() as usize).max(1);
+            (0..weighted.len().saturating_sub(length) + 1)
+                .step_by(step)
+                .filter(|&start| start + length <= weighted.len())
+                .map(|start| (cumulative[start + length] - cumulative[start]) / length as f64)
+                .collect::<Vec<f64>>()
+        };
+        self.momentary.extend(blocks(MOMENTARY_WINDOW));
+        self.short_term.extend(blocks(SHORT_TERM_WINDOW));
+    }
+
+    /// Returns the dynamics of all recordings added so far.
+    pub fn dynamics(&self) -> Dynamics {
+        let level = |amplitude: f64| if amplitude > 0.0 { (20.0 * amplitude.log10()).max(LEVEL_FLOOR as f64) as f32 } else { LEVEL_FLOOR };
+        let peak = level(self.peak as f64);
+        // a full-scale sine has an RMS of -3 dBFS
+        let rms = level((self.sum_squares / self.num_samples.max(1) as f64).sqrt());
+
+        let integrated_loudness = gate(&self.momentary, 10.0).map(|blocks| block_loudness(blocks.iter().sum::<f64>() / blocks.len() as f64));
+        let loudness_range = gate(&self.short_term, 20.0).map(|blocks| {
+            let mut loudness: Vec<f32> = blocks.iter().map(|&energy| block_loudness(energy)).collect();
+            loudness.sort_by(f32::total_cmp);
+            let percentile = |p: f32| loudness[((p * (loudness.len() - 1) as f32).round() as usize).min(loudness.len() - 1)];
+            percentile(0.95) - percentile(0.10)
+        });
+        let minutes = self.duration as f32 / 60.0;
+
+        Dynamics {
+            duration: self.duration as f32,
+            peak,
+            rms,
+            crest_factor: peak - rms,
+            integrated_loudness,
+            loudness_range,
+            peak_to_loudness: integrated_loudness.map(|loudness| peak - loudness),
+            onset_density: if minutes > 0.0 { self.onset_count as f32 / minutes } else { 0.0 },
+        }
+    }
+}
+
+/// Computes the dynamics of a single mono recording, see [`DynamicsMeter`].
+pub fn dynamics(audio_array: &Array1<f32>, fs: u32, onset_count: usize) -> Dynamics {
+    let mut meter = DynamicsMeter::new();
+    meter.add(audio_array, fs, onset_count);
+    meter.dynamics()
+}
+
+/// Returns the loudness in LUFS of a block with the given mean square of the K-weighted signal.
+fn block_loudness(energy: f64) -> f32 {
+    (-0.691 + 10.0 * energy.max(1e-30).log10()) as f32
+}
+
+/// Applies the two-stage gating of ITU-R BS.1770 to block energies: blocks below the
+/// absolute gate of [`LOUDNESS_FLOOR`] are dropped, and then blocks more than `relative` LU
+/// below the loudness of the mean of the remaining blocks.
+///
+/// # Returns
+/// - The energies of the gated blocks, or `None` if no block remains
+fn gate(blocks: &[f64], relative: f32) -> Option<Vec<f64>> {
+    let above: Vec<f64> = blocks.iter().copied().filter(|&energy| block_loudness(energy) > LOUDNESS_FLOOR).collect();
+    if above.is_empty() {
+        return None;
+    }
+    let threshold = block_loudness(above.iter().sum::<f64>() / above.len() as f64) - relative;
+    let gated: Vec<f64> = above.into_iter().filter(|&energy| block_loudness(energy) > threshold).collect();
+    (!gated.is_empty()).then_some(gated)
+}
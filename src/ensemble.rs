@@ -0,0 +1,101 @@
+use ndarray::Array1;
+
+use crate::config::NoveltyConfig;
+use crate::onsets::detect_onsets;
+
+/// Parameter variations of the runs of [`onset_stability`].
+///
+/// Every combination of a window factor and a threshold factor is one run.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Jitter {
+    /// Factors applied to the window length
+    pub window_factors: Vec<f32>,
+    /// Factors applied to the onset threshold
+    pub threshold_factors: Vec<f32>,
+}
+
+impl Default for Jitter {
+    /// Halves and doubles the window length and scales the threshold by 0.75 and 1.5, which
+    /// together with the unchanged parameters gives nine runs.
+    fn default() -> Self {
+        Jitter {
+            window_factors: vec![0.5, 1.0, 2.0],
+            threshold_factors: vec![0.75, 1.0, 1.5],
+        }
+    }
+}
+
+/// An onset with the fraction of jittered detector runs that found it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StableOnset {
+    /// Time of the onset in seconds
+    pub time: f32,
+    /// Novelty value at the peak
+    pub strength: f32,
+    /// Fraction of runs with an onset within the tolerance, from 0 to 1
+    pub stability: f32,
+}
+
+/// Detects onsets and estimates how stable each of them is under parameter changes.
+///
+/// The onsets are detected with the given configuration and threshold, see
+/// [`detect_onsets`]. The detector is then run again for every combination of a window
+/// factor and a threshold factor of `jitter`, keeping the hop length and thus the frame
+/// rate, and each onset gets the fraction of runs that detected an onset within
+/// `tolerance` seconds of it. Unlike the strength, which only reflects the peak height in
+/// one novelty function, this shows whether an onset depends on a particular choice of
+/// parameters.
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples
+/// - `fs`: Sampling rate of the audio
+/// - `config`: Novelty configuration of the reported onsets
+/// - `threshold`: Onset threshold as a fraction of the maximum novelty
+/// - `jitter`: Parameter variations
+/// - `tolerance`: Maximum time difference in seconds to count an onset of a run as a detection
+///
+/// # Returns
+/// - The onsets of the unchanged parameters with their stability
+///
+/// # Errors
+/// Returns an error if `jitter` has no runs or a novelty computation fails.
+pub fn onset_stability(audio_array: &Array1<f32>, fs: u32, config: &NoveltyConfig, threshold: f32, jitter: &Jitter, tolerance: f32) -> anyhow::Result<Vec<StableOnset>> {
+    let num_runs = jitter.window_factors.len() * jitter.threshold_factors.len();
+    if num_runs == 0 {
+        anyhow::bail!("Jitter must have at least one window and one threshold factor!");
+    }
+    let scale = |length: u32, factor: f32| ((length as f32 * factor).round() as u32).max(1);
+
+    let novelty = config.compute(audio_array.clone(), fs)?;
+    let onsets = detect_onsets(&novelty, threshold);
+
+    let mut detections = vec![0usize; onsets.len()];
+    for &window_factor in &jitter.window_factors {
+        let jittered = NoveltyConfig {
+            window_length: scale(config.window_length, window_factor),
+            resolutions: config.resolutions.iter().map(|&length| scale(length, window_factor)).collect(),
+            ..config.clone()
+        };
+        let novelty = jittered.compute(audio_array.clone(), fs)?;
+        for &threshold_factor in &jitter.threshold_factors {
+            let run = detect_onsets(&novelty, threshold * threshold_factor);
+            for (count, onset) in detections.iter_mut().zip(&onsets) {
+                if run.iter().any(|other| (other.time - onset.time).abs() <= tolerance) {
+                    *count += 1;
+                }
+            }
+        }
+    }
+
+    Ok(onsets
+        .iter()
+        .zip(detections)
+        .map(|(onset, count)| StableOnset {
+            time: onset.time,
+            strength: onset.strength,
+            stability: count as f32 / num_runs as f32,
+        })
+        .collect())
+}
@@ -0,0 +1,270 @@
+use std::collections::{HashMap, HashSet};
+
+use ndarray::Array1;
+
+use crate::novelty::NoveltyCurve;
+use crate::sync::{cross_correlation, zero_mean};
+use crate::tempo::{default_tempogram, estimate_tempo};
+
+/// Frames per second of a fingerprint.
+pub const FINGERPRINT_RATE: f32 = 10.0;
+
+/// Number of MinHash values per fingerprint for [`find_duplicates`].
+const NUM_MINHASHES: usize = 32;
+
+/// Number of MinHash values per band, which must be equal for two fingerprints to be
+/// compared. Two values per band make recordings that share a third of their shingles
+/// likely candidates.
+const ROWS_PER_BAND: usize = 2;
+
+/// Number of consecutive gaps between novelty peaks that form a shingle.
+const SHINGLE_GAPS: usize = 4;
+
+/// Smallest fingerprint value of a novelty peak used for shingles.
+const PEAK_LEVEL: u8 = 32;
+
+/// Compact description of a recording derived from its novelty function, for finding
+/// duplicates and versions across an archive.
+///
+/// The novelty function is reduced to [`FINGERPRINT_RATE`] frames per second by keeping the
+/// maximum of each frame, so onsets survive, normalized, and quantized to one byte per
+/// frame, about 36 kB per hour of audio.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fingerprint {
+    /// Path of the recording
+    pub path: String,
+    /// Global tempo estimate in BPM, if there is one
+    pub tempo: Option<f32>,
+    /// Quantized novelty at [`FINGERPRINT_RATE`] frames per second
+    pub values: Vec<u8>,
+}
+
+impl Fingerprint {
+    /// Computes the fingerprint of a recording from its novelty function.
+    pub fn from_novelty(path: &str, novelty: &NoveltyCurve) -> Fingerprint {
+        let frames_per_value = novelty.fs_feature / FINGERPRINT_RATE;
+        let num_values = (novelty.values.len() as f32 / frames_per_value).ceil() as usize;
+        let pooled: Vec<f32> = (0..num_values)
+            .map(|n| {
+                let start = (n as f32 * frames_per_value) as usize;
+                let end = (((n + 1) as f32 * frames_per_value) as usize).clamp(start + 1, novelty.values.len());
+                novelty.values.iter().skip(start).take(end - start).fold(0.0_f32, |acc, &v| acc.max(v))
+            })
+            .collect();
+        let max_value = pooled.iter().fold(0.0_f32, |acc, &v| acc.max(v));
+        let scale = if max_value > 0.0 { 255.0 / max_value } else { 0.0 };
+
+        Fingerprint {
+            path: path.to_string(),
+            tempo: estimate_tempo(&default_tempogram(novelty)),
+            values: pooled.iter().map(|&v| (v.max(0.0) * scale).round() as u8).collect(),
+        }
+    }
+
+    /// Duration of the fingerprinted recording in seconds.
+    pub fn duration(&self) -> f32 {
+        self.values.len() as f32 / FINGERPRINT_RATE
+    }
+}
+
+/// Where a query matches a fingerprint, see [`match_fingerprint`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FingerprintMatch {
+    /// Start of the query within the fingerprinted recording in seconds, negative if the
+    /// recording lies within a longer query
+    pub offset: f32,
+    /// Normalized correlation at the offset, from -1 to 1
+    pub score: f32,
+}
+
+/// Finds where the shorter of two fingerprints fits best into the longer one.
+///
+/// This is the normalized cross-correlation over all offsets at which the shorter
+/// fingerprint lies completely within the longer one. Each excerpt of the longer fingerprint
+/// is normalized on its own, so scores don't depend on how much longer it is, and a clip
+/// scores as high against the full recording as against a copy of the clip itself.
+///
+/// # Returns
+/// - The best offset and its score, or `None` if a fingerprint is empty or constant
+pub fn match_fingerprint(query: &Fingerprint, candidate: &Fingerprint) -> Option<FingerprintMatch> {
+    let min_overlap = query.values.len().min(candidate.values.len());
+    let (lag, score) = best_alignment(&candidate.values, &query.values, min_overlap)?;
+    Some(FingerprintMatch { offset: lag as f32 / FINGERPRINT_RATE, score })
+}
+
+/// Returns the lag `k` at which `a[n + k]` and `b[n]` overlap by at least `min_overlap`
+/// frames with the highest normalized cross-correlation, and that correlation.
+///
+/// Both excerpts are normalized to zero mean and unit variance over the overlap, using
+/// running sums of the values and their squares for all lags at once.
+fn best_alignment(a: &[u8], b: &[u8], min_overlap: usize) -> Option<(isize, f32)> {
+    if a.is_empty() || b.is_empty() || min_overlap > a.len().min(b.len()) {
+        return None;
+    }
+    // centering doesn't change the result, but keeps the correlation precise in 32 bits
+    let to_array = |values: &[u8]| zero_mean(&Array1::from_iter(values.iter().map(|&v| v as f32)));
+    let (a, b) = (to_array(a), to_array(b));
+    let running_sums = |values: &Array1<f32>| {
+        let mut sums = vec![(0.0_f64, 0.0_f64)];
+        for &v in values {
+            let (sum, squares) = sums[sums.len() - 1];
+            sums.push((sum + v as f64, squares + (v as f64).powi(2)));
+        }
+        sums
+    };
+    let (sums_a, sums_b) = (running_sums(&a), running_sums(&b));
+    let correlation = cross_correlation(&a, &b);
+
+    let (len_a, len_b) = (a.len() as isize, b.len() as isize);
+    let min_overlap = min_overlap.max(1) as isize;
+    ((min_overlap - len_b)..=(len_a - min_overlap))
+        .filter_map(|k| {
+            let (start_a, start_b) = (k.max(0) as usize, (-k).max(0) as usize);
+            let overlap = (len_a - k).min(len_b) as usize - start_b;
+            let window = |sums: &[(f64, f64)], start: usize| {
+                let (sum, squares) = (sums[start + overlap].0 - sums[start].0, sums[start + overlap].1 - sums[start].1);
+                (sum, squares - sum * sum / overlap as f64)
+            };
+            let ((sum_a, variance_a), (sum_b, variance_b)) = (window(&sums_a, start_a), window(&sums_b, start_b));
+            if variance_a <= 1e-6 || variance_b <= 1e-6 {
+                return None;
+            }
+            let product = correlation[k.rem_euclid(correlation.len() as isize) as usize] as f64;
+            let covariance = product - sum_a * sum_b / overlap as f64;
+            Some((k, (covariance / (variance_a * variance_b).sqrt()) as f32))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+/// Ranks fingerprints by how well a query matches them, e.g. to find the recordings a clip
+/// was taken from, see [`match_fingerprint`].
+///
+/// # Returns
+/// - The indices of the `count` best matching fingerprints with their matches, best first
+pub fn search_fingerprints(query: &Fingerprint, index: &[Fingerprint], count: usize) -> Vec<(usize, FingerprintMatch)> {
+    let mut matches: Vec<(usize, FingerprintMatch)> = index
+        .iter()
+        .enumerate()
+        .filter_map(|(i, candidate)| Some((i, match_fingerprint(query, candidate)?)))
+        .collect();
+    matches.sort_by(|a, b| b.1.score.total_cmp(&a.1.score));
+    matches.truncate(count);
+    matches
+}
+
+/// Two recordings that are likely duplicates or overlap, see [`find_duplicates`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Duplicate {
+    /// Index of the first fingerprint
+    pub first: usize,
+    /// Index of the second fingerprint
+    pub second: usize,
+    /// Start of the second recording within the first in seconds, negative if it started
+    /// earlier
+    pub offset: f32,
+    /// Duration of the overlap in seconds
+    pub overlap: f32,
+    /// Normalized correlation over the overlap, from -1 to 1
+    pub score: f32,
+}
+
+/// Finds pairs of fingerprinted recordings that are likely duplicates or overlap, e.g.
+/// copies in an archive or recordings of the same event that were split differently.
+///
+/// Comparing all pairs is quadratic in the number of recordings, so candidates are found
+/// with locality-sensitive hashing first. Each fingerprint is described by shingles, the
+/// gaps between consecutive novelty peaks, whose set is summarized by MinHash values.
+/// Recordings that share many shingles likely agree on some band of these values, and only
+/// recordings sharing a band are compared with the normalized cross-correlation of their
+/// fingerprints. Recordings too short or too quiet for a shingle are never candidates.
+///
+/// # Arguments
+/// - `fingerprints`: Fingerprints of the recordings
+/// - `threshold`: Smallest score of a reported pair, e.g. 0.8
+/// - `min_overlap`: Shortest overlap in seconds to consider, or the whole shorter recording
+///   if it is shorter than this
+/// - `tempo_tolerance`: Largest relative difference of the tempo estimates of a reported
+///   pair, e.g. 0.04, or `None` to ignore tempo; pairs without an estimate are kept
+///
+/// # Returns
+/// - The likely duplicates, best first
+pub fn find_duplicates(
+    fingerprints: &[Fingerprint],
+    threshold: f32,
+    min_overlap: f32,
+    tempo_tolerance: Option<f32>,
+) -> Vec<Duplicate> {
+    let signatures: Vec<Option<[u64; NUM_MINHASHES]>> = fingerprints.iter().map(minhash_signature).collect();
+    let mut buckets: HashMap<(usize, &[u64]), Vec<usize>> = HashMap::new();
+    for (i, signature) in signatures.iter().enumerate() {
+        if let Some(signature) = signature {
+            for (band, rows) in signature.chunks(ROWS_PER_BAND).enumerate() {
+                buckets.entry((band, rows)).or_default().push(i);
+            }
+        }
+    }
+    let candidates: HashSet<(usize, usize)> = buckets
+        .values()
+        .flat_map(|members| {
+            members.iter().enumerate().flat_map(move |(n, &i)| members[n + 1..].iter().map(move |&j| (i, j)))
+        })
+        .collect();
+
+    let min_overlap = (min_overlap * FINGERPRINT_RATE).round() as usize;
+    let mut duplicates: Vec<Duplicate> = candidates
+        .into_iter()
+        .filter(|&(i, j)| match (tempo_tolerance, fingerprints[i].tempo, fingerprints[j].tempo) {
+            (Some(tolerance), Some(first), Some(second)) => (first - second).abs() <= tolerance * first.max(second),
+            _ => true,
+        })
+        .filter_map(|(first, second)| {
+            let (a, b) = (&fingerprints[first].values, &fingerprints[second].values);
+            let (lag, score) = best_alignment(a, b, min_overlap.min(a.len()).min(b.len()))?;
+            let overlap = (a.len() as isize - lag).min(b.len() as isize) - (-lag).max(0);
+            (score >= threshold).then_some(Duplicate {
+                first,
+                second,
+                offset: lag as f32 / FINGERPRINT_RATE,
+                overlap: overlap as f32 / FINGERPRINT_RATE,
+                score,
+            })
+        })
+        .collect();
+    duplicates.sort_by(|a, b| b.score.total_cmp(&a.score).then((a.first, a.second).cmp(&(b.first, b.second))));
+    duplicates
+}
+
+/// Returns the MinHash signature of the shingles of a fingerprint, or `None` without any
+/// shingle.
+fn minhash_signature(fingerprint: &Fingerprint) -> Option<[u64; NUM_MINHASHES]> {
+    let values = &fingerprint.values;
+    let peaks: Vec<usize> = (1..values.len().saturating_sub(1))
+        .filter(|&n| values[n] >= PEAK_LEVEL && values[n] > values[n - 1] && values[n] >= values[n + 1])
+        .collect();
+    let shingles: HashSet<u64> = peaks
+        .windows(SHINGLE_GAPS + 1)
+        .map(|window| window.windows(2).fold(0, |shingle, pair| (shingle << 8) | (pair[1] - pair[0]).min(255) as u64))
+        .collect();
+    if shingles.is_empty() {
+        return None;
+    }
+
+    let mut signature = [u64::MAX; NUM_MINHASHES];
+    for shingle in shingles {
+        for (seed, minimum) in signature.iter_mut().enumerate() {
+            *minimum = (*minimum).min(mix(shingle ^ (seed as u64).wrapping_mul(0x9E3779B97F4A7C15)));
+        }
+    }
+    Some(signature)
+}
+
+/// Scrambles the bits of a value (the SplitMix64 finalizer), as one of a family of hash
+/// functions for MinHash.
+fn mix(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D049BB133111EB);
+    value ^ (value >> 31)
+}
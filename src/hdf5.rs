@@ -0,0 +1,355 @@
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+
+use ndarray::ArrayViewD;
+
+/// Largest number of attributes per group; more would need the dense attribute storage of
+/// HDF5, which [`Hdf5Writer`] doesn't implement.
+pub const MAX_HDF5_ATTRIBUTES: usize = 8;
+
+/// Address that HDF5 uses for storage that isn't allocated.
+const UNDEFINED_ADDRESS: u64 = u64::MAX;
+
+/// Size of the version 2 superblock at the start of the file.
+const SUPERBLOCK_SIZE: u64 = 48;
+
+/// Value of an HDF5 attribute, stored as a scalar.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Hdf5Value {
+    /// Signed 64-bit integer
+    Integer(i64),
+    /// 64-bit float
+    Float(f64),
+    /// Fixed-length UTF-8 string, which h5py reads as `bytes`
+    Text(String),
+}
+
+/// A dataset whose samples were written, to be described in an object header on finishing.
+struct DatasetEntry {
+    name: String,
+    shape: Vec<u64>,
+    address: u64,
+}
+
+/// A group below the root group, to be described in an object header on finishing.
+struct GroupEntry {
+    name: String,
+    attributes: Vec<(String, Hdf5Value)>,
+    datasets: Vec<DatasetEntry>,
+}
+
+/// Writes groups of `float32` datasets with scalar attributes to an HDF5 file, e.g. the
+/// results of a batch with one group per track, which h5py and other HDF5 libraries read.
+///
+/// The samples of each group are written as soon as it is added, so only the layout of the
+/// file is kept in memory. The object headers and the superblock that make the file readable
+/// are written by [`Hdf5Writer::finish`]; a file that isn't finished is not valid HDF5.
+///
+/// Only the subset of the format needed for this layout is written: a version 2 superblock,
+/// version 2 object headers with links and attributes stored in the header, and contiguous
+/// datasets without compression.
+pub struct Hdf5Writer {
+    file: BufWriter<File>,
+    position: u64,
+    attributes: Vec<(String, Hdf5Value)>,
+    groups: Vec<GroupEntry>,
+}
+
+impl Hdf5Writer {
+    /// Creates the file, replacing an existing one.
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be created.
+    pub fn create(path: &str) -> anyhow::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        // the superblock points to the root group, which is written last
+        file.write_all(&[0; SUPERBLOCK_SIZE as usize])?;
+        Ok(Hdf5Writer { file, position: SUPERBLOCK_SIZE, attributes: Vec::new(), groups: Vec::new() })
+    }
+
+    /// Sets an attribute of the root group.
+    ///
+    /// # Errors
+    /// Returns an error if the root group would get more than [`MAX_HDF5_ATTRIBUTES`]
+    /// attributes.
+    pub fn set_attribute(&mut self, name: &str, value: Hdf5Value) -> anyhow::Result<()> {
+        self.attributes.retain(|(existing, _)| existing != name);
+        if self.attributes.len() >= MAX_HDF5_ATTRIBUTES {
+            anyhow::bail!("The root group can't have more than {} attributes", MAX_HDF5_ATTRIBUTES);
+        }
+        self.attributes.push((name.to_string(), value));
+        Ok(())
+    }
+
+    /// Adds a group below the root group and writes the samples of its datasets.
+    ///
+    /// # Errors
+    /// Returns an error if a name is empty, `.`, or contains `/`, if the group or one of its
+    /// datasets already exists, if it has more than [`MAX_HDF5_ATTRIBUTES`] attributes, or if
+    /// writing to the file fails.
+    pub fn add_group(&mut self, name: &str, attributes: &[(&str, Hdf5Value)], datasets: &[(&str, ArrayViewD<'_, f32>)]) -> anyhow::Result<()> {
+        check_name(name)?;
+        if self.groups.iter().any(|group| group.name == name) {
+            anyhow::bail!("Group {} already exists", name);
+        }
+        if attributes.len() > MAX_HDF5_ATTRIBUTES {
+            anyhow::bail!("Group {} can't have more than {} attributes", name, MAX_HDF5_ATTRIBUTES);
+        }
+        for (k, (dataset, _)) in datasets.iter().enumerate() {
+            check_name(dataset)?;
+            if datasets[..k].iter().any(|(other, _)| other == dataset) {
+                anyhow::bail!("Dataset {} appears twice in group {}", dataset, name);
+            }
+        }
+
+        let mut entries = Vec::with_capacity(datasets.len());
+        for (dataset, values) in datasets {
+            let address = if values.is_empty() { UNDEFINED_ADDRESS } else { self.position };
+            // the samples are stored in row-major order, as HDF5 and NumPy expect
+            for v in values.as_standard_layout().iter() {
+                self.file.write_all(&v.to_le_bytes())?;
+            }
+            self.position += 4 * values.len() as u64;
+            entries.push(DatasetEntry {
+                name: dataset.to_string(),
+                shape: values.shape().iter().map(|&n| n as u64).collect(),
+                address,
+            });
+        }
+        self.groups.push(GroupEntry {
+            name: name.to_string(),
+            attributes: attributes.iter().map(|(name, value)| (name.to_string(), value.clone())).collect(),
+            datasets: entries,
+        });
+        Ok(())
+    }
+
+    /// Writes the object headers of all groups and datasets and the superblock.
+    ///
+    /// # Errors
+    /// Returns an error if a name or text attribute is longer than an object header message
+    /// can hold, or if writing to the file fails.
+    pub fn finish(mut self) -> anyhow::Result<()> {
+        let groups = std::mem::take(&mut self.groups);
+        let mut group_links = Vec::with_capacity(groups.len());
+        for group in &groups {
+            let mut messages = group_messages(&group.attributes)?;
+            for dataset in &group.datasets {
+                let address = self.write_object_header(&dataset_messages(dataset))?;
+                messages.push((LINK, 0, link_message(&dataset.name, address)));
+            }
+            group_links.push((group.name.as_str(), self.write_object_header(&messages)?));
+        }
+        let mut messages = group_messages(&self.attributes)?;
+        messages.extend(group_links.into_iter().map(|(name, address)| (LINK, 0, link_message(name, address))));
+        let root = self.write_object_header(&messages)?;
+
+        let mut superblock = b"\x89HDF\r\n\x1a\n".to_vec();
+        // version 2 with 8-byte addresses and lengths
+        superblock.extend_from_slice(&[2, 8, 8, 0]);
+        superblock.extend_from_slice(&0_u64.to_le_bytes());
+        superblock.extend_from_slice(&UNDEFINED_ADDRESS.to_le_bytes());
+        superblock.extend_from_slice(&self.position.to_le_bytes());
+        superblock.extend_from_slice(&root.to_le_bytes());
+        superblock.extend_from_slice(&lookup3(&superblock).to_le_bytes());
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&superblock)?;
+        self.file.flush()?;
+        Ok(())
+    }
+
+    /// Writes a version 2 object header with the given messages (type, flags, data).
+    ///
+    /// # Returns
+    /// - The address of the object header
+    fn write_object_header(&mut self, messages: &[(u8, u8, Vec<u8>)]) -> anyhow::Result<u64> {
+        let mut body = Vec::new();
+        for (kind, flags, data) in messages {
+            let size = u16::try_from(data.len()).map_err(|_| anyhow::anyhow!("HDF5 message of {} bytes is too large", data.len()))?;
+            body.push(*kind);
+            body.extend_from_slice(&size.to_le_bytes());
+            body.push(*flags);
+            body.extend_from_slice(data);
+        }
+        // the flags select a 4-byte size of the first chunk and no timestamps
+        let mut header = b"OHDR".to_vec();
+        header.extend_from_slice(&[2, 0x02]);
+        header.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        header.extend_from_slice(&body);
+        header.extend_from_slice(&lookup3(&header).to_le_bytes());
+
+        let address = self.position;
+        self.file.write_all(&header)?;
+        self.position += header.len() as u64;
+        Ok(address)
+    }
+}
+
+// Types of the object header messages that are written
+const DATASPACE: u8 = 0x01;
+const LINK_INFO: u8 = 0x02;
+const DATATYPE: u8 = 0x03;
+const FILL_VALUE: u8 = 0x05;
+const LINK: u8 = 0x06;
+const DATA_LAYOUT: u8 = 0x08;
+const GROUP_INFO: u8 = 0x0A;
+const ATTRIBUTE: u8 = 0x0C;
+
+/// Flag of object header messages that never change.
+const CONSTANT: u8 = 0x01;
+
+/// Checks that a name can be used for a link.
+fn check_name(name: &str) -> anyhow::Result<()> {
+    if name.is_empty() || name == "." || name.contains('/') {
+        anyhow::bail!("Invalid HDF5 name {:?}", name);
+    }
+    Ok(())
+}
+
+/// Returns the messages of a group with compact link storage and the given attributes, to
+/// which the links are added.
+fn group_messages(attributes: &[(String, Hdf5Value)]) -> anyhow::Result<Vec<(u8, u8, Vec<u8>)>> {
+    // version 0 without creation order, and no fractal heap or B-tree for dense storage
+    let mut link_info = vec![0, 0];
+    link_info.extend_from_slice(&UNDEFINED_ADDRESS.to_le_bytes());
+    link_info.extend_from_slice(&UNDEFINED_ADDRESS.to_le_bytes());
+    let mut messages = vec![(LINK_INFO, 0, link_info), (GROUP_INFO, 0, vec![0, 0])];
+    for (name, value) in attributes {
+        messages.push((ATTRIBUTE, 0, attribute_message(name, value)?));
+    }
+    Ok(messages)
+}
+
+/// Returns the messages of a contiguous `float32` dataset.
+fn dataset_messages(dataset: &DatasetEntry) -> Vec<(u8, u8, Vec<u8>)> {
+    let mut layout = vec![3, 1];
+    layout.extend_from_slice(&dataset.address.to_le_bytes());
+    layout.extend_from_slice(&(4 * dataset.shape.iter().product::<u64>()).to_le_bytes());
+    vec![
+        (DATASPACE, 0, dataspace(&dataset.shape)),
+        (DATATYPE, CONSTANT, float_type(4)),
+        // version 3, allocated early, fill value written only if set, and no fill value
+        (FILL_VALUE, CONSTANT, vec![3, 0x09]),
+        (DATA_LAYOUT, 0, layout),
+    ]
+}
+
+/// Returns a version 2 dataspace message, scalar for an empty shape.
+fn dataspace(shape: &[u64]) -> Vec<u8> {
+    let mut bytes = vec![2, shape.len() as u8, 0, if shape.is_empty() { 0 } else { 1 }];
+    for n in shape {
+        bytes.extend_from_slice(&n.to_le_bytes());
+    }
+    bytes
+}
+
+/// Returns the datatype message of a little-endian IEEE float with 4 or 8 bytes.
+fn float_type(size: u32) -> Vec<u8> {
+    let (sign, exponent_location, exponent_size, mantissa_size, bias) = if size == 4 { (31, 23, 8, 23, 127_u32) } else { (63, 52, 11, 52, 1023) };
+    // class 1 in version 1, with an implied leading mantissa bit
+    let mut bytes = vec![0x11, 0x20, sign, 0];
+    bytes.extend_from_slice(&size.to_le_bytes());
+    bytes.extend_from_slice(&0_u16.to_le_bytes());
+    bytes.extend_from_slice(&(8 * size as u16).to_le_bytes());
+    bytes.extend_from_slice(&[exponent_location, exponent_size, 0, mantissa_size]);
+    bytes.extend_from_slice(&bias.to_le_bytes());
+    bytes
+}
+
+/// Returns a version 3 attribute message with a scalar value.
+fn attribute_message(name: &str, value: &Hdf5Value) -> anyhow::Result<Vec<u8>> {
+    let (datatype, data) = match value {
+        Hdf5Value::Integer(v) => {
+            // class 0 in version 1, signed
+            let mut datatype = vec![0x10, 0x08, 0, 0];
+            datatype.extend_from_slice(&8_u32.to_le_bytes());
+            datatype.extend_from_slice(&0_u16.to_le_bytes());
+            datatype.extend_from_slice(&64_u16.to_le_bytes());
+            (datatype, v.to_le_bytes().to_vec())
+        }
+        Hdf5Value::Float(v) => (float_type(8), v.to_le_bytes().to_vec()),
+        Hdf5Value::Text(text) => {
+            // class 3 in version 1, null-terminated UTF-8
+            let mut datatype = vec![0x13, 0x10, 0, 0];
+            datatype.extend_from_slice(&(text.len() as u32 + 1).to_le_bytes());
+            let mut data = text.as_bytes().to_vec();
+            data.push(0);
+            (datatype, data)
+        }
+    };
+    let dataspace = dataspace(&[]);
+    let name_size = u16::try_from(name.len() + 1).map_err(|_| anyhow::anyhow!("Attribute name {} is too long", name))?;
+
+    let mut bytes = vec![3, 0];
+    bytes.extend_from_slice(&name_size.to_le_bytes());
+    bytes.extend_from_slice(&(datatype.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(&(dataspace.len() as u16).to_le_bytes());
+    // UTF-8 name
+    bytes.push(1);
+    bytes.extend_from_slice(name.as_bytes());
+    bytes.push(0);
+    bytes.extend_from_slice(&datatype);
+    bytes.extend_from_slice(&dataspace);
+    bytes.extend_from_slice(&data);
+    Ok(bytes)
+}
+
+/// Returns a version 1 message of a hard link with a UTF-8 name.
+fn link_message(name: &str, address: u64) -> Vec<u8> {
+    let long = name.len() > u8::MAX as usize;
+    // the flags select the character set field and a 1- or 2-byte name length
+    let mut bytes = vec![1, 0x10 | long as u8, 1];
+    if long {
+        bytes.extend_from_slice(&(name.len() as u16).to_le_bytes());
+    } else {
+        bytes.push(name.len() as u8);
+    }
+    bytes.extend_from_slice(name.as_bytes());
+    bytes.extend_from_slice(&address.to_le_bytes());
+    bytes
+}
+
+/// Jenkins' lookup3 hash with an initial value of 0, the checksum of HDF5 metadata.
+fn lookup3(key: &[u8]) -> u32 {
+    let word = |bytes: &[u8]| bytes.iter().rev().fold(0_u32, |acc, &b| (acc << 8) | b as u32);
+    let init = 0xdeadbeef_u32.wrapping_add(key.len() as u32);
+    let (mut a, mut b, mut c) = (init, init, init);
+
+    let mut rest = key;
+    while rest.len() > 12 {
+        a = a.wrapping_add(word(&rest[0..4]));
+        b = b.wrapping_add(word(&rest[4..8]));
+        c = c.wrapping_add(word(&rest[8..12]));
+        // mix
+        a = a.wrapping_sub(c) ^ c.rotate_left(4);
+        c = c.wrapping_add(b);
+        b = b.wrapping_sub(a) ^ a.rotate_left(6);
+        a = a.wrapping_add(c);
+        c = c.wrapping_sub(b) ^ b.rotate_left(8);
+        b = b.wrapping_add(a);
+        a = a.wrapping_sub(c) ^ c.rotate_left(16);
+        c = c.wrapping_add(b);
+        b = b.wrapping_sub(a) ^ a.rotate_left(19);
+        a = a.wrapping_add(c);
+        c = c.wrapping_sub(b) ^ b.rotate_left(4);
+        b = b.wrapping_add(a);
+        rest = &rest[12..];
+    }
+    if rest.is_empty() {
+        return c;
+    }
+    let mut last = [0; 12];
+    last[..rest.len()].copy_from_slice(rest);
+    a = a.wrapping_add(word(&last[0..4]));
+    b = b.wrapping_add(word(&last[4..8]));
+    c = c.wrapping_add(word(&last[8..12]));
+    // final
+    c = (c ^ b).wrapping_sub(b.rotate_left(14));
+    a = (a ^ c).wrapping_sub(c.rotate_left(11));
+    b = (b ^ a).wrapping_sub(a.rotate_left(25));
+    c = (c ^ b).wrapping_sub(b.rotate_left(16));
+    a = (a ^ c).wrapping_sub(c.rotate_left(4));
+    b = (b ^ a).wrapping_sub(a.rotate_left(14));
+    c = (c ^ b).wrapping_sub(b.rotate_left(24));
+    c
+}
@@ -0,0 +1,76 @@
+use ndarray::{Array1, Array2, Axis};
+
+use crate::denoise::Stft;
+use crate::postprocess::median_filter_view;
+
+/// Component of a harmonic-percussive separation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Component {
+    /// Sustained tones, which are smooth over time
+    Harmonic,
+    /// Transients such as drum hits and plucks, which are smooth over frequency
+    Percussive,
+}
+
+/// Default length in frames and bins of the median filters of [`hpss`].
+pub const HPSS_KERNEL: usize = 17;
+
+/// Separates a signal into its harmonic and percussive components by median filtering of
+/// the spectrogram (Fitzgerald, 2010).
+///
+/// In a magnitude spectrogram, harmonic sounds form horizontal lines and percussive sounds
+/// vertical lines. Median filtering each frequency bin over time enhances the harmonic part,
+/// median filtering each frame over frequency the percussive part. Each bin is assigned to
+/// both components by soft Wiener masks from the two enhanced spectrograms, and the
+/// components are resynthesized with the original phases, so they add up to the input up
+/// to the accuracy of the overlap-add. The short-time Fourier transform is that of
+/// [`crate::spectral_gate`].
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples
+/// - `window_length`: Size of the analysis window and FFT
+/// - `kernel`: Length of the median filters in frames and bins, e.g. [`HPSS_KERNEL`]
+///
+/// # Returns
+/// - A tuple of the harmonic and the percussive component, with the length of the input
+pub fn hpss(audio_array: &Array1<f32>, window_length: usize, kernel: usize) -> (Array1<f32>, Array1<f32>) {
+    if audio_array.is_empty() || window_length == 0 {
+        return (audio_array.clone(), Array1::zeros(audio_array.len()));
+    }
+    let stft = Stft::new(audio_array, window_length);
+
+    let mut magnitudes = Array2::zeros((stft.num_bins(), stft.num_frames));
+    for frame in 0..stft.num_frames {
+        for (bin, value) in stft.spectrum(frame).iter().take(stft.num_bins()).enumerate() {
+            magnitudes[[bin, frame]] = value.norm();
+        }
+    }
+    let harmonic = median_filter_axis(&magnitudes, Axis(1), kernel);
+    let percussive = median_filter_axis(&magnitudes, Axis(0), kernel);
+
+    // Wiener mask of the percussive component for bin k of the given frame
+    let percussive_mask = |frame: usize, k: usize| {
+        let bin = k.min(window_length - k);
+        let (h, p) = (harmonic[[bin, frame]].powi(2), percussive[[bin, frame]].powi(2));
+        if h + p > 1e-12 { p / (h + p) } else { 0.5 }
+    };
+    let harmonic_signal = stft.resynthesize(|frame, spectrum| {
+        spectrum.iter_mut().enumerate().for_each(|(k, value)| *value *= 1.0 - percussive_mask(frame, k));
+    });
+    let percussive_signal = stft.resynthesize(|frame, spectrum| {
+        spectrum.iter_mut().enumerate().for_each(|(k, value)| *value *= percussive_mask(frame, k));
+    });
+    (harmonic_signal, percussive_signal)
+}
+
+/// Applies the centered median filter of [`median_filter`](crate::median_filter) to each
+/// lane along one axis.
+fn median_filter_axis(values: &Array2<f32>, axis: Axis, length: usize) -> Array2<f32> {
+    let mut filtered = Array2::zeros(values.raw_dim());
+    for (lane, mut output) in values.lanes(axis).into_iter().zip(filtered.lanes_mut(axis)) {
+        output.assign(&median_filter_view(lane, length));
+    }
+    filtered
+}
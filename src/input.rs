@@ -0,0 +1,154 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use ndarray::Array;
+
+use crate::novelty::NoveltyCurve;
+
+/// Reads a novelty function from a CSV file with `time` and `novelty` columns.
+///
+/// The first line is treated as a header. Times are expected in seconds or as `hh:mm:ss.mmm`
+/// timestamps, and the feature rate is inferred from the first and last time stamps unless
+/// `fs_feature` is given. Files written with sample or frame indices in a `sample` or `frame`
+/// column are read only with `fs_feature`, since the indices don't tell the feature rate.
+///
+/// # Errors
+/// Returns an error if the file can't be read, a row can't be parsed, or the feature rate
+/// can't be inferred.
+pub fn read_novelty_csv(path: &str, fs_feature: Option<f32>) -> anyhow::Result<NoveltyCurve> {
+    read_novelty_csv_from(BufReader::new(File::open(path)?), path, fs_feature)
+}
+
+/// Reads a novelty function in the CSV format of [`read_novelty_csv`] from any reader, e.g.
+/// standard input to compose the stages of a pipeline with Unix pipes.
+///
+/// `source` names the input in error messages.
+///
+/// # Errors
+/// Returns an error if reading fails, a row can't be parsed, or the feature rate can't be
+/// inferred.
+pub fn read_novelty_csv_from<R: BufRead>(reader: R, source: &str, fs_feature: Option<f32>) -> anyhow::Result<NoveltyCurve> {
+    let mut lines = reader.lines();
+    let header = lines.next().transpose()?.unwrap_or_default();
+    let column = header.split(',').next().unwrap_or_default().trim().to_string();
+    if fs_feature.is_none() && (column == "sample" || column == "frame") {
+        anyhow::bail!("Can't infer the feature rate of {} from {} indices. Please specify it explicitly.", source, column);
+    }
+
+    let mut times = Vec::new();
+    let mut values = Vec::new();
+    for (index, line) in lines.enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let parts: Vec<&str> = line.split(',').collect();
+        if parts.len() < 2 {
+            anyhow::bail!("Expected time and novelty columns in line {} of {}", index + 2, source);
+        }
+        let time = parts[0].trim();
+        times.push(parse_time(time).ok_or_else(|| anyhow::anyhow!("Invalid time {} in line {} of {}", time, index + 2, source))?);
+        values.push(parts[1].trim().parse::<f32>()?);
+    }
+
+    let fs_feature = match fs_feature {
+        Some(fs_feature) => fs_feature,
+        None => {
+            if times.len() < 2 || times[times.len() - 1] <= times[0] {
+                anyhow::bail!("Can't infer the feature rate of {}. Please specify it explicitly.", source);
+            }
+            ((times.len() - 1) as f64 / (times[times.len() - 1] - times[0])) as f32
+        }
+    };
+
+    Ok(NoveltyCurve {
+        values: Array::from_vec(values),
+        fs_feature,
+    })
+}
+
+/// Parses a time in seconds, or a `hh:mm:ss.mmm` timestamp as written with
+/// [`crate::TimeUnit::Timestamp`], which is negative with a leading `-`.
+fn parse_time(field: &str) -> Option<f64> {
+    if !field.contains(':') {
+        return field.parse().ok();
+    }
+    let (sign, field) = match field.strip_prefix('-') {
+        Some(field) => (-1.0, field),
+        None => (1.0, field),
+    };
+    let parts: Vec<&str> = field.split(':').collect();
+    let [hours, minutes, seconds] = parts.as_slice() else {
+        return None;
+    };
+    let (hours, minutes, seconds) = (hours.parse::<u32>().ok()?, minutes.parse::<u32>().ok()?, seconds.parse::<f64>().ok()?);
+    Some(sign * (hours as f64 * 3600.0 + minutes as f64 * 60.0 + seconds))
+}
+
+/// An annotated event, e.g. a hand-labeled onset.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Annotation {
+    /// Time of the event in seconds
+    pub time: f32,
+    /// Label of the event, if the file has one
+    pub label: Option<String>,
+}
+
+/// Reads annotations from an Audacity label file or a CSV or text file with times in seconds
+/// in the first column.
+///
+/// Audacity label files have tab-separated start time, end time, and label columns; the
+/// start time is used, and the frequency ranges that Audacity writes on separate lines
+/// starting with `\` are skipped. In other files, a second column that isn't a number is
+/// used as the label, so `time,label` lists keep their labels while the output of the
+/// `onsets` subcommand is read without labels. A first line that doesn't start with a
+/// number is treated as a header, and empty lines are skipped.
+///
+/// # Errors
+/// Returns an error if the file can't be read or a row can't be parsed.
+pub fn read_annotations(path: &str) -> anyhow::Result<Vec<Annotation>> {
+    let reader = BufReader::new(File::open(path)?);
+
+    let mut annotations = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.starts_with('\\') {
+            continue;
+        }
+        let fields: Vec<&str> = if line.contains('\t') { line.split('\t').collect() } else { line.split([',', ' ']).collect() };
+        let field = fields[0].trim();
+        if field.is_empty() {
+            continue;
+        }
+        let time = match field.parse::<f32>() {
+            Ok(time) => time,
+            Err(_) if index == 0 => continue,
+            Err(_) => anyhow::bail!("Invalid onset time {} in line {} of {}", field, index + 1, path),
+        };
+
+        // Audacity labels follow the end time, other labels the time
+        let label = match fields.as_slice() {
+            [_, end, label @ ..] if line.contains('\t') && end.trim().parse::<f32>().is_ok() => label.join("\t"),
+            [_, label, ..] if label.trim().parse::<f32>().is_err() => label.to_string(),
+            _ => String::new(),
+        };
+        let label = label.trim();
+        annotations.push(Annotation {
+            time,
+            label: (!label.is_empty()).then(|| label.to_string()),
+        });
+    }
+    Ok(annotations)
+}
+
+/// Reads onset times in seconds from the first column of a CSV or text file, e.g. onset
+/// annotations, Audacity labels, or the output of the `onsets` subcommand, see
+/// [`read_annotations`].
+///
+/// # Errors
+/// Returns an error if the file can't be read or a row can't be parsed.
+pub fn read_onset_times(path: &str) -> anyhow::Result<Vec<f32>> {
+    Ok(read_annotations(path)?.into_iter().map(|annotation| annotation.time).collect())
+}
@@ -0,0 +1,118 @@
+use crate::onsets::Onset;
+
+/// Default width of the inter-onset interval histogram bins in seconds.
+pub const DEFAULT_BIN_WIDTH: f32 = 0.01;
+/// Default width of the inter-onset interval clusters in seconds.
+pub const DEFAULT_CLUSTER_WIDTH: f32 = 0.025;
+/// Default longest inter-onset interval in seconds, corresponding to 24 BPM.
+pub const DEFAULT_MAX_INTERVAL: f32 = 2.5;
+
+/// A cluster of similar inter-onset intervals, serving as a tempo hypothesis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IoiCluster {
+    /// Mean interval of the cluster in seconds
+    pub interval: f32,
+    /// Tempo corresponding to the interval in BPM
+    pub bpm: f32,
+    /// Number of intervals in the cluster
+    pub count: usize,
+    /// Score of the cluster including support by related clusters
+    pub score: f32,
+}
+
+/// Returns the intervals between consecutive onsets in seconds.
+pub fn inter_onset_intervals(onsets: &[Onset]) -> Vec<f32> {
+    onsets.windows(2).map(|pair| pair[1].time - pair[0].time).collect()
+}
+
+/// Computes a histogram of the intervals between consecutive onsets.
+///
+/// Bin `k` counts the intervals in `[k * bin_width, (k + 1) * bin_width)`. Intervals of
+/// `max_interval` or more are not counted.
+pub fn ioi_histogram(onsets: &[Onset], bin_width: f32, max_interval: f32) -> Vec<usize> {
+    let num_bins = (max_interval / bin_width).ceil() as usize;
+    let mut counts = vec![0; num_bins];
+    for interval in inter_onset_intervals(onsets) {
+        if interval < max_interval {
+            let bin = ((interval / bin_width) as usize).min(num_bins.saturating_sub(1));
+            counts[bin] += 1;
+        }
+    }
+    counts
+}
+
+/// Clusters inter-onset intervals into tempo hypotheses.
+///
+/// This follows the tempo induction of Dixon (2001): the intervals between all pairs of
+/// onsets up to `max_interval` apart, not only consecutive ones, are grouped into clusters
+/// of width `cluster_width`. Each cluster is scored by its size, and clusters whose
+/// intervals are integer multiples of each other support each other's score. This works
+/// well for sparse percussive material, where the tempogram has little to analyze.
+///
+/// # Returns
+/// - The clusters sorted by descending score
+pub fn cluster_iois(onsets: &[Onset], cluster_width: f32, max_interval: f32) -> Vec<IoiCluster> {
+    // (sum of intervals, count) per cluster
+    let mut clusters: Vec<(f32, usize)> = Vec::new();
+    for (i, first) in onsets.iter().enumerate() {
+        for second in &onsets[i + 1..] {
+            let interval = second.time - first.time;
+            if interval >= max_interval {
+                break;
+            }
+            if interval <= 0.0 {
+                continue;
+            }
+            match clusters.iter_mut().find(|(sum, count)| (sum / *count as f32 - interval).abs() < cluster_width) {
+                Some((sum, count)) => {
+                    *sum += interval;
+                    *count += 1;
+                }
+                None => clusters.push((interval, 1)),
+            }
+        }
+    }
+
+    // merge clusters whose means have drifted together
+    clusters.sort_by(|a, b| (a.0 / a.1 as f32).total_cmp(&(b.0 / b.1 as f32)));
+    let mut merged: Vec<(f32, usize)> = Vec::new();
+    for (sum, count) in clusters {
+        match merged.last_mut() {
+            Some(last) if (last.0 / last.1 as f32 - sum / count as f32).abs() < cluster_width => {
+                last.0 += sum;
+                last.1 += count;
+            }
+            _ => merged.push((sum, count)),
+        }
+    }
+
+    let intervals: Vec<f32> = merged.iter().map(|(sum, count)| sum / *count as f32).collect();
+    let mut result: Vec<IoiCluster> = merged
+        .iter()
+        .zip(&intervals)
+        .map(|(&(_, count), &interval)| {
+            let mut score = 10.0 * count as f32;
+            for (&(_, other_count), &other_interval) in merged.iter().zip(&intervals) {
+                // support by clusters at integer ratios, weighted less for higher ratios
+                for ratio in 2..=8 {
+                    let weight = if ratio <= 4 { 6 - ratio } else { 1 } as f32;
+                    if (interval - ratio as f32 * other_interval).abs() < cluster_width
+                        || (other_interval - ratio as f32 * interval).abs() < cluster_width
+                    {
+                        score += weight * other_count as f32;
+                    }
+                }
+            }
+            IoiCluster {
+                interval,
+                bpm: 60.0 / interval,
+                count,
+                score,
+            }
+        })
+        .collect();
+
+    result.sort_by(|a, b| b.score.total_cmp(&a.score));
+    result
+}
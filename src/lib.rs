@@ -0,0 +1,217 @@
+//! Compute energy-based novelty functions from audio.
+//!
+//! The command-line tool in `main.rs` is a thin wrapper around this library. Programmatic
+//! users typically build a [`NoveltyConfig`] and call [`NoveltyConfig::compute`]:
+//!
+//! ```no_run
+//! use novelty_rust::{audio_path_to_array, NoveltyConfig};
+//!
+//! let (audio_array, fs) = audio_path_to_array("assets/LJ037-0171.wav")?;
+//! let config = NoveltyConfig::energy().window(1024).hop(256).gamma(10.0).build()?;
+//! let novelty = config.compute(audio_array, fs)?;
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! The core computation only depends on `ndarray`-level crates. Everything else is behind
+//! Cargo features: `wav` for WAV, AIFF, and CAF decoding, `aac` for AAC decoding, `serde`
+//! for (de)serializing configuration and result types, `sqlite` for the results database,
+//! `nn` for neural network onset models in ONNX format, `plugins` for novelty detectors
+//! loaded from shared libraries, `scripting` for Rhai post-processing scripts, `pipeline`
+//! for declarative processing pipelines in TOML, `cloud` for `s3://` and `gs://` URLs,
+//! `parquet` for Parquet output, and `cli` (default) for the command-line tool.
+
+#[cfg(feature = "aac")]
+pub mod aac;
+pub mod activity;
+#[cfg(feature = "wav")]
+pub mod aiff;
+pub mod alignment;
+pub mod analysis;
+pub mod artifacts;
+pub mod bands;
+#[cfg(feature = "wav")]
+pub mod audio;
+pub mod beats;
+#[cfg(feature = "wav")]
+pub mod bwf;
+#[cfg(feature = "wav")]
+pub mod caf;
+pub mod channels;
+pub mod classify;
+pub mod compression;
+pub mod config;
+pub mod dataset;
+pub mod denoise;
+#[cfg(feature = "sqlite")]
+pub mod database;
+pub mod descriptors;
+pub mod dynamics;
+pub mod ensemble;
+pub mod fingerprint;
+pub mod hdf5;
+pub mod hpss;
+pub mod input;
+pub mod ioi;
+pub mod loops;
+pub mod loudness;
+pub mod manifest;
+pub mod markers;
+pub mod mel;
+pub mod meter;
+pub mod metrics;
+pub mod midi;
+pub mod multires;
+#[cfg(feature = "nn")]
+pub mod nn;
+pub mod novelty;
+pub mod npz;
+pub mod onsets;
+pub mod output;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+#[cfg(feature = "pipeline")]
+pub mod pipeline;
+pub mod pitch;
+#[cfg(feature = "plugins")]
+pub mod plugin;
+pub mod png;
+pub mod postprocess;
+pub mod preview;
+pub mod provenance;
+pub mod qc;
+#[cfg(feature = "wav")]
+pub mod riff;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod segments;
+pub mod sonify;
+pub mod spectral;
+pub mod stats;
+pub mod storage;
+pub mod streaming;
+pub mod structure;
+pub mod summary;
+pub mod sync;
+pub mod tempo;
+pub mod thumbnail;
+pub mod timecode;
+pub mod vegalite;
+pub mod wavelet;
+pub mod weighting;
+
+#[cfg(feature = "aac")]
+pub use aac::{read_aac, read_aac_info};
+pub use activity::{detect_activity, ActivitySegment, ActivityThresholds};
+#[cfg(feature = "wav")]
+pub use aiff::{read_aiff, read_aiff_header};
+pub use alignment::{align_onsets, snap_annotations, Alignment, DEFAULT_ALIGNMENT_TOLERANCE};
+pub use analysis::{analyze, Analysis};
+pub use artifacts::{
+    detect_artifacts, normalize_excluding, Artifact, ArtifactKind, CLIPPING_LEVEL, MIN_CLIPPING_RUN, MIN_DROPOUT_DURATION,
+};
+pub use bands::{bark_filterbank, erb_filterbank, novelty_bands, BandScale};
+#[cfg(feature = "wav")]
+pub use audio::{
+    audio_path_info, audio_path_to_array, audio_path_to_channels, audio_path_to_channels_lenient, read_audio_channels,
+    write_segments, write_wav,
+};
+pub use beats::{beat_grid_stats, track_beats, Beat, GridStats};
+#[cfg(feature = "wav")]
+pub use bwf::{read_broadcast_info, BroadcastInfo};
+#[cfg(feature = "wav")]
+pub use caf::{read_caf, read_caf_header};
+pub use channels::{fuse_onsets, mid_side, FusedOnset, StereoMode};
+pub use classify::{
+    classify_content, classify_drums, classify_onsets, onset_attacks, ATTACK_ENVELOPE_WINDOW, Attack, ContentClass,
+    DrumHit, DrumVoice, HAT_MIN_FREQUENCY, KICK_MAX_FREQUENCY, MAX_ATTACK_TIME, NOISE_FLATNESS, OnsetCharacter,
+    OnsetKind, SPEECH_HIGH_ZCR_RATIO, SPEECH_LOW_ENERGY_RATIO, SegmentContent,
+};
+pub use compression::Compression;
+pub use config::{Compat, Method, NoveltyConfig, NoveltyConfigBuilder};
+pub use dataset::{frame_features, FrameFeatures};
+pub use denoise::spectral_gate;
+#[cfg(feature = "sqlite")]
+pub use database::ResultsDatabase;
+pub use descriptors::{compute_descriptors, zero_crossing_rate, Descriptor};
+pub use dynamics::{dynamics, Dynamics, DynamicsMeter, LEVEL_FLOOR};
+pub use ensemble::{onset_stability, Jitter, StableOnset};
+pub use fingerprint::{
+    find_duplicates, match_fingerprint, search_fingerprints, Duplicate, Fingerprint, FingerprintMatch, FINGERPRINT_RATE,
+};
+pub use hdf5::{Hdf5Value, Hdf5Writer, MAX_HDF5_ATTRIBUTES};
+pub use hpss::{hpss, Component, HPSS_KERNEL};
+pub use input::{read_annotations, read_novelty_csv, read_novelty_csv_from, read_onset_times, Annotation};
+pub use ioi::{cluster_iois, inter_onset_intervals, ioi_histogram, IoiCluster};
+pub use loops::{find_loop_points, LoopParams, LoopPoint, MAX_LOOP_CANDIDATES};
+pub use loudness::{k_weighting, loudness, LOUDNESS_FLOOR, MOMENTARY_WINDOW, SHORT_TERM_WINDOW};
+pub use manifest::{expand_output_template, find_files, read_manifest, BatchRecord, BatchStatus, ManifestEntry};
+pub use markers::{
+    markers_from_times, regions_from_boundaries, write_ardour_locations, write_edl_markers, write_fcpxml_markers,
+    write_markers, write_plain_markers, write_reaper_csv, Marker, MarkerFormat,
+};
+pub use mel::{mel_features, mel_filterbank, novelty_mel, onset_strength_librosa};
+pub use meter::{beat_strengths, estimate_meter, track_downbeats, MeterCandidate};
+pub use metrics::{Metrics, LATENCY_BUCKETS};
+pub use midi::{
+    onset_velocities, write_midi, MIDI_NOTE_LENGTH, MIDI_TICKS_PER_QUARTER, VELOCITY_LOUDNESS_RANGE,
+    VELOCITY_LOUDNESS_WINDOW, VelocityMapping, VelocitySource,
+};
+pub use multires::{fuse_novelty, resample_novelty, Fusion};
+#[cfg(feature = "nn")]
+pub use nn::novelty_nn;
+pub use novelty::{
+    novelty_energy, novelty_energy_aggregated, novelty_energy_compressed, novelty_zcr, rms_envelope, Aggregation, NoveltyCurve,
+};
+pub use npz::{write_npy, write_npz, NpyArray, NpyData};
+pub use onsets::{detect_onsets, pick_peaks, refine_onsets, Onset, PeakPicking};
+pub use output::{
+    write_activity_csv, write_alignment_csv, write_audacity_labels, write_batch_report_csv, write_beats_csv,
+    write_classified_activity_csv, write_csv, write_csv_to, write_csv_with_columns, write_duplicates_csv,
+    write_dynamics_csv, write_fused_onsets_csv, write_ioi_clusters_csv, write_ioi_histogram_csv, write_lag_csv,
+    write_loops_csv, write_matches_csv, write_meter_csv, write_novelty_json, write_onsets_csv,
+    write_onsets_csv_with_columns, write_qc_csv, write_snapped_annotations_csv, write_stable_onsets_csv,
+    write_summary_csv, write_tempo_candidates_csv, write_tempo_csv, write_tempo_histogram_csv, write_tempogram_csv,
+    write_thumbnail_csv, FrameTiming, OnsetColumns, TimeUnit,
+};
+// `self::` tells the module apart from the parquet crate
+#[cfg(feature = "parquet")]
+pub use self::parquet::write_novelty_parquet;
+#[cfg(feature = "pipeline")]
+pub use pipeline::{
+    DetectorStage, ExportStage, PeakPickStage, Pipeline, PipelineOutput, PreprocessStage, SmoothStage, Stage,
+};
+pub use pitch::{note_name, onset_pitches, yin, OnsetPitch, PitchParams};
+#[cfg(feature = "plugins")]
+pub use plugin::{
+    find_plugins, novelty_plugin, plugin_dirs, resolve_plugin, Plugin, PLUGIN_ABI_VERSION, PLUGIN_PATH_VARIABLE,
+};
+pub use png::{write_curve_png, write_heatmap_png, DEFAULT_PLOT_HEIGHT, MAX_PLOT_WIDTH};
+pub use postprocess::{attack_release, median_filter, AttackRelease};
+pub use preview::{sparkline, sparkline_annotated};
+pub use provenance::{format_timestamp, sha256_file, sha256_hex, Provenance, Sha256};
+pub use qc::{quality_check, QcLimits, QcReport, SILENCE_BLOCK, SILENCE_LEVEL};
+#[cfg(feature = "wav")]
+pub use riff::{
+    decode_samples, decode_samples_with_order, deinterleave, read_wav, read_wav_channels, read_wav_header,
+    read_wav_lenient, AudioInfo, ByteOrder, SampleFormat,
+};
+#[cfg(feature = "scripting")]
+pub use script::PostScript;
+pub use segments::{split_audio, Segment};
+pub use sonify::{sonify_novelty, Carrier};
+pub use spectral::{magnitude_spectrogram, novelty_spectrum, novelty_spectrum_fmp};
+pub use stats::{signal_stats, SignalStats};
+pub use storage::{is_object_url, path_exists, read_path, write_path, StagedInput, StagedOutput};
+pub use streaming::StreamingNovelty;
+pub use structure::{structure_boundaries, structure_novelty};
+pub use summary::{summarize, Summary};
+pub use sync::{novelty_lag, Lag};
+pub use tempo::{
+    cyclic_tempogram, default_tempogram, estimate_tempo, fourier_tempogram, tempo_candidates, tempo_salience,
+    TempoCandidate, Tempogram,
+};
+pub use thumbnail::{thumbnail, Thumbnail, ThumbnailCriterion};
+pub use timecode::Timecode;
+pub use vegalite::{vega_lite_spec, write_vega_lite, VegaLiteData, VEGA_LITE_SCHEMA};
+pub use wavelet::{dwt_step, novelty_wavelet, wavelet_decomposition};
+pub use weighting::{apply_weighting, Weighting};
@@ -0,0 +1,139 @@
+use ndarray::{s, Array1, ArrayView1};
+
+use crate::novelty::NoveltyCurve;
+
+/// Number of parts of each sustained region whose quietest novelty minima are paired as loop
+/// start and end, which bounds the number of waveform comparisons.
+pub const MAX_LOOP_CANDIDATES: usize = 8;
+
+/// Parameters of [`find_loop_points`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LoopParams {
+    /// Novelty below this fraction of the maximum counts as sustained
+    pub sustain_threshold: f32,
+    /// Shortest loop in seconds
+    pub min_length: f32,
+    /// Longest loop in seconds, `None` for no limit
+    pub max_length: Option<f32>,
+    /// Length in seconds of the waveforms compared around the loop start and end
+    pub window: f32,
+    /// Largest shift in seconds by which the loop end is moved to match the waveform at the start
+    pub search: f32,
+    /// Number of loops to return
+    pub count: usize,
+}
+
+impl Default for LoopParams {
+    /// Returns parameters for loops of at least 0.5 s in regions below 20% of the maximum
+    /// novelty, compared over 10 ms with the end moved by up to 5 ms, and the best 5 loops.
+    fn default() -> Self {
+        LoopParams {
+            sustain_threshold: 0.2,
+            min_length: 0.5,
+            max_length: None,
+            window: 0.01,
+            search: 0.005,
+            count: 5,
+        }
+    }
+}
+
+/// A loop proposed by [`find_loop_points`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LoopPoint {
+    /// First sample of the loop
+    pub start: usize,
+    /// Sample after the last sample of the loop, at which playback jumps back to `start`
+    pub end: usize,
+    /// Correlation of the waveforms around start and end, weighted by the ratio of their
+    /// levels, from -1 to 1
+    pub score: f32,
+}
+
+/// Proposes loop points within the sustained regions of a recording, e.g. to loop the sustain
+/// of a sampled instrument note without audible clicks or jumps in timbre.
+///
+/// Sustained regions are runs of frames whose novelty is below `sustain_threshold` times the
+/// maximum of the novelty function. Each region is divided into [`MAX_LOOP_CANDIDATES`] equal
+/// parts, and the quietest local minima of the novelty function in the parts are paired as
+/// loop start and end. For each pair, the
+/// end is moved by up to `search` seconds to the position where the waveform of `window`
+/// seconds around it best matches the waveform around the start, so the jump continues the
+/// waveform. The score is the normalized correlation of the two waveforms, weighted by the
+/// ratio of the smaller to the larger RMS level so jumps across a decay are penalized.
+/// Silence also has little novelty, but its waveforms don't correlate and score near 0.
+///
+/// # Arguments
+/// - `audio_array`: Mono samples of the recording
+/// - `fs`: Sampling rate of the audio
+/// - `novelty`: Novelty function of the recording
+/// - `params`: Parameters of the search
+///
+/// Returns the best `count` loops, best first.
+pub fn find_loop_points(audio_array: &Array1<f32>, fs: u32, novelty: &NoveltyCurve, params: &LoopParams) -> Vec<LoopPoint> {
+    let values = &novelty.values;
+    let threshold = params.sustain_threshold * values.iter().copied().fold(0.0_f32, f32::max);
+    let samples_per_frame = fs as f32 / novelty.fs_feature;
+    let to_samples = |seconds: f32| (seconds * fs as f32).round() as usize;
+    let (half, search) = ((to_samples(params.window) / 2).max(1), to_samples(params.search));
+    let (min_length, max_length) = (to_samples(params.min_length), params.max_length.map_or(usize::MAX, to_samples));
+
+    let mut loops = Vec::new();
+    let mut n = 0;
+    while n < values.len() {
+        if values[n] >= threshold {
+            n += 1;
+            continue;
+        }
+        let first = n;
+        while n < values.len() && values[n] < threshold {
+            n += 1;
+        }
+        let last = n;
+
+        // the quietest local minimum of each part, spread over the region even if it is flat
+        let parts = MAX_LOOP_CANDIDATES.min(last - first);
+        let is_minimum = |k: usize| (k == first || values[k] <= values[k - 1]) && (k + 1 == last || values[k] <= values[k + 1]);
+        let minima: Vec<usize> = (0..parts)
+            .filter_map(|p| {
+                let (lo, hi) = (first + (last - first) * p / parts, first + (last - first) * (p + 1) / parts);
+                (lo..hi).filter(|&k| is_minimum(k)).min_by(|&a, &b| values[a].total_cmp(&values[b]))
+            })
+            .collect();
+
+        for (i, &a) in minima.iter().enumerate() {
+            for &b in &minima[i + 1..] {
+                let start = (a as f32 * samples_per_frame).round() as usize;
+                let end = (b as f32 * samples_per_frame).round() as usize;
+                if start < half || start + half > audio_array.len() {
+                    continue;
+                }
+                let reference = audio_array.slice(s![start - half..start + half]);
+                let best = (end.saturating_sub(search)..=end + search)
+                    .filter(|&end| end >= start + min_length && end - start <= max_length && end + half <= audio_array.len())
+                    .map(|end| (end, similarity(&reference, &audio_array.slice(s![end - half..end + half]))))
+                    .max_by(|a, b| a.1.total_cmp(&b.1));
+                if let Some((end, score)) = best {
+                    loops.push(LoopPoint { start, end, score });
+                }
+            }
+        }
+    }
+
+    loops.sort_by(|a, b| b.score.total_cmp(&a.score));
+    loops.truncate(params.count);
+    loops
+}
+
+/// Returns the normalized correlation of two waveforms, weighted by the ratio of the smaller
+/// to the larger RMS level, or 0 if either is silent.
+fn similarity(a: &ArrayView1<f32>, b: &ArrayView1<f32>) -> f32 {
+    let (energy_a, energy_b) = (a.dot(a), b.dot(b));
+    if energy_a <= 0.0 || energy_b <= 0.0 {
+        return 0.0;
+    }
+    let correlation = a.dot(b) / (energy_a * energy_b).sqrt();
+    correlation * (energy_a.min(energy_b) / energy_a.max(energy_b)).sqrt()
+}
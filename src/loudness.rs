@@ -0,0 +1,105 @@
+use ndarray::Array1;
+
+/// Length of the momentary loudness window in seconds, per ITU-R BS.1770.
+pub const MOMENTARY_WINDOW: f32 = 0.4;
+
+/// Length of the short-term loudness window in seconds, per EBU R 128.
+pub const SHORT_TERM_WINDOW: f32 = 3.0;
+
+/// Loudness of silent frames in LUFS, the absolute gate of ITU-R BS.1770.
+pub const LOUDNESS_FLOOR: f32 = -70.0;
+
+/// Second-order IIR filter in direct form I.
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+}
+
+impl Biquad {
+    fn filter(&self, signal: &mut [f64]) {
+        let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+        for value in signal.iter_mut() {
+            let x0 = *value;
+            let y0 = self.b[0] * x0 + self.b[1] * x1 + self.b[2] * x2 - self.a[0] * y1 - self.a[1] * y2;
+            (x2, x1, y2, y1) = (x1, x0, y1, y0);
+            *value = y0;
+        }
+    }
+}
+
+/// Applies the K-weighting of ITU-R BS.1770 to a signal.
+///
+/// The K-weighting is a high shelf of about +4 dB above 2 kHz, modeling the acoustic effect
+/// of the head, followed by a high-pass filter (RLB weighting). The filter coefficients are
+/// derived from the analog prototypes for any sampling rate and match the coefficients
+/// tabulated in the standard at 48 kHz.
+pub fn k_weighting(audio_array: &Array1<f32>, fs: u32) -> Array1<f32> {
+    let fs = fs as f64;
+
+    let shelf = {
+        let (f0, gain, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+        let k = (std::f64::consts::PI * f0 / fs).tan();
+        let vh = 10.0_f64.powf(gain / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        Biquad {
+            b: [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        }
+    };
+    let highpass = {
+        let (f0, q) = (38.13547087602444, 0.5003270373238773);
+        let k = (std::f64::consts::PI * f0 / fs).tan();
+        let a0 = 1.0 + k / q + k * k;
+        Biquad {
+            b: [1.0, -2.0, 1.0],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        }
+    };
+
+    let mut signal: Vec<f64> = audio_array.iter().map(|&x| x as f64).collect();
+    shelf.filter(&mut signal);
+    highpass.filter(&mut signal);
+    signal.into_iter().map(|x| x as f32).collect()
+}
+
+/// Computes the loudness of a mono signal in LUFS per ITU-R BS.1770 for each frame.
+///
+/// The signal is K-weighted, see [`k_weighting`], and the loudness of a frame is
+/// `-0.691 + 10 log10(z)`, where `z` is the mean square of the weighted signal over a window
+/// of `window` seconds, e.g. [`MOMENTARY_WINDOW`] or [`SHORT_TERM_WINDOW`]. Frames are laid
+/// out as for the energy-based novelty function: frame `m` is centered on sample
+/// `m * hop_length`, with zeros outside the signal. Unlike the standard meters, whose
+/// windows end at the current sample, the centered windows keep the loudness aligned with
+/// the novelty function. Loudness below [`LOUDNESS_FLOOR`] is clipped to it.
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples
+/// - `fs`: Sampling rate of the audio
+/// - `hop_length`: Step size between successive frames
+/// - `window`: Length of the integration window in seconds
+pub fn loudness(audio_array: &Array1<f32>, fs: u32, hop_length: usize, window: f32) -> Array1<f32> {
+    let weighted = k_weighting(audio_array, fs);
+    let num_samples = weighted.len();
+    let window_length = ((window * fs as f32).round() as usize).max(1);
+    let half_window = window_length / 2;
+
+    // cumulative sum of squares, so each frame takes constant time
+    let mut cumulative = Vec::with_capacity(num_samples + 1);
+    cumulative.push(0.0_f64);
+    for &x in weighted.iter() {
+        cumulative.push(cumulative[cumulative.len() - 1] + (x as f64) * (x as f64));
+    }
+
+    Array1::from_shape_fn(num_samples.div_ceil(hop_length), |frame| {
+        let center = frame * hop_length;
+        let start = center.saturating_sub(half_window);
+        let end = (center + window_length - half_window).min(num_samples);
+        let mean_square = (cumulative[end] - cumulative[start]) / window_length as f64;
+        if mean_square > 0.0 {
+            (-0.691 + 10.0 * mean_square.log10()).max(LOUDNESS_FLOOR as f64) as f32
+        } else {
+            LOUDNESS_FLOOR
+        }
+    })
+}
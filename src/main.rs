@@ -1,174 +1,3835 @@
-use std::f32::consts::E;
-use std::io::Write;
-use std::path::Path;
-
-use clap::Parser;
-use hann_rs::get_hann_window;
-use ndarray::{Array, Array1, s, concatenate, Axis};
-use ndarray_conv::{ConvExt, ConvMode, PaddingMode};
-use ndarray_stats::QuantileExt;
-use wavers::{Wav, Samples, read};
-
-/// Reads a mono WAV file from the given path and returns the audio samples as a 1D array,
-/// along with the sampling rate.
+use std::collections::{HashSet, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use ndarray::{s, Array1, Array2, Axis};
+use novelty_rust::{
+    align_onsets, alignment, analyze, audio_path_info, audio_path_to_array, audio_path_to_channels, beat_grid_stats,
+    beats, classify_content, classify_drums, classify_onsets, cluster_iois, compute_descriptors, cyclic_tempogram,
+    decode_samples, default_tempogram, detect_activity, detect_artifacts, detect_onsets, estimate_meter, estimate_tempo,
+    expand_output_template, find_duplicates, find_files, find_loop_points, find_plugins, format_timestamp,
+    frame_features, fuse_onsets, hpss, ioi, ioi_histogram, is_object_url, markers_from_times, mel_features, mid_side,
+    normalize_excluding, novelty_lag, onset_attacks, onset_pitches, onset_stability, onset_velocities, path_exists,
+    pick_peaks, plugin_dirs, quality_check, read_annotations, read_broadcast_info, read_manifest, read_novelty_csv,
+    read_novelty_csv_from, read_onset_times, read_path, read_wav_header, refine_onsets, regions_from_boundaries,
+    resolve_plugin, search_fingerprints, sha256_file, sha256_hex, snap_annotations, sonify_novelty, sparkline_annotated,
+    split_audio, structure, structure_boundaries, summarize, tempo, tempo_candidates, tempo_salience, thumbnail,
+    track_beats, track_downbeats, write_activity_csv, write_alignment_csv, write_audacity_labels,
+    write_batch_report_csv, write_beats_csv, write_classified_activity_csv, write_csv_to, write_csv_with_columns,
+    write_curve_png, write_duplicates_csv, write_dynamics_csv, write_fused_onsets_csv, write_grid_stats_csv,
+    write_heatmap_png, write_ioi_clusters_csv, write_ioi_histogram_csv, write_lag_csv, write_loops_csv, write_markers,
+    write_matches_csv, write_meter_csv, write_midi, write_novelty_json, write_npy, write_onsets_csv,
+    write_onsets_csv_with_columns, write_path, write_qc_csv, write_segments, write_snapped_annotations_csv,
+    write_summary_csv, write_tempo_candidates_csv, write_tempo_histogram_csv, write_tempogram_csv, write_thumbnail_csv,
+    write_vega_lite, write_wav, ActivitySegment, ActivityThresholds, Aggregation, Analysis, Annotation, ArtifactKind,
+    AttackRelease, BatchRecord, BatchStatus, Beat, BroadcastInfo, Carrier, Component, Compression, DEFAULT_PLOT_HEIGHT,
+    Descriptor, DynamicsMeter, Fingerprint, FingerprintMatch, FrameTiming, Fusion, HPSS_KERNEL, Hdf5Value, Hdf5Writer,
+    Jitter, LoopParams, ManifestEntry, Marker, MarkerFormat, Method, Metrics, NoveltyConfig, NoveltyConfigBuilder,
+    NoveltyCurve, NpyArray, NpyData, Onset, OnsetColumns, PLUGIN_PATH_VARIABLE, PeakPicking, Pipeline, PitchParams,
+    Plugin, PostScript, Provenance, QcLimits, QcReport, ResultsDatabase, Segment, SegmentContent, StagedInput,
+    StagedOutput, StereoMode, StreamingNovelty, Summary, Tempogram, ThumbnailCriterion, TimeUnit, Timecode,
+    VegaLiteData, VelocityMapping, VelocitySource, Weighting,
+};
+#[cfg(feature = "parquet")]
+use novelty_rust::write_novelty_parquet;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Struct to represent and parse command-line arguments.
+///
+/// Without a subcommand, the novelty function of `path_in` is written to `path_out` as CSV.
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None, args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Path to the input mono audio file (WAV), or - to read a WAV stream from standard input
+    #[arg(required = true)]
+    path_in: Option<String>,
+
+    /// Path to the output CSV file, or - to write to standard output
+    #[arg(required = true)]
+    path_out: Option<String>,
+
+    #[command(flatten)]
+    novelty: NoveltyArgs,
+
+    #[command(flatten)]
+    sonify: SonifyArgs,
+
+    #[command(flatten)]
+    summary: SummaryArgs,
+
+    #[command(flatten)]
+    csv: CsvArgs,
+
+    /// Print a sparkline of the novelty function with onset markers to the terminal
+    #[arg(long)]
+    preview: bool,
+
+    /// Width of the preview in characters (default: 80)
+    #[arg(long, default_value_t = 80)]
+    preview_width: usize,
+
+    /// Mark the events of this annotation file, an Audacity label file or a list of times in seconds, with | in a third line of the preview
+    #[arg(long, requires = "preview")]
+    annotations: Option<String>,
+
+    /// Format of the messages on standard error: text, or json for one machine-readable event per line, e.g. for log ingestion (default: text)
+    #[arg(long, value_enum, global = true, default_value_t = LogFormatArg::Text)]
+    log_format: LogFormatArg,
+}
+
+/// Formats of the messages on standard error.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum LogFormatArg {
+    Text,
+    Json,
+}
+
+/// Severity of a log event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Level {
+    Info,
+    Warning,
+    Error,
+}
+
+/// Log format selected on the command line, set once in `main`.
+static LOG_FORMAT: OnceLock<LogFormatArg> = OnceLock::new();
+
+/// Logs an event to standard error.
+///
+/// In text mode, only the message is printed, so events without a message are silent. In
+/// JSON mode, every event is printed as one JSON object per line with the timestamp, level,
+/// event name, message, and the given fields, e.g. the path or timings.
+fn log_event(level: Level, event: &str, message: Option<String>, fields: serde_json::Value) {
+    if LOG_FORMAT.get().copied().unwrap_or(LogFormatArg::Text) == LogFormatArg::Text {
+        if let Some(message) = message {
+            eprintln!("{}", message);
+        }
+        return;
+    }
+    let mut object = serde_json::Map::new();
+    object.insert("timestamp".to_string(), json!(format_timestamp(SystemTime::now())));
+    object.insert("level".to_string(), json!(format!("{:?}", level).to_lowercase()));
+    object.insert("event".to_string(), json!(event));
+    if let Some(message) = message {
+        object.insert("message".to_string(), json!(message));
+    }
+    if let serde_json::Value::Object(fields) = fields {
+        object.extend(fields);
+    }
+    eprintln!("{}", serde_json::Value::Object(object));
+}
+
+/// Subcommands besides the default novelty computation.
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Compute novelty, onsets, tempo, and signal statistics and write a combined JSON report
+    Analyze(AnalyzeArgs),
+
+    /// Detect onsets and write their times and strengths to a CSV file
+    Onsets(OnsetsArgs),
+
+    /// Estimate tempo candidates with confidences and write them to a CSV file
+    Tempo(TempoArgs),
+
+    /// Track beats and downbeats and write their times and positions in the bar to a CSV file
+    Beats(BeatsArgs),
+
+    /// Estimate the meter from accentuation on the beat grid and write the candidates to a CSV file
+    Meter(MeterArgs),
+
+    /// Cluster inter-onset intervals and write the tempo hypotheses to a CSV file
+    Ioi(IoiArgs),
+
+    /// Snap rough annotations to the nearest novelty peak and write the corrected times
+    Snap(SnapArgs),
+
+    /// Select the most eventful or representative excerpt and write its time range to a CSV file
+    Thumbnail(ThumbnailArgs),
+
+    /// Propose seamless loop points within sustained regions and write them to a CSV file
+    Loops(LoopsArgs),
+
+    /// Report the dynamics of an audio file or of each file in a directory and the whole corpus in a CSV file
+    Stats(StatsArgs),
+
+    /// Detect active and inactive segments from the smoothed energy envelope and write them to a CSV file
+    Activity(ActivityArgs),
+
+    /// Estimate the time offset between two recordings of the same event and write it to a CSV file
+    Sync(SyncArgs),
+
+    /// Write novelty fingerprints of the audio files in a directory to an index file for the query subcommand
+    Index(IndexArgs),
+
+    /// Find the indexed recordings most similar to a query clip and write them to a CSV file
+    Query(QueryArgs),
+
+    /// Find likely duplicate or overlapping recordings in an index and write them to a CSV file
+    Duplicates(DuplicatesArgs),
+
+    /// Compute the novelty functions of all files listed in a manifest
+    Batch(BatchArgs),
+
+    /// Write frame-aligned mel spectrogram, novelty, and onset labels to an NPZ file for training onset models
+    Features(FeaturesArgs),
+
+    /// Process the files of a batch handed out by a coordinator started with batch --serve
+    ///
+    /// Only connect to a trusted coordinator: the worker takes all options from its command
+    /// line, so the libraries, scripts, and models named by its --plugin, --post-script, and
+    /// --model are loaded and run on this machine, and the unauthenticated protocol doesn't
+    /// verify who sent it.
+    Worker(WorkerArgs),
+
+    /// Check audio files against limits for clipping, dropouts, DC offset, silence, and duration, failing if any file fails
+    Qc(QcArgs),
+
+    /// Check outputs against their provenance sidecars for changed inputs or outputs
+    Verify(VerifyArgs),
+
+    /// Run the stages of a pipeline specification in TOML on audio files and write its exports
+    Pipeline(PipelineArgs),
+
+    /// Serve a local web page showing the waveform, novelty function, and onsets of audio files or novelty CSV files
+    View(ViewArgs),
+
+    /// List the novelty detector plugins found in the directories of NOVELTY_PLUGIN_PATH
+    Plugins,
+}
+
+/// Parses an overlap given as a percentage such as `75%` or as a fraction such as `0.75`.
+fn parse_overlap(value: &str) -> Result<f32, String> {
+    let overlap = match value.trim().strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f32>().map_err(|e| e.to_string())? / 100.0,
+        None => value.trim().parse::<f32>().map_err(|e| e.to_string())?,
+    };
+    if !(0.0..1.0).contains(&overlap) {
+        return Err("overlap must be at least 0% and less than 100%".to_string());
+    }
+    Ok(overlap)
+}
+
+/// Parameters of the novelty function shared by all modes.
+#[derive(Args, Clone, Debug)]
+struct NoveltyArgs {
+    /// Novelty method: energy-based novelty, RMS envelope, zero-crossing-rate novelty, mel-band, linear, Bark-band, or ERB-band spectral flux, wavelet band-energy novelty, the onset activation of a neural network given with --model, or the detector of a plugin given with --plugin (default: energy)
+    #[arg(long, value_enum)]
+    method: Option<MethodArg>,
+
+    /// ONNX model of the nn method, which gets the compressed mel spectrogram with shape (1, frames, n_mels) and returns one onset activation per frame (requires the nn feature)
+    #[arg(long)]
+    model: Option<PathBuf>,
+
+    /// Novelty detector of the plugin method: a shared library, or the name of a plugin in the directories of NOVELTY_PLUGIN_PATH, see the plugins subcommand
+    #[arg(long)]
+    plugin: Option<String>,
+
+    /// Rhai script for post-processing: its novelty(values, fs_feature) function transforms the novelty function after smoothing, and its onset(time, strength) function selects the onsets of the onsets subcommand
+    #[arg(long)]
+    post_script: Option<PathBuf>,
+
+    /// Match a reference implementation numerically, selecting its default parameters
+    #[arg(long, value_enum)]
+    compat: Option<CompatArg>,
+
+    /// Window length in samples (default: 1024, or the default of the reference implementation with --compat)
+    #[arg(long)]
+    window_length: Option<u32>,
+
+    /// Hop length in samples (default: 256, or the default of the reference implementation with --compat)
+    #[arg(long)]
+    hop_length: Option<u32>,
+
+    /// Overlap of successive frames as a percentage or fraction of the window length, e.g. 75% or 0.75, as an alternative to --hop-length
+    #[arg(long, value_parser = parse_overlap, conflicts_with = "hop_length")]
+    overlap: Option<f32>,
+
+    /// Aggregation of the windowed energy for the energy method: windowed sum, weighted mean, or RMS (default: sum)
+    #[arg(long, value_enum, default_value_t = AggregationArg::Sum)]
+    aggregation: AggregationArg,
+
+    /// Compression function applied to the local energy (default: log)
+    #[arg(long, value_enum, default_value_t = CompressionArg::Log)]
+    compression: CompressionArg,
+
+    /// Compression parameter gamma for log and tanh compression (default: 10.0, or 100.0 for spectral novelty with --compat fmp)
+    #[arg(long)]
+    gamma: Option<f32>,
+
+    /// Whether to normalize the novelty function (default: true, or false with --compat librosa)
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    norm: Option<bool>,
+
+    /// Number of bands for the mel, bark, and erb methods, or decomposition levels for the wavelet method (default: 128 for mel, 24 for bark, 40 for erb, 8 for wavelet)
+    #[arg(long)]
+    n_mels: Option<u32>,
+
+    /// Half length in frames of the local average subtracted by the spectral method, 0 disables it (default: 10)
+    #[arg(long)]
+    local_average: Option<u32>,
+
+    /// Frequency weighting applied to the signal before the novelty computation (default: none)
+    #[arg(long, value_enum, default_value_t = WeightingArg::None)]
+    weighting: WeightingArg,
+
+    /// Subtract a per-bin noise floor, estimated as this percentile of the frame magnitudes, before the novelty computation, e.g. 10
+    #[arg(long)]
+    noise_gate: Option<f32>,
+
+    /// Median filter the novelty function over this many frames to remove isolated spikes, e.g. 5 (default: 0, disabled)
+    #[arg(long)]
+    median_filter: Option<u32>,
+
+    /// Smooth the novelty function with this attack time constant in seconds, e.g. 0 to follow rises immediately (default: no smoothing)
+    #[arg(long)]
+    attack: Option<f32>,
+
+    /// Smooth the novelty function with this release time constant in seconds, e.g. 0.3 for a slow decay after peaks (default: no smoothing)
+    #[arg(long)]
+    release: Option<f32>,
+
+    /// Compute the novelty on the harmonic or percussive component of a harmonic-percussive separation, e.g. percussive for onsets in dense harmonic mixes (default: whole signal)
+    #[arg(long, value_enum)]
+    hpss: Option<ComponentArg>,
+
+    /// Compute the novelty at several window lengths in samples and fuse them into one normalized novelty function, e.g. 512,2048,8192 (replaces --window-length)
+    #[arg(long, value_delimiter = ',')]
+    resolutions: Vec<u32>,
+
+    /// Combination of the novelty functions of several --resolutions (default: mean)
+    #[arg(long, value_enum, default_value_t = FusionArg::Mean)]
+    fusion: FusionArg,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    /// Analyze the mid or side channel of a stereo input, or both with the side novelty as an additional CSV column (default: mono input required)
+    #[arg(long, value_enum)]
+    stereo: Option<StereoArg>,
+
+    /// Analyze the recoverable samples of malformed WAV files, e.g. with wrong chunk sizes, junk chunks, or truncated data, with a warning instead of failing
+    #[arg(long)]
+    lenient: bool,
+
+    /// Normalize the novelty function without the frames around clipped regions and dropouts, so these artifacts don't dominate the curve
+    #[arg(long)]
+    exclude_artifacts: bool,
+}
+
+impl NoveltyArgs {
+    /// Builds the novelty configuration from the parsed arguments.
+    fn config(&self) -> anyhow::Result<NoveltyConfig> {
+        let mut builder = match (self.compat, self.method) {
+            (Some(CompatArg::Librosa), Some(_)) => anyhow::bail!("--method can't be combined with --compat librosa, which selects the method itself"),
+            (Some(CompatArg::Librosa), None) => NoveltyConfig::librosa(),
+            (Some(CompatArg::Fmp), method) => NoveltyConfig::fmp(Method::from(method.unwrap_or(MethodArg::Energy))),
+            (None, method) => NoveltyConfigBuilder::from(Method::from(method.unwrap_or(MethodArg::Energy))),
+        };
+
+        if let Some(window_length) = self.window_length {
+            builder = builder.window(window_length);
+        }
+        if let Some(hop_length) = self.hop_length {
+            builder = builder.hop(hop_length);
+        }
+        if let Some(overlap) = self.overlap {
+            builder = builder.overlap(overlap);
+        }
+        if let Some(norm) = self.norm {
+            builder = builder.norm(norm);
+        }
+        if let Some(gamma) = self.gamma {
+            builder = builder.gamma(gamma);
+        }
+        if let Some(n_mels) = self.n_mels {
+            builder = builder.n_mels(n_mels);
+        }
+        if let Some(local_average) = self.local_average {
+            builder = builder.local_average(local_average);
+        }
+        if let Some(median_filter) = self.median_filter {
+            builder = builder.median_filter(median_filter);
+        }
+        if self.attack.is_some() || self.release.is_some() {
+            builder = builder.smoothing(Some(AttackRelease {
+                attack: self.attack.unwrap_or(0.0),
+                release: self.release.unwrap_or(0.0),
+            }));
+        }
+
+        builder
+            .aggregation(self.aggregation.into())
+            .compression(self.compression.into())
+            .weighting(self.weighting.into())
+            .noise_gate(self.noise_gate)
+            .model(self.model.clone())
+            .plugin(self.plugin.as_deref().map(|plugin| resolve_plugin(plugin, &plugin_dirs())).transpose()?)
+            .post_script(self.post_script.clone())
+            .hpss(self.hpss.map(Component::from))
+            .resolutions(self.resolutions.clone())
+            .fusion(self.fusion.into())
+            .build()
+    }
+
+    /// Reads the audio signal to analyze: the mono input, or the mid or side channel of a
+    /// stereo input with --stereo.
+    fn read_audio(&self, path: &str) -> anyhow::Result<(Array1<f32>, u32)> {
+        let input = StagedInput::fetch(path)?;
+        let (source, path) = (path, input.path());
+        if let Some(reason) = self.limits.exceeded(path)? {
+            anyhow::bail!("{} exceeds the limits: {}", source, reason);
+        }
+        let stereo = match self.stereo {
+            None if self.lenient => {
+                let (channels, fs) = self.read_channels(path, source)?;
+                if channels.nrows() != 1 {
+                    anyhow::bail!("Can only handle mono files currently. Please convert input audio file to mono.");
+                }
+                return Ok((channels.row(0).to_owned(), fs));
+            }
+            None => return audio_path_to_array(path),
+            Some(StereoArg::MidSide) => anyhow::bail!("--stereo mid-side is only supported when writing a novelty CSV file"),
+            Some(stereo) => StereoMode::from(stereo),
+        };
+        let (channels, fs) = self.read_channels(path, source)?;
+        let (mid, side) = mid_side(&channels)?;
+        Ok((if stereo == StereoMode::Side { side } else { mid }, fs))
+    }
+
+    /// Reads all channels of a local audio file, recovering what it can from malformed WAV
+    /// files with --lenient. Repairs are logged as warnings about `source`.
+    fn read_channels(&self, path: &str, source: &str) -> anyhow::Result<(Array2<f32>, u32)> {
+        if !self.lenient {
+            return audio_path_to_channels(path);
+        }
+        let (channels, fs, repairs) = audio_path_to_channels_lenient(path)?;
+        for repair in repairs {
+            log_event(
+                Level::Warning,
+                "input_repaired",
+                Some(format!("Repaired {}: {}", source, repair)),
+                json!({ "path": source, "repair": repair }),
+            );
+        }
+        Ok((channels, fs))
+    }
+
+    /// Returns a copy with parameters overridden by pairs of option name and value, e.g.
+    /// from a batch manifest.
+    fn with_overrides(&self, overrides: &[(String, String)]) -> anyhow::Result<NoveltyArgs> {
+        let mut args = self.clone();
+        for (name, value) in overrides {
+            let invalid = |error: String| anyhow::anyhow!("Invalid value {} for {}: {}", value, name, error);
+            match name.replace('-', "_").as_str() {
+                "method" => args.method = Some(MethodArg::from_str(value, true).map_err(invalid)?),
+                "compat" => args.compat = Some(CompatArg::from_str(value, true).map_err(invalid)?),
+                "model" => args.model = Some(PathBuf::from(value)),
+                "plugin" => args.plugin = Some(value.clone()),
+                "post_script" => args.post_script = Some(PathBuf::from(value)),
+                "window_length" => args.window_length = Some(value.parse()?),
+                "hop_length" => {
+                    args.hop_length = Some(value.parse()?);
+                    args.overlap = None;
+                }
+                "overlap" => {
+                    args.overlap = Some(parse_overlap(value).map_err(invalid)?);
+                    args.hop_length = None;
+                }
+                "aggregation" => args.aggregation = AggregationArg::from_str(value, true).map_err(invalid)?,
+                "compression" => args.compression = CompressionArg::from_str(value, true).map_err(invalid)?,
+                "weighting" => args.weighting = WeightingArg::from_str(value, true).map_err(invalid)?,
+                "noise_gate" => args.noise_gate = Some(value.parse()?),
+                "median_filter" => args.median_filter = Some(value.parse()?),
+                "attack" => args.attack = Some(value.parse()?),
+                "release" => args.release = Some(value.parse()?),
+                "hpss" => args.hpss = Some(ComponentArg::from_str(value, true).map_err(invalid)?),
+                "resolutions" => {
+                    // semicolons keep the list in a single column of a CSV manifest
+                    args.resolutions = value.split([',', ';']).map(|w| w.trim().parse()).collect::<Result<_, _>>()?;
+                }
+                "fusion" => args.fusion = FusionArg::from_str(value, true).map_err(invalid)?,
+                "stereo" => args.stereo = Some(StereoArg::from_str(value, true).map_err(invalid)?),
+                "gamma" => args.gamma = Some(value.parse()?),
+                "norm" => args.norm = Some(value.parse()?),
+                "n_mels" => args.n_mels = Some(value.parse()?),
+                "local_average" => args.local_average = Some(value.parse()?),
+                _ => anyhow::bail!("Unknown parameter {}", name),
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// Limits on the size of inputs, checked from the file header before decoding.
+#[derive(Args, Clone, Debug)]
+struct LimitArgs {
+    /// Reject inputs longer than this many seconds before decoding them
+    #[arg(long)]
+    max_duration: Option<f32>,
+
+    /// Reject inputs whose processing is estimated to need more memory than this many bytes before decoding them, e.g. 512M or 2G
+    #[arg(long, value_parser = parse_size)]
+    max_memory: Option<u64>,
+
+    /// What to do with inputs over a limit: fail, or compute the energy novelty of mono WAV input in blocks when writing a novelty CSV file (default: fail)
+    #[arg(long, value_enum, default_value_t = LimitActionArg::Fail)]
+    on_limit: LimitActionArg,
+
+    /// Stream the input in blocks as with --on-limit stream, saving progress next to the output periodically and resuming from it after an interruption
+    #[arg(long)]
+    checkpoint: bool,
+
+    /// Seconds of audio between checkpoints (default: 600)
+    #[arg(long, default_value_t = 600.0, requires = "checkpoint")]
+    checkpoint_interval: f32,
+}
+
+impl LimitArgs {
+    /// Returns a description of the limit an input exceeds, judged from its header. Inputs of
+    /// unknown length, such as standard input, aren't checked.
+    fn exceeded(&self, path: &str) -> anyhow::Result<Option<String>> {
+        if (self.max_duration.is_none() && self.max_memory.is_none()) || path == "-" {
+            return Ok(None);
+        }
+        let info = audio_path_info(path)?;
+        if let (Some(max_duration), Some(duration)) = (self.max_duration, info.duration()) {
+            if duration > max_duration {
+                return Ok(Some(format!("duration of {:.1} s is over --max-duration {} s", duration, max_duration)));
+            }
+        }
+        if let (Some(max_memory), Some(memory)) = (self.max_memory, info.estimated_memory()) {
+            if memory > max_memory {
+                return Ok(Some(format!("estimated memory of {} MiB is over --max-memory {} MiB", memory >> 20, max_memory >> 20)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Parses a size in bytes with an optional binary suffix, e.g. `512M` or `2G`.
+fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let (number, shift) = match value.char_indices().last() {
+        Some((i, 'K' | 'k')) => (&value[..i], 10),
+        Some((i, 'M' | 'm')) => (&value[..i], 20),
+        Some((i, 'G' | 'g')) => (&value[..i], 30),
+        Some((i, 'T' | 't')) => (&value[..i], 40),
+        _ => (value, 0),
+    };
+    let number = number.trim().parse::<f64>().map_err(|e| e.to_string())?;
+    if number < 0.0 {
+        return Err("size must not be negative".to_string());
+    }
+    Ok((number * (1_u64 << shift) as f64) as u64)
+}
+
+/// Actions for inputs over a limit selectable on the command line.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum LimitActionArg {
+    Fail,
+    Stream,
+}
+
+/// Novelty methods selectable on the command line.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum MethodArg {
+    Energy,
+    Rms,
+    Zcr,
+    Mel,
+    Spectral,
+    Bark,
+    Erb,
+    Wavelet,
+    Nn,
+    Plugin,
+}
+
+impl From<MethodArg> for Method {
+    fn from(method: MethodArg) -> Self {
+        match method {
+            MethodArg::Energy => Method::Energy,
+            MethodArg::Rms => Method::Rms,
+            MethodArg::Zcr => Method::Zcr,
+            MethodArg::Mel => Method::Mel,
+            MethodArg::Spectral => Method::Spectral,
+            MethodArg::Bark => Method::Bark,
+            MethodArg::Erb => Method::Erb,
+            MethodArg::Wavelet => Method::Wavelet,
+            MethodArg::Nn => Method::Nn,
+            MethodArg::Plugin => Method::Plugin,
+        }
+    }
+}
+
+/// Reference implementations selectable with `--compat`.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum CompatArg {
+    Librosa,
+    Fmp,
+}
+
+/// Aggregations of the windowed energy selectable on the command line.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum AggregationArg {
+    Sum,
+    Mean,
+    Rms,
+}
+
+impl From<AggregationArg> for Aggregation {
+    fn from(aggregation: AggregationArg) -> Self {
+        match aggregation {
+            AggregationArg::Sum => Aggregation::Sum,
+            AggregationArg::Mean => Aggregation::Mean,
+            AggregationArg::Rms => Aggregation::Rms,
+        }
+    }
+}
+
+/// Frequency weightings selectable on the command line.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum WeightingArg {
+    None,
+    A,
+    C,
+}
+
+impl From<WeightingArg> for Weighting {
+    fn from(weighting: WeightingArg) -> Self {
+        match weighting {
+            WeightingArg::None => Weighting::None,
+            WeightingArg::A => Weighting::A,
+            WeightingArg::C => Weighting::C,
+        }
+    }
+}
+
+/// Components of a harmonic-percussive separation selectable on the command line.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ComponentArg {
+    Harmonic,
+    Percussive,
+}
+
+impl From<ComponentArg> for Component {
+    fn from(component: ComponentArg) -> Self {
+        match component {
+            ComponentArg::Harmonic => Component::Harmonic,
+            ComponentArg::Percussive => Component::Percussive,
+        }
+    }
+}
+
+/// Fusions of several resolutions selectable on the command line.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum FusionArg {
+    Mean,
+    Max,
+}
+
+impl From<FusionArg> for Fusion {
+    fn from(fusion: FusionArg) -> Self {
+        match fusion {
+            FusionArg::Mean => Fusion::Mean,
+            FusionArg::Max => Fusion::Max,
+        }
+    }
+}
+
+/// Stereo modes selectable on the command line.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum StereoArg {
+    Mid,
+    Side,
+    MidSide,
+}
+
+impl From<StereoArg> for StereoMode {
+    fn from(stereo: StereoArg) -> Self {
+        match stereo {
+            StereoArg::Mid => StereoMode::Mid,
+            StereoArg::Side => StereoMode::Side,
+            StereoArg::MidSide => StereoMode::MidSide,
+        }
+    }
+}
+
+/// Compression functions selectable on the command line.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum CompressionArg {
+    Log,
+    Tanh,
+    Sqrt,
+    None,
+}
+
+impl From<CompressionArg> for Compression {
+    fn from(compression: CompressionArg) -> Self {
+        match compression {
+            CompressionArg::Log => Compression::Log,
+            CompressionArg::Tanh => Compression::Tanh,
+            CompressionArg::Sqrt => Compression::Sqrt,
+            CompressionArg::None => Compression::None,
+        }
+    }
+}
+
+/// Options of the novelty output.
+#[derive(Args, Clone, Debug)]
+struct CsvArgs {
+    /// Output format of the novelty function (default: inferred from the extension of the output path, CSV for unknown extensions)
+    #[arg(long, value_enum)]
+    format: Option<OutputFormatArg>,
+
+    /// Additional framewise descriptors to write as CSV columns, e.g. centroid,rolloff,flatness or momentary for a loudness curve
+    #[arg(long, value_enum, value_delimiter = ',')]
+    descriptors: Vec<DescriptorArg>,
+
+    /// Whether the time column refers to the center or the start of each frame (default: center)
+    #[arg(long, value_enum, default_value_t = FrameTimingArg::Center)]
+    frame_timing: FrameTimingArg,
+
+    /// Offset the time column by the BWF time reference of a WAV input, so times are on the production timeline in seconds since midnight rather than relative to the file
+    #[arg(long)]
+    timecode_offset: bool,
+
+    /// Unit of the time column of CSV output: seconds, sample or frame indices, or hh:mm:ss.mmm timestamps (default: seconds)
+    #[arg(long, value_enum, default_value_t = TimeUnitArg::Seconds)]
+    time_unit: TimeUnitArg,
+
+    /// Also write a <output>.prov.json sidecar with the SHA-256 hashes of input and output, timestamps, and parameters, which the verify subcommand checks
+    #[arg(long)]
+    provenance: bool,
+}
+
+impl CsvArgs {
+    /// Returns the selected descriptors.
+    fn descriptors(&self) -> Vec<Descriptor> {
+        self.descriptors.iter().map(|&d| d.into()).collect()
+    }
+
+    /// Returns the selected output format, or the format inferred from the output path.
+    fn format(&self, path_out: &str) -> anyhow::Result<OutputFormatArg> {
+        let format = self.format.unwrap_or_else(|| OutputFormatArg::from_path(path_out));
+        #[cfg(not(feature = "parquet"))]
+        if format == OutputFormatArg::Parquet {
+            anyhow::bail!("Parquet output requires the parquet feature!");
+        }
+        Ok(format)
+    }
+
+    /// Returns the options that change the contents of the output file, by their
+    /// command-line names.
+    fn parameters(&self) -> serde_json::Value {
+        json!({
+            "format": self.format.map(|format| value_name(&format)),
+            "descriptors": self.descriptors.iter().map(value_name).collect::<Vec<_>>(),
+            "frame_timing": value_name(&self.frame_timing),
+            "timecode_offset": self.timecode_offset,
+            "time_unit": value_name(&self.time_unit),
+        })
+    }
+
+    /// Returns the extension of generated output paths, `csv` unless another format is selected.
+    fn extension(&self) -> &'static str {
+        self.format.map_or("csv", OutputFormatArg::extension)
+    }
+}
+
+/// File formats of the novelty function.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormatArg {
+    Csv,
+    Json,
+    Npy,
+    Parquet,
+    Png,
+}
+
+impl OutputFormatArg {
+    /// Infers the format from the extension of the output path, CSV for unknown extensions
+    /// as before the other formats were added.
+    fn from_path(path: &str) -> Self {
+        let extension = Path::new(path).extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+        match extension.to_lowercase().as_str() {
+            "json" => OutputFormatArg::Json,
+            "npy" => OutputFormatArg::Npy,
+            "parquet" => OutputFormatArg::Parquet,
+            "png" => OutputFormatArg::Png,
+            _ => OutputFormatArg::Csv,
+        }
+    }
+
+    /// File extension of the format, without the dot.
+    fn extension(self) -> &'static str {
+        match self {
+            OutputFormatArg::Csv => "csv",
+            OutputFormatArg::Json => "json",
+            OutputFormatArg::Npy => "npy",
+            OutputFormatArg::Parquet => "parquet",
+            OutputFormatArg::Png => "png",
+        }
+    }
+}
+
+/// Reference points of the frame time stamps.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum FrameTimingArg {
+    Center,
+    Start,
+}
+
+impl From<FrameTimingArg> for FrameTiming {
+    fn from(frame_timing: FrameTimingArg) -> Self {
+        match frame_timing {
+            FrameTimingArg::Center => FrameTiming::Center,
+            FrameTimingArg::Start => FrameTiming::Start,
+        }
+    }
+}
+
+/// Units of the time column.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum TimeUnitArg {
+    Seconds,
+    Samples,
+    Frames,
+    Timestamp,
+}
+
+impl TimeUnitArg {
+    /// Returns the time unit, with sample indices at the sampling rate `fs` of the audio.
+    fn unit(self, fs: u32) -> TimeUnit {
+        match self {
+            TimeUnitArg::Seconds => TimeUnit::Seconds,
+            TimeUnitArg::Samples => TimeUnit::Samples(fs),
+            TimeUnitArg::Frames => TimeUnit::Frames,
+            TimeUnitArg::Timestamp => TimeUnit::Timestamp,
+        }
+    }
+}
+
+/// Framewise descriptors that can be added to the CSV output.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum DescriptorArg {
+    Centroid,
+    Rolloff,
+    Flatness,
+    Zcr,
+    Momentary,
+    ShortTerm,
+}
+
+impl From<DescriptorArg> for Descriptor {
+    fn from(descriptor: DescriptorArg) -> Self {
+        match descriptor {
+            DescriptorArg::Centroid => Descriptor::Centroid,
+            DescriptorArg::Rolloff => Descriptor::Rolloff,
+            DescriptorArg::Flatness => Descriptor::Flatness,
+            DescriptorArg::Zcr => Descriptor::Zcr,
+            DescriptorArg::Momentary => Descriptor::Momentary,
+            DescriptorArg::ShortTerm => Descriptor::ShortTerm,
+        }
+    }
+}
+
+/// Carrier signals for the sonification.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum CarrierArg {
+    Tone,
+    Noise,
+}
+
+/// Options to render the novelty function as audio.
+#[derive(Args, Debug)]
+struct SonifyArgs {
+    /// Also render the novelty function as audio to this WAV file
+    #[arg(long)]
+    sonify: Option<String>,
+
+    /// Carrier that is amplitude-modulated by the novelty function (default: tone)
+    #[arg(long, value_enum, default_value_t = CarrierArg::Tone)]
+    sonify_carrier: CarrierArg,
+
+    /// Frequency of the tone carrier in Hz (default: 1000.0)
+    #[arg(long, default_value_t = 1000.0)]
+    sonify_frequency: f32,
+}
+
+impl SonifyArgs {
+    /// Returns the selected carrier signal.
+    fn carrier(&self) -> Carrier {
+        match self.sonify_carrier {
+            CarrierArg::Tone => Carrier::Tone(self.sonify_frequency),
+            CarrierArg::Noise => Carrier::Noise,
+        }
+    }
+}
+
+/// Options to collect aggregate statistics of the novelty function.
+#[derive(Args, Debug)]
+struct SummaryArgs {
+    /// Also append onset count, onset density, mean/max novelty, and tempo to this summary CSV file
+    #[arg(long)]
+    summary: Option<String>,
+
+    /// Onset peaks below this fraction of the maximum novelty are not counted in the summary (default: 0.1)
+    #[arg(long, default_value_t = 0.1)]
+    onset_threshold: f32,
+
+    /// Also append the tempo histogram, the normalized tempo salience from 30 to 300 BPM, to this CSV file
+    #[arg(long)]
+    tempo_histogram: Option<String>,
+}
+
+/// Arguments of the `analyze` subcommand.
+#[derive(Args, Debug)]
+struct AnalyzeArgs {
+    /// Path to the input mono audio file (WAV), or - to read a WAV stream from standard input
+    #[arg()]
+    path_in: String,
+
+    /// Path to the output JSON report
+    #[arg()]
+    path_out: String,
+
+    #[command(flatten)]
+    novelty: NoveltyArgs,
+
+    /// Onset peaks below this fraction of the maximum novelty are discarded (default: 0.1)
+    #[arg(long, default_value_t = 0.1)]
+    onset_threshold: f32,
+}
+
+/// Input of the stages that operate on a novelty function.
+#[derive(Args, Debug)]
+struct NoveltyInputArgs {
+    /// Path to the input mono audio file (WAV) or a novelty CSV file (time,novelty), or - to read either from standard input
+    #[arg()]
+    path_in: String,
+
+    #[command(flatten)]
+    novelty: NoveltyArgs,
+
+    /// Feature rate of a novelty CSV input (default: inferred from the time column)
+    #[arg(long)]
+    feature_rate: Option<f32>,
+}
+
+impl NoveltyInputArgs {
+    /// Whether the input is a novelty CSV file, or standard input that doesn't start like an
+    /// audio stream.
+    fn is_novelty_csv(&self) -> bool {
+        if self.path_in == "-" {
+            return !stdin_is_audio();
+        }
+        self.path_in.to_lowercase().ends_with(".csv")
+    }
+
+    /// Reads the novelty function from a CSV input, or computes it from an audio input.
+    fn load(&self) -> anyhow::Result<NoveltyCurve> {
+        if self.is_novelty_csv() {
+            let mut novelty = if self.path_in == "-" {
+                read_novelty_csv_from(std::io::stdin().lock(), "standard input", self.feature_rate)?
+            } else {
+                let input = StagedInput::fetch(&self.path_in)?;
+                read_novelty_csv(input.path(), self.feature_rate)?
+            };
+            // the script is the only post-processing that also applies to novelty inputs
+            if let Some(path) = &self.novelty.post_script {
+                novelty.values = PostScript::load(path)?.transform_novelty(&novelty.values, novelty.fs_feature)?;
+            }
+            return Ok(novelty);
+        }
+        let config = self.novelty.config()?;
+        let (audio_array, fs) = self.novelty.read_audio(&self.path_in)?;
+        config.compute(audio_array, fs)
+    }
+}
+
+/// Checks whether standard input starts with the magic bytes of a WAV, AIFF, or CAF stream,
+/// without consuming them.
+fn stdin_is_audio() -> bool {
+    let mut stdin = std::io::stdin().lock();
+    stdin
+        .fill_buf()
+        .is_ok_and(|bytes| [b"RIFF", b"RF64", b"BW64", b"FORM", b"caff"].iter().any(|magic| bytes.starts_with(*magic)))
+}
+
+/// Arguments of the `onsets` subcommand.
+#[derive(Args, Debug)]
+struct OnsetsArgs {
+    #[command(flatten)]
+    input: NoveltyInputArgs,
+
+    /// Path to the output CSV file
+    #[arg()]
+    path_out: String,
+
+    /// Onset peaks below this fraction of the maximum novelty are discarded (default: 0.1)
+    #[arg(long, default_value_t = 0.1)]
+    onset_threshold: f32,
+
+    /// Refine onset times below the hop length by quadratic interpolation around each peak
+    #[arg(long)]
+    refine: bool,
+
+    #[command(flatten)]
+    peak_picking: PeakPickingArgs,
+
+    /// Detect onsets in each channel of a multichannel input and merge detections within this many seconds, reporting which channels triggered, e.g. 0.03
+    #[arg(long, conflicts_with_all = ["stereo", "export_segments"])]
+    fuse_channels: Option<f32>,
+
+    #[command(flatten)]
+    segments: SegmentArgs,
+
+    #[command(flatten)]
+    markers: MarkerArgs,
+
+    #[command(flatten)]
+    timecode: TimecodeArgs,
+
+    #[command(flatten)]
+    midi: MidiArgs,
+
+    /// Compare the detected onsets with the annotations in this file, an Audacity label file or a list of times in seconds, and print the number of matched, missed, and extra onsets
+    #[arg(long, conflicts_with = "fuse_channels")]
+    annotations: Option<String>,
+
+    /// Also write the alignment of the detected onsets with the annotations to this CSV file: matches with their deviation, missed annotations, and extra detections
+    #[arg(long, requires = "annotations")]
+    alignment: Option<String>,
+
+    /// Maximum time difference in seconds for a detected onset to match an annotation (default: 0.05)
+    #[arg(long, default_value_t = alignment::DEFAULT_ALIGNMENT_TOLERANCE)]
+    alignment_tolerance: f32,
+
+    /// Export the segments between the --boundaries as regions instead of the onsets as markers
+    #[arg(long, requires = "markers")]
+    marker_regions: bool,
+
+    /// Classify each onset as hard (percussive) or soft (tonal) from the audio around it and add the label, attack time, and brightness as columns
+    #[arg(long, conflicts_with = "fuse_channels")]
+    classify: bool,
+
+    /// Add the start of each onset's attack, at the local energy minimum before it, and the time to the energy peak as columns
+    #[arg(long, conflicts_with = "fuse_channels")]
+    attack_times: bool,
+
+    /// Classify each onset as kick, snare, or hat from the energy of its attack in three bands and add the label and the band fractions as columns
+    #[arg(long, conflicts_with = "fuse_channels")]
+    drums: bool,
+
+    /// Estimate the pitch after each monophonic onset with YIN and add the frequency, MIDI note number, note name, and confidence as columns
+    #[arg(long, conflicts_with = "fuse_channels")]
+    pitch: bool,
+
+    /// Lowest detectable pitch in Hz (default: 50)
+    #[arg(long, default_value_t = 50.0, requires = "pitch")]
+    pitch_min: f32,
+
+    /// Highest detectable pitch in Hz (default: 2000)
+    #[arg(long, default_value_t = 2000.0, requires = "pitch")]
+    pitch_max: f32,
+
+    /// Length in seconds of the pitch analysis window after each onset, at least two periods of --pitch-min (default: 0.05)
+    #[arg(long, default_value_t = 0.05, requires = "pitch")]
+    pitch_window: f32,
+
+    /// Rerun the detection with halved and doubled window lengths and thresholds scaled by 0.75 and 1.5, and add the fraction of runs that found each onset as a stability column
+    #[arg(long, conflicts_with_all = ["fuse_channels", "refine"])]
+    stability: bool,
+
+    /// Maximum time difference in seconds for an onset of a jittered run to count as a detection (default: 0.03)
+    #[arg(long, default_value_t = 0.03, requires = "stability")]
+    stability_tolerance: f32,
+
+    /// Also write the percussive component of a harmonic-percussive separation of the input audio, which holds the attacks, to this WAV file
+    #[arg(long, conflicts_with = "fuse_channels")]
+    export_transients: Option<String>,
+
+    /// Also write a Vega-Lite specification plotting the novelty function and onsets to this JSON file, e.g. for display in Jupyter or Observable
+    #[arg(long, conflicts_with = "fuse_channels")]
+    vega_lite: Option<String>,
+
+    /// Data of the Vega-Lite specification: embedded, or referenced by the paths of the novelty CSV input and the onsets output (default: inline)
+    #[arg(long, value_enum, default_value_t = VegaDataArg::Inline, requires = "vega_lite")]
+    vega_data: VegaDataArg,
+}
+
+/// Data sources of an exported Vega-Lite specification.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum VegaDataArg {
+    Inline,
+    Reference,
+}
+
+impl OnsetsArgs {
+    /// Detects onsets with the selected peak picking, refined if requested and selected by
+    /// the post-processing script if it has an onset function.
+    fn detect(&self, novelty: &NoveltyCurve) -> anyhow::Result<Vec<Onset>> {
+        let onsets = match self.peak_picking.params(novelty.fs_feature) {
+            Some(params) => pick_peaks(novelty, &params)?,
+            None => detect_onsets(novelty, self.onset_threshold),
+        };
+        let onsets = if self.refine { refine_onsets(novelty, &onsets) } else { onsets };
+        match &self.input.novelty.post_script {
+            Some(path) => PostScript::load(path)?.filter_onsets(onsets),
+            None => Ok(onsets),
+        }
+    }
+}
+
+/// Options to cut the input audio into segments.
+#[derive(Args, Debug)]
+struct SegmentArgs {
+    /// Also cut the input audio at the boundaries and write one WAV file per segment to this directory
+    #[arg(long)]
+    export_segments: Option<String>,
+
+    /// Boundaries to cut at: the detected onsets or structural boundaries between sections (default: onsets)
+    #[arg(long, value_enum, default_value_t = BoundaryArg::Onsets)]
+    boundaries: BoundaryArg,
+
+    /// Half length of the checkerboard kernel for structural boundaries in seconds (default: 3.0)
+    #[arg(long, default_value_t = structure::DEFAULT_KERNEL_SECONDS)]
+    structure_kernel: f32,
+}
+
+/// Options to write event times as SMPTE timecode.
+#[derive(Args, Debug)]
+struct TimecodeArgs {
+    /// Write times as SMPTE timecode at this video frame rate instead of seconds, and quantize EDL and FCPXML markers to its frames, e.g. 25, or 29.97 for drop-frame timecode
+    #[arg(long)]
+    timecode_fps: Option<f32>,
+
+    /// Timecode at the start of the recording, HH:MM:SS:FF (default: 00:00:00:00)
+    #[arg(long, requires = "timecode_fps")]
+    timecode_start: Option<String>,
+}
+
+impl TimecodeArgs {
+    /// Returns the selected timecode, if any.
+    fn timecode(&self) -> anyhow::Result<Option<Timecode>> {
+        self.timecode_fps
+            .map(|fps| Timecode::new(fps, self.timecode_start.as_deref().unwrap_or("00:00:00:00")))
+            .transpose()
+    }
+}
+
+/// Options to export onsets as MIDI notes.
+#[derive(Args, Debug)]
+struct MidiArgs {
+    /// Also write the onsets as notes to this standard MIDI file, e.g. to re-trigger drum samples in a DAW
+    #[arg(long = "midi", id = "midi", conflicts_with = "fuse_channels")]
+    path: Option<String>,
+
+    /// Note number of the MIDI notes (default: 36, a kick drum on the General MIDI drum channel)
+    #[arg(long, default_value_t = 36, requires = "midi")]
+    midi_note: u8,
+
+    /// Channel of the MIDI notes from 1 to 16 (default: 10, the General MIDI drum channel)
+    #[arg(long, default_value_t = 10, requires = "midi")]
+    midi_channel: u8,
+
+    /// Derive the velocities from the novelty peak height or the loudness after each onset (default: strength)
+    #[arg(long, value_enum, default_value_t = VelocitySourceArg::Strength, requires = "midi")]
+    velocity_source: VelocitySourceArg,
+
+    /// Exponent of the velocity curve: 1 is linear, below 1 lifts soft hits, above 1 emphasizes loud ones (default: 1.0)
+    #[arg(long, default_value_t = 1.0, requires = "midi")]
+    velocity_curve: f32,
+
+    /// Velocity of the softest onsets (default: 1)
+    #[arg(long, default_value_t = 1, requires = "midi")]
+    velocity_min: u8,
+
+    /// Velocity of the strongest onset (default: 127)
+    #[arg(long, default_value_t = 127, requires = "midi")]
+    velocity_max: u8,
+}
+
+impl MidiArgs {
+    /// Returns the selected velocity mapping.
+    fn mapping(&self) -> VelocityMapping {
+        VelocityMapping {
+            source: self.velocity_source.into(),
+            curve: self.velocity_curve,
+            min: self.velocity_min,
+            max: self.velocity_max,
+        }
+    }
+}
+
+/// Sources of MIDI velocities.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum VelocitySourceArg {
+    Strength,
+    Loudness,
+}
+
+impl From<VelocitySourceArg> for VelocitySource {
+    fn from(source: VelocitySourceArg) -> Self {
+        match source {
+            VelocitySourceArg::Strength => VelocitySource::Strength,
+            VelocitySourceArg::Loudness => VelocitySource::Loudness,
+        }
+    }
+}
+
+/// Options to export detected events as DAW markers.
+#[derive(Args, Debug)]
+struct MarkerArgs {
+    /// Also export the events as markers to this file for import into a DAW
+    #[arg(long = "markers", id = "markers")]
+    path: Option<String>,
+
+    /// Format of the exported markers: CSV for the region/marker manager of REAPER, locations of an Ardour session, plain "minutes:seconds label" lines, or an EDL or FCPXML file for video editors (default: reaper)
+    #[arg(long, value_enum, default_value_t = MarkerFormatArg::Reaper)]
+    marker_format: MarkerFormatArg,
+}
+
+impl MarkerArgs {
+    /// Validates that the marker file does not already exist and that video formats have the
+    /// frame rate of `--timecode-fps`, if requested.
+    fn validate(&self, timecode: Option<Timecode>) -> anyhow::Result<()> {
+        if let Some(path) = &self.path {
+            validate_output(path)?;
+            if matches!(self.marker_format, MarkerFormatArg::Edl | MarkerFormatArg::Fcpxml) && timecode.is_none() {
+                anyhow::bail!("--marker-format edl and fcpxml need the video frame rate --timecode-fps");
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the markers if requested, with the sampling rate of the audio input for Ardour.
+    fn write(&self, input: &NoveltyInputArgs, markers: &[Marker], timecode: Option<Timecode>) -> anyhow::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let fs = match self.marker_format {
+            MarkerFormatArg::Ardour if !input.is_novelty_csv() => Some(audio_path_info(&input.path_in)?.fs),
+            _ => None,
+        };
+        write_markers(path, markers, self.marker_format.into(), fs, timecode)
+    }
+}
+
+/// File formats of exported markers.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum MarkerFormatArg {
+    Reaper,
+    Ardour,
+    Plain,
+    Edl,
+    Fcpxml,
+}
+
+impl From<MarkerFormatArg> for MarkerFormat {
+    fn from(format: MarkerFormatArg) -> Self {
+        match format {
+            MarkerFormatArg::Reaper => MarkerFormat::Reaper,
+            MarkerFormatArg::Ardour => MarkerFormat::Ardour,
+            MarkerFormatArg::Plain => MarkerFormat::Plain,
+            MarkerFormatArg::Edl => MarkerFormat::Edl,
+            MarkerFormatArg::Fcpxml => MarkerFormat::Fcpxml,
+        }
+    }
+}
+
+/// Boundaries at which segments are cut.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum BoundaryArg {
+    Onsets,
+    Structure,
+}
+
+/// Parameters of the librosa peak picker, all window lengths in frames.
+///
+/// If any of them is given, onsets are detected with the librosa peak picker instead of the
+/// threshold, and the remaining parameters take the defaults of `librosa.onset.onset_detect`.
+#[derive(Args, Debug)]
+struct PeakPickingArgs {
+    /// Frames before a peak in which it must be the maximum (librosa default: 30 ms)
+    #[arg(long)]
+    pre_max: Option<usize>,
+
+    /// Frames after a peak, including the peak, in which it must be the maximum (librosa default: 1)
+    #[arg(long)]
+    post_max: Option<usize>,
+
+    /// Frames before a peak included in the moving average (librosa default: 100 ms)
+    #[arg(long)]
+    pre_avg: Option<usize>,
+
+    /// Frames after a peak, including the peak, included in the moving average (librosa default: 100 ms + 1)
+    #[arg(long)]
+    post_avg: Option<usize>,
+
+    /// Minimum amount by which a peak must exceed the moving average (librosa default: 0.07)
+    #[arg(long)]
+    delta: Option<f32>,
+
+    /// Minimum number of frames between consecutive peaks (librosa default: 30 ms)
+    #[arg(long)]
+    wait: Option<usize>,
+}
+
+impl PeakPickingArgs {
+    /// Returns the peak picking parameters, or `None` if none of them were given.
+    fn params(&self, fs_feature: f32) -> Option<PeakPicking> {
+        if self.pre_max.is_none()
+            && self.post_max.is_none()
+            && self.pre_avg.is_none()
+            && self.post_avg.is_none()
+            && self.delta.is_none()
+            && self.wait.is_none()
+        {
+            return None;
+        }
+        let defaults = PeakPicking::librosa_defaults(fs_feature);
+        Some(PeakPicking {
+            pre_max: self.pre_max.unwrap_or(defaults.pre_max),
+            post_max: self.post_max.unwrap_or(defaults.post_max),
+            pre_avg: self.pre_avg.unwrap_or(defaults.pre_avg),
+            post_avg: self.post_avg.unwrap_or(defaults.post_avg),
+            delta: self.delta.unwrap_or(defaults.delta),
+            wait: self.wait.unwrap_or(defaults.wait),
+        })
+    }
+}
+
+/// Arguments of the `tempo` subcommand.
+#[derive(Args, Debug)]
+struct TempoArgs {
+    #[command(flatten)]
+    input: NoveltyInputArgs,
+
+    /// Path to the output CSV file
+    #[arg()]
+    path_out: String,
+
+    /// Number of tempo candidates to report, best first (default: 3)
+    #[arg(long, default_value_t = tempo::DEFAULT_NUM_CANDIDATES)]
+    candidates: usize,
+
+    /// Also export the tempogram as CSV, NumPy array, or PNG heatmap, selected by the extension .csv, .npy, or .png (repeatable)
+    #[arg(long, value_name = "FILE")]
+    tempogram: Vec<String>,
+
+    /// Also export the cyclic tempogram, which sums tempi an octave apart, in the same formats as --tempogram (repeatable)
+    #[arg(long, value_name = "FILE")]
+    cyclic_tempogram: Vec<String>,
+
+    /// Number of bins per tempo octave of the cyclic tempogram (default: 60)
+    #[arg(long, default_value_t = tempo::DEFAULT_CYCLIC_BINS)]
+    cyclic_bins: usize,
+
+    /// Also append the tempo histogram, the normalized tempo salience, to this CSV file, which collects one row per input
+    #[arg(long)]
+    tempo_histogram: Option<String>,
+}
+
+/// Arguments of the `beats` subcommand.
+#[derive(Args, Debug)]
+struct BeatsArgs {
+    #[command(flatten)]
+    input: NoveltyInputArgs,
+
+    /// Path to the output CSV file
+    #[arg()]
+    path_out: String,
+
+    #[command(flatten)]
+    tracking: BeatTrackingArgs,
+
+    /// Also write statistics of the beats relative to a constant grid to this CSV file: inter-beat interval deviation, drift, and fraction of matching beats
+    #[arg(long)]
+    grid_stats: Option<String>,
+
+    /// Maximum deviation in seconds of a beat from the grid to count as matching (default: 0.02)
+    #[arg(long, default_value_t = beats::DEFAULT_GRID_TOLERANCE)]
+    grid_tolerance: f32,
+
+    #[command(flatten)]
+    markers: MarkerArgs,
+
+    #[command(flatten)]
+    timecode: TimecodeArgs,
+}
+
+/// Arguments of the `meter` subcommand.
+#[derive(Args, Debug)]
+struct MeterArgs {
+    #[command(flatten)]
+    input: NoveltyInputArgs,
+
+    /// Path to the output CSV file
+    #[arg()]
+    path_out: String,
+
+    #[command(flatten)]
+    tracking: BeatTrackingArgs,
+}
+
+/// Parameters of the beat tracker.
+#[derive(Args, Debug)]
+struct BeatTrackingArgs {
+    /// Expected tempo in BPM (default: estimated from the novelty function)
+    #[arg(long)]
+    bpm: Option<f32>,
+
+    /// Weight of the tempo penalty relative to the novelty values (default: 1.0)
+    #[arg(long, default_value_t = 1.0)]
+    tightness: f32,
+}
+
+impl BeatTrackingArgs {
+    /// Tracks beats at the given or estimated tempo.
+    fn track(&self, novelty: &NoveltyCurve) -> anyhow::Result<Vec<Beat>> {
+        let tempo = match self.bpm {
+            Some(bpm) => bpm,
+            None => global_tempo(novelty)?,
+        };
+        Ok(track_beats(novelty, tempo, self.tightness))
+    }
+}
+
+/// Arguments of the `ioi` subcommand.
+#[derive(Args, Debug)]
+struct IoiArgs {
+    #[command(flatten)]
+    input: NoveltyInputArgs,
+
+    /// Path to the output CSV file with the tempo hypotheses
+    #[arg()]
+    path_out: String,
+
+    /// Also write the histogram of inter-onset intervals to this CSV file
+    #[arg(long)]
+    histogram: Option<String>,
+
+    /// Onset peaks below this fraction of the maximum novelty are discarded (default: 0.1)
+    #[arg(long, default_value_t = 0.1)]
+    onset_threshold: f32,
+
+    /// Width of the histogram bins in seconds (default: 0.01)
+    #[arg(long, default_value_t = ioi::DEFAULT_BIN_WIDTH)]
+    bin_width: f32,
+
+    /// Width of the interval clusters in seconds (default: 0.025)
+    #[arg(long, default_value_t = ioi::DEFAULT_CLUSTER_WIDTH)]
+    cluster_width: f32,
+
+    /// Longest inter-onset interval to consider in seconds (default: 2.5)
+    #[arg(long, default_value_t = ioi::DEFAULT_MAX_INTERVAL)]
+    max_interval: f32,
+}
+
+/// Arguments of the `snap` subcommand.
+#[derive(Args, Debug)]
+struct SnapArgs {
+    #[command(flatten)]
+    input: NoveltyInputArgs,
+
+    /// Path to the annotation file, an Audacity label file or a list of times in seconds
+    #[arg()]
+    annotations: String,
+
+    /// Path to the output file: an Audacity label file for .txt, a CSV file with the corrected and original times otherwise
+    #[arg()]
+    path_out: String,
+
+    /// Maximum distance in seconds an annotation is moved (default: 0.05)
+    #[arg(long, default_value_t = alignment::DEFAULT_ALIGNMENT_TOLERANCE)]
+    tolerance: f32,
+
+    /// Novelty peaks below this fraction of the maximum novelty are ignored (default: 0.0)
+    #[arg(long, default_value_t = 0.0)]
+    onset_threshold: f32,
+
+    /// Snap to peak times refined below the hop length by quadratic interpolation
+    #[arg(long)]
+    refine: bool,
+}
+
+/// Arguments of the `sync` subcommand.
+#[derive(Args, Debug)]
+struct SyncArgs {
+    /// Path to the reference recording (WAV) or its novelty CSV file (time,novelty)
+    #[arg()]
+    reference: String,
+
+    /// Path to the recording to align (WAV) or its novelty CSV file
+    #[arg()]
+    other: String,
+
+    /// Path to the output CSV file with the lag, the start of the other recording within the reference in seconds, and its correlation
+    #[arg()]
+    path_out: String,
+
+    #[command(flatten)]
+    novelty: NoveltyArgs,
+
+    /// Feature rate of novelty CSV inputs (default: inferred from the time column)
+    #[arg(long)]
+    feature_rate: Option<f32>,
+
+    /// Largest offset in seconds to consider in either direction (default: any offset with some overlap)
+    #[arg(long)]
+    max_lag: Option<f32>,
+}
+
+/// Arguments of the `index` subcommand.
+#[derive(Args, Debug)]
+struct IndexArgs {
+    /// Directory of the audio files to index
+    #[arg()]
+    input_dir: String,
+
+    /// Path to the index file to write, with one fingerprint in JSON per line
+    #[arg()]
+    path_out: String,
+
+    /// Also index the files in subdirectories
+    #[arg(long)]
+    recursive: bool,
+
+    /// Extensions of the audio files in the input directory (default: wav)
+    #[arg(long, value_delimiter = ',', default_value = "wav")]
+    extensions: Vec<String>,
+
+    #[command(flatten)]
+    novelty: NoveltyArgs,
+}
+
+/// Arguments of the `query` subcommand.
+#[derive(Args, Debug)]
+struct QueryArgs {
+    /// Path to an index file written by the index subcommand
+    #[arg()]
+    index: String,
+
+    #[command(flatten)]
+    input: NoveltyInputArgs,
+
+    /// Path to the output CSV file with the best matches, the start of the query within them in seconds, and their scores
+    #[arg()]
+    path_out: String,
+
+    /// Number of matches to report, best first (default: 10)
+    #[arg(long, default_value_t = 10)]
+    top: usize,
+}
+
+/// Arguments of the `duplicates` subcommand.
+#[derive(Args, Debug)]
+struct DuplicatesArgs {
+    /// Path to an index file written by the index subcommand
+    #[arg()]
+    index: String,
+
+    /// Path to the output CSV file with pairs of recordings, the start of the second within the first and their overlap in seconds, and their scores
+    #[arg()]
+    path_out: String,
+
+    /// Smallest score of a reported pair, from -1 to 1 (default: 0.8)
+    #[arg(long, default_value_t = 0.8)]
+    threshold: f32,
+
+    /// Shortest overlap in seconds to report, or the whole shorter recording if it is shorter (default: 10)
+    #[arg(long, default_value_t = 10.0)]
+    min_overlap: f32,
+
+    /// Only report pairs whose tempo estimates differ by at most this fraction, e.g. 0.04 (default: ignore tempo)
+    #[arg(long)]
+    tempo_tolerance: Option<f32>,
+}
+
+/// Arguments of the `stats` subcommand.
+#[derive(Args, Debug)]
+struct StatsArgs {
+    /// Path to the input mono audio file (WAV), - to read a WAV stream from standard input, or a directory of audio files
+    #[arg()]
+    path_in: String,
+
+    /// Path to the output CSV file with the dynamics of each file, and of the whole corpus for a directory
+    #[arg()]
+    path_out: String,
+
+    /// Also include the files in subdirectories of an input directory
+    #[arg(long)]
+    recursive: bool,
+
+    /// Extensions of the audio files in an input directory (default: wav)
+    #[arg(long, value_delimiter = ',', default_value = "wav")]
+    extensions: Vec<String>,
+
+    #[command(flatten)]
+    novelty: NoveltyArgs,
+
+    /// Onset peaks below this fraction of the maximum novelty are discarded for the onset density (default: 0.1)
+    #[arg(long, default_value_t = 0.1)]
+    onset_threshold: f32,
+}
+
+/// Arguments of the `activity` subcommand.
+#[derive(Args, Debug)]
+struct ActivityArgs {
+    /// Path to the input mono audio file (WAV), or - to read a WAV stream from standard input
+    #[arg()]
+    path_in: String,
+
+    /// Path to the output CSV file with the start and end of each segment in seconds and whether it is active
+    #[arg()]
+    path_out: String,
+
+    #[command(flatten)]
+    novelty: NoveltyArgs,
+
+    /// Level in dB relative to the loudest frame above which the recording becomes active (default: -30)
+    #[arg(long, default_value_t = -30.0, allow_negative_numbers = true)]
+    high: f32,
+
+    /// Level in dB relative to the loudest frame below which the recording becomes inactive (default: -40)
+    #[arg(long, default_value_t = -40.0, allow_negative_numbers = true)]
+    low: f32,
+
+    /// Shortest active segment in seconds; shorter ones become inactive (default: 0.1)
+    #[arg(long, default_value_t = 0.1)]
+    min_active: f32,
+
+    /// Shortest inactive segment in seconds between active ones; shorter ones become active (default: 0.3)
+    #[arg(long, default_value_t = 0.3)]
+    min_inactive: f32,
+
+    /// Label each active segment as speech, music, or other instead of active and add the low-energy ratio, high zero-crossing rate ratio, and spectral flatness as columns
+    #[arg(long)]
+    classify: bool,
+
+    /// Also write one WAV file per active segment to this directory
+    #[arg(long)]
+    export_segments: Option<String>,
+
+    #[command(flatten)]
+    timecode: TimecodeArgs,
+}
+
+/// Arguments of the `thumbnail` subcommand.
+#[derive(Args, Debug)]
+struct ThumbnailArgs {
+    /// Path to the input mono audio file (WAV), or - to read a WAV stream from standard input
+    #[arg()]
+    path_in: String,
+
+    /// Path to the output CSV file
+    #[arg()]
+    path_out: String,
+
+    #[command(flatten)]
+    novelty: NoveltyArgs,
+
+    /// Length of the excerpt in seconds (default: 10.0)
+    #[arg(long, default_value_t = 10.0)]
+    duration: f32,
+
+    /// Select the excerpt with the most novelty or the one most similar to the whole recording (default: eventful)
+    #[arg(long, value_enum, default_value_t = CriterionArg::Eventful)]
+    criterion: CriterionArg,
+
+    /// Also write the excerpt to this WAV file
+    #[arg(long)]
+    export: Option<String>,
+}
+
+/// Arguments of the `loops` subcommand.
+#[derive(Args, Debug)]
+struct LoopsArgs {
+    /// Path to the input mono audio file (WAV), or - to read a WAV stream from standard input
+    #[arg()]
+    path_in: String,
+
+    /// Path to the output CSV file with the start and end of each loop in seconds and samples and its score, best first
+    #[arg()]
+    path_out: String,
+
+    #[command(flatten)]
+    novelty: NoveltyArgs,
+
+    /// Novelty below this fraction of the maximum counts as sustained (default: 0.2)
+    #[arg(long, default_value_t = 0.2)]
+    sustain_threshold: f32,
+
+    /// Shortest loop in seconds (default: 0.5)
+    #[arg(long, default_value_t = 0.5)]
+    min_length: f32,
+
+    /// Longest loop in seconds (default: no limit)
+    #[arg(long)]
+    max_length: Option<f32>,
+
+    /// Length in seconds of the waveforms compared around the loop start and end (default: 0.01)
+    #[arg(long, default_value_t = 0.01)]
+    match_window: f32,
+
+    /// Largest shift in seconds by which the loop end is moved to match the waveform at the start (default: 0.005)
+    #[arg(long, default_value_t = 0.005)]
+    search: f32,
+
+    /// Number of loops to write (default: 5)
+    #[arg(long, default_value_t = 5)]
+    count: usize,
+}
+
+/// Arguments of the `verify` subcommand.
+#[derive(Args, Debug)]
+struct VerifyArgs {
+    /// Paths to outputs written with --provenance, whose <output>.prov.json sidecars are checked
+    #[arg(required = true)]
+    outputs: Vec<String>,
+}
+
+/// Arguments of the `qc` subcommand.
+#[derive(Args, Debug)]
+struct QcArgs {
+    /// Paths to the input audio files or directories of audio files
+    #[arg(required = true)]
+    inputs: Vec<String>,
+
+    /// Also write the measurements and failures of each file to this CSV file
+    #[arg(long)]
+    report: Option<String>,
+
+    /// Also check the files in subdirectories of input directories
+    #[arg(long)]
+    recursive: bool,
+
+    /// Extensions of the audio files in input directories (default: wav)
+    #[arg(long, value_delimiter = ',', default_value = "wav")]
+    extensions: Vec<String>,
+
+    /// Largest total duration of clipped regions in seconds (default: 0)
+    #[arg(long, default_value_t = 0.0)]
+    max_clipping: f32,
+
+    /// Largest total duration of dropouts in seconds (default: 0)
+    #[arg(long, default_value_t = 0.0)]
+    max_dropouts: f32,
+
+    /// Largest absolute mean of the samples of any channel (default: 0.01)
+    #[arg(long, default_value_t = 0.01)]
+    max_dc_offset: f32,
+
+    /// Largest percentage of silence, in blocks of 50 ms below -60 dBFS (default: 50)
+    #[arg(long, default_value_t = 50.0)]
+    max_silence: f32,
+
+    /// Shortest allowed duration in seconds (default: no limit)
+    #[arg(long)]
+    min_duration: Option<f32>,
+
+    /// Longest allowed duration in seconds (default: no limit)
+    #[arg(long)]
+    max_duration: Option<f32>,
+}
+
+/// Arguments of the `pipeline` subcommand.
+#[derive(Args, Debug)]
+struct PipelineArgs {
+    /// Path to the pipeline specification in TOML, with [[stage]] tables for preprocess, detector, smooth, peak-pick, and export
+    #[arg()]
+    spec: PathBuf,
+
+    /// Paths to the input mono audio files, whose file stems fill the {stem} placeholder of the export paths
+    #[arg(required = true)]
+    inputs: Vec<String>,
+}
+
+/// Arguments of the `view` subcommand.
+#[derive(Args, Debug)]
+struct ViewArgs {
+    /// Paths to the input audio files, or novelty CSV files written with --provenance, whose sidecars name their audio
+    #[arg(required = true)]
+    inputs: Vec<String>,
+
+    /// Address to serve the page on (default: 127.0.0.1:8080)
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    address: String,
+
+    #[command(flatten)]
+    novelty: NoveltyArgs,
+
+    /// Feature rate of novelty CSV inputs (default: inferred from the time column)
+    #[arg(long)]
+    feature_rate: Option<f32>,
+
+    /// Onset peaks below this fraction of the maximum novelty are not shown (default: 0.1)
+    #[arg(long, default_value_t = 0.1)]
+    onset_threshold: f32,
+}
+
+/// Arguments of the `features` subcommand.
+#[derive(Args, Debug)]
+struct FeaturesArgs {
+    /// Path to the input mono audio file (WAV), or - to read a WAV stream from standard input
+    #[arg()]
+    path_in: String,
+
+    /// Path to the output NPZ file
+    #[arg()]
+    path_out: String,
+
+    #[command(flatten)]
+    novelty: NoveltyArgs,
+
+    /// Label the onsets listed in the first column of this CSV or text file, in seconds, instead of the detected onsets
+    #[arg(long)]
+    annotations: Option<String>,
+
+    /// Detected onset peaks below this fraction of the maximum novelty are discarded (default: 0.1)
+    #[arg(long, default_value_t = 0.1, conflicts_with = "annotations")]
+    onset_threshold: f32,
+}
+
+/// Thumbnail criteria selectable on the command line.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum CriterionArg {
+    Eventful,
+    Representative,
+}
+
+impl From<CriterionArg> for ThumbnailCriterion {
+    fn from(criterion: CriterionArg) -> Self {
+        match criterion {
+            CriterionArg::Eventful => ThumbnailCriterion::Eventful,
+            CriterionArg::Representative => ThumbnailCriterion::Representative,
+        }
+    }
+}
+
+/// Arguments of the `batch` subcommand.
+#[derive(Args, Debug)]
+struct BatchArgs {
+    /// Directory with the input audio files, as an alternative to --manifest-in
+    #[arg(required_unless_present = "manifest_in", conflicts_with = "manifest_in")]
+    input_dir: Option<String>,
+
+    /// CSV file with a `path` column listing the inputs, an optional `output` column, and optional parameter columns such as `window_length`
+    #[arg(long)]
+    manifest_in: Option<String>,
+
+    /// Also process the subdirectories of the input directory, mirroring them under the output directory
+    #[arg(long)]
+    recursive: bool,
+
+    /// Extensions of the audio files in the input directory (default: wav)
+    #[arg(long, value_delimiter = ',', default_value = "wav")]
+    extensions: Vec<String>,
+
+    /// Directory for the outputs of files without an output in the manifest (default: next to the input)
+    #[arg(long)]
+    output_dir: Option<String>,
+
+    /// File name template for the outputs of files without an output in the manifest, e.g. {stem}_{method}_w{window}.csv, with the placeholders {stem}, {method}, {window}, {hop}, {compression}, {gamma}, and {mels} (default: {stem} with the extension of the output format)
+    #[arg(long)]
+    output_template: Option<String>,
+
+    #[command(flatten)]
+    novelty: NoveltyArgs,
+
+    #[command(flatten)]
+    summary: SummaryArgs,
+
+    #[command(flatten)]
+    csv: CsvArgs,
+
+    /// Number of files to process concurrently, 0 for one per CPU core (default: 1)
+    #[arg(long, default_value_t = 1)]
+    jobs: usize,
+
+    /// Skip files whose output already exists, e.g. to resume an interrupted batch
+    #[arg(long)]
+    skip_existing: bool,
+
+    /// Only skip existing outputs that were computed with the same parameters
+    #[arg(long, requires = "skip_existing")]
+    check_params: bool,
+
+    /// Write a report with the status, duration, onset count, tempo, and errors of all files to this JSON or CSV file
+    #[arg(long)]
+    report: Option<String>,
+
+    /// Append the novelty functions and onsets of all files to this SQLite database
+    #[arg(long)]
+    db: Option<String>,
+
+    /// Also store the novelty functions, mel spectrograms, sampling rates, and parameters of all processed files in this HDF5 file, with one group per file
+    #[arg(long)]
+    hdf5: Option<String>,
+
+    /// Coordinate workers instead of processing the files here: listen on this address, e.g. 0.0.0.0:7878, and hand out the files to worker instances, which need the same paths, e.g. on a shared file system
+    #[arg(long, value_name = "ADDRESS", conflicts_with_all = ["db", "hdf5", "summary", "tempo_histogram"])]
+    serve: Option<String>,
+
+    /// Seconds a worker may take for one file before it is considered hung and the file is handed out again (default: 3600)
+    #[arg(long, default_value_t = 3600.0, requires = "serve")]
+    worker_timeout: f32,
+
+    /// Serve metrics of the files processed so far for Prometheus at http://ADDRESS/metrics while the batch runs
+    #[arg(long, value_name = "ADDRESS")]
+    metrics: Option<String>,
+}
+
+/// Arguments of the `worker` subcommand.
+#[derive(Args, Debug)]
+struct WorkerArgs {
+    /// Address of the coordinator, e.g. coordinator.local:7878
+    #[arg()]
+    coordinator: String,
+
+    /// Number of files to process concurrently, 0 for one per CPU core (default: 1)
+    #[arg(long, default_value_t = 1)]
+    jobs: usize,
+
+    /// Serve metrics of the files processed so far for Prometheus at http://ADDRESS/metrics while the worker runs
+    #[arg(long, value_name = "ADDRESS")]
+    metrics: Option<String>,
+}
+
+/// Outcome of one successfully handled batch entry.
+enum BatchOutcome {
+    Processed(Option<Summary>),
+    Skipped,
+}
+
+impl BatchOutcome {
+    /// Status of a batch entry with this result, as counted in reports and metrics.
+    fn status(result: &anyhow::Result<BatchOutcome>) -> BatchStatus {
+        match result {
+            Ok(BatchOutcome::Processed(_)) => BatchStatus::Processed,
+            Ok(BatchOutcome::Skipped) => BatchStatus::Skipped,
+            Err(_) => BatchStatus::Failed,
+        }
+    }
+}
+
+/// Batch entries with the results of their processing, in the order they finished.
+type BatchResults<'a> = Vec<(&'a ManifestEntry, anyhow::Result<BatchOutcome>)>;
+
+/// Message from the coordinator of a distributed batch to a worker, sent as one line of JSON.
+#[derive(Serialize, Deserialize)]
+enum CoordinatorMessage {
+    /// Command line of the batch, from which the worker takes the processing options
+    Batch(Vec<String>),
+    /// Entry to process next
+    Process(ManifestEntry),
+    /// All entries are done
+    Done,
+}
+
+/// Reply of a worker to a [`CoordinatorMessage::Process`], sent as one line of JSON.
+#[derive(Serialize, Deserialize)]
+enum WorkerMessage {
+    Processed(Option<Summary>),
+    Skipped,
+    Failed(String),
+}
+
+/// Interval in which the coordinator checks for new workers and requeued entries.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Number of times an entry is handed out without a reply before it is recorded as failed,
+/// so a file that crashes or hangs every worker doesn't keep the batch from finishing.
+const MAX_ATTEMPTS: usize = 3;
+
+/// JSON report written by the `batch` subcommand with `--report`.
+#[derive(Serialize)]
+struct BatchReport<'a> {
+    processed: usize,
+    skipped: usize,
+    failed: usize,
+    files: &'a [BatchRecord],
+}
+
+/// JSON report written by the `analyze` subcommand.
+#[derive(Serialize)]
+struct Report<'a> {
+    path: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    broadcast: Option<BroadcastInfo>,
+    #[serde(flatten)]
+    analysis: &'a Analysis,
+}
+
+/// Serializes appending to summary files across batch workers.
+static SUMMARY_LOCK: Mutex<()> = Mutex::new(());
+
+/// Number of samples decoded at once when streaming inputs over a limit.
+const STREAM_BLOCK_SAMPLES: usize = 65536;
+
+/// Validates that the output file does not already exist.
+fn validate_output(path_out: &str) -> anyhow::Result<()> {
+    if is_object_url(path_out) {
+        anyhow::bail!("Object storage URLs are only supported for novelty function outputs, not {}", path_out);
+    }
+    if Path::new(path_out).exists() {
+        anyhow::bail!("Output path must not already exist!");
+    }
+    Ok(())
+}
+
+/// Computes the novelty function and writes it to a file in the selected format.
+///
+/// Returns the novelty function, the aggregate statistics if they are appended to a summary
+/// file or if `with_summary` requests them anyway, and the mel spectrogram of the audio and its
+/// sampling rate if `with_mel` requests them, computed from the audio already read.
+#[allow(clippy::too_many_arguments)]
+fn run_novelty(
+    path_in: &str,
+    path_out: &str,
+    novelty_args: &NoveltyArgs,
+    sonify: Option<(&str, Carrier)>,
+    summary_args: &SummaryArgs,
+    csv_args: &CsvArgs,
+    with_summary: bool,
+    with_mel: bool,
+) -> anyhow::Result<(NoveltyCurve, Option<Summary>, Option<(Array2<f32>, u32)>)> {
+    let (started, timer) = (SystemTime::now(), Instant::now());
+    let format = csv_args.format(path_out)?;
+    if csv_args.time_unit != TimeUnitArg::Seconds && format != OutputFormatArg::Csv {
+        anyhow::bail!("--time-unit only applies to CSV output");
+    }
+    if csv_args.provenance && (path_in == "-" || path_out == "-") {
+        anyhow::bail!("--provenance needs an input and an output file, not standard input or output");
+    }
+    if path_out == "-" {
+        if novelty_args.limits.checkpoint {
+            anyhow::bail!("--checkpoint needs an output file, not standard output");
+        } else if format != OutputFormatArg::Csv {
+            anyhow::bail!("Only CSV can be written to standard output");
+        }
+    } else if is_object_url(path_out) {
+        if novelty_args.limits.checkpoint {
+            anyhow::bail!("--checkpoint needs a local output file, not {}", path_out);
+        }
+    } else {
+        validate_output(path_out)?;
+    }
+    if let Some((path_sonify, _)) = sonify {
+        validate_output(path_sonify)?;
+    }
+    let config = novelty_args.config()?;
+
+    // inputs and outputs in object storage go through local temporary files
+    let output = (path_out != "-").then(|| StagedOutput::create(path_out)).transpose()?;
+    let input = StagedInput::fetch(path_in)?;
+    let (source, destination) = (path_in, path_out);
+    log_event(Level::Info, "file_started", None, json!({ "path": source }));
+    let path_in = input.path();
+    let path_out = output.as_ref().map_or(path_out, |output| output.path());
+
+    // inputs over --max-duration or --max-memory are streamed in blocks if requested, and all
+    // inputs with --checkpoint
+    let (novelty, columns, artifacts, mel, fs) = match novelty_args.limits.exceeded(path_in)? {
+        Some(reason) if novelty_args.limits.on_limit != LimitActionArg::Stream => anyhow::bail!("{} exceeds the limits: {}", source, reason),
+        reason if reason.is_some() || novelty_args.limits.checkpoint => {
+            if novelty_args.stereo.is_some() || !csv_args.descriptors.is_empty() || novelty_args.exclude_artifacts {
+                match reason {
+                    Some(reason) => anyhow::bail!(
+                        "{} exceeds the limits ({}) and can't be streamed with --stereo, --descriptors, or --exclude-artifacts",
+                        source,
+                        reason
+                    ),
+                    None => anyhow::bail!("--checkpoint streams the input and can't be combined with --stereo, --descriptors, or --exclude-artifacts"),
+                }
+            }
+            if with_mel {
+                anyhow::bail!("{} is streamed, so its mel spectrogram can't be stored with --hdf5", source);
+            }
+            let (novelty, fs) = stream_novelty(path_in, path_out, &config, &novelty_args.limits)?;
+            (novelty, Vec::new(), Vec::new(), None, fs)
+        }
+        _ => {
+            // get audio file, with the side channel as second signal in mid/side mode
+            let (audio_array, side, fs) = match novelty_args.stereo {
+                Some(StereoArg::MidSide) => {
+                    let (channels, fs) = novelty_args.read_channels(path_in, source)?;
+                    let (mid, side) = mid_side(&channels)?;
+                    (mid, Some(side), fs)
+                }
+                _ => {
+                    let (audio_array, fs) = novelty_args.read_audio(path_in)?;
+                    (audio_array, None, fs)
+                }
+            };
+
+            // the side novelty comes first, followed by additional descriptors with the same framing
+            let mut columns: Vec<(&str, _)> = Vec::new();
+            if let Some(side) = side {
+                columns.push(("side", config.compute(side, fs)?.values));
+            }
+            let descriptors = csv_args.descriptors();
+            let descriptor_values = compute_descriptors(&audio_array, fs, config.window_length, config.hop_length, &descriptors);
+            columns.extend(descriptors.iter().map(|d| d.name()).zip(descriptor_values));
+
+            // clipped regions and dropouts are reported, and optionally excluded from normalization
+            let artifacts = detect_artifacts(&audio_array, fs);
+            if !artifacts.is_empty() {
+                let clipped = artifacts.iter().filter(|artifact| artifact.kind == ArtifactKind::Clipping).count();
+                log_event(
+                    Level::Warning,
+                    "artifacts_detected",
+                    Some(format!("{} has {} clipped regions and {} dropouts", source, clipped, artifacts.len() - clipped)),
+                    json!({ "path": source, "clipping": clipped, "dropouts": artifacts.len() - clipped }),
+                );
+            }
+
+            let mel = with_mel.then(|| {
+                let mel = mel_features(&audio_array, fs, config.window_length, config.hop_length, config.n_mels, config.compression, config.gamma);
+                (mel, fs)
+            });
+
+            // compute novelty function
+            let mut novelty = config.compute(audio_array, fs)?;
+            if novelty_args.exclude_artifacts && config.norm {
+                normalize_excluding(&mut novelty, &artifacts, config.window_length as f32 / fs as f32);
+            }
+            (novelty, columns, artifacts, mel, fs)
+        }
+    };
+
+    // render the novelty function as audio if requested
+    if let Some((path_sonify, carrier)) = sonify {
+        let sonification = sonify_novelty(&novelty, fs, carrier);
+        write_wav(path_sonify, &sonification, fs)?;
+    }
+
+    // append aggregate statistics if requested, one file at a time in batch mode
+    let need_summary = summary_args.summary.is_some() || with_summary;
+    let tempogram = (need_summary || summary_args.tempo_histogram.is_some()).then(|| default_tempogram(&novelty));
+    let summary = tempogram.as_ref().filter(|_| need_summary).map(|tempogram| {
+        let onsets = detect_onsets(&novelty, summary_args.onset_threshold);
+        summarize(&novelty, &onsets, estimate_tempo(tempogram)).with_artifacts(&artifacts)
+    });
+    if let (Some(path_summary), Some(summary)) = (&summary_args.summary, &summary) {
+        let _lock = SUMMARY_LOCK.lock().expect("Summary writer panicked");
+        write_summary_csv(path_summary, source, summary)?;
+    }
+    if let (Some(path_histogram), Some(tempogram)) = (&summary_args.tempo_histogram, &tempogram) {
+        if let Some(salience) = tempo_salience(tempogram) {
+            let _lock = SUMMARY_LOCK.lock().expect("Summary writer panicked");
+            write_tempo_histogram_csv(path_histogram, source, &tempogram.bpm, &salience)?;
+        }
+    }
+
+    // write result, where PNG plots only the novelty function
+    // in double precision, so a time of day keeps the times accurate to the sample
+    let mut offset = FrameTiming::from(csv_args.frame_timing).offset(config.window_length, fs) as f64;
+    if csv_args.timecode_offset {
+        let timecode = broadcast_info(path_in)?.and_then(|info| info.timecode_offset(fs));
+        offset += timecode.ok_or_else(|| anyhow::anyhow!("{} has no BWF time reference for --timecode-offset", source))?;
+    }
+    let time_unit = csv_args.time_unit.unit(fs);
+    match format {
+        OutputFormatArg::Csv if path_out == "-" => {
+            write_csv_to(&mut std::io::stdout().lock(), novelty.values.clone(), novelty.fs_feature, offset, time_unit, &columns)?
+        }
+        OutputFormatArg::Csv => write_csv_with_columns(path_out, novelty.values.clone(), novelty.fs_feature, offset, time_unit, &columns)?,
+        OutputFormatArg::Json => write_novelty_json(path_out, novelty.values.clone(), novelty.fs_feature, offset, &columns)?,
+        OutputFormatArg::Npy => {
+            // one row per frame with the same columns as the CSV file
+            let num_frames = novelty.values.len();
+            let mut table = Array2::zeros((num_frames, 2 + columns.len()));
+            table.column_mut(0).assign(&(Array1::range(0.0, num_frames as f32, 1.0) / novelty.fs_feature + offset as f32));
+            table.column_mut(1).assign(&novelty.values);
+            for (k, (_, values)) in columns.iter().enumerate() {
+                table.column_mut(2 + k).assign(values);
+            }
+            let data = table.as_slice().expect("Array in standard layout");
+            write_npy(path_out, &NpyArray { shape: table.shape().to_vec(), data: NpyData::F32(data) })?
+        }
+        #[cfg(feature = "parquet")]
+        OutputFormatArg::Parquet => write_novelty_parquet(path_out, novelty.values.clone(), novelty.fs_feature, offset, &columns)?,
+        #[cfg(not(feature = "parquet"))]
+        OutputFormatArg::Parquet => unreachable!("Parquet output is rejected without the parquet feature"),
+        OutputFormatArg::Png => write_curve_png(path_out, novelty.values.view(), DEFAULT_PLOT_HEIGHT)?,
+    }
+    let output_sha256 = csv_args.provenance.then(|| sha256_file(path_out)).transpose()?;
+    if let Some(output) = output {
+        output.upload()?;
+    }
+
+    // the sidecar is written last, so it only exists for complete outputs
+    if let Some(output_sha256) = output_sha256 {
+        let provenance = Provenance {
+            tool: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+            input: source.to_string(),
+            input_sha256: sha256_file(path_in)?,
+            output: destination.to_string(),
+            output_sha256,
+            started: format_timestamp(started),
+            finished: format_timestamp(SystemTime::now()),
+            parameters: config,
+        };
+        write_path(&Provenance::sidecar_path(destination), &serde_json::to_vec_pretty(&provenance)?)?;
+    }
+    log_event(
+        Level::Info,
+        "file_finished",
+        None,
+        json!({
+            "path": source,
+            "output": destination,
+            "frames": novelty.values.len(),
+            "feature_rate": novelty.fs_feature,
+            "seconds": timer.elapsed().as_secs_f64(),
+        }),
+    );
+
+    Ok((novelty, summary, mel))
+}
+
+/// Progress of a streamed novelty computation, saved with --checkpoint.
+#[derive(Serialize, Deserialize)]
+struct Checkpoint {
+    /// Configuration the values were computed with as JSON, to reject resuming with other parameters
+    parameters: String,
+    /// Number of input samples consumed
+    samples: u64,
+    /// Number of novelty values flushed to the partial results file
+    values: usize,
+    /// State of the detector after the consumed samples
+    state: StreamingNovelty,
+}
+
+/// Computes the energy novelty of a mono WAV file block by block with [`StreamingNovelty`],
+/// so the decoded signal is never held in memory as a whole.
+///
+/// The result matches [`NoveltyConfig::compute`], including normalization, which is applied
+/// once all values are known. With --checkpoint, the unnormalized values are appended to
+/// `<path_out>.partial` and the detector state is saved to `<path_out>.checkpoint` every
+/// --checkpoint-interval seconds of audio. An existing checkpoint is resumed, and both files
+/// are removed once the computation is complete.
+fn stream_novelty(path_in: &str, path_out: &str, config: &NoveltyConfig, limits: &LimitArgs) -> anyhow::Result<(NoveltyCurve, u32)> {
+    let mut reader = BufReader::new(File::open(path_in)?);
+    let (sample_format, info) = read_wav_header(&mut reader)?;
+    if info.channels != 1 {
+        anyhow::bail!("Streaming requires a mono WAV file, {} has {} channels", path_in, info.channels);
+    }
+    let mut data = reader.take(info.frames.map_or(u64::MAX, |frames| frames * sample_format.bytes() as u64));
+
+    let path_checkpoint = format!("{}.checkpoint", path_out);
+    let path_partial = format!("{}.partial", path_out);
+    let parameters = serde_json::to_string(config)?;
+
+    // resume from the last checkpoint, dropping values flushed after it
+    let mut streaming = StreamingNovelty::new(config, info.fs)?;
+    let mut samples = 0;
+    let mut values = Vec::new();
+    if limits.checkpoint && Path::new(&path_checkpoint).exists() {
+        let checkpoint: Checkpoint = serde_json::from_reader(BufReader::new(File::open(&path_checkpoint)?))?;
+        if checkpoint.parameters != parameters {
+            anyhow::bail!("Checkpoint {} was saved with different parameters", path_checkpoint);
+        }
+        let partial = std::fs::read_to_string(&path_partial)?;
+        values = partial.lines().take(checkpoint.values).map(str::parse).collect::<Result<Vec<f32>, _>>()?;
+        if values.len() != checkpoint.values {
+            anyhow::bail!("Partial results {} are incomplete", path_partial);
+        }
+        let skip = checkpoint.samples * sample_format.bytes() as u64;
+        if std::io::copy(&mut (&mut data).take(skip), &mut std::io::sink())? != skip {
+            anyhow::bail!("Checkpoint {} is beyond the end of {}", path_checkpoint, path_in);
+        }
+        streaming = checkpoint.state;
+        samples = checkpoint.samples;
+        let position = samples as f32 / info.fs as f32;
+        log_event(
+            Level::Info,
+            "checkpoint_resumed",
+            Some(format!("Resuming {} at {:.1} s", path_in, position)),
+            json!({ "path": path_in, "position": position }),
+        );
+    }
+    let mut partial = if limits.checkpoint {
+        let mut partial = BufWriter::new(File::create(&path_partial)?);
+        values.iter().try_for_each(|value| writeln!(partial, "{}", value))?;
+        Some(partial)
+    } else {
+        None
+    };
+
+    let fs_feature = streaming.fs_feature();
+    let checkpoint_samples = ((limits.checkpoint_interval * info.fs as f32) as u64).max(1);
+    let mut flushed = values.len();
+    let mut block = Vec::new();
+    loop {
+        block.clear();
+        (&mut data).take((STREAM_BLOCK_SAMPLES * sample_format.bytes()) as u64).read_to_end(&mut block)?;
+        if block.is_empty() {
+            break;
+        }
+        let block_samples = decode_samples(&block, sample_format)?;
+        values.extend(streaming.push_samples(&block_samples).into_iter().map(|(_, value)| value));
+
+        // flush the values before the checkpoint that refers to them
+        let previous = samples;
+        samples += block_samples.len() as u64;
+        if let Some(partial) = partial.as_mut().filter(|_| samples / checkpoint_samples > previous / checkpoint_samples) {
+            values[flushed..].iter().try_for_each(|value| writeln!(partial, "{}", value))?;
+            partial.flush()?;
+            partial.get_ref().sync_data()?;
+            flushed = values.len();
+
+            let checkpoint = Checkpoint { parameters: parameters.clone(), samples, values: flushed, state: streaming.clone() };
+            let path_temp = format!("{}.tmp", path_checkpoint);
+            serde_json::to_writer(File::create(&path_temp)?, &checkpoint)?;
+            std::fs::rename(&path_temp, &path_checkpoint)?;
+            log_event(
+                Level::Info,
+                "frames_processed",
+                None,
+                json!({ "path": path_in, "frames": flushed, "position": samples as f32 / info.fs as f32 }),
+            );
+        }
+    }
+    values.extend(streaming.finish().into_iter().map(|(_, value)| value));
+
+    if partial.is_some() {
+        for path in [&path_checkpoint, &path_partial] {
+            if Path::new(path).exists() {
+                std::fs::remove_file(path)?;
+            }
+        }
+    }
+
+    let mut values = Array1::from_vec(values);
+    let max_value = values.fold(0.0_f32, |acc, &v| acc.max(v));
+    if config.norm && max_value > 0.0 {
+        values.mapv_inplace(|v| v / max_value);
+    }
+    Ok((NoveltyCurve { values, fs_feature }, info.fs))
+}
+
+/// Computes the novelty functions of all files in a manifest.
+fn run_batch(args: &BatchArgs) -> anyhow::Result<()> {
+    let timer = Instant::now();
+    let entries = match (&args.manifest_in, &args.input_dir) {
+        (Some(manifest_in), _) => read_manifest(manifest_in)?,
+        (None, Some(input_dir)) => directory_entries(Path::new(input_dir), args)?,
+        (None, None) => anyhow::bail!("Either an input directory or --manifest-in is required!"),
+    };
+    check_batch_outputs(args, &entries)?;
+    if let Some(path_report) = &args.report {
+        validate_output(path_report)?;
+    }
+    let database = args.db.as_deref().map(ResultsDatabase::open).transpose()?.map(Mutex::new);
+    let hdf5 = match &args.hdf5 {
+        Some(path) => {
+            validate_output(path)?;
+            let mut hdf5 = Hdf5Writer::create(path)?;
+            hdf5.set_attribute("tool", Hdf5Value::Text(format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))))?;
+            Some(Mutex::new(hdf5))
+        }
+        None => None,
+    };
+    if let Some(output_dir) = args.output_dir.as_ref().filter(|output_dir| !is_object_url(output_dir)) {
+        std::fs::create_dir_all(output_dir)?;
+    }
+
+    let metrics = Metrics::default();
+    let results = with_metrics(args.metrics.as_deref(), &metrics, || match &args.serve {
+        Some(address) => {
+            let timeout = Duration::try_from_secs_f32(args.worker_timeout).ok().filter(|timeout| !timeout.is_zero());
+            let timeout = timeout.ok_or_else(|| anyhow::anyhow!("--worker-timeout must be greater than zero"))?;
+            serve_batch(address, &entries, &metrics, timeout)
+        }
+        None => {
+            // workers take the next unprocessed entry until all are done
+            let next = AtomicUsize::new(0);
+            let results = Mutex::new(Vec::with_capacity(entries.len()));
+            std::thread::scope(|scope| {
+                for _ in 0..num_jobs(args.jobs).min(entries.len()) {
+                    scope.spawn(|| {
+                        while let Some(entry) = entries.get(next.fetch_add(1, Ordering::Relaxed)) {
+                            let timer = Instant::now();
+                            let result = process_batch_entry(args, entry, database.as_ref(), hdf5.as_ref());
+                            metrics.record(BatchOutcome::status(&result), timer.elapsed().as_secs_f64());
+                            results.lock().expect("Batch worker panicked").push((entry, result));
+                        }
+                    });
+                }
+            });
+            Ok(results.into_inner().expect("Batch worker panicked"))
+        }
+    })?;
+
+    if let Some(hdf5) = hdf5 {
+        hdf5.into_inner().expect("HDF5 writer panicked").finish()?;
+    }
+
+    // report failures without aborting the other files
+    let failures: Vec<_> = results.iter().filter_map(|(entry, result)| Some((entry, result.as_ref().err()?))).collect();
+    for (entry, error) in &failures {
+        log_event(
+            Level::Error,
+            "file_failed",
+            Some(format!("Failed to process {}: {:#}", entry.path_in, error)),
+            json!({ "path": entry.path_in, "error": format!("{:#}", error) }),
+        );
+    }
+    let skipped = results.iter().filter(|(_, result)| matches!(result, Ok(BatchOutcome::Skipped))).count();
+    let succeeded = results.len() - failures.len() - skipped;
+    log_event(
+        Level::Info,
+        "batch_finished",
+        Some(format!("Processed {} files: {} succeeded, {} skipped, {} failed", results.len(), succeeded, skipped, failures.len())),
+        json!({
+            "files": results.len(),
+            "succeeded": succeeded,
+            "skipped": skipped,
+            "failed": failures.len(),
+            "seconds": timer.elapsed().as_secs_f64(),
+        }),
+    );
+
+    if let Some(path_report) = &args.report {
+        // report the files in manifest order regardless of which worker finished first
+        let mut records: Vec<(usize, BatchRecord)> = results
+            .iter()
+            .map(|(entry, result)| {
+                let index = entries.iter().position(|e| std::ptr::eq(e, *entry)).unwrap_or(0);
+                let (summary, error) = match result {
+                    Ok(BatchOutcome::Processed(summary)) => (*summary, None),
+                    Ok(BatchOutcome::Skipped) => (None, None),
+                    Err(error) => (None, Some(format!("{:#}", error))),
+                };
+                let record = BatchRecord {
+                    path: entry.path_in.clone(),
+                    output: batch_output(args, entry).map(|path| path.to_string_lossy().into_owned()).unwrap_or_default(),
+                    status: BatchOutcome::status(result),
+                    summary,
+                    error,
+                };
+                (index, record)
+            })
+            .collect();
+        records.sort_by_key(|(index, _)| *index);
+        let records: Vec<BatchRecord> = records.into_iter().map(|(_, record)| record).collect();
+
+        if path_report.ends_with(".json") {
+            let report = BatchReport {
+                processed: results.len() - failures.len() - skipped,
+                skipped,
+                failed: failures.len(),
+                files: &records,
+            };
+            serde_json::to_writer_pretty(std::fs::File::create(path_report)?, &report)?;
+        } else {
+            write_batch_report_csv(path_report, &records)?;
+        }
+    }
+
+    if !failures.is_empty() {
+        anyhow::bail!("{} of {} files failed", failures.len(), results.len());
+    }
+    Ok(())
+}
+
+/// Returns the number of concurrent jobs, with 0 for one per CPU core.
+fn num_jobs(jobs: usize) -> usize {
+    match jobs {
+        0 => std::thread::available_parallelism().map_or(1, |jobs| jobs.get()),
+        jobs => jobs,
+    }
+}
+
+/// Writes a message of the distributed batch protocol as one line of JSON.
+fn send_message<T: Serialize>(stream: &mut TcpStream, message: &T) -> anyhow::Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Reads a message of the distributed batch protocol from one line of JSON.
+fn receive_message<T: DeserializeOwned>(reader: &mut BufReader<TcpStream>) -> anyhow::Result<T> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        anyhow::bail!("Connection closed");
+    }
+    Ok(serde_json::from_str(&line)?)
+}
+
+/// Hands out the batch entries to the workers that connect to `address` and collects their
+/// results, until all entries are done. The entry of a worker that disconnects or doesn't
+/// reply within `timeout` is handed out again, up to [`MAX_ATTEMPTS`] times, so workers can
+/// join and leave at any time.
+fn serve_batch<'a>(address: &str, entries: &'a [ManifestEntry], metrics: &Metrics, timeout: Duration) -> anyhow::Result<BatchResults<'a>> {
+    let listener = TcpListener::bind(address)?;
+    listener.set_nonblocking(true)?;
+    let local_address = listener.local_addr()?.to_string();
+    log_event(
+        Level::Info,
+        "coordinator_listening",
+        Some(format!("Waiting for workers on {}", local_address)),
+        json!({ "address": local_address }),
+    );
+
+    let command_line: Vec<String> = std::env::args().collect();
+    // entries with the number of times they were handed out without a reply
+    let queue: Mutex<VecDeque<(&ManifestEntry, usize)>> = Mutex::new(entries.iter().map(|entry| (entry, 0)).collect());
+    let results = Mutex::new(Vec::with_capacity(entries.len()));
+    let is_done = || results.lock().expect("Batch coordinator panicked").len() == entries.len();
+    std::thread::scope(|scope| {
+        while !is_done() {
+            match listener.accept() {
+                Ok((stream, worker)) => {
+                    log_event(
+                        Level::Info,
+                        "worker_connected",
+                        Some(format!("Worker {} connected", worker)),
+                        json!({ "worker": worker.to_string() }),
+                    );
+                    let (command_line, queue, results, is_done) = (&command_line, &queue, &results, &is_done);
+                    scope.spawn(move || {
+                        if let Err(error) = serve_worker(stream, command_line, queue, results, metrics, timeout, is_done) {
+                            log_event(
+                                Level::Warning,
+                                "worker_disconnected",
+                                Some(format!("Worker {} disconnected: {:#}", worker, error)),
+                                json!({ "worker": worker.to_string(), "error": format!("{:#}", error) }),
+                            );
+                        }
+                    });
+                }
+                Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => std::thread::sleep(POLL_INTERVAL),
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    })?;
+    Ok(results.into_inner().expect("Batch coordinator panicked"))
+}
+
+/// Sends the batch command line and then one entry at a time to a connected worker, until
+/// all entries are done.
 ///
 /// # Errors
-/// Returns an error if the file can't be read or if it is not mono.
-fn audio_path_to_array(path: &str) -> anyhow::Result<(Array1<f32>, u32)> {
-    let reader: Wav<i16> = Wav::from_path(path)?;
+/// Returns an error if the connection fails or the worker doesn't reply to an entry within
+/// `timeout`, after requeueing the entry in progress or, after [`MAX_ATTEMPTS`], recording
+/// it as failed.
+fn serve_worker<'a>(
+    stream: TcpStream,
+    command_line: &[String],
+    queue: &Mutex<VecDeque<(&'a ManifestEntry, usize)>>,
+    results: &Mutex<BatchResults<'a>>,
+    metrics: &Metrics,
+    timeout: Duration,
+    is_done: impl Fn() -> bool,
+) -> anyhow::Result<()> {
+    stream.set_nonblocking(false)?;
+    // a hung worker that stays connected would otherwise hold its entry forever
+    stream.set_read_timeout(Some(timeout))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    send_message(&mut writer, &CoordinatorMessage::Batch(command_line.to_vec()))?;
+    loop {
+        let entry = queue.lock().expect("Batch coordinator panicked").pop_front();
+        let Some((entry, attempts)) = entry else {
+            if is_done() {
+                return send_message(&mut writer, &CoordinatorMessage::Done);
+            }
+            // wait for the entries of other workers, which are requeued if they disconnect
+            std::thread::sleep(POLL_INTERVAL);
+            continue;
+        };
+        let timer = Instant::now();
+        let reply = send_message(&mut writer, &CoordinatorMessage::Process(entry.clone()))
+            .and_then(|()| receive_message::<WorkerMessage>(&mut reader));
+        let result = match reply {
+            Ok(WorkerMessage::Processed(summary)) => Ok(BatchOutcome::Processed(summary)),
+            Ok(WorkerMessage::Skipped) => Ok(BatchOutcome::Skipped),
+            Ok(WorkerMessage::Failed(error)) => Err(anyhow::anyhow!(error)),
+            Err(error) => {
+                if attempts + 1 < MAX_ATTEMPTS {
+                    queue.lock().expect("Batch coordinator panicked").push_back((entry, attempts + 1));
+                } else {
+                    let result = Err(anyhow::anyhow!("No worker replied in {} attempts", MAX_ATTEMPTS));
+                    metrics.record(BatchOutcome::status(&result), timer.elapsed().as_secs_f64());
+                    results.lock().expect("Batch coordinator panicked").push((entry, result));
+                }
+                return Err(error.context(format!("No reply for {}", entry.path_in)));
+            }
+        };
+        metrics.record(BatchOutcome::status(&result), timer.elapsed().as_secs_f64());
+        results.lock().expect("Batch coordinator panicked").push((entry, result));
+    }
+}
 
-    if reader.n_channels() != 1 {
-        anyhow::bail!("Can only handle mono files currently. Please convert input audio file to mono.");
+/// Connects `--jobs` times to a batch coordinator and processes the entries it hands out
+/// with the options of its command line.
+fn run_worker(args: &WorkerArgs) -> anyhow::Result<()> {
+    let metrics = Metrics::default();
+    with_metrics(args.metrics.as_deref(), &metrics, || {
+        std::thread::scope(|scope| {
+            let workers: Vec<_> =
+                (0..num_jobs(args.jobs)).map(|_| scope.spawn(|| work_for(&args.coordinator, &metrics))).collect();
+            workers.into_iter().try_for_each(|worker| worker.join().expect("Batch worker panicked"))
+        })
+    })
+}
+
+/// Processes entries for a batch coordinator over one connection until it is done.
+fn work_for(address: &str, metrics: &Metrics) -> anyhow::Result<()> {
+    let stream = TcpStream::connect(address)?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    let CoordinatorMessage::Batch(command_line) = receive_message(&mut reader)? else {
+        anyhow::bail!("Expected the batch command line from the coordinator");
+    };
+    let Some(Command::Batch(args)) = Cli::try_parse_from(&command_line)?.command else {
+        anyhow::bail!("The coordinator didn't send a batch command line");
+    };
+
+    loop {
+        let entry = match receive_message(&mut reader)? {
+            CoordinatorMessage::Process(entry) => entry,
+            CoordinatorMessage::Done => return Ok(()),
+            CoordinatorMessage::Batch(_) => anyhow::bail!("Unexpected batch command line from the coordinator"),
+        };
+        let timer = Instant::now();
+        let result = process_batch_entry(&args, &entry, None, None);
+        metrics.record(BatchOutcome::status(&result), timer.elapsed().as_secs_f64());
+        let reply = match result {
+            Ok(BatchOutcome::Processed(summary)) => WorkerMessage::Processed(summary),
+            Ok(BatchOutcome::Skipped) => WorkerMessage::Skipped,
+            Err(error) => {
+                log_event(
+                    Level::Error,
+                    "file_failed",
+                    Some(format!("Failed to process {}: {:#}", entry.path_in, error)),
+                    json!({ "path": entry.path_in, "error": format!("{:#}", error) }),
+                );
+                WorkerMessage::Failed(format!("{:#}", error))
+            }
+        };
+        send_message(&mut writer, &reply)?;
     }
+}
 
-    let (samples, sample_rate): (Samples<i16>, i32) = read::<i16, _>(path)?;
-    let samples: Vec<f32> = samples.convert().to_vec();
-    let audio_array = Array::from_vec(samples);
+/// Runs `run` while serving `metrics` at `address`, if given, on another thread.
+fn with_metrics<T>(address: Option<&str>, metrics: &Metrics, run: impl FnOnce() -> anyhow::Result<T>) -> anyhow::Result<T> {
+    let Some(address) = address else {
+        return run();
+    };
+    let listener = TcpListener::bind(address)?;
+    listener.set_nonblocking(true)?;
+    let local_address = listener.local_addr()?.to_string();
+    log_event(
+        Level::Info,
+        "metrics_listening",
+        Some(format!("Serving metrics at http://{}/metrics", local_address)),
+        json!({ "address": local_address }),
+    );
 
-    Ok((audio_array, sample_rate as u32))
+    let is_done = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            if let Err(error) = serve_metrics(&listener, metrics, &is_done) {
+                log_event(
+                    Level::Warning,
+                    "metrics_failed",
+                    Some(format!("Stopped serving metrics: {:#}", error)),
+                    json!({ "error": format!("{:#}", error) }),
+                );
+            }
+        });
+        let result = run();
+        is_done.store(true, Ordering::Relaxed);
+        result
+    })
 }
 
-/// Computes an energy-based novelty function over the input audio signal.
-///
-/// This function calculates the short-time energy using a Hann window, applies optional
-/// logarithmic compression, computes the positive energy difference over time, and
-/// normalizes the result if specified.
-///
-/// # Arguments
-/// - `audio_array`: 1D array of mono audio samples
-/// - `fs`: Sampling rate of the audio
-/// - `window_length`: Size of the analysis window
-/// - `hop_length`: Step size between successive frames
-/// - `gamma`: Compression parameter for logarithmic scaling
-/// - `norm`: Whether to normalize the output between 0 and 1
+/// Answers HTTP requests for `/metrics` with the metrics in the Prometheus text format until
+/// `is_done` is set. Other paths are not found.
+fn serve_metrics(listener: &TcpListener, metrics: &Metrics, is_done: &AtomicBool) -> anyhow::Result<()> {
+    while !is_done.load(Ordering::Relaxed) {
+        let mut stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(POLL_INTERVAL);
+                continue;
+            }
+            Err(error) => return Err(error.into()),
+        };
+        stream.set_nonblocking(false)?;
+        let Some(path) = read_request_path(&stream)? else {
+            continue;
+        };
+        let (status, body) = match path.as_str() {
+            "/metrics" => ("200 OK", metrics.render()),
+            _ => ("404 Not Found", "Not found\n".to_string()),
+        };
+        // a client that went away doesn't stop serving the others
+        let _ = write_response(&mut stream, status, "text/plain; version=0.0.4", body.as_bytes());
+    }
+    Ok(())
+}
+
+/// Reads an HTTP request from a connection, up to the empty line after the headers.
 ///
 /// # Returns
-/// - A tuple of the novelty function and its effective sampling rate
-///
-/// # Errors
-/// Returns an error if convolution or array operations fail.
-fn novelty_energy(audio_array: Array1<f32>, fs: u32, window_length: u32, hop_length: u32 , gamma: f32, norm: bool) -> anyhow::Result<(Array1<f32>, f32)> {
-    // get window function
-    let hann_window = get_hann_window(window_length as usize).expect("Failed to get the Hann window");
-    let hann_window_array = Array::from_vec(hann_window);
+/// - The requested path without query string, or `None` if the request line can't be read
+fn read_request_path(stream: &TcpStream) -> anyhow::Result<Option<String>> {
+    stream.set_read_timeout(Some(POLL_INTERVAL * 10))?;
+    // the request line, e.g. "GET /metrics HTTP/1.1", followed by headers up to an empty line
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request = String::new();
+    let mut header = String::new();
+    if reader.read_line(&mut request).is_err() {
+        return Ok(None);
+    }
+    while reader.read_line(&mut header).is_ok_and(|count| count > 0 && !header.trim().is_empty()) {
+        header.clear();
+    }
+    let path = request.split_whitespace().nth(1).unwrap_or_default();
+    Ok(Some(path.split('?').next().unwrap_or_default().to_string()))
+}
 
-    // Compute the feature sampling rate
-    let fs_feature = (fs as f32) / (hop_length as f32);
+/// Writes an HTTP response that closes the connection.
+fn write_response(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8]) -> std::io::Result<()> {
+    let header = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    stream.write_all(header.as_bytes())?;
+    stream.write_all(body)
+}
 
-    // Compute local energy with squared window and signal
-    let energy_local = audio_array.powf(2.0).conv(&hann_window_array.powf(2.0), ConvMode::Same, PaddingMode::Zeros)?;
-    let mut energy_local_subsample = energy_local.slice_move(s![..;hop_length]);
+/// Lists the audio files of an input directory as batch entries, with the outputs mirroring
+/// the directory structure under the output directory.
+fn directory_entries(input_dir: &Path, args: &BatchArgs) -> anyhow::Result<Vec<ManifestEntry>> {
+    let files = find_files(input_dir, &args.extensions, args.recursive)?;
+    files
+        .iter()
+        .map(|path| {
+            let path_in = path.to_str().ok_or_else(|| anyhow::anyhow!("Invalid input path {}", path.display()))?;
+            let path_out = match &args.output_dir {
+                Some(output_dir) => {
+                    let relative = path.strip_prefix(input_dir).unwrap_or(path);
+                    let file_name = batch_file_name(args, path_in, &[])?;
+                    Some(Path::new(output_dir).join(relative).with_file_name(file_name).to_string_lossy().into_owned())
+                }
+                None => None,
+            };
+            Ok(ManifestEntry {
+                path_in: path_in.to_string(),
+                path_out,
+                overrides: Vec::new(),
+            })
+        })
+        .collect()
+}
 
-    // Apply logarithmic compression if gamma > 0
-    if gamma != 0.0 {
-        energy_local_subsample.mapv_inplace(|v| (1.0 + gamma * v).log(E));
+/// Computes the novelty function of one manifest entry, unless its output already exists and
+/// skipping is requested.
+fn process_batch_entry(
+    args: &BatchArgs,
+    entry: &ManifestEntry,
+    database: Option<&Mutex<ResultsDatabase>>,
+    hdf5: Option<&Mutex<Hdf5Writer>>,
+) -> anyhow::Result<BatchOutcome> {
+    let path_out = batch_output(args, entry)?;
+    let path_out = path_out.to_str().ok_or_else(|| anyhow::anyhow!("Invalid output path {}", path_out.display()))?;
+    if let Some(parent) = Path::new(path_out).parent().filter(|_| !is_object_url(path_out)) {
+        std::fs::create_dir_all(parent)?;
     }
+    let novelty_args = args.novelty.with_overrides(&entry.overrides)?;
 
-    // Compute the difference of consecutive energy values
-    let mut energy_local_diff = &energy_local_subsample.slice(s![1..]) - &energy_local_subsample.slice(s![..-1]);
+    // the parameter hash is written once the output is complete, so a missing hash also
+    // reveals outputs left incomplete by an interrupted run
+    let config = novelty_args.config()?;
+    let params_hash = parameter_hash(&config, novelty_args.stereo, &args.csv)?;
+    let path_params = format!("{}.params", path_out);
+    if args.skip_existing && path_exists(path_out)? {
+        if args.check_params {
+            let previous = read_path(&path_params).map(|bytes| String::from_utf8_lossy(&bytes).into_owned()).unwrap_or_default();
+            if previous.trim() != params_hash {
+                anyhow::bail!("Existing output {} was computed with different parameters or is incomplete", path_out);
+            }
+        }
+        return Ok(BatchOutcome::Skipped);
+    }
 
-    // Pad with a trailing zero to maintain the original length
-    energy_local_diff = concatenate(Axis(0), &[energy_local_diff.view(), Array::zeros(1).view()])?;
+    let with_summary = args.report.is_some() || database.is_some();
+    let (novelty, summary, mel) = run_novelty(&entry.path_in, path_out, &novelty_args, None, &args.summary, &args.csv, with_summary, hdf5.is_some())?;
+    if let Some(database) = database {
+        let onsets = detect_onsets(&novelty, args.summary.onset_threshold);
+        let tempo = summary.and_then(|summary| summary.tempo);
+        let mut database = database.lock().expect("Database writer panicked");
+        database.insert(&entry.path_in, &serde_json::to_string(&config)?, &novelty, &onsets, tempo)?;
+    }
+    if let (Some(hdf5), Some((mel, fs))) = (hdf5, mel) {
+        let attributes = [
+            ("path", Hdf5Value::Text(entry.path_in.clone())),
+            ("fs", Hdf5Value::Integer(fs as i64)),
+            ("fs_feature", Hdf5Value::Float(novelty.fs_feature as f64)),
+            ("parameters", Hdf5Value::Text(serde_json::to_string(&config)?)),
+        ];
+        let name = hdf5_group_name(&entry.path_in);
+        let datasets = [("novelty", novelty.values.view().into_dyn()), ("mel", mel.t().into_dyn())];
+        hdf5.lock().expect("HDF5 writer panicked").add_group(&name, &attributes, &datasets)?;
+    }
+    write_path(&path_params, format!("{}\n", params_hash).as_bytes())?;
+    Ok(BatchOutcome::Processed(summary))
+}
 
-    // Apply half-wave rectification (set negative values to zero)
-    let mut novelty_energy = energy_local_diff;
-    novelty_energy.mapv_inplace(|v| if v < 0.0 { 0.0 } else { v });
+/// Returns the name of the HDF5 group of an input path. Groups can't be nested by path here,
+/// so path separators are percent-encoded, as is `%` itself, which keeps the names of
+/// different paths apart.
+fn hdf5_group_name(path_in: &str) -> String {
+    path_in.replace('%', "%25").replace('/', "%2F").replace('\\', "%5C")
+}
 
-    // Normalize if requested
-    if norm {
-        let max_value = *novelty_energy.max()?;
-        if max_value > 0.0 {
-            novelty_energy.mapv_inplace(|v| v / max_value);
+/// Checks that no two entries of a batch share an output path, so no worker overwrites the
+/// results of another. Entries whose output path is invalid fail on their own when processed.
+fn check_batch_outputs(args: &BatchArgs, entries: &[ManifestEntry]) -> anyhow::Result<()> {
+    let mut outputs = HashSet::new();
+    for entry in entries {
+        if let Ok(path_out) = batch_output(args, entry) {
+            if !outputs.insert(path_out.clone()) {
+                anyhow::bail!("Several inputs would be written to {}, e.g. {}", path_out.display(), entry.path_in);
+            }
         }
     }
+    Ok(())
+}
 
-    Ok((novelty_energy, fs_feature))
+/// Returns the output path of a manifest entry, falling back to [`default_batch_output`].
+fn batch_output(args: &BatchArgs, entry: &ManifestEntry) -> anyhow::Result<PathBuf> {
+    Ok(match &entry.path_out {
+        Some(path_out) => PathBuf::from(path_out),
+        None => {
+            let file_name = batch_file_name(args, &entry.path_in, &entry.overrides)?;
+            default_batch_output(&entry.path_in, args.output_dir.as_deref(), &file_name)
+        }
+    })
 }
 
-/// Writes a CSV file containing time vs. novelty function values.
-///
-/// # Arguments
-/// - `path`: Output file path
-/// - `novelty_energy`: 1D array of novelty values
-/// - `fs_feature`: Sampling rate of the novelty function
-/// - `fs`: Original sampling rate of the audio
+/// Returns the output file name of a batch input: the expanded --output-template with the
+/// parameters after the overrides of its manifest entry, or the input file name with the
+/// extension of the output format.
+fn batch_file_name(args: &BatchArgs, path_in: &str, overrides: &[(String, String)]) -> anyhow::Result<String> {
+    match &args.output_template {
+        Some(template) => expand_output_template(template, path_in, &args.novelty.with_overrides(overrides)?.config()?),
+        None => {
+            let file_name = Path::new(Path::new(path_in).file_name().unwrap_or_default()).with_extension(args.csv.extension());
+            Ok(file_name.to_string_lossy().into_owned())
+        }
+    }
+}
+
+/// Returns a hash of the parameters that determine a novelty output file as a hex string.
 ///
-/// # Errors
-/// Returns an error if writing to the file fails.
-fn write_csv(path: &str, novelty_energy: Array1<f32>, fs_feature: f32, fs: u32) -> anyhow::Result<()> {
-    // Compute the time vector corresponding to each novelty value
-    let time = Array::range(0.0, novelty_energy.len() as f32, 1.0) * fs_feature / (fs as f32);
+/// This is a 64-bit FNV-1a hash of the configuration serialized as JSON and of the output
+/// options by their command-line names, which unlike the standard library's hasher and the
+/// `Debug` formatting of the types is stable across Rust versions.
+fn parameter_hash(config: &NoveltyConfig, stereo: Option<StereoArg>, csv_args: &CsvArgs) -> anyhow::Result<String> {
+    let params = serde_json::to_string(&json!({
+        "config": serde_json::to_value(config)?,
+        "stereo": stereo.map(|stereo| value_name(&stereo)),
+        "output": csv_args.parameters(),
+    }))?;
+    let hash = params
+        .bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+    Ok(format!("{:016x}", hash))
+}
+
+/// Returns the name of a value as it is given on the command line.
+fn value_name(value: &impl ValueEnum) -> String {
+    value.to_possible_value().map_or_else(String::new, |value| value.get_name().to_string())
+}
+
+/// Returns the output path of a batch input with the given file name, in `output_dir` if
+/// given and next to the input otherwise.
+fn default_batch_output(path_in: &str, output_dir: Option<&str>, file_name: &str) -> PathBuf {
+    match output_dir {
+        Some(output_dir) => Path::new(output_dir).join(file_name),
+        None => Path::new(path_in).with_file_name(file_name),
+    }
+}
+
+/// Runs all analysis stages and writes a JSON report.
+fn run_analyze(args: &AnalyzeArgs) -> anyhow::Result<()> {
+    validate_output(&args.path_out)?;
+    let config = args.novelty.config()?;
+
+    let (audio_array, fs) = args.novelty.read_audio(&args.path_in)?;
+    let analysis = analyze(audio_array, fs, &config, args.onset_threshold)?;
+
+    let report = Report { path: &args.path_in, broadcast: broadcast_info(&args.path_in)?, analysis: &analysis };
+    let file = std::fs::File::create(&args.path_out)?;
+    serde_json::to_writer_pretty(file, &report)?;
+
+    Ok(())
+}
 
-    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+/// Estimates the offset between two recordings and writes it to a CSV file.
+fn run_sync(args: &SyncArgs) -> anyhow::Result<()> {
+    validate_output(&args.path_out)?;
+    let load = |path: &String| {
+        let input = NoveltyInputArgs { path_in: path.clone(), novelty: args.novelty.clone(), feature_rate: args.feature_rate };
+        input.load()
+    };
+    let lag = novelty_lag(&load(&args.reference)?, &load(&args.other)?, args.max_lag)?;
+    log_event(
+        Level::Info,
+        "recordings_synced",
+        Some(format!("Offset of {} in {}: {:.3} s (correlation {:.3})", args.other, args.reference, lag.lag, lag.correlation)),
+        json!({ "reference": args.reference, "other": args.other, "lag": lag.lag, "correlation": lag.correlation }),
+    );
+    write_lag_csv(&args.path_out, &lag)
+}
 
-    // Write header
-    writeln!(file, "time,novelty").expect("Could not write to file!");
+/// Computes the fingerprints of the audio files in a directory and writes them to an index
+/// file, skipping files that fail.
+fn run_index(args: &IndexArgs) -> anyhow::Result<()> {
+    validate_output(&args.path_out)?;
+    let files = find_files(Path::new(&args.input_dir), &args.extensions, args.recursive)?;
+    let config = args.novelty.config()?;
 
-    // Write time and novelty values
-    for (cur_time, cur_novelty) in time.iter().zip(novelty_energy.iter()) {
-        writeln!(file, "{:.05},{:.05}", cur_time, cur_novelty).expect("Could not write to file!");
+    let mut writer = BufWriter::new(File::create(&args.path_out)?);
+    let mut failed = 0;
+    for path in &files {
+        let path = path.to_string_lossy();
+        let fingerprint = args
+            .novelty
+            .read_audio(&path)
+            .and_then(|(audio_array, fs)| config.compute(audio_array, fs))
+            .map(|novelty| Fingerprint::from_novelty(&path, &novelty));
+        match fingerprint {
+            Ok(fingerprint) => {
+                serde_json::to_writer(&mut writer, &fingerprint)?;
+                writeln!(writer)?;
+            }
+            Err(error) => {
+                failed += 1;
+                log_event(
+                    Level::Error,
+                    "file_failed",
+                    Some(format!("Failed to index {}: {:#}", path, error)),
+                    json!({ "path": path, "error": format!("{:#}", error) }),
+                );
+            }
+        }
     }
+    writer.flush()?;
+    log_event(
+        Level::Info,
+        "index_finished",
+        Some(format!("Indexed {} of {} files", files.len() - failed, files.len())),
+        json!({ "files": files.len(), "failed": failed }),
+    );
 
+    if failed > 0 {
+        anyhow::bail!("{} of {} files failed", failed, files.len());
+    }
     Ok(())
 }
 
-/// Struct to represent and parse command-line arguments.
-#[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
-struct Cli {
-    /// Path to the input mono audio file (WAV)
-    #[arg()]
-    path_in: String,
+/// Reads the fingerprints of an index file written by the `index` subcommand.
+fn read_index(path: &str) -> anyhow::Result<Vec<Fingerprint>> {
+    let reader = BufReader::new(File::open(path)?);
+    reader
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+        .map(|(i, line)| {
+            serde_json::from_str(&line?).map_err(|error| anyhow::anyhow!("Invalid fingerprint in line {} of {}: {}", i + 1, path, error))
+        })
+        .collect()
+}
 
-    /// Path to the output CSV file
-    #[arg()]
-    path_out: String,
+/// Ranks the indexed recordings by their similarity to a query and writes the best matches.
+fn run_query(args: &QueryArgs) -> anyhow::Result<()> {
+    validate_output(&args.path_out)?;
+    let index = read_index(&args.index)?;
+    let query = Fingerprint::from_novelty(&args.input.path_in, &args.input.load()?);
 
-    /// Window length in samples (default: 1024)
-    #[arg(long, default_value_t = 1024)]
-    window_length: u32,
+    let matches = search_fingerprints(&query, &index, args.top);
+    let matches: Vec<(&str, FingerprintMatch)> = matches.iter().map(|(i, found)| (index[*i].path.as_str(), *found)).collect();
+    write_matches_csv(&args.path_out, &matches)
+}
 
-    /// Hop length in samples (default: 256)
-    #[arg(long, default_value_t = 256)]
-    hop_length: u32,
+/// Finds likely duplicate or overlapping recordings in an index and writes them.
+fn run_duplicates(args: &DuplicatesArgs) -> anyhow::Result<()> {
+    validate_output(&args.path_out)?;
+    if args.min_overlap < 0.0 {
+        anyhow::bail!("Minimum overlap must not be negative!");
+    }
+    let index = read_index(&args.index)?;
 
-    /// Logarithmic compression parameter gamma (default: 10.0)
-    #[arg(long, default_value_t = 10.0)]
-    gamma: f32,
+    let duplicates = find_duplicates(&index, args.threshold, args.min_overlap, args.tempo_tolerance);
+    log_event(
+        Level::Info,
+        "duplicates_found",
+        Some(format!("Found {} likely duplicates among {} recordings", duplicates.len(), index.len())),
+        json!({ "recordings": index.len(), "duplicates": duplicates.len() }),
+    );
+    write_duplicates_csv(&args.path_out, &index, &duplicates)
+}
 
-    /// Whether to normalize the novelty function (default: true)
-    #[arg(long, default_value_t = true)]
-    norm: bool,
+/// Reads the BWF metadata of a local input file, if it has any.
+fn broadcast_info(path: &str) -> anyhow::Result<Option<BroadcastInfo>> {
+    if path == "-" || is_object_url(path) {
+        return Ok(None);
+    }
+    read_broadcast_info(&mut BufReader::new(File::open(path)?))
+}
+
+/// Estimates the global tempo of a novelty function with the default tempogram parameters.
+fn global_tempo(novelty: &NoveltyCurve) -> anyhow::Result<f32> {
+    estimate_tempo(&default_tempogram(novelty)).ok_or_else(|| anyhow::anyhow!("Could not estimate a tempo, the novelty function is empty."))
 }
 
-impl Cli {
-    /// Validates that the output file does not already exist.
-    fn validate(&self) -> anyhow::Result<()> {
-        if Path::new(&self.path_out).exists() {
-            anyhow::bail!("Output path must not already exist!");
+/// Detects onsets and writes them to a CSV file.
+fn run_onsets(args: &OnsetsArgs) -> anyhow::Result<()> {
+    validate_output(&args.path_out)?;
+    if let Some(path) = &args.export_transients {
+        if args.input.is_novelty_csv() {
+            anyhow::bail!("Exporting transients requires an audio input!");
+        }
+        validate_output(path)?;
+    }
+    if args.classify && args.input.is_novelty_csv() {
+        anyhow::bail!("Classifying onsets requires an audio input!");
+    }
+    if let Some(path) = &args.vega_lite {
+        if matches!(args.vega_data, VegaDataArg::Reference) && (!args.input.is_novelty_csv() || args.input.path_in == "-") {
+            anyhow::bail!("Referencing the data of the Vega-Lite specification requires a novelty CSV file as input!");
+        }
+        validate_output(path)?;
+    }
+    if args.stability && args.input.is_novelty_csv() {
+        anyhow::bail!("Estimating the onset stability requires an audio input!");
+    }
+    if args.attack_times && args.input.is_novelty_csv() {
+        anyhow::bail!("Estimating attack times requires an audio input!");
+    }
+    if args.drums && args.input.is_novelty_csv() {
+        anyhow::bail!("Classifying drums requires an audio input!");
+    }
+    if args.pitch && args.input.is_novelty_csv() {
+        anyhow::bail!("Estimating pitches requires an audio input!");
+    }
+    if let Some(path) = &args.midi.path {
+        if matches!(args.midi.velocity_source, VelocitySourceArg::Loudness) && args.input.is_novelty_csv() {
+            anyhow::bail!("Loudness velocities require an audio input!");
+        }
+        if args.midi.midi_note > 127 || !(1..=16).contains(&args.midi.midi_channel) {
+            anyhow::bail!("MIDI notes must be from 0 to 127 and channels from 1 to 16!");
+        }
+        args.midi.mapping().validate()?;
+        validate_output(path)?;
+    }
+    let timecode = args.timecode.timecode()?;
+    args.markers.validate(timecode)?;
+    if let Some(path) = &args.alignment {
+        validate_output(path)?;
+    }
+    if let Some(tolerance) = args.fuse_channels {
+        if args.markers.path.is_some() {
+            anyhow::bail!("--markers can't be combined with --fuse-channels");
+        }
+        return run_fused_onsets(args, tolerance, timecode);
+    }
+    let novelty = args.input.load()?;
+    let onsets = args.detect(&novelty)?;
+
+    // the requested column sets are computed for the written onsets, which are those of the
+    // unchanged parameters of the jittered runs with --stability
+    let audio = if args.classify || args.attack_times || args.drums || args.pitch || args.stability {
+        Some(args.input.novelty.read_audio(&args.input.path_in)?)
+    } else {
+        None
+    };
+    let (mut stable_onsets, mut stabilities) = (Vec::new(), Vec::new());
+    if let (true, Some((audio_array, fs))) = (args.stability, &audio) {
+        if args.peak_picking.params(novelty.fs_feature).is_some() {
+            anyhow::bail!("--stability jitters the onset threshold and can't be combined with the peak picker");
+        }
+        let config = args.input.novelty.config()?;
+        for onset in onset_stability(audio_array, *fs, &config, args.onset_threshold, &Jitter::default(), args.stability_tolerance)? {
+            let frame = (onset.time * novelty.fs_feature).round() as usize;
+            stable_onsets.push(Onset { frame, time: onset.time, strength: onset.strength });
+            stabilities.push(onset.stability);
+        }
+    }
+    let written = if args.stability { &stable_onsets } else { &onsets };
+    let (mut characters, mut attacks, mut hits, mut pitches) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    if let Some((audio_array, fs)) = &audio {
+        let config = args.input.novelty.config()?;
+        if args.classify {
+            characters = classify_onsets(audio_array, *fs, written, config.window_length as usize);
+        }
+        if args.attack_times {
+            attacks = onset_attacks(audio_array, *fs, written);
+        }
+        if args.drums {
+            hits = classify_drums(audio_array, *fs, written, config.window_length as usize);
+        }
+        if args.pitch {
+            let params = PitchParams { fmin: args.pitch_min, fmax: args.pitch_max, window: args.pitch_window, ..PitchParams::default() };
+            pitches = onset_pitches(audio_array, *fs, written, &params)?;
+        }
+    }
+    let columns: Vec<OnsetColumns> = [
+        args.classify.then_some(OnsetColumns::Character(&characters)),
+        args.attack_times.then_some(OnsetColumns::Attack(&attacks)),
+        args.drums.then_some(OnsetColumns::Drum(&hits)),
+        args.pitch.then_some(OnsetColumns::Pitch(&pitches)),
+        args.stability.then_some(OnsetColumns::Stability(&stabilities)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    write_onsets_csv_with_columns(&args.path_out, written, timecode, &columns)?;
+
+    if let Some(dir) = &args.segments.export_segments {
+        if args.input.is_novelty_csv() {
+            anyhow::bail!("Exporting segments requires an audio input!");
+        }
+        let (audio_array, fs) = args.input.novelty.read_audio(&args.input.path_in)?;
+        let boundaries = segment_boundaries(args, &onsets, Some((&audio_array, fs)))?;
+        let stem = Path::new(&args.input.path_in).file_stem().and_then(|stem| stem.to_str()).unwrap_or("segment");
+        write_segments(Path::new(dir), stem, &split_audio(&audio_array, fs, &boundaries), fs)?;
+    }
+
+    if let Some(path) = &args.export_transients {
+        let config = args.input.novelty.config()?;
+        let (audio_array, fs) = args.input.novelty.read_audio(&args.input.path_in)?;
+        let (_, percussive) = hpss(&audio_array, config.window_length as usize, HPSS_KERNEL);
+        write_wav(path, &percussive, fs)?;
+    }
+
+    if let Some(path) = &args.vega_lite {
+        let data = match args.vega_data {
+            VegaDataArg::Inline => VegaLiteData::Inline,
+            VegaDataArg::Reference => VegaLiteData::Reference { novelty: args.input.path_in.clone(), onsets: args.path_out.clone() },
+        };
+        write_vega_lite(path, &novelty, &onsets, &data)?;
+    }
+
+    if let Some(path) = &args.midi.path {
+        let audio = match args.midi.velocity_source {
+            VelocitySourceArg::Strength => None,
+            VelocitySourceArg::Loudness => Some(args.input.novelty.read_audio(&args.input.path_in)?),
+        };
+        let velocities = onset_velocities(&onsets, audio.as_ref().map(|(audio_array, fs)| (audio_array, *fs)), &args.midi.mapping())?;
+        write_midi(path, &onsets, &velocities, args.midi.midi_note, args.midi.midi_channel)?;
+    }
+
+    if args.markers.path.is_some() {
+        let markers = if args.marker_regions {
+            let audio = match args.segments.boundaries {
+                BoundaryArg::Onsets => None,
+                BoundaryArg::Structure => Some(args.input.novelty.read_audio(&args.input.path_in)?),
+            };
+            let boundaries = segment_boundaries(args, &onsets, audio.as_ref().map(|(audio_array, fs)| (audio_array, *fs)))?;
+            let duration = novelty.values.len() as f32 / novelty.fs_feature;
+            regions_from_boundaries(&boundaries, duration, "Segment")
+        } else {
+            markers_from_times(&onsets.iter().map(|onset| onset.time).collect::<Vec<_>>(), "Onset")
+        };
+        args.markers.write(&args.input, &markers, timecode)?;
+    }
+
+    if let Some(path_annotations) = &args.annotations {
+        let annotations = read_annotations(path_annotations)?;
+        let times: Vec<f32> = onsets.iter().map(|onset| onset.time).collect();
+        let alignments = align_onsets(&times, &annotations, args.alignment_tolerance);
+        let matched = alignments.iter().filter(|alignment| alignment.deviation().is_some()).count();
+        let (missed, extra) = (annotations.len() - matched, times.len() - matched);
+        log_event(
+            Level::Info,
+            "annotations_aligned",
+            Some(format!("{} of {} annotations matched, {} missed, {} extra onsets", matched, annotations.len(), missed, extra)),
+            json!({ "matched": matched, "annotations": annotations.len(), "missed": missed, "extra": extra }),
+        );
+        if let Some(path) = &args.alignment {
+            write_alignment_csv(path, &alignments)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the segment boundaries in seconds selected with --boundaries: the onset times, or
+/// the structural boundaries of the audio, which is required for them, preceded by zero.
+fn segment_boundaries(args: &OnsetsArgs, onsets: &[Onset], audio: Option<(&Array1<f32>, u32)>) -> anyhow::Result<Vec<f32>> {
+    match (args.segments.boundaries, audio) {
+        (BoundaryArg::Onsets, _) => Ok(onsets.iter().map(|onset| onset.time).collect()),
+        (BoundaryArg::Structure, Some((audio_array, fs))) => {
+            let config = args.input.novelty.config()?;
+            let mut boundaries = structure_boundaries(audio_array, fs, &config, args.segments.structure_kernel, args.onset_threshold)?;
+            boundaries.insert(0, 0.0);
+            Ok(boundaries)
+        }
+        (BoundaryArg::Structure, None) => anyhow::bail!("Structural boundaries require an audio input!"),
+    }
+}
+
+/// Detects onsets in each channel of the input separately and writes the fused onsets to a
+/// CSV file.
+fn run_fused_onsets(args: &OnsetsArgs, tolerance: f32, timecode: Option<Timecode>) -> anyhow::Result<()> {
+    if args.input.is_novelty_csv() {
+        anyhow::bail!("Fusing channels requires an audio input!");
+    }
+    if tolerance < 0.0 {
+        anyhow::bail!("Fusion tolerance must not be negative!");
+    }
+    let config = args.input.novelty.config()?;
+    if let Some(reason) = args.input.novelty.limits.exceeded(&args.input.path_in)? {
+        anyhow::bail!("{} exceeds the limits: {}", args.input.path_in, reason);
+    }
+
+    let (channels, fs) = args.input.novelty.read_channels(&args.input.path_in, &args.input.path_in)?;
+    let per_channel = channels
+        .outer_iter()
+        .map(|channel| args.detect(&config.compute(channel.to_owned(), fs)?))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    write_fused_onsets_csv(&args.path_out, &fuse_onsets(&per_channel, tolerance), timecode)
+}
+
+/// Estimates tempo candidates with confidences and writes them to a CSV file.
+fn run_tempo(args: &TempoArgs) -> anyhow::Result<()> {
+    validate_output(&args.path_out)?;
+    for path in args.tempogram.iter().chain(&args.cyclic_tempogram) {
+        validate_output(path)?;
+    }
+    if args.cyclic_bins == 0 {
+        anyhow::bail!("--cyclic-bins must be positive!");
+    }
+    let novelty = args.input.load()?;
+    let tempogram = default_tempogram(&novelty);
+    let candidates = tempo_candidates(&tempogram, args.candidates);
+    if candidates.is_empty() {
+        anyhow::bail!("Could not estimate a tempo, the novelty function is empty.");
+    }
+    write_tempo_candidates_csv(&args.path_out, &candidates)?;
+    if let (Some(path_histogram), Some(salience)) = (&args.tempo_histogram, tempo_salience(&tempogram)) {
+        write_tempo_histogram_csv(path_histogram, &args.input.path_in, &tempogram.bpm, &salience)?;
+    }
+    for path in &args.tempogram {
+        write_tempogram(path, &tempogram)?;
+    }
+    if !args.cyclic_tempogram.is_empty() {
+        let cyclic = cyclic_tempogram(&tempogram, tempo::DEFAULT_MIN_BPM, args.cyclic_bins);
+        for path in &args.cyclic_tempogram {
+            write_tempogram(path, &cyclic)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes a tempogram in the format given by the file extension: a CSV file, a NumPy array
+/// with shape (frames, tempi), or a PNG heatmap with time running to the right and the
+/// tempo upwards.
+fn write_tempogram(path: &str, tempogram: &Tempogram) -> anyhow::Result<()> {
+    let extension = Path::new(path).extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+    match extension.to_lowercase().as_str() {
+        "csv" => write_tempogram_csv(path, tempogram),
+        "npy" => {
+            let values = tempogram.values.t().as_standard_layout().into_owned();
+            let data = values.as_slice().expect("Array in standard layout");
+            write_npy(path, &NpyArray { shape: values.shape().to_vec(), data: NpyData::F32(data) })
+        }
+        "png" => write_heatmap_png(path, tempogram.values.slice(s![..;-1, ..])),
+        _ => anyhow::bail!("Unsupported tempogram format {}, expected .csv, .npy, or .png", path),
+    }
+}
+
+/// Tracks beats and writes them to a CSV file.
+fn run_beats(args: &BeatsArgs) -> anyhow::Result<()> {
+    validate_output(&args.path_out)?;
+    if let Some(path_stats) = &args.grid_stats {
+        validate_output(path_stats)?;
+    }
+    let timecode = args.timecode.timecode()?;
+    args.markers.validate(timecode)?;
+    let novelty = args.input.load()?;
+    let mut beats = args.tracking.track(&novelty)?;
+    track_downbeats(&novelty, &mut beats);
+    write_beats_csv(&args.path_out, &beats, timecode)?;
+
+    // the grid has the given tempo, or the tempo that fits the beats best
+    if let Some(path_stats) = &args.grid_stats {
+        let stats = beat_grid_stats(&beats, args.tracking.bpm, args.grid_tolerance)
+            .ok_or_else(|| anyhow::anyhow!("Could not compute beat grid statistics, fewer than three beats were tracked."))?;
+        write_grid_stats_csv(path_stats, &stats)?;
+    }
+    let times: Vec<f32> = beats.iter().map(|beat| beat.time).collect();
+    args.markers.write(&args.input, &markers_from_times(&times, "Beat"), timecode)
+}
+
+/// Estimates the meter from the tracked beats and writes the candidates to a CSV file.
+fn run_meter(args: &MeterArgs) -> anyhow::Result<()> {
+    validate_output(&args.path_out)?;
+    let novelty = args.input.load()?;
+    let beats = args.tracking.track(&novelty)?;
+    let candidates = estimate_meter(&novelty, &beats);
+    if candidates.is_empty() {
+        anyhow::bail!("Could not estimate a meter, too few beats or no accentuation.");
+    }
+    write_meter_csv(&args.path_out, &candidates)
+}
+
+/// Clusters inter-onset intervals and writes the tempo hypotheses to a CSV file.
+fn run_snap(args: &SnapArgs) -> anyhow::Result<()> {
+    validate_output(&args.path_out)?;
+    if args.tolerance < 0.0 {
+        anyhow::bail!("Tolerance must not be negative!");
+    }
+
+    let annotations = read_annotations(&args.annotations)?;
+    let novelty = args.input.load()?;
+    let mut peaks = detect_onsets(&novelty, args.onset_threshold);
+    if args.refine {
+        peaks = refine_onsets(&novelty, &peaks);
+    }
+    let times: Vec<f32> = peaks.iter().map(|peak| peak.time).collect();
+    let snapped = snap_annotations(&times, &annotations, args.tolerance);
+    let num_snapped = snapped.iter().filter(|alignment| alignment.detection.is_some()).count();
+    log_event(
+        Level::Info,
+        "annotations_snapped",
+        Some(format!("Snapped {} of {} annotations", num_snapped, annotations.len())),
+        json!({ "snapped": num_snapped, "annotations": annotations.len() }),
+    );
+
+    if args.path_out.to_lowercase().ends_with(".txt") {
+        let corrected: Vec<Annotation> = snapped
+            .iter()
+            .filter_map(|alignment| {
+                let annotation = alignment.annotation.as_ref()?;
+                Some(Annotation {
+                    time: alignment.detection.unwrap_or(annotation.time),
+                    label: annotation.label.clone(),
+                })
+            })
+            .collect();
+        write_audacity_labels(&args.path_out, &corrected)
+    } else {
+        write_snapped_annotations_csv(&args.path_out, &snapped)
+    }
+}
+
+fn run_ioi(args: &IoiArgs) -> anyhow::Result<()> {
+    validate_output(&args.path_out)?;
+    if let Some(path_histogram) = &args.histogram {
+        validate_output(path_histogram)?;
+    }
+    if args.bin_width <= 0.0 || args.cluster_width <= 0.0 || args.max_interval <= 0.0 {
+        anyhow::bail!("Bin width, cluster width, and maximum interval must be greater than zero!");
+    }
+
+    let novelty = args.input.load()?;
+    let onsets = detect_onsets(&novelty, args.onset_threshold);
+
+    if let Some(path_histogram) = &args.histogram {
+        let counts = ioi_histogram(&onsets, args.bin_width, args.max_interval);
+        write_ioi_histogram_csv(path_histogram, &counts, args.bin_width)?;
+    }
+    let clusters = cluster_iois(&onsets, args.cluster_width, args.max_interval);
+    write_ioi_clusters_csv(&args.path_out, &clusters)
+}
+
+/// Selects a thumbnail and writes its time range to a CSV file.
+fn run_thumbnail(args: &ThumbnailArgs) -> anyhow::Result<()> {
+    validate_output(&args.path_out)?;
+    if let Some(path_export) = &args.export {
+        validate_output(path_export)?;
+    }
+    if args.duration <= 0.0 {
+        anyhow::bail!("Duration must be greater than zero!");
+    }
+    let config = args.novelty.config()?;
+
+    let (audio_array, fs) = args.novelty.read_audio(&args.path_in)?;
+    let excerpt = thumbnail(audio_array.clone(), fs, &config, args.duration, args.criterion.into())?;
+
+    if let Some(path_export) = &args.export {
+        write_wav(path_export, &excerpt.excerpt(&audio_array, fs), fs)?;
+    }
+    write_thumbnail_csv(&args.path_out, &excerpt)
+}
+
+/// Proposes loop points within the sustained regions of the input and writes them to a CSV
+/// file.
+fn run_loops(args: &LoopsArgs) -> anyhow::Result<()> {
+    validate_output(&args.path_out)?;
+    if args.min_length <= 0.0 || args.match_window <= 0.0 || args.search < 0.0 {
+        anyhow::bail!("Loop lengths and the match window must be greater than zero and the search must not be negative!");
+    }
+    let config = args.novelty.config()?;
+    let params = LoopParams {
+        sustain_threshold: args.sustain_threshold,
+        min_length: args.min_length,
+        max_length: args.max_length,
+        window: args.match_window,
+        search: args.search,
+        count: args.count,
+    };
+
+    let (audio_array, fs) = args.novelty.read_audio(&args.path_in)?;
+    let novelty = config.compute(audio_array.clone(), fs)?;
+    let loops = find_loop_points(&audio_array, fs, &novelty, &params);
+    if loops.is_empty() {
+        log_event(Level::Warning, "no_loops", Some(format!("No loops found in {}", args.path_in)), json!({ "path": args.path_in }));
+    }
+    write_loops_csv(&args.path_out, &loops, fs)
+}
+
+/// Profiles the dynamics of an audio file, or of each file in a directory and the whole
+/// corpus, and writes them to a CSV file.
+fn run_stats(args: &StatsArgs) -> anyhow::Result<()> {
+    validate_output(&args.path_out)?;
+    let config = args.novelty.config()?;
+    let is_dir = Path::new(&args.path_in).is_dir();
+    let files: Vec<String> = if is_dir {
+        let files = find_files(Path::new(&args.path_in), &args.extensions, args.recursive)?;
+        files.iter().map(|path| path.to_string_lossy().into_owned()).collect()
+    } else {
+        vec![args.path_in.clone()]
+    };
+
+    let mut corpus = DynamicsMeter::new();
+    let mut profiles = Vec::with_capacity(files.len() + 1);
+    for path in &files {
+        let (audio_array, fs) = args.novelty.read_audio(path)?;
+        let novelty = config.compute(audio_array.clone(), fs)?;
+        let onset_count = detect_onsets(&novelty, args.onset_threshold).len();
+
+        let mut meter = DynamicsMeter::new();
+        meter.add(&audio_array, fs, onset_count);
+        if is_dir {
+            corpus.add(&audio_array, fs, onset_count);
+        }
+        profiles.push((path.as_str(), meter.dynamics()));
+    }
+    if is_dir {
+        // the whole corpus as one recording, after the files
+        profiles.push(("corpus", corpus.dynamics()));
+    }
+    write_dynamics_csv(&args.path_out, &profiles)
+}
+
+/// Detects the active and inactive segments of the input and writes them, and optionally the
+/// audio of the active segments.
+fn run_activity(args: &ActivityArgs) -> anyhow::Result<()> {
+    validate_output(&args.path_out)?;
+    let timecode = args.timecode.timecode()?;
+    let config = args.novelty.config()?;
+    let thresholds = ActivityThresholds {
+        high: args.high,
+        low: args.low,
+        // --attack and --release smooth the envelope instead of the novelty function
+        smoothing: config.smoothing.unwrap_or(ActivityThresholds::default().smoothing),
+        min_active: args.min_active,
+        min_inactive: args.min_inactive,
+    };
+
+    let (audio_array, fs) = args.novelty.read_audio(&args.path_in)?;
+    let segments = detect_activity(audio_array.clone(), fs, config.window_length, config.hop_length, &thresholds)?;
+    let samples = |segment: &ActivitySegment| {
+        let start = ((segment.start * fs as f32).round() as usize).min(audio_array.len());
+        let end = ((segment.end * fs as f32).round() as usize).clamp(start, audio_array.len());
+        audio_array.slice(s![start..end]).to_owned()
+    };
+
+    if let Some(dir) = &args.export_segments {
+        let active: Vec<Segment> = segments
+            .iter()
+            .filter(|segment| segment.active)
+            .map(|segment| Segment { start: segment.start, end: segment.end, samples: samples(segment) })
+            .collect();
+        let stem = Path::new(&args.path_in).file_stem().and_then(|stem| stem.to_str()).unwrap_or("segment");
+        write_segments(Path::new(dir), stem, &active, fs)?;
+    }
+    if args.classify {
+        let contents: Vec<Option<SegmentContent>> = segments
+            .iter()
+            .map(|segment| {
+                let (window_length, hop_length) = (config.window_length as usize, config.hop_length as usize);
+                segment.active.then(|| classify_content(&samples(segment), window_length, hop_length))
+            })
+            .collect();
+        return write_classified_activity_csv(&args.path_out, &segments, &contents, timecode);
+    }
+    write_activity_csv(&args.path_out, &segments, timecode)
+}
+
+/// Writes the frame-level features of the input with detected or annotated onset labels to
+/// an NPZ file.
+fn run_features(args: &FeaturesArgs) -> anyhow::Result<()> {
+    validate_output(&args.path_out)?;
+    let config = args.novelty.config()?;
+
+    let (audio_array, fs) = args.novelty.read_audio(&args.path_in)?;
+    let novelty = config.compute(audio_array.clone(), fs)?;
+    let onset_times = match &args.annotations {
+        Some(path) => read_onset_times(path)?,
+        None => detect_onsets(&novelty, args.onset_threshold).iter().map(|onset| onset.time).collect(),
+    };
+
+    frame_features(&audio_array, fs, &config, novelty, &onset_times)?.write_npz(&args.path_out)
+}
+
+/// Returns the SHA-256 hash of a local file or an object as hex string.
+fn hash_path(path: &str) -> anyhow::Result<String> {
+    if is_object_url(path) {
+        Ok(sha256_hex(&read_path(path)?))
+    } else {
+        sha256_file(path)
+    }
+}
+
+/// Checks each output against its provenance sidecar: it is corrupt if its hash changed, and
+/// stale if the hash of its input changed or the input is gone.
+fn run_verify(args: &VerifyArgs) -> anyhow::Result<()> {
+    let mut num_failed = 0;
+    for path_out in &args.outputs {
+        let status = read_path(&Provenance::sidecar_path(path_out))
+            .and_then(|sidecar| Ok(serde_json::from_slice::<Provenance>(&sidecar)?))
+            .and_then(|provenance| {
+                if hash_path(path_out)? != provenance.output_sha256 {
+                    return Ok("corrupt: the output changed".to_string());
+                }
+                Ok(match hash_path(&provenance.input) {
+                    Ok(hash) if hash == provenance.input_sha256 => "ok".to_string(),
+                    Ok(_) => format!("stale: {} changed since {}", provenance.input, provenance.finished),
+                    Err(_) => format!("stale: {} is gone", provenance.input),
+                })
+            })
+            .unwrap_or_else(|error| format!("unverifiable: {:#}", error));
+        if status != "ok" {
+            num_failed += 1;
+        }
+        println!("{}\t{}", path_out, status);
+    }
+    if num_failed > 0 {
+        anyhow::bail!("{} of {} outputs failed verification", num_failed, args.outputs.len());
+    }
+    Ok(())
+}
+
+/// Checks the quality of each input file, printing its status, and fails if any file fails
+/// to decode or violates a limit.
+fn run_qc(args: &QcArgs) -> anyhow::Result<()> {
+    if let Some(path_report) = &args.report {
+        validate_output(path_report)?;
+    }
+    let limits = QcLimits {
+        max_clipping: args.max_clipping,
+        max_dropouts: args.max_dropouts,
+        max_dc_offset: args.max_dc_offset,
+        max_silence: args.max_silence,
+        min_duration: args.min_duration,
+        max_duration: args.max_duration,
+    };
+    let mut files = Vec::new();
+    for input in &args.inputs {
+        if Path::new(input).is_dir() {
+            let found = find_files(Path::new(input), &args.extensions, args.recursive)?;
+            files.extend(found.iter().map(|path| path.to_string_lossy().into_owned()));
+        } else {
+            files.push(input.clone());
+        }
+    }
+
+    let mut results = Vec::with_capacity(files.len());
+    for path in &files {
+        // a file that doesn't decode fails the format check
+        let result = audio_path_to_channels(path)
+            .map(|(channels, fs)| quality_check(&channels, fs, &limits))
+            .map_err(|error| format!("invalid format: {:#}", error));
+        match &result {
+            Ok(report) if report.passed() => println!("{}\tpass", path),
+            Ok(report) => println!("{}\tfail: {}", path, report.failures.join("; ")),
+            Err(error) => println!("{}\tfail: {}", path, error),
+        }
+        results.push((path.as_str(), result));
+    }
+    if let Some(path_report) = &args.report {
+        write_qc_csv(path_report, &results)?;
+    }
+
+    let num_failed = results.iter().filter(|(_, result)| !result.as_ref().is_ok_and(QcReport::passed)).count();
+    if num_failed > 0 {
+        anyhow::bail!("{} of {} files failed quality control", num_failed, files.len());
+    }
+    Ok(())
+}
+
+/// Waveform buckets per second served to the view page, enough to zoom in to a few
+/// milliseconds per pixel.
+const VIEW_PEAK_RATE: f32 = 200.0;
+
+/// Largest number of waveform buckets served to the view page per track, so the page stays
+/// responsive for long recordings.
+const VIEW_MAX_PEAKS: usize = 200_000;
+
+/// The decoded audio and analysis of a file shown by the `view` subcommand.
+struct ViewTrack {
+    name: String,
+    audio_array: Array1<f32>,
+    fs: u32,
+    novelty: NoveltyCurve,
+    onsets: Vec<Onset>,
+}
+
+impl ViewTrack {
+    /// Loads an audio file and computes its novelty function, or reads a novelty CSV file and
+    /// the audio named in its provenance sidecar.
+    fn load(path: &str, args: &ViewArgs) -> anyhow::Result<Self> {
+        let input = NoveltyInputArgs { path_in: path.to_string(), novelty: args.novelty.clone(), feature_rate: args.feature_rate };
+        let (audio_array, fs, novelty) = if input.is_novelty_csv() {
+            let novelty = input.load()?;
+            let sidecar = Provenance::sidecar_path(path);
+            let provenance: Provenance = read_path(&sidecar)
+                .and_then(|bytes| Ok(serde_json::from_slice(&bytes)?))
+                .map_err(|error| anyhow::anyhow!("Failed to find the audio of {} in {}: {:#}", path, sidecar, error))?;
+            let (audio_array, fs) = args.novelty.read_audio(&provenance.input)?;
+            (audio_array, fs, novelty)
+        } else {
+            let (audio_array, fs) = args.novelty.read_audio(path)?;
+            let novelty = args.novelty.config()?.compute(audio_array.clone(), fs)?;
+            (audio_array, fs, novelty)
+        };
+        let onsets = detect_onsets(&novelty, args.onset_threshold);
+        Ok(ViewTrack { name: path.to_string(), audio_array, fs, novelty, onsets })
+    }
+
+    /// Returns the novelty function, onsets, and waveform as JSON for the view page.
+    ///
+    /// The waveform is reduced to the minimum and maximum of buckets of [`VIEW_PEAK_RATE`] per
+    /// second, or fewer for recordings longer than [`VIEW_MAX_PEAKS`] buckets.
+    fn to_json(&self) -> serde_json::Value {
+        let duration = self.audio_array.len() as f32 / self.fs as f32;
+        let num_peaks = ((duration * VIEW_PEAK_RATE).ceil() as usize).clamp(1, VIEW_MAX_PEAKS).min(self.audio_array.len().max(1));
+        let bucket = self.audio_array.len().div_ceil(num_peaks).max(1);
+        let peaks: Vec<[f32; 2]> = self
+            .audio_array
+            .axis_chunks_iter(Axis(0), bucket)
+            .map(|chunk| chunk.iter().fold([0.0_f32, 0.0_f32], |[low, high], &v| [low.min(v), high.max(v)]))
+            .collect();
+        let onsets: Vec<serde_json::Value> = self.onsets.iter().map(|onset| json!({ "time": onset.time, "strength": onset.strength })).collect();
+        json!({
+            "name": self.name,
+            "duration": duration,
+            "fs_feature": self.novelty.fs_feature,
+            "novelty": self.novelty.values.to_vec(),
+            "onsets": onsets,
+            "peak_rate": self.fs as f32 / bucket as f32,
+            "peaks": peaks,
+        })
+    }
+
+    /// Encodes the audio as a mono 16-bit WAV file for playback in the browser.
+    fn to_wav(&self) -> Vec<u8> {
+        let data_size = (self.audio_array.len() * 2) as u32;
+        let mut bytes = Vec::with_capacity(44 + data_size as usize);
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16_u32.to_le_bytes());
+        bytes.extend_from_slice(&1_u16.to_le_bytes()); // PCM
+        bytes.extend_from_slice(&1_u16.to_le_bytes()); // mono
+        bytes.extend_from_slice(&self.fs.to_le_bytes());
+        bytes.extend_from_slice(&(self.fs * 2).to_le_bytes());
+        bytes.extend_from_slice(&2_u16.to_le_bytes());
+        bytes.extend_from_slice(&16_u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_size.to_le_bytes());
+        for &v in self.audio_array.iter() {
+            bytes.extend_from_slice(&((v.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16).to_le_bytes());
+        }
+        bytes
+    }
+}
+
+/// Loads the inputs and serves a page showing them until interrupted.
+///
+/// The page at `/` fetches the list of tracks from `/tracks.json`, the analysis of each
+/// track from `/tracks/<index>.json`, and its audio from `/tracks/<index>.wav`.
+fn run_view(args: &ViewArgs) -> anyhow::Result<()> {
+    let tracks = args.inputs.iter().map(|path| ViewTrack::load(path, args)).collect::<anyhow::Result<Vec<_>>>()?;
+    let track_list: Vec<serde_json::Value> = tracks.iter().map(|track| json!({ "name": track.name })).collect();
+    let track_list = serde_json::to_vec(&track_list)?;
+
+    let listener = TcpListener::bind(&args.address)
+        .map_err(|error| anyhow::anyhow!("Failed to listen on {}: {}", args.address, error))?;
+    let local_address = listener.local_addr()?.to_string();
+    log_event(
+        Level::Info,
+        "view_listening",
+        Some(format!("Serving {} tracks on http://{}/", tracks.len(), local_address)),
+        json!({ "address": local_address, "tracks": tracks.len() }),
+    );
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+        let Some(path) = read_request_path(&stream)? else {
+            continue;
+        };
+        let track = |extension: &str| {
+            path.strip_prefix("/tracks/")
+                .and_then(|name| name.strip_suffix(extension))
+                .and_then(|index| index.parse::<usize>().ok())
+                .and_then(|index| tracks.get(index))
+        };
+        // a browser that went away doesn't stop serving
+        let _ = match path.as_str() {
+            "/" => write_response(&mut stream, "200 OK", "text/html; charset=utf-8", include_str!("view.html").as_bytes()),
+            "/tracks.json" => write_response(&mut stream, "200 OK", "application/json", &track_list),
+            _ => match (track(".json"), track(".wav")) {
+                (Some(track), _) => write_response(&mut stream, "200 OK", "application/json", track.to_json().to_string().as_bytes()),
+                (_, Some(track)) => write_response(&mut stream, "200 OK", "audio/wav", &track.to_wav()),
+                _ => write_response(&mut stream, "404 Not Found", "text/plain", b"Not found\n"),
+            },
+        };
+    }
+    Ok(())
+}
+
+/// Runs a pipeline specification on each input, after checking that none of the exports
+/// exist yet, so no input is processed if any would fail.
+fn run_pipeline(args: &PipelineArgs) -> anyhow::Result<()> {
+    let pipeline = Pipeline::load(&args.spec)?;
+    for path_in in &args.inputs {
+        for path_out in pipeline.outputs(path_in)? {
+            validate_output(&path_out)?;
+        }
+    }
+    pipeline.check_outputs(args.inputs.iter().map(String::as_str))?;
+
+    for path_in in &args.inputs {
+        let (audio_array, fs) = audio_path_to_array(path_in)?;
+        let output = pipeline.run(audio_array, fs, path_in)?;
+        let message = match &output.onsets {
+            Some(onsets) => format!("{}: {} onsets, wrote {}", path_in, onsets.len(), output.written.join(", ")),
+            None => format!("{}: wrote {}", path_in, output.written.join(", ")),
+        };
+        log_event(
+            Level::Info,
+            "pipeline_finished",
+            Some(message),
+            json!({ "path": path_in, "onsets": output.onsets.as_ref().map(Vec::len), "outputs": output.written }),
+        );
+    }
+    Ok(())
+}
+
+/// Lists the plugins found in the directories of NOVELTY_PLUGIN_PATH with their names, and
+/// the libraries that failed to load with their errors.
+fn run_plugins() -> anyhow::Result<()> {
+    let dirs = plugin_dirs();
+    if dirs.is_empty() {
+        log_event(Level::Warning, "plugin_path_unset", Some(format!("{} is not set", PLUGIN_PATH_VARIABLE)), json!({}));
+    }
+    for path in find_plugins(&dirs)? {
+        match Plugin::load(&path) {
+            Ok(plugin) => println!("{}\t{}", plugin.name(), path.display()),
+            Err(error) => log_event(
+                Level::Warning,
+                "plugin_skipped",
+                Some(format!("Skipping {}: {:#}", path.display(), error)),
+                json!({ "path": path.display().to_string(), "error": format!("{:#}", error) }),
+            ),
         }
-        Ok(())
     }
+    Ok(())
 }
 
-/// Entry point of the application. Parses arguments, computes the novelty function,
-/// and writes the results to a CSV file.
+/// Entry point of the application. Parses arguments and dispatches to the selected mode.
 ///
 /// # Errors
 /// Returns an error if any step in the pipeline fails.
 fn main() -> anyhow::Result<()> {
     // parse command line arguments
-    let args = Cli::parse();
-    args.validate()?;
+    let cli = Cli::parse();
+    LOG_FORMAT.set(cli.log_format).expect("Log format set twice");
 
-    // get audio file
-    let (audio_array, fs) = audio_path_to_array(&args.path_in)?;
-
-    // compute novelty function
-    let (novelty_energy, fs_feature) = novelty_energy(audio_array, fs, args.window_length, args.hop_length, args.gamma, args.norm)?;
+    let result = run(&cli);
+    // in JSON mode, errors are events too, rather than text that breaks the log stream
+    if let (Err(error), LogFormatArg::Json) = (&result, cli.log_format) {
+        log_event(Level::Error, "failed", Some(format!("{:#}", error)), json!({}));
+        std::process::exit(1);
+    }
+    result
+}
 
-    // write csv result
-    write_csv(&args.path_out, novelty_energy, fs_feature, fs)?;
+/// Dispatches to the selected mode.
+fn run(cli: &Cli) -> anyhow::Result<()> {
+    match &cli.command {
+        Some(Command::Analyze(args)) => run_analyze(args),
+        Some(Command::Onsets(args)) => run_onsets(args),
+        Some(Command::Tempo(args)) => run_tempo(args),
+        Some(Command::Beats(args)) => run_beats(args),
+        Some(Command::Meter(args)) => run_meter(args),
+        Some(Command::Ioi(args)) => run_ioi(args),
+        Some(Command::Snap(args)) => run_snap(args),
+        Some(Command::Stats(args)) => run_stats(args),
+        Some(Command::Activity(args)) => run_activity(args),
+        Some(Command::Sync(args)) => run_sync(args),
+        Some(Command::Index(args)) => run_index(args),
+        Some(Command::Query(args)) => run_query(args),
+        Some(Command::Duplicates(args)) => run_duplicates(args),
+        Some(Command::Thumbnail(args)) => run_thumbnail(args),
+        Some(Command::Loops(args)) => run_loops(args),
+        Some(Command::Batch(args)) => run_batch(args),
+        Some(Command::Features(args)) => run_features(args),
+        Some(Command::Worker(args)) => run_worker(args),
+        Some(Command::Qc(args)) => run_qc(args),
+        Some(Command::Verify(args)) => run_verify(args),
+        Some(Command::Pipeline(args)) => run_pipeline(args),
+        Some(Command::View(args)) => run_view(args),
+        Some(Command::Plugins) => run_plugins(),
+        None => {
+            // both paths are required by clap when no subcommand is given
+            let path_in = cli.path_in.as_deref().expect("Missing input path");
+            let path_out = cli.path_out.as_deref().expect("Missing output path");
+            if cli.preview && path_out == "-" {
+                anyhow::bail!("--preview can't be combined with writing the novelty function to standard output");
+            }
+            let sonify = cli.sonify.sonify.as_deref().map(|path| (path, cli.sonify.carrier()));
+            let (novelty, _, _) = run_novelty(path_in, path_out, &cli.novelty, sonify, &cli.summary, &cli.csv, false, false)?;
 
-    Ok(())
+            // quick look at the result before opening the CSV file
+            if cli.preview {
+                let onsets = detect_onsets(&novelty, cli.summary.onset_threshold);
+                let annotations = cli.annotations.as_deref().map(read_onset_times).transpose()?.unwrap_or_default();
+                println!("{}", sparkline_annotated(&novelty, &onsets, &annotations, cli.preview_width));
+                println!("{:.02} s, {} onsets", novelty.values.len() as f32 / novelty.fs_feature, onsets.len());
+            }
+            Ok(())
+        }
+    }
 }
@@ -0,0 +1,163 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use crate::config::NoveltyConfig;
+use crate::summary::Summary;
+
+/// One input file of a batch job, as listed in a manifest.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ManifestEntry {
+    /// Path to the input file
+    pub path_in: String,
+    /// Path to the output file, if given
+    pub path_out: Option<String>,
+    /// Parameter overrides as pairs of column name and value
+    pub overrides: Vec<(String, String)>,
+}
+
+/// Status of one input file after a batch job.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum BatchStatus {
+    /// The output was computed
+    Processed,
+    /// The output already existed and was kept
+    Skipped,
+    /// Processing failed
+    Failed,
+}
+
+/// The outcome of one input file of a batch job, as collected in a batch report.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BatchRecord {
+    /// Path to the input file
+    pub path: String,
+    /// Path to the output file
+    pub output: String,
+    /// Whether the file was processed, skipped, or failed
+    pub status: BatchStatus,
+    /// Aggregate statistics of the novelty function, if it was computed
+    pub summary: Option<Summary>,
+    /// Error message of a failed file
+    pub error: Option<String>,
+}
+
+/// Reads a batch manifest from a CSV file.
+///
+/// The first line is a header. The `path` column is required and lists the input files.
+/// The optional `output` column lists the output files. All other columns are parameter
+/// overrides named like the command-line options, e.g. `window_length` or `method`. Empty
+/// cells don't override anything, and empty lines are skipped. Relative paths are kept as
+/// they are, i.e. they are relative to the working directory.
+///
+/// # Errors
+/// Returns an error if the file can't be read, the `path` column is missing, or a row has
+/// a different number of cells than the header.
+pub fn read_manifest(path: &str) -> anyhow::Result<Vec<ManifestEntry>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut lines = reader.lines();
+
+    let header = match lines.next() {
+        Some(line) => line?,
+        None => anyhow::bail!("Manifest {} is empty", path),
+    };
+    let columns: Vec<String> = header.split(',').map(|column| column.trim().to_string()).collect();
+    let Some(path_column) = columns.iter().position(|column| column == "path") else {
+        anyhow::bail!("Manifest {} has no path column", path);
+    };
+    let output_column = columns.iter().position(|column| column == "output");
+
+    let mut entries = Vec::new();
+    for (index, line) in lines.enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let cells: Vec<&str> = line.split(',').map(|cell| cell.trim()).collect();
+        if cells.len() != columns.len() {
+            anyhow::bail!("Expected {} cells in line {} of {}, got {}", columns.len(), index + 2, path, cells.len());
+        }
+
+        let overrides = columns
+            .iter()
+            .zip(&cells)
+            .enumerate()
+            .filter(|&(i, (_, cell))| i != path_column && Some(i) != output_column && !cell.is_empty())
+            .map(|(_, (column, cell))| (column.clone(), cell.to_string()))
+            .collect();
+        entries.push(ManifestEntry {
+            path_in: cells[path_column].to_string(),
+            path_out: output_column.map(|i| cells[i].to_string()).filter(|cell| !cell.is_empty()),
+            overrides,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Finds the files in `dir` whose extension is one of `extensions`, ignoring case.
+///
+/// With `recursive`, subdirectories are searched as well. The paths are sorted, so batch
+/// jobs process a directory in a reproducible order.
+///
+/// # Errors
+/// Returns an error if a directory can't be read.
+pub fn find_files(dir: &Path, extensions: &[String], recursive: bool) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                if recursive {
+                    dirs.push(path);
+                }
+            } else if path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| extensions.iter().any(|e| e.eq_ignore_ascii_case(extension)))
+            {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Expands an output file name template for a batch input, e.g. `{stem}_{method}_w{window}.csv`,
+/// so the outputs of parameter sweeps over a corpus don't overwrite each other and name their
+/// parameters.
+///
+/// The placeholders are `{stem}`, the input file name without extension, and the parameters
+/// of the configuration: `{method}`, `{window}` and `{hop}` in samples, `{compression}`,
+/// `{gamma}`, and `{mels}`, the number of mel bands.
+///
+/// # Errors
+/// Returns an error if the template has an unknown or unclosed placeholder.
+pub fn expand_output_template(template: &str, path_in: &str, config: &NoveltyConfig) -> anyhow::Result<String> {
+    let mut name = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        name.push_str(&rest[..start]);
+        let end = rest[start..].find('}').ok_or_else(|| anyhow::anyhow!("Unclosed placeholder in output template {}", template))?;
+        let value = match &rest[start + 1..start + end] {
+            "stem" => Path::new(path_in).file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+            "method" => format!("{:?}", config.method).to_lowercase(),
+            "window" => config.window_length.to_string(),
+            "hop" => config.hop_length.to_string(),
+            "compression" => format!("{:?}", config.compression).to_lowercase(),
+            "gamma" => config.gamma.to_string(),
+            "mels" => config.n_mels.to_string(),
+            placeholder => anyhow::bail!("Unknown placeholder {{{}}} in output template {}", placeholder, template),
+        };
+        name.push_str(&value);
+        rest = &rest[start + end + 1..];
+    }
+    name.push_str(rest);
+    Ok(name)
+}
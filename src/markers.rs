@@ -0,0 +1,294 @@
+use std::io::Write;
+
+use crate::timecode::Timecode;
+
+/// File formats of [`write_markers`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum MarkerFormat {
+    /// CSV file for the region/marker manager of REAPER, see [`write_reaper_csv`]
+    #[default]
+    Reaper,
+    /// Locations of an Ardour session, see [`write_ardour_locations`]
+    Ardour,
+    /// Plain text with one `minutes:seconds label` line per marker, see [`write_plain_markers`]
+    Plain,
+    /// CMX 3600 edit decision list with one event per marker, see [`write_edl_markers`]
+    Edl,
+    /// Final Cut Pro XML project with the markers on a gap, see [`write_fcpxml_markers`]
+    Fcpxml,
+}
+
+/// A named marker at a point in time, or a region if it has an end.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Marker {
+    /// Name shown in the DAW
+    pub name: String,
+    /// Time of the marker or start of the region in seconds
+    pub start: f32,
+    /// End of the region in seconds, `None` for a marker
+    pub end: Option<f32>,
+}
+
+/// Returns one marker per event time, named with the prefix and the number of the event,
+/// starting at 1.
+pub fn markers_from_times(times: &[f32], prefix: &str) -> Vec<Marker> {
+    times
+        .iter()
+        .enumerate()
+        .map(|(k, &time)| Marker {
+            name: format!("{} {}", prefix, k + 1),
+            start: time,
+            end: None,
+        })
+        .collect()
+}
+
+/// Returns the regions between consecutive boundary times, with the last region ending at
+/// `end`, named with the prefix and the number of the region, starting at 1.
+///
+/// Boundaries are sorted first, and regions of zero length are skipped.
+pub fn regions_from_boundaries(boundaries: &[f32], end: f32, prefix: &str) -> Vec<Marker> {
+    let mut boundaries = boundaries.to_vec();
+    boundaries.sort_by(f32::total_cmp);
+    boundaries.push(end);
+    boundaries
+        .windows(2)
+        .filter(|pair| pair[1] > pair[0])
+        .enumerate()
+        .map(|(k, pair)| Marker {
+            name: format!("{} {}", prefix, k + 1),
+            start: pair[0],
+            end: Some(pair[1]),
+        })
+        .collect()
+}
+
+/// Formats a time in seconds as `minutes:seconds.milliseconds`, e.g. `1:02.500`.
+fn format_minutes(seconds: f32) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!("{}:{:02}.{:03}", millis / 60_000, millis / 1000 % 60, millis % 1000)
+}
+
+/// Escapes the characters of a marker name that are special in XML attributes.
+fn escape_xml(name: &str) -> String {
+    name.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Writes markers and regions to a CSV file for the region/marker manager of REAPER.
+///
+/// The columns are `#,Name,Start,End,Length`, where `#` is `M` or `R` followed by the number
+/// of the marker or region, and times are given as `minutes:seconds.milliseconds`. The end
+/// and length of markers are left empty.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_reaper_csv(path: &str, markers: &[Marker]) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    writeln!(file, "#,Name,Start,End,Length")?;
+    let (mut num_markers, mut num_regions) = (0, 0);
+    for marker in markers {
+        let name = marker.name.replace('"', "\"\"");
+        match marker.end {
+            Some(end) => {
+                num_regions += 1;
+                let (start, end, length) = (format_minutes(marker.start), format_minutes(end), format_minutes(end - marker.start));
+                writeln!(file, "R{},\"{}\",{},{},{}", num_regions, name, start, end, length)?;
+            }
+            None => {
+                num_markers += 1;
+                writeln!(file, "M{},\"{}\",{},,", num_markers, name, format_minutes(marker.start))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes markers and regions as the `Locations` element of an Ardour session file.
+///
+/// Ardour has no marker import, so the `Location` elements have to be pasted into the
+/// `Locations` element of the `.ardour` session file while the session is closed. Positions
+/// are given in samples at the sampling rate `fs` of the session, as in the session format
+/// of Ardour 6. Markers are point locations (`IsMark`) and regions are range markers
+/// (`IsRangeMarker`).
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_ardour_locations(path: &str, markers: &[Marker], fs: u32) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    let sample = |time: f32| (time.max(0.0) as f64 * fs as f64).round() as u64;
+
+    writeln!(file, "<Locations>")?;
+    for (k, marker) in markers.iter().enumerate() {
+        let name = escape_xml(&marker.name);
+        let (end, flags) = match marker.end {
+            Some(end) => (end, "IsRangeMarker"),
+            None => (marker.start, "IsMark"),
+        };
+        writeln!(
+            file,
+            "  <Location id=\"{}\" name=\"{}\" start=\"{}\" end=\"{}\" flags=\"{}\" locked=\"0\" timestamp=\"0\" cue=\"0\"/>",
+            // ids well above those Ardour assigns to the locations of a new session
+            1_000_000 + k,
+            name,
+            sample(marker.start),
+            sample(end),
+            flags
+        )?;
+    }
+    writeln!(file, "</Locations>")?;
+
+    Ok(())
+}
+
+/// Writes markers as plain text with one `minutes:seconds.milliseconds label` line per
+/// marker, e.g. `1:02.500 Onset 3`, which many DAWs and video platforms import as
+/// chapters or markers. Regions are written as markers at their start.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_plain_markers(path: &str, markers: &[Marker]) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    for marker in markers {
+        writeln!(file, "{} {}", format_minutes(marker.start), marker.name)?;
+    }
+
+    Ok(())
+}
+
+/// Writes markers and regions as an edit decision list (EDL) in the CMX 3600 format, with
+/// times quantized to the frames of the timecode, e.g. to cut video to the beat in an editor.
+///
+/// Each marker is an event of one frame, and each region an event over its length, with
+/// source and record timecode at the marker. The name, color, and duration in frames follow
+/// on a comment line `|C:ResolveColorBlue |M:name |D:frames`, which DaVinci Resolve imports
+/// as timeline markers.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_edl_markers(path: &str, markers: &[Marker], timecode: Timecode) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    writeln!(file, "TITLE: Markers")?;
+    writeln!(file, "FCM: {}", if timecode.drop_frame() { "DROP FRAME" } else { "NON-DROP FRAME" })?;
+    for (k, marker) in markers.iter().enumerate() {
+        let start = timecode.frame(marker.start as f64);
+        let end = marker.end.map_or(start, |end| timecode.frame(end as f64)).max(start + 1);
+        let (start_tc, end_tc) = (timecode.format_frame(start), timecode.format_frame(end));
+        writeln!(file)?;
+        writeln!(file, "{:03}  001      V     C        {} {} {} {}", k + 1, start_tc, end_tc, start_tc, end_tc)?;
+        // the separators of the comment line can't be part of the name
+        let name = marker.name.replace(['|', '\n', '\r'], " ");
+        writeln!(file, " |C:ResolveColorBlue |M:{} |D:{}", name, end - start)?;
+    }
+
+    Ok(())
+}
+
+/// Writes markers and regions as a Final Cut Pro XML (FCPXML 1.9) project, with times
+/// quantized to the frames of the timecode, e.g. to cut video to the beat in an editor.
+///
+/// The project holds a gap clip from the start timecode to one frame after the last marker
+/// or region, on which markers last one frame and regions their length. Final Cut Pro and
+/// DaVinci Resolve import the file as a new project whose markers can be copied.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_fcpxml_markers(path: &str, markers: &[Marker], timecode: Timecode) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    // FCPXML times are rational numbers of seconds, e.g. 1001/30000s for a frame at 29.97
+    let time = |frames: u64| {
+        let (mut numerator, mut denominator) = (frames * timecode.rate_denominator as u64, timecode.rate_numerator as u64);
+        let (mut a, mut b) = (numerator, denominator);
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        if a > 1 {
+            (numerator, denominator) = (numerator / a, denominator / a);
+        }
+        if denominator == 1 {
+            format!("{}s", numerator)
+        } else {
+            format!("{}/{}s", numerator, denominator)
+        }
+    };
+    let spans: Vec<(u64, u64)> = markers
+        .iter()
+        .map(|marker| {
+            let start = timecode.frame(marker.start as f64);
+            (start, marker.end.map_or(start, |end| timecode.frame(end as f64)).max(start + 1))
+        })
+        .collect();
+    let end = spans.iter().map(|&(_, end)| end).max().unwrap_or(timecode.start + 1);
+    let duration = time(end - timecode.start);
+    let start = time(timecode.start);
+
+    writeln!(file, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(file, "<!DOCTYPE fcpxml>")?;
+    writeln!(file, "<fcpxml version=\"1.9\">")?;
+    writeln!(file, "  <resources>")?;
+    // the dimensions only set up the created project, the markers don't depend on them
+    writeln!(file, "    <format id=\"r1\" frameDuration=\"{}\" width=\"1920\" height=\"1080\"/>", time(1))?;
+    writeln!(file, "  </resources>")?;
+    writeln!(file, "  <library>")?;
+    writeln!(file, "    <event name=\"Markers\">")?;
+    writeln!(file, "      <project name=\"Markers\">")?;
+    writeln!(
+        file,
+        "        <sequence format=\"r1\" duration=\"{}\" tcStart=\"{}\" tcFormat=\"{}\">",
+        duration,
+        start,
+        if timecode.drop_frame() { "DF" } else { "NDF" }
+    )?;
+    writeln!(file, "          <spine>")?;
+    writeln!(file, "            <gap name=\"Gap\" offset=\"{}\" start=\"{}\" duration=\"{}\">", start, start, duration)?;
+    for (marker, &(start, end)) in markers.iter().zip(&spans) {
+        writeln!(file, "              <marker start=\"{}\" duration=\"{}\" value=\"{}\"/>", time(start), time(end - start), escape_xml(&marker.name))?;
+    }
+    writeln!(file, "            </gap>")?;
+    writeln!(file, "          </spine>")?;
+    writeln!(file, "        </sequence>")?;
+    writeln!(file, "      </project>")?;
+    writeln!(file, "    </event>")?;
+    writeln!(file, "  </library>")?;
+    writeln!(file, "</fcpxml>")?;
+
+    Ok(())
+}
+
+/// Writes markers and regions in the given format.
+///
+/// # Arguments
+/// - `path`: Output file path
+/// - `markers`: Markers and regions to write
+/// - `format`: File format
+/// - `fs`: Sampling rate of the session, only needed for [`MarkerFormat::Ardour`]
+/// - `timecode`: Video frame rate and start timecode, only needed for [`MarkerFormat::Edl`]
+///   and [`MarkerFormat::Fcpxml`]
+///
+/// # Errors
+/// Returns an error if writing to the file fails, the Ardour format has no sampling rate, or
+/// the EDL and FCPXML formats have no timecode.
+pub fn write_markers(path: &str, markers: &[Marker], format: MarkerFormat, fs: Option<u32>, timecode: Option<Timecode>) -> anyhow::Result<()> {
+    match format {
+        MarkerFormat::Reaper => write_reaper_csv(path, markers),
+        MarkerFormat::Ardour => {
+            let fs = fs.ok_or_else(|| anyhow::anyhow!("Ardour markers need the sampling rate of the session"))?;
+            write_ardour_locations(path, markers, fs)
+        }
+        MarkerFormat::Plain => write_plain_markers(path, markers),
+        MarkerFormat::Edl | MarkerFormat::Fcpxml => {
+            let timecode = timecode.ok_or_else(|| anyhow::anyhow!("EDL and FCPXML markers need a video frame rate"))?;
+            if format == MarkerFormat::Edl {
+                write_edl_markers(path, markers, timecode)
+            } else {
+                write_fcpxml_markers(path, markers, timecode)
+            }
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use ndarray::{s, Array1, Array2, Axis};
+use ndarray_stats::QuantileExt;
+
+use crate::compression::Compression;
+use crate::config::NoveltyConfig;
+use crate::novelty::normalize_max;
+use crate::spectral::{magnitude_spectrogram, periodic_hann, stft_magnitude};
+
+/// Converts a frequency in Hz to the Slaney mel scale (linear below 1 kHz, logarithmic above).
+pub fn hz_to_mel(frequency: f64) -> f64 {
+    let f_sp = 200.0 / 3.0;
+    let min_log_hz = 1000.0;
+    let min_log_mel = min_log_hz / f_sp;
+    let logstep = 6.4_f64.ln() / 27.0;
+
+    if frequency >= min_log_hz {
+        min_log_mel + (frequency / min_log_hz).ln() / logstep
+    } else {
+        frequency / f_sp
+    }
+}
+
+/// Converts a value on the Slaney mel scale to a frequency in Hz, the inverse of [`hz_to_mel`].
+pub fn mel_to_hz(mel: f64) -> f64 {
+    let f_sp = 200.0 / 3.0;
+    let min_log_hz = 1000.0;
+    let min_log_mel = min_log_hz / f_sp;
+    let logstep = 6.4_f64.ln() / 27.0;
+
+    if mel >= min_log_mel {
+        min_log_hz * (logstep * (mel - min_log_mel)).exp()
+    } else {
+        f_sp * mel
+    }
+}
+
+/// Computes a mel filterbank with triangular, area-normalized filters.
+///
+/// This matches `librosa.filters.mel` with `htk=False` and `norm="slaney"`.
+///
+/// # Arguments
+/// - `fs`: Sampling rate of the audio
+/// - `n_fft`: FFT size of the spectrogram the filterbank is applied to
+/// - `n_mels`: Number of mel bands
+/// - `fmin`: Lowest frequency in Hz
+/// - `fmax`: Highest frequency in Hz
+///
+/// # Returns
+/// - Filter weights with shape (`n_mels`, `n_fft / 2 + 1`)
+pub fn mel_filterbank(fs: u32, n_fft: usize, n_mels: usize, fmin: f32, fmax: f32) -> Array2<f32> {
+    let num_bins = n_fft / 2 + 1;
+    let fft_frequencies: Vec<f64> = (0..num_bins).map(|k| k as f64 * fs as f64 / n_fft as f64).collect();
+
+    // band edges equally spaced on the mel scale
+    let min_mel = hz_to_mel(fmin as f64);
+    let max_mel = hz_to_mel(fmax as f64);
+    let mel_frequencies: Vec<f64> = (0..n_mels + 2)
+        .map(|i| mel_to_hz(min_mel + (max_mel - min_mel) * i as f64 / (n_mels + 1) as f64))
+        .collect();
+
+    Array2::from_shape_fn((n_mels, num_bins), |(i, k)| {
+        let lower = (fft_frequencies[k] - mel_frequencies[i]) / (mel_frequencies[i + 1] - mel_frequencies[i]);
+        let upper = (mel_frequencies[i + 2] - fft_frequencies[k]) / (mel_frequencies[i + 2] - mel_frequencies[i + 1]);
+        let enorm = 2.0 / (mel_frequencies[i + 2] - mel_frequencies[i]);
+        (lower.min(upper).max(0.0) * enorm) as f32
+    })
+}
+
+/// Computes a novelty function from the increase of mel-band energies over time.
+///
+/// The power spectrogram is computed with the same framing as the energy-based novelty
+/// function and mapped to `n_mels` mel bands. Each band is compressed, differentiated, and
+/// half-wave rectified, and the bands are averaged.
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples
+/// - `fs`: Sampling rate of the audio
+/// - `window_length`: Size of the analysis window and FFT
+/// - `hop_length`: Step size between successive frames
+/// - `n_mels`: Number of mel bands
+/// - `compression`: Compression function applied to the mel-band energies
+/// - `gamma`: Parameter of the compression function
+/// - `norm`: Whether to normalize the output between 0 and 1
+///
+/// # Returns
+/// - A tuple of the novelty function and its effective sampling rate
+///
+/// # Errors
+/// Returns an error if normalization fails.
+#[allow(clippy::too_many_arguments)]
+pub fn novelty_mel(audio_array: &Array1<f32>, fs: u32, window_length: u32, hop_length: u32, n_mels: u32, compression: Compression, gamma: f32, norm: bool) -> anyhow::Result<(Array1<f32>, f32)> {
+    let fs_feature = (fs as f32) / (hop_length as f32);
+
+    let spectrogram = magnitude_spectrogram(audio_array, window_length as usize, hop_length as usize);
+    let filterbank = mel_filterbank(fs, window_length as usize, n_mels as usize, 0.0, fs as f32 / 2.0);
+    let mut novelty = band_flux(&filterbank, &spectrogram, compression, gamma);
+
+    if norm {
+        normalize_max(&mut novelty)?;
+    }
+
+    Ok((novelty, fs_feature))
+}
+
+/// Computes the compressed mel spectrogram that [`novelty_mel`] differentiates.
+///
+/// # Returns
+/// - Compressed mel-band energies with shape (`n_mels`, number of frames), with the framing
+///   of [`magnitude_spectrogram`]
+pub fn mel_features(audio_array: &Array1<f32>, fs: u32, window_length: u32, hop_length: u32, n_mels: u32, compression: Compression, gamma: f32) -> Array2<f32> {
+    let spectrogram = magnitude_spectrogram(audio_array, window_length as usize, hop_length as usize);
+    let filterbank = mel_filterbank(fs, window_length as usize, n_mels as usize, 0.0, fs as f32 / 2.0);
+    let mut mel = filterbank.dot(&spectrogram.mapv(|v| v * v));
+    mel.mapv_inplace(|v| compression.apply(v, gamma));
+    mel
+}
+
+/// Maps a magnitude spectrogram to band energies, compresses them, and averages the
+/// half-wave rectified increase over the bands.
+///
+/// The novelty of frame m is the increase towards frame m + 1, with a trailing zero.
+pub(crate) fn band_flux(filterbank: &Array2<f32>, spectrogram: &Array2<f32>, compression: Compression, gamma: f32) -> Array1<f32> {
+    band_power_flux(filterbank.dot(&spectrogram.mapv(|v| v * v)), compression, gamma)
+}
+
+/// Compresses band energies with shape (bands, frames) and averages the half-wave rectified
+/// increase over the bands, like [`band_flux`].
+pub(crate) fn band_power_flux(mut band_power: Array2<f32>, compression: Compression, gamma: f32) -> Array1<f32> {
+    band_power.mapv_inplace(|v| compression.apply(v, gamma));
+
+    let num_frames = band_power.ncols();
+    let mut novelty = Array1::zeros(num_frames);
+    for m in 0..num_frames.saturating_sub(1) {
+        let diff = &band_power.column(m + 1) - &band_power.column(m);
+        novelty[m] = diff.mapv(|v| v.max(0.0)).mean().unwrap_or(0.0);
+    }
+    novelty
+}
+
+/// Computes an onset strength envelope that numerically matches `librosa.onset.onset_strength`.
+///
+/// This follows librosa 0.10 with its defaults `lag=1`, `max_size=1`, `center=True`, and
+/// mean aggregation: a centered STFT with a periodic Hann window and zero padding
+/// (`1 + len / hop_length` frames), a Slaney mel spectrogram in dB with `top_db=80`, and
+/// the envelope delayed by `1 + n_fft / (2 * hop_length)` frames as librosa does.
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples
+/// - `fs`: Sampling rate of the audio
+/// - `n_fft`: FFT size (librosa default: 2048)
+/// - `hop_length`: Step size between successive frames (librosa default: 512)
+/// - `n_mels`: Number of mel bands (librosa default: 128)
+///
+/// # Returns
+/// - A tuple of the onset strength envelope and its effective sampling rate
+pub fn onset_strength_librosa(audio_array: &Array1<f32>, fs: u32, n_fft: u32, hop_length: u32, n_mels: u32) -> (Array1<f32>, f32) {
+    let fs_feature = (fs as f32) / (hop_length as f32);
+    let n_fft = n_fft as usize;
+    let hop_length = hop_length as usize;
+
+    let num_frames = 1 + audio_array.len() / hop_length;
+    let spectrogram = stft_magnitude(audio_array, &periodic_hann(n_fft), hop_length, num_frames);
+    let filterbank = mel_filterbank(fs, n_fft, n_mels as usize, 0.0, fs as f32 / 2.0);
+    let mel_power = filterbank.dot(&spectrogram.mapv(|v| v * v));
+
+    // power_to_db with ref=1.0, amin=1e-10, top_db=80.0
+    let mut mel_db = mel_power.mapv(|v| 10.0 * v.max(1e-10).log10());
+    let max_db = mel_db.max().copied().unwrap_or(0.0);
+    mel_db.mapv_inplace(|v| v.max(max_db - 80.0));
+
+    // rectified first-order difference, averaged over the mel bands
+    let diff = &mel_db.slice(s![.., 1..]) - &mel_db.slice(s![.., ..-1]);
+    let envelope = diff.mapv(|v| v.max(0.0)).mean_axis(Axis(0));
+
+    // librosa pads the envelope with lag + n_fft / (2 * hop_length) leading zeros
+    let pad_width = 1 + n_fft / (2 * hop_length);
+    let mut onset_strength = Array1::zeros(num_frames);
+    if let Some(envelope) = envelope {
+        for (m, value) in envelope.iter().enumerate() {
+            if m + pad_width < num_frames {
+                onset_strength[m + pad_width] = *value;
+            }
+        }
+    }
+
+    (onset_strength, fs_feature)
+}
+
+/// Computes compressed mel spectra normalized to unit length per frame, so that the dot
+/// product of two frames is their cosine similarity.
+///
+/// The window length, hop length, number of mel bands, and compression are taken from `config`.
+///
+/// # Returns
+/// - Features with shape (`n_mels`, number of frames)
+pub(crate) fn normalized_mel_features(audio_array: &Array1<f32>, fs: u32, config: &NoveltyConfig) -> Array2<f32> {
+    let spectrogram = magnitude_spectrogram(audio_array, config.window_length as usize, config.hop_length as usize);
+    let filterbank = mel_filterbank(fs, config.window_length as usize, config.n_mels as usize, 0.0, fs as f32 / 2.0);
+    let mut features = filterbank.dot(&spectrogram);
+    features.mapv_inplace(|v| config.compression.apply(v, config.gamma));
+
+    for mut column in features.columns_mut() {
+        let norm = column.dot(&column).sqrt();
+        if norm > 0.0 {
+            column.mapv_inplace(|v| v / norm);
+        }
+    }
+
+    features
+}
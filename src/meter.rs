@@ -0,0 +1,112 @@
+use crate::beats::Beat;
+use crate::novelty::NoveltyCurve;
+
+/// Numbers of beats per bar considered by [`estimate_meter`].
+pub const BEATS_PER_BAR_CANDIDATES: [usize; 3] = [2, 3, 4];
+
+/// A meter hypothesis estimated by [`estimate_meter`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MeterCandidate {
+    /// Number of beats per bar, e.g. 3 for 3/4 and 4 for 4/4
+    pub beats_per_bar: usize,
+    /// Index of the first beat that starts a bar
+    pub phase: usize,
+    /// Accentuation of the strongest beat position relative to the others
+    pub score: f32,
+    /// Score relative to all candidates with a positive score, between 0 and 1
+    pub confidence: f32,
+}
+
+impl MeterCandidate {
+    /// Returns the meter as a time signature with quarter-note beats, e.g. `3/4`.
+    pub fn time_signature(&self) -> String {
+        format!("{}/4", self.beats_per_bar)
+    }
+}
+
+/// Returns the strength of each beat as the maximum novelty within one frame of the beat.
+pub fn beat_strengths(novelty: &NoveltyCurve, beats: &[Beat]) -> Vec<f32> {
+    let values = &novelty.values;
+    beats
+        .iter()
+        .map(|beat| {
+            let start = beat.frame.saturating_sub(1);
+            let end = (beat.frame + 2).min(values.len());
+            (start..end).map(|n| values[n]).fold(0.0, f32::max)
+        })
+        .collect()
+}
+
+/// Estimates the meter from the accentuation pattern of the novelty function on a beat grid.
+///
+/// The beat strengths are standardized, and for each number of beats per bar in
+/// [`BEATS_PER_BAR_CANDIDATES`] and each phase, the mean standardized strength of the beats
+/// at that position in the bar is computed. The score of a candidate is the mean of its
+/// most accented position, which is taken as the downbeat. If two candidates are equally
+/// accented, e.g. strictly alternating strong and weak beats, the shorter bar is preferred.
+///
+/// # Returns
+/// - The candidates sorted by descending score, or an empty list if there are fewer than
+///   two bars of the longest candidate or all beats are equally strong
+pub fn estimate_meter(novelty: &NoveltyCurve, beats: &[Beat]) -> Vec<MeterCandidate> {
+    let strengths = beat_strengths(novelty, beats);
+    let max_beats_per_bar = BEATS_PER_BAR_CANDIDATES.iter().copied().max().unwrap_or(1);
+    if strengths.len() < 2 * max_beats_per_bar {
+        return Vec::new();
+    }
+
+    let num_beats = strengths.len() as f32;
+    let mean = strengths.iter().sum::<f32>() / num_beats;
+    let std = (strengths.iter().map(|s| (s - mean).powi(2)).sum::<f32>() / num_beats).sqrt();
+    if std <= 0.0 {
+        return Vec::new();
+    }
+    let standardized: Vec<f32> = strengths.iter().map(|s| (s - mean) / std).collect();
+
+    let mut candidates: Vec<MeterCandidate> = BEATS_PER_BAR_CANDIDATES
+        .iter()
+        .map(|&beats_per_bar| {
+            let (phase, score) = (0..beats_per_bar)
+                .map(|phase| {
+                    let position: Vec<f32> = standardized.iter().skip(phase).step_by(beats_per_bar).copied().collect();
+                    (phase, position.iter().sum::<f32>() / position.len() as f32)
+                })
+                .fold((0, f32::NEG_INFINITY), |best, current| if current.1 > best.1 { current } else { best });
+            MeterCandidate {
+                beats_per_bar,
+                phase,
+                score,
+                confidence: 0.0,
+            }
+        })
+        .collect();
+
+    let total: f32 = candidates.iter().map(|c| c.score.max(0.0)).sum();
+    for candidate in candidates.iter_mut() {
+        candidate.confidence = if total > 0.0 { candidate.score.max(0.0) / total } else { 0.0 };
+    }
+
+    // stable sort keeps shorter bars first on ties, up to rounding errors
+    let rounded = |candidate: &MeterCandidate| (candidate.score * 1e4).round();
+    candidates.sort_by(|a, b| rounded(b).total_cmp(&rounded(a)));
+    candidates
+}
+
+/// Estimates the downbeats of a beat sequence and sets the position of each beat in its bar.
+///
+/// The bar length and the first downbeat are taken from the best candidate of
+/// [`estimate_meter`], i.e. the bar-length periodicity with the strongest accentuation of
+/// the novelty function. The beats before the first downbeat are labeled as the end of an
+/// incomplete bar.
+///
+/// # Returns
+/// - The meter candidate used for the labels, or `None` if no meter could be estimated, in
+///   which case the beats are left unchanged
+pub fn track_downbeats(novelty: &NoveltyCurve, beats: &mut [Beat]) -> Option<MeterCandidate> {
+    let meter = *estimate_meter(novelty, beats).first()?;
+    for (i, beat) in beats.iter_mut().enumerate() {
+        beat.position_in_bar = Some((i + meter.beats_per_bar - meter.phase) % meter.beats_per_bar + 1);
+    }
+    Some(meter)
+}
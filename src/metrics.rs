@@ -0,0 +1,66 @@
+use std::sync::Mutex;
+
+use crate::manifest::BatchStatus;
+
+/// Upper bounds in seconds of the buckets of the processing latency histogram.
+pub const LATENCY_BUCKETS: [f64; 11] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0];
+
+/// Counters of a long-running batch or worker process, rendered in the Prometheus text
+/// exposition format for monitoring.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    state: Mutex<MetricsState>,
+}
+
+#[derive(Debug, Default)]
+struct MetricsState {
+    processed: u64,
+    skipped: u64,
+    failed: u64,
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    latency_sum: f64,
+    latency_count: u64,
+}
+
+impl Metrics {
+    /// Counts one file with its status. The processing time of processed files is added to
+    /// the latency histogram, while skipped and failed files would distort it.
+    pub fn record(&self, status: BatchStatus, seconds: f64) {
+        let mut state = self.state.lock().expect("Metrics writer panicked");
+        match status {
+            BatchStatus::Processed => {
+                state.processed += 1;
+                for (bucket, bound) in state.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+                    if seconds <= bound {
+                        *bucket += 1;
+                    }
+                }
+                state.latency_sum += seconds;
+                state.latency_count += 1;
+            }
+            BatchStatus::Skipped => state.skipped += 1,
+            BatchStatus::Failed => state.failed += 1,
+        }
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format (version 0.0.4).
+    pub fn render(&self) -> String {
+        let state = self.state.lock().expect("Metrics writer panicked");
+        let mut lines = vec![
+            "# HELP novelty_rust_files_total Files handled, by status.".to_string(),
+            "# TYPE novelty_rust_files_total counter".to_string(),
+        ];
+        for (status, count) in [("processed", state.processed), ("skipped", state.skipped), ("failed", state.failed)] {
+            lines.push(format!("novelty_rust_files_total{{status=\"{}\"}} {}", status, count));
+        }
+        lines.push("# HELP novelty_rust_processing_seconds Processing time of processed files.".to_string());
+        lines.push("# TYPE novelty_rust_processing_seconds histogram".to_string());
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(state.buckets) {
+            lines.push(format!("novelty_rust_processing_seconds_bucket{{le=\"{}\"}} {}", bound, count));
+        }
+        lines.push(format!("novelty_rust_processing_seconds_bucket{{le=\"+Inf\"}} {}", state.latency_count));
+        lines.push(format!("novelty_rust_processing_seconds_sum {}", state.latency_sum));
+        lines.push(format!("novelty_rust_processing_seconds_count {}", state.latency_count));
+        lines.iter().map(|line| format!("{}\n", line)).collect()
+    }
+}
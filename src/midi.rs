@@ -0,0 +1,184 @@
+use std::io::Write;
+
+use ndarray::{s, Array1};
+
+use crate::onsets::Onset;
+
+/// Ticks per quarter note of the MIDI files written by [`write_midi`].
+pub const MIDI_TICKS_PER_QUARTER: u16 = 480;
+
+/// Length in seconds of the notes written by [`write_midi`], unless the next onset comes first.
+pub const MIDI_NOTE_LENGTH: f32 = 0.1;
+
+/// Length in seconds of the window after each onset whose RMS level is the loudness of
+/// [`VelocitySource::Loudness`].
+pub const VELOCITY_LOUDNESS_WINDOW: f32 = 0.05;
+
+/// Dynamic range in dB below the loudest onset that [`VelocitySource::Loudness`] maps to
+/// velocities.
+pub const VELOCITY_LOUDNESS_RANGE: f32 = 60.0;
+
+/// What the velocity of an onset is derived from, see [`onset_velocities`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum VelocitySource {
+    /// The height of the novelty peak, relative to the strongest onset
+    #[default]
+    Strength,
+    /// The RMS level in dB of the audio in [`VELOCITY_LOUDNESS_WINDOW`] seconds after the
+    /// onset, over [`VELOCITY_LOUDNESS_RANGE`] dB below the loudest onset
+    Loudness,
+}
+
+/// Mapping of onsets to MIDI velocities, see [`onset_velocities`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VelocityMapping {
+    /// What the velocity is derived from
+    pub source: VelocitySource,
+    /// Exponent applied to the normalized value before scaling, 1 for a linear mapping,
+    /// below 1 to lift soft hits and above 1 to emphasize loud ones
+    pub curve: f32,
+    /// Velocity of the softest possible onset, from 1 to 127
+    pub min: u8,
+    /// Velocity of the strongest onset, from 1 to 127
+    pub max: u8,
+}
+
+impl Default for VelocityMapping {
+    /// Returns a linear mapping of the novelty peak height to velocities from 1 to 127.
+    fn default() -> Self {
+        VelocityMapping { source: VelocitySource::Strength, curve: 1.0, min: 1, max: 127 }
+    }
+}
+
+impl VelocityMapping {
+    /// Validates the velocity range and curve.
+    ///
+    /// # Errors
+    /// Returns an error if the velocities aren't from 1 to 127 with `min` up to `max`, or if
+    /// the curve isn't greater than zero.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.min == 0 || self.max > 127 || self.min > self.max {
+            anyhow::bail!("Velocities must be from 1 to 127, got {} to {}", self.min, self.max);
+        }
+        if !(self.curve > 0.0 && self.curve.is_finite()) {
+            anyhow::bail!("Velocity curve must be greater than zero, got {}", self.curve);
+        }
+        Ok(())
+    }
+}
+
+/// Maps onsets to MIDI velocities, e.g. so re-triggered drum hits keep the dynamics of the
+/// recording.
+///
+/// The value of each onset is normalized to the range from 0 to 1 relative to the strongest
+/// onset, raised to the power of `curve`, and scaled to the range from `min` to `max`.
+///
+/// # Arguments
+/// - `onsets`: Detected onsets
+/// - `audio`: Mono samples and sampling rate of the audio, only needed for
+///   [`VelocitySource::Loudness`]
+/// - `mapping`: Source and curve of the mapping
+///
+/// # Errors
+/// Returns an error if the velocity range or curve is invalid, or if the loudness source has
+/// no audio.
+pub fn onset_velocities(onsets: &[Onset], audio: Option<(&Array1<f32>, u32)>, mapping: &VelocityMapping) -> anyhow::Result<Vec<u8>> {
+    mapping.validate()?;
+
+    let values: Vec<f32> = match mapping.source {
+        VelocitySource::Strength => {
+            let max = onsets.iter().map(|onset| onset.strength).fold(0.0_f32, f32::max);
+            onsets.iter().map(|onset| if max > 0.0 { onset.strength.max(0.0) / max } else { 1.0 }).collect()
+        }
+        VelocitySource::Loudness => {
+            let (audio_array, fs) = audio.ok_or_else(|| anyhow::anyhow!("Loudness velocities need the audio"))?;
+            let window = ((VELOCITY_LOUDNESS_WINDOW * fs as f32).round() as usize).max(1);
+            let levels: Vec<f32> = onsets
+                .iter()
+                .map(|onset| {
+                    let start = ((onset.time.max(0.0) * fs as f32).round() as usize).min(audio_array.len());
+                    let samples = audio_array.slice(s![start..(start + window).min(audio_array.len())]);
+                    let mean_square = samples.mapv(|v| v * v).sum() / samples.len().max(1) as f32;
+                    10.0 * mean_square.max(1e-12).log10()
+                })
+                .collect();
+            let max = levels.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            levels.iter().map(|level| ((level - max) / VELOCITY_LOUDNESS_RANGE + 1.0).clamp(0.0, 1.0)).collect()
+        }
+    };
+
+    let range = (mapping.max - mapping.min) as f32;
+    Ok(values.iter().map(|value| mapping.min + (value.powf(mapping.curve) * range).round() as u8).collect())
+}
+
+/// Appends a variable-length quantity of a MIDI file.
+fn push_variable_length(bytes: &mut Vec<u8>, value: u32) {
+    let mut groups = vec![(value & 0x7f) as u8];
+    let mut rest = value >> 7;
+    while rest > 0 {
+        groups.push((rest & 0x7f) as u8 | 0x80);
+        rest >>= 7;
+    }
+    bytes.extend(groups.iter().rev());
+}
+
+/// Writes onsets as notes to a standard MIDI file (format 0) at 120 BPM, so the onset times
+/// are kept in seconds, e.g. to re-trigger drum samples in a DAW.
+///
+/// Each onset is a note of [`MIDI_NOTE_LENGTH`] seconds, or up to the next onset if that comes
+/// first, with the given velocity. Channel 10 is the drum channel of General MIDI, on which
+/// e.g. note 36 is a kick drum and note 38 a snare drum.
+///
+/// # Arguments
+/// - `path`: Output file path
+/// - `onsets`: Detected onsets, sorted by time
+/// - `velocities`: Velocity of each onset, see [`onset_velocities`]
+/// - `note`: MIDI note number of all notes
+/// - `channel`: MIDI channel from 1 to 16
+///
+/// # Errors
+/// Returns an error if writing to the file fails, the number of velocities differs from the
+/// number of onsets, or the note or channel is out of range.
+pub fn write_midi(path: &str, onsets: &[Onset], velocities: &[u8], note: u8, channel: u8) -> anyhow::Result<()> {
+    if onsets.len() != velocities.len() {
+        anyhow::bail!("Got {} onsets but {} velocities", onsets.len(), velocities.len());
+    }
+    if note > 127 || !(1..=16).contains(&channel) {
+        anyhow::bail!("MIDI notes must be from 0 to 127 and channels from 1 to 16, got note {} on channel {}", note, channel);
+    }
+    // 120 BPM, i.e. two quarter notes per second
+    let tick = |time: f32| (time.max(0.0) as f64 * 2.0 * MIDI_TICKS_PER_QUARTER as f64).round() as u32;
+
+    // note offs sort before note ons at the same tick, so repeated notes are retriggered
+    let mut events: Vec<(u32, u8, u8)> = Vec::with_capacity(2 * onsets.len());
+    for (k, (onset, &velocity)) in onsets.iter().zip(velocities).enumerate() {
+        let end = onsets.get(k + 1).map_or(f32::INFINITY, |next| next.time).min(onset.time + MIDI_NOTE_LENGTH);
+        events.push((tick(onset.time), 0x90, velocity.clamp(1, 127)));
+        events.push((tick(end).max(tick(onset.time) + 1), 0x80, 0));
+    }
+    events.sort_by_key(|&(tick, status, _)| (tick, status));
+
+    // tempo of 500000 microseconds per quarter note
+    let mut track = vec![0x00, 0xff, 0x51, 0x03, 0x07, 0xa1, 0x20];
+    let mut previous = 0;
+    for (tick, status, velocity) in events {
+        push_variable_length(&mut track, tick - previous);
+        track.extend([status | (channel - 1), note, velocity]);
+        previous = tick;
+    }
+    track.extend([0x00, 0xff, 0x2f, 0x00]);
+
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(b"MThd")?;
+    file.write_all(&6_u32.to_be_bytes())?;
+    file.write_all(&[0, 0, 0, 1])?;
+    file.write_all(&MIDI_TICKS_PER_QUARTER.to_be_bytes())?;
+    file.write_all(b"MTrk")?;
+    file.write_all(&(track.len() as u32).to_be_bytes())?;
+    file.write_all(&track)?;
+
+    Ok(())
+}
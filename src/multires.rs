@@ -0,0 +1,73 @@
+use ndarray::Array1;
+
+use crate::novelty::NoveltyCurve;
+
+/// How novelty functions computed at several resolutions are combined into one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Fusion {
+    /// Mean of the normalized novelty functions, favoring events visible at all resolutions
+    #[default]
+    Mean,
+    /// Maximum of the normalized novelty functions, keeping events visible at any resolution
+    Max,
+}
+
+/// Resamples a novelty function to another feature rate by linear interpolation.
+///
+/// Frames are taken as samples at times `n / fs_feature`. Frames past the end of the
+/// novelty function repeat its last value.
+///
+/// # Arguments
+/// - `novelty`: Novelty function to resample
+/// - `fs_feature`: Target feature rate in frames per second
+/// - `length`: Number of frames of the result
+pub fn resample_novelty(novelty: &NoveltyCurve, fs_feature: f32, length: usize) -> Array1<f32> {
+    let values = &novelty.values;
+    if values.is_empty() {
+        return Array1::zeros(length);
+    }
+    let last = values.len() - 1;
+    Array1::from_shape_fn(length, |n| {
+        let position = n as f32 / fs_feature * novelty.fs_feature;
+        let index = (position.floor() as usize).min(last);
+        let fraction = (position - index as f32).clamp(0.0, 1.0);
+        values[index] * (1.0 - fraction) + values[(index + 1).min(last)] * fraction
+    })
+}
+
+/// Fuses novelty functions computed at several resolutions, e.g. with short windows for
+/// sharp transients and long windows for slow swells, into one novelty function.
+///
+/// All novelty functions are resampled to the feature rate and length of the first one,
+/// see [`resample_novelty`], and normalized to a maximum of 1 so that differences in
+/// scale between the resolutions don't bias the fusion.
+///
+/// # Returns
+/// - The fused novelty function at the feature rate of the first novelty function, with
+///   values between 0 and 1
+///
+/// # Errors
+/// Returns an error if no novelty functions are given.
+pub fn fuse_novelty(curves: &[NoveltyCurve], fusion: Fusion) -> anyhow::Result<NoveltyCurve> {
+    let reference = curves.first().ok_or_else(|| anyhow::anyhow!("No novelty functions to fuse"))?;
+    let (fs_feature, length) = (reference.fs_feature, reference.values.len());
+
+    let mut fused: Array1<f32> = Array1::zeros(length);
+    for curve in curves {
+        let mut values = resample_novelty(curve, fs_feature, length);
+        let max = values.fold(0.0f32, |max, &v| max.max(v));
+        if max > 0.0 {
+            values /= max;
+        }
+        match fusion {
+            Fusion::Mean => fused += &values,
+            Fusion::Max => fused.zip_mut_with(&values, |f, &v| *f = f.max(v)),
+        }
+    }
+    if fusion == Fusion::Mean {
+        fused /= curves.len() as f32;
+    }
+    Ok(NoveltyCurve { values: fused, fs_feature })
+}
@@ -0,0 +1,66 @@
+use std::path::Path;
+
+use ndarray::Array1;
+use tract_onnx::prelude::*;
+
+use crate::compression::Compression;
+use crate::mel::mel_features;
+use crate::novelty::normalize_max;
+
+/// Computes an onset strength function with a pretrained neural network in ONNX format.
+///
+/// The network gets the compressed mel spectrogram of the signal, see
+/// [`crate::mel_features`], as a tensor with shape (1, frames, `n_mels`) and must return one
+/// onset activation per frame, e.g. with shape (1, frames) or (1, frames, 1). Models trained
+/// on other features or framings, such as the CNN and RNN onset models of madmom, need to
+/// be exported with a matching preprocessing or retrained on this input. Negative
+/// activations are clipped to zero.
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples
+/// - `fs`: Sampling rate of the audio
+/// - `window_length`: Size of the analysis window and FFT
+/// - `hop_length`: Step size between successive frames
+/// - `n_mels`: Number of mel bands of the network input
+/// - `compression`: Compression function applied to the mel-band energies
+/// - `gamma`: Parameter of the compression function
+/// - `model`: Path to the ONNX model
+/// - `norm`: Whether to normalize the output between 0 and 1
+///
+/// # Returns
+/// - A tuple of the onset strength function and its effective sampling rate
+///
+/// # Errors
+/// Returns an error if the model can't be loaded or run, or doesn't return one activation
+/// per frame.
+#[allow(clippy::too_many_arguments)]
+pub fn novelty_nn(audio_array: &Array1<f32>, fs: u32, window_length: u32, hop_length: u32, n_mels: u32, compression: Compression, gamma: f32, model: &Path, norm: bool) -> anyhow::Result<(Array1<f32>, f32)> {
+    let fs_feature = (fs as f32) / (hop_length as f32);
+
+    let mel = mel_features(audio_array, fs, window_length, hop_length, n_mels, compression, gamma);
+    let num_frames = mel.ncols();
+
+    // frames along the second axis, bands along the third
+    let features: Vec<f32> = mel.t().iter().copied().collect();
+    let input = Tensor::from_shape(&[1, num_frames, n_mels as usize], &features)?;
+
+    let runnable = tract_onnx::onnx()
+        .model_for_path(model)
+        .map_err(|error| anyhow::anyhow!("Failed to load the model {}: {}", model.display(), error))?
+        .with_input_fact(0, f32::fact([1, num_frames, n_mels as usize]).into())?
+        .into_optimized()?
+        .into_runnable()?;
+    let outputs = runnable.run(tvec!(input.into()))?;
+    let activations = outputs.first().ok_or_else(|| anyhow::anyhow!("The model has no output"))?;
+    let activations = activations.as_slice::<f32>()?;
+    if activations.len() != num_frames {
+        anyhow::bail!("The model returned {} activations for {} frames", activations.len(), num_frames);
+    }
+
+    let mut novelty = Array1::from_iter(activations.iter().map(|v| v.max(0.0)));
+    if norm {
+        normalize_max(&mut novelty)?;
+    }
+
+    Ok((novelty, fs_feature))
+}
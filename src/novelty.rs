@@ -0,0 +1,237 @@
+use hann_rs::get_hann_window;
+use ndarray::{Array, Array1, s, concatenate, Axis};
+use ndarray_conv::{ConvExt, ConvMode, PaddingMode};
+use ndarray_stats::QuantileExt;
+
+use crate::compression::Compression;
+use crate::descriptors::zero_crossing_rate;
+
+/// A novelty function together with its effective sampling rate.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NoveltyCurve {
+    /// Novelty value per frame
+    pub values: Array1<f32>,
+    /// Sampling rate of the novelty function (frames per second)
+    pub fs_feature: f32,
+}
+
+/// How the windowed energy of a frame is aggregated before compression.
+///
+/// The aggregation changes the absolute magnitude of the local energy and therefore the
+/// effect of `gamma` in logarithmic and tanh compression.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Aggregation {
+    /// Sum of the squared samples weighted by the squared window, as in the FMP notebooks
+    #[default]
+    Sum,
+    /// Weighted mean, i.e. the sum divided by the energy of the window, which doesn't depend
+    /// on the window length
+    Mean,
+    /// Square root of the weighted mean, which is on the scale of the signal amplitude
+    Rms,
+}
+
+/// Computes an energy-based novelty function over the input audio signal.
+///
+/// This function calculates the short-time energy using a Hann window, applies optional
+/// logarithmic compression, computes the positive energy difference over time, and
+/// normalizes the result if specified.
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples
+/// - `fs`: Sampling rate of the audio
+/// - `window_length`: Size of the analysis window
+/// - `hop_length`: Step size between successive frames
+/// - `gamma`: Compression parameter for logarithmic scaling
+/// - `norm`: Whether to normalize the output between 0 and 1
+///
+/// # Returns
+/// - A tuple of the novelty function and its effective sampling rate
+///
+/// # Errors
+/// Returns an error if convolution or array operations fail.
+pub fn novelty_energy(audio_array: Array1<f32>, fs: u32, window_length: u32, hop_length: u32 , gamma: f32, norm: bool) -> anyhow::Result<(Array1<f32>, f32)> {
+    novelty_energy_compressed(audio_array, fs, window_length, hop_length, Compression::Log, gamma, norm)
+}
+
+/// Computes an energy-based novelty function with a selectable compression function.
+///
+/// This is [`novelty_energy`] with the logarithmic compression generalized to any
+/// [`Compression`].
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples
+/// - `fs`: Sampling rate of the audio
+/// - `window_length`: Size of the analysis window
+/// - `hop_length`: Step size between successive frames
+/// - `compression`: Compression function applied to the local energy
+/// - `gamma`: Parameter of the compression function
+/// - `norm`: Whether to normalize the output between 0 and 1
+///
+/// # Returns
+/// - A tuple of the novelty function and its effective sampling rate
+///
+/// # Errors
+/// Returns an error if convolution or array operations fail.
+pub fn novelty_energy_compressed(audio_array: Array1<f32>, fs: u32, window_length: u32, hop_length: u32, compression: Compression, gamma: f32, norm: bool) -> anyhow::Result<(Array1<f32>, f32)> {
+    novelty_energy_aggregated(audio_array, fs, window_length, hop_length, Aggregation::Sum, compression, gamma, norm)
+}
+
+/// Computes an energy-based novelty function with a selectable aggregation of the windowed
+/// energy and compression function.
+///
+/// This is [`novelty_energy_compressed`] with the windowed sum generalized to any
+/// [`Aggregation`].
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples
+/// - `fs`: Sampling rate of the audio
+/// - `window_length`: Size of the analysis window
+/// - `hop_length`: Step size between successive frames
+/// - `aggregation`: Aggregation of the windowed energy
+/// - `compression`: Compression function applied to the local energy
+/// - `gamma`: Parameter of the compression function
+/// - `norm`: Whether to normalize the output between 0 and 1
+///
+/// # Returns
+/// - A tuple of the novelty function and its effective sampling rate
+///
+/// # Errors
+/// Returns an error if convolution or array operations fail.
+#[allow(clippy::too_many_arguments)]
+pub fn novelty_energy_aggregated(audio_array: Array1<f32>, fs: u32, window_length: u32, hop_length: u32, aggregation: Aggregation, compression: Compression, gamma: f32, norm: bool) -> anyhow::Result<(Array1<f32>, f32)> {
+    // Compute the feature sampling rate
+    let fs_feature = (fs as f32) / (hop_length as f32);
+
+    // Compute local energy with squared window and signal
+    let mut energy_local_subsample = local_energy(audio_array, window_length, hop_length)?;
+
+    // Aggregate relative to the energy of the window
+    if aggregation != Aggregation::Sum {
+        let hann_window = get_hann_window(window_length as usize).expect("Failed to get the Hann window");
+        let window_energy: f32 = hann_window.iter().map(|w| w * w).sum();
+        match aggregation {
+            Aggregation::Mean => energy_local_subsample.mapv_inplace(|v| v.max(0.0) / window_energy),
+            Aggregation::Rms => energy_local_subsample.mapv_inplace(|v| (v.max(0.0) / window_energy).sqrt()),
+            Aggregation::Sum => {}
+        }
+    }
+
+    // Apply compression
+    energy_local_subsample.mapv_inplace(|v| compression.apply(v, gamma));
+
+    // Compute the difference of consecutive energy values
+    let mut energy_local_diff = &energy_local_subsample.slice(s![1..]) - &energy_local_subsample.slice(s![..-1]);
+
+    // Pad with a trailing zero to maintain the original length
+    energy_local_diff = concatenate(Axis(0), &[energy_local_diff.view(), Array::zeros(1).view()])?;
+
+    // Apply half-wave rectification (set negative values to zero)
+    let mut novelty_energy = energy_local_diff;
+    novelty_energy.mapv_inplace(|v| if v < 0.0 { 0.0 } else { v });
+
+    // Normalize if requested
+    if norm {
+        normalize_max(&mut novelty_energy)?;
+    }
+
+    Ok((novelty_energy, fs_feature))
+}
+
+/// Computes the RMS envelope of the input audio signal.
+///
+/// The envelope uses the same framing as [`novelty_energy`]: the local energy is computed
+/// with a squared Hann window, divided by the energy of the window, and the square root is
+/// taken. It is not a novelty function in the strict sense, but serves as a baseline and
+/// for gain-riding applications.
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples
+/// - `fs`: Sampling rate of the audio
+/// - `window_length`: Size of the analysis window
+/// - `hop_length`: Step size between successive frames
+/// - `norm`: Whether to normalize the output between 0 and 1
+///
+/// # Returns
+/// - A tuple of the RMS envelope and its effective sampling rate
+///
+/// # Errors
+/// Returns an error if convolution or array operations fail.
+pub fn rms_envelope(audio_array: Array1<f32>, fs: u32, window_length: u32, hop_length: u32, norm: bool) -> anyhow::Result<(Array1<f32>, f32)> {
+    let fs_feature = (fs as f32) / (hop_length as f32);
+
+    let hann_window = get_hann_window(window_length as usize).expect("Failed to get the Hann window");
+    let window_energy: f32 = hann_window.iter().map(|w| w * w).sum();
+
+    let mut rms = local_energy(audio_array, window_length, hop_length)?;
+    rms.mapv_inplace(|v| (v.max(0.0) / window_energy).sqrt());
+
+    if norm {
+        normalize_max(&mut rms)?;
+    }
+
+    Ok((rms, fs_feature))
+}
+
+/// Computes a novelty function from the increase of the zero-crossing rate over time.
+///
+/// The zero-crossing rate is computed per frame with the same framing as
+/// [`novelty_energy`], followed by the positive difference over time. It is cheap to
+/// compute and responds well to unvoiced consonant onsets in speech.
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples
+/// - `fs`: Sampling rate of the audio
+/// - `window_length`: Size of the analysis window
+/// - `hop_length`: Step size between successive frames
+/// - `norm`: Whether to normalize the output between 0 and 1
+///
+/// # Returns
+/// - A tuple of the novelty function and its effective sampling rate
+///
+/// # Errors
+/// Returns an error if array operations fail.
+pub fn novelty_zcr(audio_array: Array1<f32>, fs: u32, window_length: u32, hop_length: u32, norm: bool) -> anyhow::Result<(Array1<f32>, f32)> {
+    let fs_feature = (fs as f32) / (hop_length as f32);
+
+    let zcr = zero_crossing_rate(&audio_array, window_length as usize, hop_length as usize);
+
+    // Half-wave rectified difference, padded with a trailing zero
+    let zcr_diff = &zcr.slice(s![1..]) - &zcr.slice(s![..-1]);
+    let mut novelty_zcr = concatenate(Axis(0), &[zcr_diff.view(), Array::zeros(1).view()])?;
+    novelty_zcr.mapv_inplace(|v| v.max(0.0));
+
+    if norm {
+        normalize_max(&mut novelty_zcr)?;
+    }
+
+    Ok((novelty_zcr, fs_feature))
+}
+
+/// Computes the local energy with a squared Hann window, subsampled by the hop length.
+///
+/// # Errors
+/// Returns an error if the convolution fails.
+fn local_energy(audio_array: Array1<f32>, window_length: u32, hop_length: u32) -> anyhow::Result<Array1<f32>> {
+    // get window function
+    let hann_window = get_hann_window(window_length as usize).expect("Failed to get the Hann window");
+    let hann_window_array = Array::from_vec(hann_window);
+
+    let energy_local = audio_array.powf(2.0).conv(&hann_window_array.powf(2.0), ConvMode::Same, PaddingMode::Zeros)?;
+    Ok(energy_local.slice_move(s![..;hop_length]))
+}
+
+/// Divides all values by their maximum, if it is positive.
+///
+/// # Errors
+/// Returns an error if the array is empty or contains NaN values.
+pub(crate) fn normalize_max(values: &mut Array1<f32>) -> anyhow::Result<()> {
+    let max_value = *values.max()?;
+    if max_value > 0.0 {
+        values.mapv_inplace(|v| v / max_value);
+    }
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use std::io::Write;
+
+/// Samples of an array written by [`write_npz`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NpyData<'a> {
+    /// 32-bit floats, stored as NumPy `float32`
+    F32(&'a [f32]),
+    /// Unsigned bytes, stored as NumPy `uint8`
+    U8(&'a [u8]),
+}
+
+/// An array in C order with its shape, to be stored in NumPy's `.npy` format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NpyArray<'a> {
+    /// Length of each dimension
+    pub shape: Vec<usize>,
+    /// Samples in row-major order
+    pub data: NpyData<'a>,
+}
+
+impl NpyArray<'_> {
+    /// Encodes the array as a `.npy` file of format version 1.0.
+    ///
+    /// # Errors
+    /// Returns an error if the number of samples doesn't match the shape.
+    pub fn to_npy(&self) -> anyhow::Result<Vec<u8>> {
+        let (descr, len) = match self.data {
+            NpyData::F32(data) => ("<f4", data.len()),
+            NpyData::U8(data) => ("|u1", data.len()),
+        };
+        if self.shape.iter().product::<usize>() != len {
+            anyhow::bail!("Array with {} samples doesn't match the shape {:?}", len, self.shape);
+        }
+
+        let shape = match self.shape.as_slice() {
+            [n] => format!("({},)", n),
+            dims => format!("({})", dims.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(", ")),
+        };
+        let mut header = format!("{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}", descr, shape);
+        // the header is padded with spaces and a newline so the data is aligned to 64 bytes
+        let total = (10 + header.len() + 1).div_ceil(64) * 64;
+        header.push_str(&" ".repeat(total - 10 - header.len() - 1));
+        header.push('\n');
+
+        let mut bytes = Vec::with_capacity(total + len * 4);
+        bytes.extend_from_slice(b"\x93NUMPY\x01\x00");
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        match self.data {
+            NpyData::F32(data) => data.iter().for_each(|v| bytes.extend_from_slice(&v.to_le_bytes())),
+            NpyData::U8(data) => bytes.extend_from_slice(data),
+        }
+        Ok(bytes)
+    }
+}
+
+/// Writes an array to a NumPy `.npy` file, which `numpy.load` reads as an array.
+///
+/// # Errors
+/// Returns an error if writing to the file fails or the array doesn't match its shape.
+pub fn write_npy(path: &str, array: &NpyArray) -> anyhow::Result<()> {
+    std::fs::File::create(path)?.write_all(&array.to_npy()?)?;
+    Ok(())
+}
+
+/// Writes arrays to a NumPy `.npz` file, which `numpy.load` reads as a dictionary of arrays.
+///
+/// The file is an uncompressed ZIP archive with one `<name>.npy` member per array.
+///
+/// # Errors
+/// Returns an error if writing to the file fails, an array doesn't match its shape, or the
+/// archive would exceed the 4 GiB limit of ZIP files without the ZIP64 extension.
+pub fn write_npz(path: &str, arrays: &[(&str, NpyArray)]) -> anyhow::Result<()> {
+    let mut archive = Vec::new();
+    let mut central_directory = Vec::new();
+    for (name, array) in arrays {
+        let name = format!("{}.npy", name);
+        let data = array.to_npy()?;
+        let (offset, size, crc) = (u32::try_from(archive.len())?, u32::try_from(data.len())?, crc32(&data));
+
+        // local file header: version 2.0, no flags, stored, modified 1980-01-01 00:00
+        archive.extend_from_slice(&0x04034b50_u32.to_le_bytes());
+        archive.extend_from_slice(&[20, 0, 0, 0, 0, 0, 0, 0, 0x21, 0]);
+        for field in [crc, size, size] {
+            archive.extend_from_slice(&field.to_le_bytes());
+        }
+        archive.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        archive.extend_from_slice(&[0, 0]);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(&data);
+
+        central_directory.extend_from_slice(&0x02014b50_u32.to_le_bytes());
+        central_directory.extend_from_slice(&[20, 0, 20, 0, 0, 0, 0, 0, 0, 0, 0x21, 0]);
+        for field in [crc, size, size] {
+            central_directory.extend_from_slice(&field.to_le_bytes());
+        }
+        central_directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        // no extra field or comment, disk 0, no attributes
+        central_directory.extend_from_slice(&[0; 12]);
+        central_directory.extend_from_slice(&offset.to_le_bytes());
+        central_directory.extend_from_slice(name.as_bytes());
+    }
+
+    let (directory_offset, directory_size) = (u32::try_from(archive.len())?, u32::try_from(central_directory.len())?);
+    archive.extend_from_slice(&central_directory);
+    archive.extend_from_slice(&0x06054b50_u32.to_le_bytes());
+    archive.extend_from_slice(&[0, 0, 0, 0]);
+    archive.extend_from_slice(&(arrays.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(arrays.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&directory_size.to_le_bytes());
+    archive.extend_from_slice(&directory_offset.to_le_bytes());
+    archive.extend_from_slice(&[0, 0]);
+
+    std::fs::File::create(path)?.write_all(&archive)?;
+    Ok(())
+}
+
+/// Computes the CRC-32 checksum of ZIP archives and PNG chunks.
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB88320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
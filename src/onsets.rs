@@ -0,0 +1,160 @@
+use ndarray::s;
+use ndarray_stats::QuantileExt;
+
+use crate::novelty::NoveltyCurve;
+
+/// An onset detected as a peak of a novelty function.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Onset {
+    /// Frame index of the peak in the novelty function
+    pub frame: usize,
+    /// Time of the onset in seconds, between frames if refined with [`refine_onsets`]
+    pub time: f32,
+    /// Novelty value at the peak
+    pub strength: f32,
+}
+
+/// Detects onsets as the local maxima of a novelty function.
+///
+/// A frame is a peak if its value is larger than its left neighbor and not smaller than its
+/// right neighbor. Peaks below `threshold` times the maximum of the novelty function are
+/// discarded, so the threshold behaves the same for normalized and unnormalized curves.
+pub fn detect_onsets(novelty: &NoveltyCurve, threshold: f32) -> Vec<Onset> {
+    let values = &novelty.values;
+    let max_value = values.max().copied().unwrap_or(0.0);
+    if max_value <= 0.0 {
+        return Vec::new();
+    }
+    let min_strength = threshold * max_value;
+
+    (1..values.len().saturating_sub(1))
+        .filter(|&i| values[i] > values[i - 1] && values[i] >= values[i + 1] && values[i] >= min_strength)
+        .map(|i| Onset {
+            frame: i,
+            time: i as f32 / novelty.fs_feature,
+            strength: values[i],
+        })
+        .collect()
+}
+
+/// Parameters of the peak picker of `librosa.util.peak_pick`, see [`pick_peaks`].
+///
+/// All window lengths and `wait` are in frames of the novelty function, as in librosa 0.10.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PeakPicking {
+    /// Number of frames before a peak in which it must be the maximum
+    pub pre_max: usize,
+    /// Number of frames after a peak, including the peak, in which it must be the maximum
+    pub post_max: usize,
+    /// Number of frames before a peak included in the moving average
+    pub pre_avg: usize,
+    /// Number of frames after a peak, including the peak, included in the moving average
+    pub post_avg: usize,
+    /// Minimum amount by which a peak must exceed the moving average
+    pub delta: f32,
+    /// Minimum number of frames between consecutive peaks
+    pub wait: usize,
+}
+
+impl PeakPicking {
+    /// Returns the defaults of `librosa.onset.onset_detect` for a novelty function with the
+    /// given feature rate: 30 ms before the maximum, 100 ms around the average, a delta of
+    /// 0.07, and a wait of 30 ms.
+    pub fn librosa_defaults(fs_feature: f32) -> Self {
+        PeakPicking {
+            pre_max: (0.03 * fs_feature).floor() as usize,
+            post_max: 1,
+            pre_avg: (0.10 * fs_feature).floor() as usize,
+            post_avg: (0.10 * fs_feature).floor() as usize + 1,
+            delta: 0.07,
+            wait: (0.03 * fs_feature).floor() as usize,
+        }
+    }
+}
+
+/// Detects onsets with the peak picker of `librosa.util.peak_pick`.
+///
+/// Frame `n` is a peak if all of the following hold, with windows clipped at the edges:
+/// - `x[n] == max(x[n - pre_max..n + post_max])`
+/// - `x[n] >= mean(x[n - pre_avg..n + post_avg]) + delta`
+/// - `n - previous_n > wait`, where `previous_n` is the last peak
+///
+/// As in librosa, frames with a value of zero are never peaks. Note that
+/// `librosa.onset.onset_detect` normalizes the onset strength to the range 0 to 1 before
+/// peak picking, which corresponds to a novelty function computed with `norm`.
+///
+/// # Errors
+/// Returns an error if `post_max` or `post_avg` is zero or `delta` is negative or not finite.
+pub fn pick_peaks(novelty: &NoveltyCurve, params: &PeakPicking) -> anyhow::Result<Vec<Onset>> {
+    if params.post_max == 0 || params.post_avg == 0 {
+        anyhow::bail!("post_max and post_avg must be greater than zero!");
+    }
+    if !params.delta.is_finite() || params.delta < 0.0 {
+        anyhow::bail!("delta must be a non-negative number!");
+    }
+
+    let values = &novelty.values;
+    let len = values.len();
+    let window = |n: usize, pre: usize, post: usize| values.slice(s![n.saturating_sub(pre)..(n + post).min(len)]);
+
+    let mut onsets = Vec::new();
+    let mut previous: Option<usize> = None;
+    for n in 0..len {
+        let value = values[n];
+        if value == 0.0 {
+            continue;
+        }
+        let max_value = window(n, params.pre_max, params.post_max).fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+        if value != max_value {
+            continue;
+        }
+        let mean_value = window(n, params.pre_avg, params.post_avg).mean().unwrap_or(0.0);
+        if value < mean_value + params.delta {
+            continue;
+        }
+        if previous.is_some_and(|previous| n <= previous + params.wait) {
+            continue;
+        }
+        previous = Some(n);
+        onsets.push(Onset {
+            frame: n,
+            time: n as f32 / novelty.fs_feature,
+            strength: value,
+        });
+    }
+
+    Ok(onsets)
+}
+
+/// Refines onset times to sub-frame resolution by quadratic interpolation.
+///
+/// A parabola is fitted through each peak and its two neighbors, and the onset time and
+/// strength are moved to the vertex of the parabola. The vertex lies within half a frame of
+/// the peak, so the frame index is kept. This reduces the quantization of onset times
+/// below the hop length, e.g. for timing analysis of drums.
+pub fn refine_onsets(novelty: &NoveltyCurve, onsets: &[Onset]) -> Vec<Onset> {
+    let values = &novelty.values;
+
+    onsets
+        .iter()
+        .map(|onset| {
+            let i = onset.frame;
+            if i == 0 || i + 1 >= values.len() {
+                return *onset;
+            }
+            let (left, center, right) = (values[i - 1], values[i], values[i + 1]);
+            let curvature = left - 2.0 * center + right;
+            if curvature >= 0.0 {
+                return *onset;
+            }
+            let offset = (0.5 * (left - right) / curvature).clamp(-0.5, 0.5);
+            Onset {
+                frame: i,
+                time: (i as f32 + offset) / novelty.fs_feature,
+                strength: center - 0.25 * (left - right) * offset,
+            }
+        })
+        .collect()
+}
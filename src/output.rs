@@ -0,0 +1,859 @@
+use std::io::Write;
+
+use ndarray::{Array, Array1};
+
+use crate::activity::ActivitySegment;
+use crate::alignment::Alignment;
+use crate::beats::{Beat, GridStats};
+use crate::channels::FusedOnset;
+use crate::classify::{Attack, DrumHit, OnsetCharacter, SegmentContent};
+use crate::dynamics::Dynamics;
+use crate::ensemble::StableOnset;
+use crate::fingerprint::{Duplicate, Fingerprint, FingerprintMatch};
+use crate::input::Annotation;
+use crate::ioi::IoiCluster;
+use crate::loops::LoopPoint;
+use crate::qc::QcReport;
+use crate::manifest::{BatchRecord, BatchStatus};
+use crate::meter::MeterCandidate;
+use crate::onsets::Onset;
+use crate::pitch::{note_name, OnsetPitch};
+use crate::summary::Summary;
+use crate::sync::Lag;
+use crate::tempo::{TempoCandidate, Tempogram};
+use crate::thumbnail::Thumbnail;
+use crate::timecode::Timecode;
+
+/// The point within a frame that the time stamps of a novelty function refer to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum FrameTiming {
+    /// The center of the analysis window, where frame `m` lies at sample `m * hop_length`
+    #[default]
+    Center,
+    /// The first sample of the analysis window, half a window before its center
+    Start,
+}
+
+impl FrameTiming {
+    /// Returns the time in seconds of the first frame for windows of `window_length` samples.
+    ///
+    /// With [`FrameTiming::Start`], this is negative, since the first window starts before
+    /// the signal.
+    pub fn offset(self, window_length: u32, fs: u32) -> f32 {
+        match self {
+            FrameTiming::Center => 0.0,
+            FrameTiming::Start => -(window_length as f32 / 2.0) / fs as f32,
+        }
+    }
+}
+
+/// How the time column of a novelty CSV file is written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum TimeUnit {
+    /// Seconds with five decimals, which the stages that read novelty CSV files expect
+    #[default]
+    Seconds,
+    /// Index of the nearest sample at the given sampling rate of the audio
+    Samples(u32),
+    /// Index of the frame of the novelty function
+    Frames,
+    /// `hh:mm:ss.mmm`, e.g. for long broadcast recordings where seconds are hard to read
+    Timestamp,
+}
+
+impl TimeUnit {
+    /// Header of the time column: `sample` or `frame` for indices, and `time` otherwise.
+    pub fn column(self) -> &'static str {
+        match self {
+            TimeUnit::Samples(_) => "sample",
+            TimeUnit::Frames => "frame",
+            TimeUnit::Seconds | TimeUnit::Timestamp => "time",
+        }
+    }
+
+    /// Formats the time of frame `frame` at `time` seconds.
+    ///
+    /// The time is in double precision, so sample indices and timestamps stay exact for
+    /// recordings of many hours.
+    pub fn format(self, frame: usize, time: f64) -> String {
+        match self {
+            TimeUnit::Seconds => format!("{:.05}", time),
+            TimeUnit::Samples(fs) => format!("{}", (time * fs as f64).round() as i64),
+            TimeUnit::Frames => format!("{}", frame),
+            TimeUnit::Timestamp => {
+                // with --frame-timing start, the first frames lie before the signal
+                let millis = (time * 1000.0).round() as i64;
+                let sign = if millis < 0 { "-" } else { "" };
+                let millis = millis.unsigned_abs();
+                format!("{}{:02}:{:02}:{:02}.{:03}", sign, millis / 3_600_000, millis / 60_000 % 60, millis / 1000 % 60, millis % 1000)
+            }
+        }
+    }
+}
+
+/// Formats a time in seconds for the event CSV files, or as SMPTE timecode if given.
+fn format_time(time: f32, timecode: Option<Timecode>) -> String {
+    match timecode {
+        Some(timecode) => timecode.format(time as f64),
+        None => format!("{:.05}", time),
+    }
+}
+
+/// Writes a CSV file containing time vs. novelty function values.
+///
+/// Frame `i` is written at time `offset + i / fs_feature` in seconds, see
+/// [`FrameTiming::offset`].
+///
+/// # Arguments
+/// - `path`: Output file path
+/// - `novelty_energy`: 1D array of novelty values
+/// - `fs_feature`: Sampling rate of the novelty function
+/// - `offset`: Time of the first frame in seconds
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_csv(path: &str, novelty_energy: Array1<f32>, fs_feature: f32, offset: f64) -> anyhow::Result<()> {
+    write_csv_with_columns(path, novelty_energy, fs_feature, offset, TimeUnit::Seconds, &[])
+}
+
+/// Writes a CSV file containing time vs. novelty function values, followed by additional
+/// framewise columns such as descriptors.
+///
+/// # Arguments
+/// - `path`: Output file path
+/// - `novelty_energy`: 1D array of novelty values
+/// - `fs_feature`: Sampling rate of the novelty function
+/// - `offset`: Time of the first frame in seconds
+/// - `time_unit`: How the time column is written
+/// - `columns`: Pairs of column header and framewise values, with one value per novelty frame
+///
+/// # Errors
+/// Returns an error if a column has the wrong length or writing to the file fails.
+pub fn write_csv_with_columns(path: &str, novelty_energy: Array1<f32>, fs_feature: f32, offset: f64, time_unit: TimeUnit, columns: &[(&str, Array1<f32>)]) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    write_csv_to(&mut file, novelty_energy, fs_feature, offset, time_unit, columns)
+}
+
+/// Writes time vs. novelty function values and additional framewise columns as CSV to any
+/// writer, e.g. standard output to pipe the novelty function into another command, see
+/// [`write_csv_with_columns`].
+///
+/// # Errors
+/// Returns an error if a column has the wrong length or writing fails.
+pub fn write_csv_to<W: Write>(writer: &mut W, novelty_energy: Array1<f32>, fs_feature: f32, offset: f64, time_unit: TimeUnit, columns: &[(&str, Array1<f32>)]) -> anyhow::Result<()> {
+    for (name, values) in columns {
+        if values.len() != novelty_energy.len() {
+            anyhow::bail!("Column {} has {} values, expected {}", name, values.len(), novelty_energy.len());
+        }
+    }
+
+    // Write header
+    let mut header = format!("{},novelty", time_unit.column());
+    for (name, _) in columns {
+        header.push(',');
+        header.push_str(name);
+    }
+    writeln!(writer, "{}", header)?;
+
+    // Write time, novelty, and additional values, with the time of each frame computed from
+    // its index in double precision
+    for (i, cur_novelty) in novelty_energy.iter().enumerate() {
+        let cur_time = offset + i as f64 / fs_feature as f64;
+        let mut line = format!("{},{:.05}", time_unit.format(i, cur_time), cur_novelty);
+        for (_, values) in columns {
+            line.push_str(&format!(",{:.05}", values[i]));
+        }
+        writeln!(writer, "{}", line)?;
+    }
+
+    Ok(())
+}
+
+/// Writes a JSON file containing the novelty function and additional framewise columns.
+///
+/// The object holds the feature rate `fs_feature`, the time of the first frame `offset`, and
+/// one array per CSV column: `time`, `novelty`, and the additional columns under their names.
+///
+/// # Errors
+/// Returns an error if a column has the wrong length or writing to the file fails.
+pub fn write_novelty_json(path: &str, novelty_energy: Array1<f32>, fs_feature: f32, offset: f64, columns: &[(&str, Array1<f32>)]) -> anyhow::Result<()> {
+    for (name, values) in columns {
+        if values.len() != novelty_energy.len() {
+            anyhow::bail!("Column {} has {} values, expected {}", name, values.len(), novelty_energy.len());
+        }
+    }
+    // times in double precision, which stay exact for offsets such as a time of day
+    let time = Array::range(0.0, novelty_energy.len() as f64, 1.0) / fs_feature as f64 + offset;
+    // JSON has no NaN or infinity, so non-finite values are written as null
+    let array = |values: &Array1<f64>| {
+        let values: Vec<String> = values
+            .iter()
+            .map(|v| if v.is_finite() { format!("{:.05}", v) } else { "null".to_string() })
+            .collect();
+        format!("[{}]", values.join(","))
+    };
+
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    write!(file, "{{\"fs_feature\":{},\"offset\":{:.05},\"time\":{},\"novelty\":{}", fs_feature, offset, array(&time), array(&novelty_energy.mapv(f64::from)))?;
+    for (name, values) in columns {
+        write!(file, ",\"{}\":{}", name.replace('\\', "\\\\").replace('"', "\\\""), array(&values.mapv(f64::from)))?;
+    }
+    writeln!(file, "}}")?;
+
+    Ok(())
+}
+
+/// Writes a CSV file containing onset times and strengths.
+///
+/// With a timecode, times are written as SMPTE timecode instead of seconds.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_onsets_csv(path: &str, onsets: &[Onset], timecode: Option<Timecode>) -> anyhow::Result<()> {
+    write_onsets_csv_with_columns(path, onsets, timecode, &[])
+}
+
+/// Additional per-onset columns of [`write_onsets_csv_with_columns`], with one entry per onset.
+#[derive(Clone, Copy, Debug)]
+pub enum OnsetColumns<'a> {
+    /// Classification as hard or soft, attack time in seconds, and brightness
+    /// (`kind,attack_time,brightness`), see [`crate::classify_onsets`]
+    Character(&'a [OnsetCharacter]),
+    /// Start of the attack and its duration in seconds (`attack_start,attack_duration`), see
+    /// [`crate::onset_attacks`]
+    Attack(&'a [Attack]),
+    /// Drum voice and the fractions of the attack energy in the low, mid, and high band
+    /// (`voice,low,mid,high`), see [`crate::classify_drums`]
+    Drum(&'a [DrumHit]),
+    /// Pitch in Hz, nearest MIDI note number and its name, and confidence
+    /// (`frequency,midi_note,note,confidence`), see [`crate::onset_pitches`]. The pitch
+    /// columns are left empty for unvoiced onsets.
+    Pitch(&'a [OnsetPitch]),
+    /// Fraction of jittered detector runs that found the onset (`stability`), see
+    /// [`crate::onset_stability`]
+    Stability(&'a [f32]),
+}
+
+impl OnsetColumns<'_> {
+    /// Returns the number of onsets the columns have values for.
+    fn len(&self) -> usize {
+        match self {
+            OnsetColumns::Character(characters) => characters.len(),
+            OnsetColumns::Attack(attacks) => attacks.len(),
+            OnsetColumns::Drum(hits) => hits.len(),
+            OnsetColumns::Pitch(pitches) => pitches.len(),
+            OnsetColumns::Stability(stabilities) => stabilities.len(),
+        }
+    }
+
+    /// Returns the names of the columns, separated by commas.
+    fn header(&self) -> &'static str {
+        match self {
+            OnsetColumns::Character(_) => "kind,attack_time,brightness",
+            OnsetColumns::Attack(_) => "attack_start,attack_duration",
+            OnsetColumns::Drum(_) => "voice,low,mid,high",
+            OnsetColumns::Pitch(_) => "frequency,midi_note,note,confidence",
+            OnsetColumns::Stability(_) => "stability",
+        }
+    }
+
+    /// Returns the values of the `k`-th onset, separated by commas.
+    fn format(&self, k: usize, timecode: Option<Timecode>) -> String {
+        match self {
+            OnsetColumns::Character(characters) => {
+                let character = &characters[k];
+                format!("{},{:.05},{:.05}", character.kind, character.attack_time, character.brightness)
+            }
+            OnsetColumns::Attack(attacks) => format!("{},{:.05}", format_time(attacks[k].start, timecode), attacks[k].duration),
+            OnsetColumns::Drum(hits) => {
+                let hit = &hits[k];
+                format!("{},{:.05},{:.05},{:.05}", hit.voice, hit.low, hit.mid, hit.high)
+            }
+            OnsetColumns::Pitch(pitches) => match (pitches[k].frequency, pitches[k].midi_note()) {
+                (Some(frequency), Some(midi_note)) => {
+                    format!("{:.05},{},{},{:.05}", frequency, midi_note, note_name(midi_note), pitches[k].confidence)
+                }
+                _ => format!(",,,{:.05}", pitches[k].confidence),
+            },
+            OnsetColumns::Stability(stabilities) => format!("{:.05}", stabilities[k]),
+        }
+    }
+}
+
+/// Writes a CSV file containing onset times and strengths, followed by additional per-onset
+/// column sets such as classifications or pitches, in the given order.
+///
+/// With a timecode, times are written as SMPTE timecode instead of seconds, including the
+/// start of the attack but not its duration.
+///
+/// # Arguments
+/// - `path`: Output file path
+/// - `onsets`: Detected onsets
+/// - `timecode`: Timecode to write the times in, if any
+/// - `columns`: Additional column sets, each with one entry per onset
+///
+/// # Errors
+/// Returns an error if a column set has the wrong length or writing to the file fails.
+pub fn write_onsets_csv_with_columns(path: &str, onsets: &[Onset], timecode: Option<Timecode>, columns: &[OnsetColumns]) -> anyhow::Result<()> {
+    for column in columns {
+        if column.len() != onsets.len() {
+            anyhow::bail!("Got {} onsets but {} values for the columns {}", onsets.len(), column.len(), column.header());
+        }
+    }
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    let mut header = String::from("time,strength");
+    for column in columns {
+        header.push(',');
+        header.push_str(column.header());
+    }
+    writeln!(file, "{}", header)?;
+    for (k, onset) in onsets.iter().enumerate() {
+        let mut line = format!("{},{:.05}", format_time(onset.time, timecode), onset.strength);
+        for column in columns {
+            line.push(',');
+            line.push_str(&column.format(k, timecode));
+        }
+        writeln!(file, "{}", line)?;
+    }
+
+    Ok(())
+}
+
+/// Writes a CSV file containing onset times and strengths with the fraction of jittered
+/// detector runs that found each onset, see [`crate::onset_stability`].
+///
+/// With a timecode, times are written as SMPTE timecode instead of seconds.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_stable_onsets_csv(path: &str, onsets: &[StableOnset], timecode: Option<Timecode>) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    writeln!(file, "time,strength,stability")?;
+    for onset in onsets {
+        writeln!(file, "{},{:.05},{:.05}", format_time(onset.time, timecode), onset.strength, onset.stability)?;
+    }
+
+    Ok(())
+}
+
+/// Writes a CSV file containing fused onset times, strengths, and the channels that
+/// triggered them, separated by semicolons.
+///
+/// With a timecode, times are written as SMPTE timecode instead of seconds.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_fused_onsets_csv(path: &str, onsets: &[FusedOnset], timecode: Option<Timecode>) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    writeln!(file, "time,strength,channels")?;
+    for onset in onsets {
+        let channels: Vec<String> = onset.channels.iter().map(|channel| channel.to_string()).collect();
+        writeln!(file, "{},{:.05},{}", format_time(onset.time, timecode), onset.strength, channels.join(";"))?;
+    }
+
+    Ok(())
+}
+
+/// Writes a CSV file containing onsets aligned with annotations, see [`crate::align_onsets`].
+///
+/// Each row holds the annotation time, the detection time, the deviation of the detection
+/// from the annotation in seconds, and the label of the annotation. The annotation of an
+/// extra detection and the detection and deviation of a missed annotation are left empty,
+/// and labels are quoted.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_alignment_csv(path: &str, alignments: &[Alignment]) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    let format_time = |time: Option<f32>| time.map(|time| format!("{:.05}", time)).unwrap_or_default();
+
+    writeln!(file, "annotation,detection,deviation,label")?;
+    for alignment in alignments {
+        let annotation = alignment.annotation.as_ref();
+        let label = annotation.and_then(|annotation| annotation.label.as_deref());
+        writeln!(
+            file,
+            "{},{},{},{}",
+            format_time(annotation.map(|annotation| annotation.time)),
+            format_time(alignment.detection),
+            format_time(alignment.deviation()),
+            label.map(|label| format!("\"{}\"", label.replace('"', "\"\""))).unwrap_or_default()
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Writes a CSV file containing annotations snapped to onsets, see [`crate::snap_annotations`].
+///
+/// Each row holds the corrected time, the original time of the annotation, the shift
+/// between them in seconds, and the quoted label. The shift is left empty for annotations
+/// without an onset within the tolerance, which keep their original time.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_snapped_annotations_csv(path: &str, snapped: &[Alignment]) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    writeln!(file, "time,annotation,shift,label")?;
+    for alignment in snapped {
+        let label = alignment.annotation.as_ref().and_then(|annotation| annotation.label.as_deref());
+        writeln!(
+            file,
+            "{:.05},{:.05},{},{}",
+            alignment.detection.unwrap_or(alignment.time()),
+            alignment.time(),
+            alignment.deviation().map(|shift| format!("{:.05}", shift)).unwrap_or_default(),
+            label.map(|label| format!("\"{}\"", label.replace('"', "\"\""))).unwrap_or_default()
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Writes annotations as an Audacity label file with one `start<TAB>end<TAB>label` line per
+/// annotation, where start and end are both the time of the annotation, so they can be
+/// imported as point labels.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_audacity_labels(path: &str, annotations: &[Annotation]) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    for annotation in annotations {
+        let label = annotation.label.as_deref().unwrap_or_default();
+        writeln!(file, "{:.06}\t{:.06}\t{}", annotation.time, annotation.time, label)?;
+    }
+
+    Ok(())
+}
+
+/// Writes a CSV file containing beat times and their positions in the bar.
+///
+/// The position is left empty for beats without an estimated position. With a timecode,
+/// times are written as SMPTE timecode instead of seconds.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_beats_csv(path: &str, beats: &[Beat], timecode: Option<Timecode>) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    writeln!(file, "time,position_in_bar")?;
+    for beat in beats {
+        let position = beat.position_in_bar.map(|position| position.to_string()).unwrap_or_default();
+        writeln!(file, "{},{}", format_time(beat.time, timecode), position)?;
+    }
+
+    Ok(())
+}
+
+/// Writes a CSV file containing the statistics of beats relative to a constant beat grid,
+/// with times in seconds.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_grid_stats_csv(path: &str, stats: &GridStats) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    writeln!(file, "bpm,ibi_mean,ibi_std,max_drift,rms_drift,matching")?;
+    writeln!(
+        file,
+        "{:.05},{:.05},{:.05},{:.05},{:.05},{:.05}",
+        stats.bpm, stats.ibi_mean, stats.ibi_std, stats.max_drift, stats.rms_drift, stats.matching
+    )?;
+
+    Ok(())
+}
+
+/// Writes a CSV file containing a tempo estimate in BPM.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_tempo_csv(path: &str, tempo: f32) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    writeln!(file, "tempo")?;
+    writeln!(file, "{:.05}", tempo)?;
+
+    Ok(())
+}
+
+/// Writes a CSV file containing tempo candidates in BPM and their confidences.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_tempo_candidates_csv(path: &str, candidates: &[TempoCandidate]) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    writeln!(file, "tempo,confidence")?;
+    for candidate in candidates {
+        writeln!(file, "{:.05},{:.05}", candidate.bpm, candidate.confidence)?;
+    }
+
+    Ok(())
+}
+
+/// Writes a CSV file containing a tempogram with one row per frame and one column per tempo.
+///
+/// The header holds the tempo axis in BPM after the `time` column, and the time of each
+/// frame is its center in seconds.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_tempogram_csv(path: &str, tempogram: &Tempogram) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    let header: Vec<String> = tempogram.bpm.iter().map(|bpm| format!("{}", bpm)).collect();
+    writeln!(file, "time,{}", header.join(","))?;
+    for (m, frame) in tempogram.values.columns().into_iter().enumerate() {
+        let values: Vec<String> = frame.iter().map(|v| format!("{:.05}", v)).collect();
+        writeln!(file, "{:.05},{}", m as f32 / tempogram.fs_tempogram, values.join(","))?;
+    }
+
+    Ok(())
+}
+
+/// Writes a CSV file containing meter candidates, best first.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_meter_csv(path: &str, candidates: &[MeterCandidate]) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    writeln!(file, "time_signature,beats_per_bar,phase,confidence")?;
+    for candidate in candidates {
+        writeln!(file, "{},{},{},{:.05}", candidate.time_signature(), candidate.beats_per_bar, candidate.phase, candidate.confidence)?;
+    }
+
+    Ok(())
+}
+
+/// Writes a CSV file containing the time range of a thumbnail in seconds and its score.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_thumbnail_csv(path: &str, thumbnail: &Thumbnail) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    writeln!(file, "start,end,score")?;
+    writeln!(file, "{:.05},{:.05},{:.05}", thumbnail.start, thumbnail.end, thumbnail.score)?;
+
+    Ok(())
+}
+
+/// Writes a CSV file containing proposed loops with their start and end in seconds and in
+/// samples at the sampling rate `fs`, and their score, best first.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_loops_csv(path: &str, loops: &[LoopPoint], fs: u32) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    writeln!(file, "start,end,start_sample,end_sample,score")?;
+    for point in loops {
+        let (start, end) = (point.start as f64 / fs as f64, point.end as f64 / fs as f64);
+        writeln!(file, "{:.05},{:.05},{},{},{:.05}", start, end, point.start, point.end, point.score)?;
+    }
+
+    Ok(())
+}
+
+/// Writes a CSV file containing the active and inactive segments of a recording, labeled
+/// `active` or `inactive`.
+///
+/// With a timecode, times are written as SMPTE timecode instead of seconds.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_activity_csv(path: &str, segments: &[ActivitySegment], timecode: Option<Timecode>) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    writeln!(file, "start,end,label")?;
+    for segment in segments {
+        let label = if segment.active { "active" } else { "inactive" };
+        writeln!(file, "{},{},{}", format_time(segment.start, timecode), format_time(segment.end, timecode), label)?;
+    }
+
+    Ok(())
+}
+
+/// Writes a CSV file containing the active and inactive segments of a recording, with active
+/// segments labeled by their content class and its features, see [`crate::classify_content`].
+///
+/// The features are left empty for inactive segments and segments without a classification.
+/// With a timecode, times are written as SMPTE timecode instead of seconds.
+///
+/// # Errors
+/// Returns an error if writing to the file fails or the number of classifications differs
+/// from the number of segments.
+pub fn write_classified_activity_csv(path: &str, segments: &[ActivitySegment], contents: &[Option<SegmentContent>], timecode: Option<Timecode>) -> anyhow::Result<()> {
+    if segments.len() != contents.len() {
+        anyhow::bail!("Got {} segments but {} classifications", segments.len(), contents.len());
+    }
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    writeln!(file, "start,end,label,low_energy_ratio,high_zcr_ratio,flatness")?;
+    for (segment, content) in segments.iter().zip(contents) {
+        let (start, end) = (format_time(segment.start, timecode), format_time(segment.end, timecode));
+        match content {
+            Some(content) => writeln!(
+                file,
+                "{},{},{},{:.05},{:.05},{:.05}",
+                start, end, content.class, content.low_energy_ratio, content.high_zcr_ratio, content.flatness
+            )?,
+            None => {
+                let label = if segment.active { "active" } else { "inactive" };
+                writeln!(file, "{},{},{},,,", start, end, label)?
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a CSV file containing the dynamics profile of each recording or corpus, with the
+/// duration in seconds, levels in dBFS, level ratios in dB, loudness in LUFS, loudness range
+/// in LU, and onsets per minute.
+///
+/// Loudness measures are left empty for recordings that are too short or too quiet.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_dynamics_csv(path: &str, profiles: &[(&str, Dynamics)]) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    let optional = |value: Option<f32>| value.map(|value| format!("{:.05}", value)).unwrap_or_default();
+    writeln!(file, "path,duration,peak,rms,crest_factor,integrated_loudness,loudness_range,peak_to_loudness,onset_density")?;
+    for (path, dynamics) in profiles {
+        writeln!(
+            file,
+            "{},{:.05},{:.05},{:.05},{:.05},{},{},{},{:.05}",
+            path,
+            dynamics.duration,
+            dynamics.peak,
+            dynamics.rms,
+            dynamics.crest_factor,
+            optional(dynamics.integrated_loudness),
+            optional(dynamics.loudness_range),
+            optional(dynamics.peak_to_loudness),
+            dynamics.onset_density
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Writes a CSV file containing the quality control result of each recording: `pass` or
+/// `fail`, the measurements, and the quoted failures separated by semicolons. Recordings
+/// that couldn't be decoded fail with the error in place of the measurements.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_qc_csv(path: &str, results: &[(&str, Result<QcReport, String>)]) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    let quote = |text: &str| format!("\"{}\"", text.replace('"', "\"\""));
+    writeln!(file, "path,status,duration,clipping,dropouts,dc_offset,silence,failures")?;
+    for (path, result) in results {
+        match result {
+            Ok(report) => writeln!(
+                file,
+                "{},{},{:.05},{:.05},{:.05},{:.05},{:.05},{}",
+                path,
+                if report.passed() { "pass" } else { "fail" },
+                report.duration,
+                report.clipping,
+                report.dropouts,
+                report.dc_offset,
+                report.silence,
+                quote(&report.failures.join("; "))
+            )?,
+            Err(error) => writeln!(file, "{},fail,,,,,,{}", path, quote(error))?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a CSV file containing the time offset between two recordings in seconds and its
+/// correlation.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_lag_csv(path: &str, lag: &Lag) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    writeln!(file, "lag,correlation")?;
+    writeln!(file, "{:.05},{:.05}", lag.lag, lag.correlation)?;
+
+    Ok(())
+}
+
+/// Writes a CSV file containing the recordings matching a query, best first, with the start
+/// of the query within each recording in seconds and the match score.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_matches_csv(path: &str, matches: &[(&str, FingerprintMatch)]) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    writeln!(file, "path,offset,score")?;
+    for (path, found) in matches {
+        writeln!(file, "{},{:.05},{:.05}", path, found.offset, found.score)?;
+    }
+
+    Ok(())
+}
+
+/// Writes a CSV file containing pairs of likely duplicate or overlapping recordings, best
+/// first, with the paths of the fingerprints, the start of the second recording within the
+/// first and the overlap in seconds, and the match score.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_duplicates_csv(path: &str, fingerprints: &[Fingerprint], duplicates: &[Duplicate]) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    writeln!(file, "first,second,offset,overlap,score")?;
+    for duplicate in duplicates {
+        writeln!(
+            file,
+            "{},{},{:.05},{:.05},{:.05}",
+            fingerprints[duplicate.first].path,
+            fingerprints[duplicate.second].path,
+            duplicate.offset,
+            duplicate.overlap,
+            duplicate.score
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Writes a CSV file containing an inter-onset interval histogram, with the lower edge of
+/// each bin in seconds.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_ioi_histogram_csv(path: &str, counts: &[usize], bin_width: f32) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    writeln!(file, "interval,count")?;
+    for (k, count) in counts.iter().enumerate() {
+        writeln!(file, "{:.05},{}", k as f32 * bin_width, count)?;
+    }
+
+    Ok(())
+}
+
+/// Writes a CSV file containing inter-onset interval clusters as tempo hypotheses.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_ioi_clusters_csv(path: &str, clusters: &[IoiCluster]) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    writeln!(file, "tempo,interval,count,score")?;
+    for cluster in clusters {
+        writeln!(file, "{:.05},{:.05},{},{:.05}", cluster.bpm, cluster.interval, cluster.count, cluster.score)?;
+    }
+
+    Ok(())
+}
+
+/// Appends a row with aggregate statistics of one recording to a summary CSV file.
+///
+/// The header is written only if the file is new or empty, so the summaries of many
+/// recordings can be collected in one file. A missing tempo estimate is left empty.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_summary_csv(path: &str, source: &str, summary: &Summary) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    if file.metadata()?.len() == 0 {
+        writeln!(file, "path,duration,onset_count,onset_density,mean_novelty,max_novelty,tempo,clipping,dropouts")?;
+    }
+    let tempo = summary.tempo.map(|tempo| format!("{:.05}", tempo)).unwrap_or_default();
+    writeln!(
+        file,
+        "{},{:.05},{},{:.05},{:.05},{:.05},{},{:.05},{:.05}",
+        source,
+        summary.duration,
+        summary.onset_count,
+        summary.onset_density,
+        summary.mean_novelty,
+        summary.max_novelty,
+        tempo,
+        summary.clipping,
+        summary.dropouts
+    )?;
+
+    Ok(())
+}
+
+/// Appends a row with the tempo histogram of one recording to a CSV file.
+///
+/// The header holds the tempo axis in BPM after the `path` column and is written only if
+/// the file is new or empty, so the histograms of many recordings can be collected in one
+/// file, see [`crate::tempo_salience`].
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_tempo_histogram_csv(path: &str, source: &str, bpm: &Array1<f32>, salience: &Array1<f32>) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    if file.metadata()?.len() == 0 {
+        let header: Vec<String> = bpm.iter().map(|bpm| format!("{}", bpm)).collect();
+        writeln!(file, "path,{}", header.join(","))?;
+    }
+    let values: Vec<String> = salience.iter().map(|v| format!("{:.05}", v)).collect();
+    writeln!(file, "{},{}", source, values.join(","))?;
+
+    Ok(())
+}
+
+/// Writes a batch report with one row per input file.
+///
+/// The columns are the input and output paths, the status (`processed`, `skipped`, or
+/// `failed`), the aggregate statistics of [`write_summary_csv`], and the error message of a
+/// failed file. Statistics that weren't computed are left empty, and the error message is
+/// quoted.
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_batch_report_csv(path: &str, records: &[BatchRecord]) -> anyhow::Result<()> {
+    let mut file = std::fs::File::create(path)?;
+
+    writeln!(file, "path,output,status,duration,onset_count,onset_density,mean_novelty,max_novelty,tempo,error")?;
+    for record in records {
+        let status = match record.status {
+            BatchStatus::Processed => "processed",
+            BatchStatus::Skipped => "skipped",
+            BatchStatus::Failed => "failed",
+        };
+        let summary = match &record.summary {
+            Some(summary) => format!(
+                "{:.05},{},{:.05},{:.05},{:.05},{}",
+                summary.duration,
+                summary.onset_count,
+                summary.onset_density,
+                summary.mean_novelty,
+                summary.max_novelty,
+                summary.tempo.map(|tempo| format!("{:.05}", tempo)).unwrap_or_default()
+            ),
+            None => ",,,,,".to_string(),
+        };
+        let error = record.error.as_ref().map(|error| format!("\"{}\"", error.replace('"', "\"\""))).unwrap_or_default();
+        writeln!(file, "{},{},{},{},{}", record.path, record.output, status, summary, error)?;
+    }
+
+    Ok(())
+}
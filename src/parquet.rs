@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use ::parquet::basic::{Repetition, Type as PhysicalType};
+use ::parquet::data_type::{DoubleType, FloatType};
+use ::parquet::file::metadata::KeyValue;
+use ::parquet::file::properties::WriterProperties;
+use ::parquet::file::writer::SerializedFileWriter;
+use ::parquet::schema::types::Type;
+use ndarray::{Array, Array1};
+
+/// Writes a Parquet file containing the novelty function and additional framewise columns,
+/// e.g. to load the results of a large batch into a dataframe or query engine.
+///
+/// The file has one row group with the required columns `time` (`DOUBLE`), `novelty`, and the
+/// additional columns under their names (`FLOAT`), uncompressed. The feature rate `fs_feature`
+/// and the time of the first frame `offset` are stored as key-value metadata. An existing file
+/// at `path` is replaced.
+///
+/// # Errors
+/// Returns an error if a column has the wrong length or writing to the file fails.
+pub fn write_novelty_parquet(path: &str, novelty_energy: Array1<f32>, fs_feature: f32, offset: f64, columns: &[(&str, Array1<f32>)]) -> anyhow::Result<()> {
+    for (name, values) in columns {
+        if values.len() != novelty_energy.len() {
+            anyhow::bail!("Column {} has {} values, expected {}", name, values.len(), novelty_energy.len());
+        }
+    }
+    // times in double precision, which stay exact for offsets such as a time of day
+    let time = Array::range(0.0, novelty_energy.len() as f64, 1.0) / fs_feature as f64 + offset;
+
+    let field = |name: &str, physical_type| {
+        Type::primitive_type_builder(name, physical_type).with_repetition(Repetition::REQUIRED).build().map(Arc::new)
+    };
+    let mut fields = vec![field("time", PhysicalType::DOUBLE)?, field("novelty", PhysicalType::FLOAT)?];
+    for (name, _) in columns {
+        fields.push(field(name, PhysicalType::FLOAT)?);
+    }
+    let schema = Type::group_type_builder("novelty").with_fields(fields).build()?;
+    let properties = WriterProperties::builder()
+        .set_key_value_metadata(Some(vec![
+            KeyValue::new("fs_feature".to_string(), fs_feature.to_string()),
+            KeyValue::new("offset".to_string(), offset.to_string()),
+        ]))
+        .build();
+
+    let file = std::fs::File::create(path)?;
+    let mut writer = SerializedFileWriter::new(file, Arc::new(schema), Arc::new(properties))?;
+    let mut row_group = writer.next_row_group()?;
+    let float_columns = std::iter::once(&novelty_energy).chain(columns.iter().map(|(_, values)| values));
+    if let Some(mut column) = row_group.next_column()? {
+        column.typed::<DoubleType>().write_batch(time.as_slice().expect("Array in standard layout"), None, None)?;
+        column.close()?;
+    }
+    for values in float_columns {
+        let Some(mut column) = row_group.next_column()? else {
+            anyhow::bail!("Parquet schema has fewer columns than written");
+        };
+        column.typed::<FloatType>().write_batch(&values.to_vec(), None, None)?;
+        column.close()?;
+    }
+    row_group.close()?;
+    writer.close()?;
+
+    Ok(())
+}
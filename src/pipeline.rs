@@ -0,0 +1,365 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use ndarray::Array1;
+
+use crate::compression::Compression;
+use crate::config::{Compat, Method, NoveltyConfig, NoveltyConfigBuilder};
+use crate::hpss::Component;
+use crate::manifest::expand_output_template;
+use crate::multires::Fusion;
+use crate::novelty::{Aggregation, NoveltyCurve};
+use crate::onsets::{detect_onsets, pick_peaks, refine_onsets, Onset, PeakPicking};
+use crate::output::{write_csv, write_onsets_csv};
+use crate::postprocess::AttackRelease;
+use crate::weighting::Weighting;
+
+/// Signal conditioning before the novelty computation, see the fields of [`NoveltyConfig`]
+/// with the same names.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PreprocessStage {
+    pub weighting: Option<Weighting>,
+    pub noise_gate: Option<f32>,
+    pub hpss: Option<Component>,
+}
+
+/// The novelty detection method and its parameters, see the fields of [`NoveltyConfig`]
+/// with the same names. Omitted parameters take the defaults of the method.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DetectorStage {
+    pub method: Method,
+    pub window_length: Option<u32>,
+    pub hop_length: Option<u32>,
+    pub aggregation: Option<Aggregation>,
+    pub compression: Option<Compression>,
+    pub gamma: Option<f32>,
+    pub norm: Option<bool>,
+    pub n_mels: Option<u32>,
+    pub local_average: Option<u32>,
+    pub resolutions: Option<Vec<u32>>,
+    pub fusion: Option<Fusion>,
+    pub compat: Option<Compat>,
+    pub model: Option<PathBuf>,
+    pub plugin: Option<PathBuf>,
+}
+
+/// Post-processing of the novelty function: a median filter over `median_filter` frames,
+/// attack/release smoothing if either time constant is given, and a Rhai `script`, in this
+/// order.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SmoothStage {
+    /// Length of the median filter in frames
+    pub median_filter: Option<u32>,
+    /// Attack time constant in seconds (0 if only the release is given)
+    pub attack: Option<f32>,
+    /// Release time constant in seconds (0 if only the attack is given)
+    pub release: Option<f32>,
+    /// Rhai script with a `novelty` function, see `PostScript` (requires the `scripting` feature)
+    pub script: Option<PathBuf>,
+}
+
+/// Onset detection on the novelty function: peaks above `threshold` times the maximum, or
+/// the peak picker of librosa with its defaults if `librosa` is set, optionally refined
+/// below the hop length.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PeakPickStage {
+    /// Fraction of the maximum novelty below which peaks are discarded (default: 0.1)
+    pub threshold: f32,
+    /// Whether to use the peak picker of librosa instead of the threshold
+    pub librosa: bool,
+    /// Whether to refine the onset times by quadratic interpolation
+    pub refine: bool,
+}
+
+impl Default for PeakPickStage {
+    fn default() -> Self {
+        PeakPickStage { threshold: 0.1, librosa: false, refine: false }
+    }
+}
+
+/// Output files of a pipeline run: the novelty function and the onsets as CSV files. Paths
+/// are output templates, see [`expand_output_template`], so one pipeline can process many
+/// inputs, e.g. `{stem}_onsets.csv`.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExportStage {
+    /// Output template of the novelty function CSV file
+    pub novelty: Option<String>,
+    /// Output template of the onsets CSV file, which requires a peak-pick stage
+    pub onsets: Option<String>,
+}
+
+/// A stage of a [`Pipeline`], tagged with its name in the specification.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+#[serde(tag = "stage", rename_all = "kebab-case")]
+pub enum Stage {
+    /// `stage = "preprocess"`
+    Preprocess(PreprocessStage),
+    /// `stage = "detector"`
+    Detector(DetectorStage),
+    /// `stage = "smooth"`
+    Smooth(SmoothStage),
+    /// `stage = "peak-pick"`
+    PeakPick(PeakPickStage),
+    /// `stage = "export"`
+    Export(ExportStage),
+}
+
+impl Stage {
+    /// Position of the stage in the processing chain.
+    fn rank(&self) -> usize {
+        match self {
+            Stage::Preprocess(_) => 0,
+            Stage::Detector(_) => 1,
+            Stage::Smooth(_) => 2,
+            Stage::PeakPick(_) => 3,
+            Stage::Export(_) => 4,
+        }
+    }
+}
+
+/// Results of a pipeline run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PipelineOutput {
+    /// Novelty function after the smooth stage
+    pub novelty: NoveltyCurve,
+    /// Onsets of the peak-pick stage, if the pipeline has one
+    pub onsets: Option<Vec<Onset>>,
+    /// Paths of the files written by the export stages
+    pub written: Vec<String>,
+}
+
+/// A processing chain described declaratively in TOML, so complex analyses are
+/// configuration rather than shell glue.
+///
+/// The stages are listed as `[[stage]]` tables in the order preprocess, detector, smooth,
+/// peak-pick, and export. Only the detector is required, and only export may be repeated:
+///
+/// ```toml
+/// [[stage]]
+/// stage = "preprocess"
+/// hpss = "percussive"
+///
+/// [[stage]]
+/// stage = "detector"
+/// method = "spectral"
+/// window_length = 2048
+/// hop_length = 512
+///
+/// [[stage]]
+/// stage = "smooth"
+/// median_filter = 5
+///
+/// [[stage]]
+/// stage = "peak-pick"
+/// threshold = 0.2
+///
+/// [[stage]]
+/// stage = "export"
+/// novelty = "{stem}_novelty.csv"
+/// onsets = "{stem}_onsets.csv"
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pipeline {
+    stages: Vec<Stage>,
+    config: NoveltyConfig,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PipelineSpec {
+    #[serde(rename = "stage")]
+    stages: Vec<Stage>,
+}
+
+impl Pipeline {
+    /// Builds a pipeline from its stages.
+    ///
+    /// # Errors
+    /// Returns an error if the stages are out of order, a stage other than export is
+    /// repeated, the detector is missing, onsets are exported without a peak-pick stage, or
+    /// the resulting [`NoveltyConfig`] is invalid.
+    pub fn new(stages: Vec<Stage>) -> anyhow::Result<Pipeline> {
+        for pair in stages.windows(2) {
+            if pair[1].rank() < pair[0].rank() || (pair[1].rank() == pair[0].rank() && pair[1].rank() != 4) {
+                anyhow::bail!("Pipeline stages must be in the order preprocess, detector, smooth, peak-pick, export, and only export may be repeated!");
+            }
+        }
+        let has_peak_pick = stages.iter().any(|stage| matches!(stage, Stage::PeakPick(_)));
+        if stages.iter().any(|stage| matches!(stage, Stage::Export(export) if export.onsets.is_some())) && !has_peak_pick {
+            anyhow::bail!("Exporting onsets requires a peak-pick stage!");
+        }
+        let Some(Stage::Detector(detector)) = stages.iter().find(|stage| matches!(stage, Stage::Detector(_))) else {
+            anyhow::bail!("A pipeline requires a detector stage!");
+        };
+
+        let mut builder = NoveltyConfigBuilder::from(detector.method)
+            .model(detector.model.clone())
+            .plugin(detector.plugin.clone())
+            .compat(detector.compat);
+        if let Some(window_length) = detector.window_length {
+            builder = builder.window(window_length);
+        }
+        if let Some(hop_length) = detector.hop_length {
+            builder = builder.hop(hop_length);
+        }
+        if let Some(aggregation) = detector.aggregation {
+            builder = builder.aggregation(aggregation);
+        }
+        if let Some(compression) = detector.compression {
+            builder = builder.compression(compression);
+        }
+        if let Some(gamma) = detector.gamma {
+            builder = builder.gamma(gamma);
+        }
+        if let Some(norm) = detector.norm {
+            builder = builder.norm(norm);
+        }
+        if let Some(n_mels) = detector.n_mels {
+            builder = builder.n_mels(n_mels);
+        }
+        if let Some(local_average) = detector.local_average {
+            builder = builder.local_average(local_average);
+        }
+        if let Some(resolutions) = &detector.resolutions {
+            builder = builder.resolutions(resolutions.clone());
+        }
+        if let Some(fusion) = detector.fusion {
+            builder = builder.fusion(fusion);
+        }
+        for stage in &stages {
+            match stage {
+                Stage::Preprocess(preprocess) => {
+                    builder = builder.noise_gate(preprocess.noise_gate).hpss(preprocess.hpss);
+                    if let Some(weighting) = preprocess.weighting {
+                        builder = builder.weighting(weighting);
+                    }
+                }
+                Stage::Smooth(smooth) => {
+                    builder = builder.median_filter(smooth.median_filter.unwrap_or(0)).post_script(smooth.script.clone());
+                    if smooth.attack.is_some() || smooth.release.is_some() {
+                        builder = builder.smoothing(Some(AttackRelease {
+                            attack: smooth.attack.unwrap_or(0.0),
+                            release: smooth.release.unwrap_or(0.0),
+                        }));
+                    }
+                }
+                _ => {}
+            }
+        }
+        let config = builder.build()?;
+
+        Ok(Pipeline { stages, config })
+    }
+
+    /// Parses a pipeline specification in TOML, see [`Pipeline`].
+    ///
+    /// # Errors
+    /// Returns an error if the specification can't be parsed or is invalid, see
+    /// [`Pipeline::new`].
+    pub fn from_toml(source: &str) -> anyhow::Result<Pipeline> {
+        let spec: PipelineSpec = toml::from_str(source)?;
+        Pipeline::new(spec.stages)
+    }
+
+    /// Reads a pipeline specification from a TOML file.
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be read or the specification is invalid.
+    pub fn load(path: &Path) -> anyhow::Result<Pipeline> {
+        let source = std::fs::read_to_string(path)?;
+        Pipeline::from_toml(&source).map_err(|error| anyhow::anyhow!("Invalid pipeline {}: {}", path.display(), error))
+    }
+
+    /// Stages of the pipeline in processing order.
+    pub fn stages(&self) -> &[Stage] {
+        &self.stages
+    }
+
+    /// Novelty configuration of the preprocess, detector, and smooth stages.
+    pub fn config(&self) -> &NoveltyConfig {
+        &self.config
+    }
+
+    /// Output paths of the export stages for an input, see [`ExportStage`].
+    ///
+    /// # Errors
+    /// Returns an error if an output template is invalid.
+    pub fn outputs(&self, path_in: &str) -> anyhow::Result<Vec<String>> {
+        let mut outputs = Vec::new();
+        for stage in &self.stages {
+            if let Stage::Export(export) = stage {
+                for template in export.novelty.iter().chain(export.onsets.iter()) {
+                    outputs.push(expand_output_template(template, path_in, &self.config)?);
+                }
+            }
+        }
+        Ok(outputs)
+    }
+
+    /// Checks that none of the exports for the inputs exist yet and that no two of them share
+    /// a path, so running the pipeline on each input never overwrites a file.
+    ///
+    /// # Errors
+    /// Returns an error if an output template is invalid, an output already exists, or
+    /// several exports would be written to the same path.
+    pub fn check_outputs<'a>(&self, paths_in: impl IntoIterator<Item = &'a str>) -> anyhow::Result<()> {
+        let mut outputs = HashSet::new();
+        for path_in in paths_in {
+            for path_out in self.outputs(path_in)? {
+                if Path::new(&path_out).exists() {
+                    anyhow::bail!("Output path must not already exist: {}", path_out);
+                }
+                if !outputs.insert(path_out.clone()) {
+                    anyhow::bail!("Several exports would be written to {}", path_out);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the pipeline on mono audio samples and writes the files of the export stages,
+    /// whose templates are expanded for `path_in`.
+    ///
+    /// The outputs are checked with [`Pipeline::check_outputs`] before any work is done.
+    ///
+    /// # Errors
+    /// Returns an error if an output already exists, the novelty computation or peak picking
+    /// fails, or an output can't be written.
+    pub fn run(&self, audio_array: Array1<f32>, fs: u32, path_in: &str) -> anyhow::Result<PipelineOutput> {
+        self.check_outputs([path_in])?;
+        let novelty = self.config.compute(audio_array, fs)?;
+        let mut onsets = None;
+        let mut written = Vec::new();
+        for stage in &self.stages {
+            match stage {
+                Stage::PeakPick(peak_pick) => {
+                    let picked = if peak_pick.librosa {
+                        pick_peaks(&novelty, &PeakPicking::librosa_defaults(novelty.fs_feature))?
+                    } else {
+                        detect_onsets(&novelty, peak_pick.threshold)
+                    };
+                    onsets = Some(if peak_pick.refine { refine_onsets(&novelty, &picked) } else { picked });
+                }
+                Stage::Export(export) => {
+                    if let Some(template) = &export.novelty {
+                        let path = expand_output_template(template, path_in, &self.config)?;
+                        write_csv(&path, novelty.values.clone(), novelty.fs_feature, 0.0)?;
+                        written.push(path);
+                    }
+                    if let (Some(template), Some(onsets)) = (&export.onsets, &onsets) {
+                        let path = expand_output_template(template, path_in, &self.config)?;
+                        write_onsets_csv(&path, onsets, None)?;
+                        written.push(path);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(PipelineOutput { novelty, onsets, written })
+    }
+}
@@ -0,0 +1,136 @@
+use ndarray::{s, Array1, ArrayView1};
+
+use crate::onsets::Onset;
+
+/// Parameters of [`onset_pitches`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PitchParams {
+    /// Lowest detectable pitch in Hz
+    pub fmin: f32,
+    /// Highest detectable pitch in Hz
+    pub fmax: f32,
+    /// Length in seconds of the analysis window, at least two periods of `fmin`
+    pub window: f32,
+    /// Time in seconds after the onset at which the analysis window starts, so the noisy
+    /// attack is skipped
+    pub delay: f32,
+    /// Threshold on the cumulative mean normalized difference below which a period counts as
+    /// voiced, from 0 to 1
+    pub threshold: f32,
+}
+
+impl Default for PitchParams {
+    /// Returns parameters for pitches from 50 to 2000 Hz, analyzed in a window of 50 ms
+    /// starting 10 ms after the onset, with the threshold of 0.15 of the YIN paper.
+    fn default() -> Self {
+        PitchParams { fmin: 50.0, fmax: 2000.0, window: 0.05, delay: 0.01, threshold: 0.15 }
+    }
+}
+
+/// Pitch estimated after an onset, see [`onset_pitches`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OnsetPitch {
+    /// Fundamental frequency in Hz, `None` if the window is unvoiced
+    pub frequency: Option<f32>,
+    /// One minus the cumulative mean normalized difference at the selected period, from 0 to
+    /// 1, high for clearly periodic windows
+    pub confidence: f32,
+}
+
+impl OnsetPitch {
+    /// Returns the nearest MIDI note number of the pitch, 69 for A4 at 440 Hz.
+    pub fn midi_note(&self) -> Option<u8> {
+        self.frequency.map(|frequency| (69.0 + 12.0 * (frequency / 440.0).log2()).round().clamp(0.0, 127.0) as u8)
+    }
+}
+
+/// Returns the name of a MIDI note number with sharps, e.g. `A4` for 69 and `C#-1` for 1.
+pub fn note_name(midi_note: u8) -> String {
+    const NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+    format!("{}{}", NAMES[midi_note as usize % 12], midi_note as i32 / 12 - 1)
+}
+
+/// Estimates the fundamental frequency of a monophonic window with the YIN algorithm, see
+/// de Cheveigné and Kawahara (2002).
+///
+/// The period is the first lag between `fs / fmax` and `fs / fmin` whose cumulative mean
+/// normalized difference falls below `threshold`, moved to the local minimum that follows
+/// and refined by parabolic interpolation. If no lag falls below the threshold, the window
+/// is unvoiced.
+///
+/// # Returns
+/// - The frequency in Hz, if voiced, and the confidence of the selected period
+pub fn yin(window: &ArrayView1<f32>, fs: u32, fmin: f32, fmax: f32, threshold: f32) -> (Option<f32>, f32) {
+    let max_lag = ((fs as f32 / fmin).ceil() as usize).min(window.len() / 2);
+    let min_lag = ((fs as f32 / fmax).floor() as usize).max(2);
+    if min_lag + 1 >= max_lag {
+        return (None, 0.0);
+    }
+    let length = window.len() - max_lag;
+
+    // cumulative mean normalized difference function, 1 at lag 0
+    let mut normalized = vec![1.0_f32; max_lag + 2];
+    let mut sum = 0.0;
+    for lag in 1..=max_lag + 1 {
+        let difference: f32 = (0..length.min(window.len() - lag)).map(|j| (window[j] - window[j + lag]).powi(2)).sum();
+        sum += difference;
+        normalized[lag] = if sum > 0.0 { difference * lag as f32 / sum } else { 1.0 };
+    }
+
+    let Some(mut lag) = (min_lag..=max_lag).find(|&lag| normalized[lag] < threshold) else {
+        let best = (min_lag..=max_lag).map(|lag| normalized[lag]).fold(1.0_f32, f32::min);
+        return (None, (1.0 - best).clamp(0.0, 1.0));
+    };
+    while lag < max_lag && normalized[lag + 1] < normalized[lag] {
+        lag += 1;
+    }
+
+    let (previous, current, next) = (normalized[lag - 1], normalized[lag], normalized[lag + 1]);
+    let curvature = previous - 2.0 * current + next;
+    let shift = if curvature > 0.0 { (0.5 * (previous - next) / curvature).clamp(-0.5, 0.5) } else { 0.0 };
+    (Some(fs as f32 / (lag as f32 + shift)), (1.0 - current).clamp(0.0, 1.0))
+}
+
+/// Estimates the pitch after each onset with [`yin`], e.g. for a rough melody transcription
+/// of a monophonic line.
+///
+/// The analysis window of `window` seconds starts `delay` seconds after the onset, and ends
+/// at the next onset if that comes first. Windows shorter than two periods of `fmin` are
+/// unvoiced.
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples the onsets were detected in
+/// - `fs`: Sampling rate of the audio
+/// - `onsets`: Detected onsets, sorted by time
+/// - `params`: Parameters of the estimation
+///
+/// # Errors
+/// Returns an error if the frequency range, window, or threshold is invalid.
+pub fn onset_pitches(audio_array: &Array1<f32>, fs: u32, onsets: &[Onset], params: &PitchParams) -> anyhow::Result<Vec<OnsetPitch>> {
+    if !(params.fmin > 0.0 && params.fmin < params.fmax && params.fmax <= fs as f32 / 2.0) {
+        anyhow::bail!("Pitch range must be from above 0 Hz up to the Nyquist frequency, got {} to {} Hz", params.fmin, params.fmax);
+    }
+    if params.window < 2.0 / params.fmin || params.delay < 0.0 {
+        anyhow::bail!("Pitch window must be at least two periods of the lowest pitch ({:.3} s) and the delay must not be negative", 2.0 / params.fmin);
+    }
+    if !(0.0..=1.0).contains(&params.threshold) {
+        anyhow::bail!("Pitch threshold must be from 0 to 1, got {}", params.threshold);
+    }
+
+    let to_sample = |time: f32| ((time.max(0.0) * fs as f32).round() as usize).min(audio_array.len());
+    Ok(onsets
+        .iter()
+        .enumerate()
+        .map(|(i, onset)| {
+            let start = to_sample(onset.time + params.delay);
+            let end = onsets.get(i + 1).map_or(audio_array.len(), |next| to_sample(next.time)).min(to_sample(onset.time + params.delay + params.window));
+            if end < start + 2 * (fs as f32 / params.fmin).ceil() as usize {
+                return OnsetPitch { frequency: None, confidence: 0.0 };
+            }
+            let (frequency, confidence) = yin(&audio_array.slice(s![start..end]), fs, params.fmin, params.fmax, params.threshold);
+            OnsetPitch { frequency, confidence }
+        })
+        .collect())
+}
@@ -0,0 +1,175 @@
+use std::ffi::{c_char, c_int, CStr};
+use std::path::{Path, PathBuf};
+
+use libloading::Library;
+use ndarray::Array1;
+
+use crate::manifest::find_files;
+use crate::novelty::normalize_max;
+
+/// Version of the C ABI described at [`Plugin`], which plugins return from
+/// `novelty_plugin_abi_version`.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// Environment variable listing the directories searched for plugins, separated like `PATH`.
+pub const PLUGIN_PATH_VARIABLE: &str = "NOVELTY_PLUGIN_PATH";
+
+type AbiVersionFn = unsafe extern "C" fn() -> u32;
+type NameFn = unsafe extern "C" fn() -> *const c_char;
+type ComputeFn = unsafe extern "C" fn(*const f32, usize, u32, u32, u32, *mut f32, usize) -> c_int;
+
+/// A novelty detector loaded from a shared library, so custom methods can be used without
+/// changing this crate.
+///
+/// A plugin is a shared library (`.so`, `.dylib`, or `.dll`) in any language that exports
+/// three functions with the C ABI:
+///
+/// ```c
+/// uint32_t novelty_plugin_abi_version(void);
+/// const char *novelty_plugin_name(void);
+/// int32_t novelty_plugin_compute(const float *samples, size_t num_samples, uint32_t fs,
+///                                uint32_t window_length, uint32_t hop_length,
+///                                float *novelty, size_t num_frames);
+/// ```
+///
+/// The version must be [`PLUGIN_ABI_VERSION`], and the name is a static, null-terminated
+/// UTF-8 string. `novelty_plugin_compute` gets the mono samples and writes one value per
+/// frame to `novelty`, which the caller allocates with `num_frames` elements. As for the
+/// built-in methods, frame `m` is centered on sample `m * hop_length`, so there are
+/// `ceil(num_samples / hop_length)` frames. It returns 0 on success and any other value on
+/// failure, and must not keep the pointers after returning.
+pub struct Plugin {
+    library: Library,
+    name: String,
+    path: PathBuf,
+}
+
+impl Plugin {
+    /// Loads a plugin from a shared library and checks its ABI version.
+    ///
+    /// # Errors
+    /// Returns an error if the library can't be loaded, a function is missing, or the ABI
+    /// version doesn't match.
+    pub fn load(path: &Path) -> anyhow::Result<Plugin> {
+        // SAFETY: loading a library runs its initializers; plugins are trusted like any
+        // other program the user runs
+        let library = unsafe { Library::new(path)? };
+        // SAFETY: the symbols are declared with the signatures of the plugin ABI
+        let name = unsafe {
+            let version = library.get::<AbiVersionFn>(b"novelty_plugin_abi_version\0")?();
+            if version != PLUGIN_ABI_VERSION {
+                anyhow::bail!("Plugin {} has ABI version {}, expected {}", path.display(), version, PLUGIN_ABI_VERSION);
+            }
+            library.get::<ComputeFn>(b"novelty_plugin_compute\0")?;
+            let name = library.get::<NameFn>(b"novelty_plugin_name\0")?();
+            if name.is_null() {
+                anyhow::bail!("Plugin {} has no name", path.display());
+            }
+            CStr::from_ptr(name).to_string_lossy().into_owned()
+        };
+
+        Ok(Plugin { library, name, path: path.to_path_buf() })
+    }
+
+    /// Name the plugin reports, used to select it by name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Path of the shared library.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Computes the novelty function of mono audio samples with the plugin.
+    ///
+    /// # Errors
+    /// Returns an error if the plugin reports a failure or returns values that aren't finite.
+    pub fn compute(&self, audio_array: &Array1<f32>, fs: u32, window_length: u32, hop_length: u32) -> anyhow::Result<Array1<f32>> {
+        let samples = audio_array.as_standard_layout();
+        let samples = samples.as_slice().expect("Array in standard layout");
+        let num_frames = samples.len().div_ceil(hop_length as usize);
+        let mut values = vec![0.0_f32; num_frames];
+
+        // SAFETY: both buffers are valid for their lengths during the call, as the ABI requires
+        let status = unsafe {
+            let compute = self.library.get::<ComputeFn>(b"novelty_plugin_compute\0")?;
+            compute(samples.as_ptr(), samples.len(), fs, window_length, hop_length, values.as_mut_ptr(), num_frames)
+        };
+        if status != 0 {
+            anyhow::bail!("Plugin {} failed with status {}", self.name, status);
+        }
+        if values.iter().any(|v| !v.is_finite()) {
+            anyhow::bail!("Plugin {} returned values that aren't finite", self.name);
+        }
+        Ok(Array1::from_vec(values))
+    }
+}
+
+/// Returns the directories listed in [`PLUGIN_PATH_VARIABLE`].
+pub fn plugin_dirs() -> Vec<PathBuf> {
+    std::env::var_os(PLUGIN_PATH_VARIABLE).map(|paths| std::env::split_paths(&paths).collect()).unwrap_or_default()
+}
+
+/// Finds the shared libraries in the given directories, which are plugins if they export
+/// the plugin ABI, see [`Plugin`]. Directories that don't exist are skipped.
+///
+/// # Errors
+/// Returns an error if a directory can't be read.
+pub fn find_plugins(dirs: &[PathBuf]) -> anyhow::Result<Vec<PathBuf>> {
+    let extensions = [std::env::consts::DLL_EXTENSION.to_string()];
+    let mut paths = Vec::new();
+    for dir in dirs.iter().filter(|dir| dir.is_dir()) {
+        paths.extend(find_files(dir, &extensions, false)?);
+    }
+    Ok(paths)
+}
+
+/// Resolves a plugin given as path to a shared library or as the name of a plugin in one of
+/// the given directories.
+///
+/// A plugin is found by name only in a library named after it, with the platform prefix and
+/// hyphens or underscores, e.g. `libmy_detector.so` or `my-detector.dll` for `my-detector`.
+/// Only these candidates are loaded to check the name they report, since loading a library
+/// runs its initializers.
+///
+/// # Errors
+/// Returns an error if the path doesn't exist and no plugin in the directories has this name.
+pub fn resolve_plugin(plugin: &str, dirs: &[PathBuf]) -> anyhow::Result<PathBuf> {
+    if Path::new(plugin).is_file() {
+        return Ok(PathBuf::from(plugin));
+    }
+    let normalize = |name: &str| name.replace('-', "_");
+    for path in find_plugins(dirs)? {
+        let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+        let stem = stem.strip_prefix(std::env::consts::DLL_PREFIX).unwrap_or(&stem);
+        if normalize(stem) == normalize(plugin) && Plugin::load(&path).is_ok_and(|loaded| loaded.name() == plugin) {
+            return Ok(path);
+        }
+    }
+    anyhow::bail!("No plugin named {} found in {}", plugin, PLUGIN_PATH_VARIABLE)
+}
+
+/// Computes a novelty function with a plugin, see [`Plugin`].
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples
+/// - `fs`: Sampling rate of the audio
+/// - `window_length`: Size of the analysis window, passed to the plugin
+/// - `hop_length`: Step size between successive frames
+/// - `plugin`: Path to the shared library
+/// - `norm`: Whether to normalize the output between 0 and 1
+///
+/// # Returns
+/// - A tuple of the novelty function and its effective sampling rate
+///
+/// # Errors
+/// Returns an error if the plugin can't be loaded or fails.
+pub fn novelty_plugin(audio_array: &Array1<f32>, fs: u32, window_length: u32, hop_length: u32, plugin: &Path, norm: bool) -> anyhow::Result<(Array1<f32>, f32)> {
+    let fs_feature = (fs as f32) / (hop_length as f32);
+    let mut values = Plugin::load(plugin)?.compute(audio_array, fs, window_length, hop_length)?;
+    if norm {
+        normalize_max(&mut values)?;
+    }
+    Ok((values, fs_feature))
+}
@@ -0,0 +1,138 @@
+use std::io::Write;
+
+use ndarray::{s, ArrayView1, ArrayView2};
+
+use crate::npz::crc32;
+
+/// Control points of the heatmap color map, from dark blue for zero to yellow for the
+/// maximum, approximating viridis.
+const COLORS: [[f32; 3]; 5] = [[68.0, 1.0, 84.0], [59.0, 82.0, 139.0], [33.0, 145.0, 140.0], [94.0, 201.0, 98.0], [253.0, 231.0, 37.0]];
+
+/// Color of the area below a curve, the middle of the heatmap color map.
+const CURVE_COLOR: [u8; 3] = [33, 145, 140];
+
+/// Maximum number of columns of a curve plot.
+pub const MAX_PLOT_WIDTH: usize = 4096;
+
+/// Default height of a curve plot in pixels.
+pub const DEFAULT_PLOT_HEIGHT: usize = 256;
+
+/// Maps a value between 0 and 1 to an RGB color of the heatmap color map.
+fn color(value: f32) -> [u8; 3] {
+    let position = value.clamp(0.0, 1.0) * (COLORS.len() - 1) as f32;
+    let index = (position.floor() as usize).min(COLORS.len() - 2);
+    let fraction = position - index as f32;
+    let (low, high) = (COLORS[index], COLORS[index + 1]);
+    [0, 1, 2].map(|c| (low[c] + fraction * (high[c] - low[c])).round() as u8)
+}
+
+/// Computes the Adler-32 checksum of zlib streams.
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in bytes.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+/// Appends a PNG chunk with its length and checksum.
+fn push_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Writes a matrix as a PNG heatmap with one pixel per element.
+///
+/// The first row is drawn at the top. Values are scaled by the maximum of the matrix and
+/// mapped to colors from dark blue for zero to yellow for the maximum; negative values are
+/// drawn as zero. The image data is stored without compression, so no encoder is needed.
+///
+/// # Errors
+/// Returns an error if the matrix is empty or writing to the file fails.
+pub fn write_heatmap_png(path: &str, values: ArrayView2<f32>) -> anyhow::Result<()> {
+    let (height, width) = values.dim();
+    if height == 0 || width == 0 {
+        anyhow::bail!("Can't draw an empty matrix with shape ({}, {})", height, width);
+    }
+    let max = values.iter().copied().fold(0.0, f32::max);
+    let scale = if max > 0.0 { 1.0 / max } else { 0.0 };
+
+    let pixels: Vec<[u8; 3]> = values.iter().map(|&v| color(v * scale)).collect();
+    write_rgb_png(path, width, height, &pixels)
+}
+
+/// Writes a curve as a PNG plot with the area below the curve filled, e.g. to glance at a
+/// novelty function.
+///
+/// The curve is scaled by its maximum to the full `height`. Longer curves are reduced to
+/// [`MAX_PLOT_WIDTH`] columns, each drawn with the maximum of its values, so short peaks
+/// stay visible.
+///
+/// # Errors
+/// Returns an error if the curve is empty, the height is zero, or writing to the file fails.
+pub fn write_curve_png(path: &str, values: ArrayView1<f32>, height: usize) -> anyhow::Result<()> {
+    if values.is_empty() || height == 0 {
+        anyhow::bail!("Can't plot a curve of {} values with a height of {} pixels", values.len(), height);
+    }
+    let width = values.len().min(MAX_PLOT_WIDTH);
+    let columns: Vec<f32> = (0..width)
+        .map(|x| {
+            let (start, end) = (x * values.len() / width, ((x + 1) * values.len()).div_ceil(width));
+            values.slice(s![start..end]).iter().copied().fold(0.0, f32::max)
+        })
+        .collect();
+    let max = columns.iter().copied().fold(0.0, f32::max);
+    let scale = if max > 0.0 { height as f32 / max } else { 0.0 };
+
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        // the first row is the top of the plot
+        let level = (height - y) as f32;
+        pixels.extend(columns.iter().map(|&v| if v * scale >= level - 0.5 { CURVE_COLOR } else { [255, 255, 255] }));
+    }
+    write_rgb_png(path, width, height, &pixels)
+}
+
+/// Writes RGB pixels given row by row from the top as an uncompressed PNG image.
+fn write_rgb_png(path: &str, width: usize, height: usize, rgb: &[[u8; 3]]) -> anyhow::Result<()> {
+    // each row starts with filter type 0 (none)
+    let mut pixels = Vec::with_capacity(height * (3 * width + 1));
+    for row in rgb.chunks(width) {
+        pixels.push(0);
+        row.iter().for_each(|pixel| pixels.extend_from_slice(pixel));
+    }
+
+    // zlib stream of stored deflate blocks
+    let mut zlib = vec![0x78, 0x01];
+    let num_blocks = pixels.len().div_ceil(65535);
+    for (k, block) in pixels.chunks(65535).enumerate() {
+        zlib.push(u8::from(k + 1 == num_blocks));
+        zlib.extend_from_slice(&(block.len() as u16).to_le_bytes());
+        zlib.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&pixels).to_be_bytes());
+
+    // 8 bits per channel, RGB, no interlacing
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&u32::try_from(width)?.to_be_bytes());
+    header.extend_from_slice(&u32::try_from(height)?.to_be_bytes());
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    push_chunk(&mut png, b"IHDR", &header);
+    push_chunk(&mut png, b"IDAT", &zlib);
+    push_chunk(&mut png, b"IEND", &[]);
+
+    std::fs::File::create(path)?.write_all(&png)?;
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use ndarray::{Array1, ArrayView1};
+
+/// Applies a centered median filter to a novelty function.
+///
+/// Unlike linear smoothing, a median filter removes spikes shorter than half its length,
+/// e.g. from clicks or dropouts, entirely, while keeping the edges of longer events sharp.
+/// The filter is shortened at the edges.
+///
+/// # Arguments
+/// - `values`: Novelty values
+/// - `length`: Length of the filter in frames, rounded up to the next odd length; 0 and 1
+///   leave the values unchanged
+pub fn median_filter(values: &Array1<f32>, length: usize) -> Array1<f32> {
+    median_filter_view(values.view(), length)
+}
+
+/// Applies [`median_filter`] to a view, e.g. to one lane of a spectrogram.
+pub(crate) fn median_filter_view(values: ArrayView1<f32>, length: usize) -> Array1<f32> {
+    let half = length / 2;
+    let mut buffer = Vec::with_capacity(2 * half + 1);
+    Array1::from_shape_fn(values.len(), |n| {
+        buffer.clear();
+        buffer.extend(values.iter().skip(n.saturating_sub(half)).take(n.min(half) + half + 1));
+        let middle = buffer.len() / 2;
+        *buffer.select_nth_unstable_by(middle, |a, b| a.total_cmp(b)).1
+    })
+}
+
+/// Time constants of an asymmetric exponential moving average, see [`attack_release`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AttackRelease {
+    /// Time constant in seconds while the novelty rises (0 follows rises immediately)
+    pub attack: f32,
+    /// Time constant in seconds while the novelty falls (0 follows falls immediately)
+    pub release: f32,
+}
+
+impl AttackRelease {
+    /// Computes the next smoothed value from the previous one and the next novelty value.
+    pub fn step(&self, previous: f32, value: f32, fs_feature: f32) -> f32 {
+        let time_constant = if value > previous { self.attack } else { self.release };
+        let coefficient = if time_constant > 0.0 { 1.0 - (-1.0 / (time_constant * fs_feature)).exp() } else { 1.0 };
+        previous + coefficient * (value - previous)
+    }
+}
+
+/// Smooths a novelty function with an asymmetric exponential moving average, like the
+/// envelope follower of a compressor.
+///
+/// The smoothed value moves towards each novelty value by a fraction of the difference
+/// that corresponds to the attack time constant while the novelty is above it and to the
+/// release time constant otherwise. With a short attack and a long release, peaks are
+/// followed immediately and decay slowly, as desired when the novelty function drives
+/// visualizations or automation. The filter is causal and starts at zero.
+///
+/// # Arguments
+/// - `values`: Novelty values
+/// - `fs_feature`: Sampling rate of the novelty function (frames per second)
+/// - `smoothing`: Attack and release time constants
+pub fn attack_release(values: &Array1<f32>, fs_feature: f32, smoothing: AttackRelease) -> Array1<f32> {
+    let mut previous = 0.0;
+    values.mapv(|value| {
+        previous = smoothing.step(previous, value, fs_feature);
+        previous
+    })
+}
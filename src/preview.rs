@@ -0,0 +1,66 @@
+use crate::novelty::NoveltyCurve;
+use crate::onsets::Onset;
+
+/// Block characters of increasing height used by [`sparkline`].
+const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Renders a novelty function as a compact text preview for the terminal.
+///
+/// The novelty function is divided into `width` columns, each showing the maximum of its
+/// frames as a block character scaled to the overall maximum, so short peaks remain
+/// visible. A second line marks the columns containing onsets with `^`.
+///
+/// # Returns
+/// - The sparkline and the onset markers, separated by a newline
+pub fn sparkline(novelty: &NoveltyCurve, onsets: &[Onset], width: usize) -> String {
+    sparkline_annotated(novelty, onsets, &[], width)
+}
+
+/// Renders a novelty function as [`sparkline`] does, with a third line that marks the
+/// columns containing annotated events with `|`, to compare detections with annotations at a
+/// glance. Without annotations, the third line is omitted.
+///
+/// # Arguments
+/// - `novelty`: Novelty function to render
+/// - `onsets`: Detected onsets
+/// - `annotations`: Times of annotated events in seconds
+/// - `width`: Number of columns
+pub fn sparkline_annotated(novelty: &NoveltyCurve, onsets: &[Onset], annotations: &[f32], width: usize) -> String {
+    let values = &novelty.values;
+    let width = width.min(values.len()).max(1);
+    let max = values.fold(0.0_f32, |acc, &v| acc.max(v));
+    let column = |frame: usize| frame * width / values.len().max(1);
+
+    let mut peaks = vec![0.0_f32; width];
+    for (frame, &value) in values.iter().enumerate() {
+        peaks[column(frame)] = peaks[column(frame)].max(value);
+    }
+    let line: String = peaks
+        .iter()
+        .map(|&peak| {
+            let level = if max > 0.0 { (peak / max * (BLOCKS.len() - 1) as f32).round() as usize } else { 0 };
+            BLOCKS[level.min(BLOCKS.len() - 1)]
+        })
+        .collect();
+
+    let mut markers = vec![' '; width];
+    for onset in onsets.iter().filter(|onset| onset.frame < values.len()) {
+        markers[column(onset.frame)] = '^';
+    }
+    let markers: String = markers.into_iter().collect();
+    let mut preview = format!("{}\n{}", line, markers.trim_end());
+
+    if !annotations.is_empty() {
+        let mut annotation_markers = vec![' '; width];
+        for &time in annotations {
+            let frame = (time * novelty.fs_feature).round();
+            if frame >= 0.0 && (frame as usize) < values.len() {
+                annotation_markers[column(frame as usize)] = '|';
+            }
+        }
+        let annotation_markers: String = annotation_markers.into_iter().collect();
+        preview.push('\n');
+        preview.push_str(annotation_markers.trim_end());
+    }
+    preview
+}
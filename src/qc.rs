@@ -0,0 +1,129 @@
+use ndarray::{s, Array2};
+
+use crate::artifacts::{detect_artifacts, ArtifactKind};
+
+/// Level in dBFS below which a block of [`SILENCE_BLOCK`] seconds counts as silent.
+pub const SILENCE_LEVEL: f32 = -60.0;
+
+/// Length of the blocks in seconds whose level decides the silence percentage.
+pub const SILENCE_BLOCK: f32 = 0.05;
+
+/// Limits a recording must keep to pass [`quality_check`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QcLimits {
+    /// Largest total duration of clipped regions in seconds
+    pub max_clipping: f32,
+    /// Largest total duration of dropouts in seconds
+    pub max_dropouts: f32,
+    /// Largest absolute mean of the samples of any channel
+    pub max_dc_offset: f32,
+    /// Largest percentage of silent blocks, from 0 to 100
+    pub max_silence: f32,
+    /// Shortest allowed duration in seconds
+    pub min_duration: Option<f32>,
+    /// Longest allowed duration in seconds
+    pub max_duration: Option<f32>,
+}
+
+impl Default for QcLimits {
+    /// Returns limits that reject any clipping or dropout, a DC offset above 0.01, more than
+    /// 50% silence, and no duration limits.
+    fn default() -> Self {
+        QcLimits {
+            max_clipping: 0.0,
+            max_dropouts: 0.0,
+            max_dc_offset: 0.01,
+            max_silence: 50.0,
+            min_duration: None,
+            max_duration: None,
+        }
+    }
+}
+
+/// Measurements of a recording and the limits it violates, see [`quality_check`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QcReport {
+    /// Duration in seconds
+    pub duration: f32,
+    /// Total duration of clipped regions in seconds, summed over channels
+    pub clipping: f32,
+    /// Total duration of dropouts in seconds, summed over channels
+    pub dropouts: f32,
+    /// Largest absolute mean of the samples of any channel
+    pub dc_offset: f32,
+    /// Percentage of blocks in which all channels are below [`SILENCE_LEVEL`]
+    pub silence: f32,
+    /// Descriptions of the violated limits, empty if the recording passes
+    pub failures: Vec<String>,
+}
+
+impl QcReport {
+    /// Whether the recording keeps all limits.
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Checks a decoded recording against limits for clipping, dropouts, DC offset, silence,
+/// and duration, e.g. to reject broken files at ingest before expensive analysis.
+///
+/// Clipping and dropouts are found per channel with [`detect_artifacts`]. A block counts as
+/// silent if the RMS level of every channel is below [`SILENCE_LEVEL`].
+///
+/// # Arguments
+/// - `channels`: Samples with shape (channels, samples)
+/// - `fs`: Sampling rate of the audio
+/// - `limits`: Limits to check
+pub fn quality_check(channels: &Array2<f32>, fs: u32, limits: &QcLimits) -> QcReport {
+    let num_samples = channels.ncols();
+    let duration = num_samples as f32 / fs as f32;
+
+    let (mut clipping, mut dropouts) = (0.0, 0.0);
+    for channel in channels.rows() {
+        for artifact in detect_artifacts(&channel.to_owned(), fs) {
+            match artifact.kind {
+                ArtifactKind::Clipping => clipping += artifact.duration(),
+                ArtifactKind::Dropout => dropouts += artifact.duration(),
+            }
+        }
+    }
+    let dc_offset = channels.rows().into_iter().map(|channel| channel.mean().unwrap_or(0.0).abs()).fold(0.0_f32, f32::max);
+
+    let block = ((SILENCE_BLOCK * fs as f32).round() as usize).max(1);
+    let threshold = 10.0_f32.powf(SILENCE_LEVEL / 20.0);
+    let num_blocks = num_samples.div_ceil(block);
+    let silent_blocks = (0..num_blocks)
+        .filter(|&k| {
+            let (start, end) = (k * block, ((k + 1) * block).min(num_samples));
+            channels.rows().into_iter().all(|channel| {
+                let mean_square = channel.slice(s![start..end]).mapv(|v| v * v).sum() / (end - start) as f32;
+                mean_square.sqrt() < threshold
+            })
+        })
+        .count();
+    let silence = if num_blocks > 0 { 100.0 * silent_blocks as f32 / num_blocks as f32 } else { 100.0 };
+
+    let mut failures = Vec::new();
+    if clipping > limits.max_clipping {
+        failures.push(format!("clipping of {:.3} s exceeds {} s", clipping, limits.max_clipping));
+    }
+    if dropouts > limits.max_dropouts {
+        failures.push(format!("dropouts of {:.3} s exceed {} s", dropouts, limits.max_dropouts));
+    }
+    if dc_offset > limits.max_dc_offset {
+        failures.push(format!("DC offset of {:.4} exceeds {}", dc_offset, limits.max_dc_offset));
+    }
+    if silence > limits.max_silence {
+        failures.push(format!("{:.1}% silence exceeds {}%", silence, limits.max_silence));
+    }
+    if let Some(min_duration) = limits.min_duration.filter(|&min_duration| duration < min_duration) {
+        failures.push(format!("duration of {:.3} s is below {} s", duration, min_duration));
+    }
+    if let Some(max_duration) = limits.max_duration.filter(|&max_duration| duration > max_duration) {
+        failures.push(format!("duration of {:.3} s exceeds {} s", duration, max_duration));
+    }
+
+    QcReport { duration, clipping, dropouts, dc_offset, silence, failures }
+}
@@ -0,0 +1,351 @@
+use std::io::Read;
+
+use ndarray::{Array1, Array2, Axis};
+
+/// Largest header chunk, such as `fmt ` or `ds64`, that is read into memory. Valid header
+/// chunks are far smaller, so a corrupt size field fails instead of allocating gigabytes.
+const MAX_HEADER_CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Encoding of the samples in a `data` chunk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SampleFormat {
+    /// Signed integers with the given number of bits (unsigned for 8 bits, as in WAV)
+    Int(u16),
+    /// IEEE floats with the given number of bits
+    Float(u16),
+}
+
+impl SampleFormat {
+    /// Number of bytes per sample.
+    pub fn bytes(self) -> usize {
+        match self {
+            SampleFormat::Int(bits) | SampleFormat::Float(bits) => bits.div_ceil(8) as usize,
+        }
+    }
+}
+
+/// Byte order of the samples in a container.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// Little-endian, as in WAV
+    #[default]
+    Little,
+    /// Big-endian, as in AIFF
+    Big,
+}
+
+/// Decodes little-endian samples to floats in the range -1 to 1.
+///
+/// Integer samples are divided by `2^(bits - 1)`. Trailing bytes that don't form a complete
+/// sample are ignored.
+///
+/// # Errors
+/// Returns an error for unsupported bit depths.
+pub fn decode_samples(bytes: &[u8], format: SampleFormat) -> anyhow::Result<Vec<f32>> {
+    decode_samples_with_order(bytes, format, ByteOrder::Little)
+}
+
+/// Decodes samples with the given byte order to floats in the range -1 to 1.
+///
+/// This is [`decode_samples`] for containers that store samples big-endian, such as AIFF.
+///
+/// # Errors
+/// Returns an error for unsupported bit depths.
+pub fn decode_samples_with_order(bytes: &[u8], format: SampleFormat, order: ByteOrder) -> anyhow::Result<Vec<f32>> {
+    if !matches!(format, SampleFormat::Int(8 | 16 | 24 | 32) | SampleFormat::Float(32 | 64)) {
+        anyhow::bail!("Unsupported sample format {:?}", format);
+    }
+    // bring each sample to little-endian byte order
+    let chunks = bytes.chunks_exact(format.bytes()).map(|chunk| {
+        let mut sample = [0u8; 8];
+        sample[..chunk.len()].copy_from_slice(chunk);
+        if order == ByteOrder::Big {
+            sample[..chunk.len()].reverse();
+        }
+        sample
+    });
+    let samples = match format {
+        SampleFormat::Int(8) => chunks.map(|b| (b[0] as f32 - 128.0) / 128.0).collect(),
+        SampleFormat::Int(16) => chunks.map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0).collect(),
+        SampleFormat::Int(24) => chunks.map(|b| (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8388608.0).collect(),
+        SampleFormat::Int(32) => chunks.map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2147483648.0).collect(),
+        SampleFormat::Float(32) => chunks.map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect(),
+        SampleFormat::Float(64) => chunks.map(|b| f64::from_le_bytes(b) as f32).collect(),
+        _ => unreachable!("Sample format checked above"),
+    };
+    Ok(samples)
+}
+
+/// Reads a mono WAV file from a stream, e.g. standard input, and returns the audio samples as
+/// a 1D array, along with the sampling rate.
+///
+/// Unlike [`crate::audio_path_to_array`], the stream doesn't need to be seekable. Chunks
+/// are read in order, and the `fmt ` chunk must precede the `data` chunk. Since tools
+/// writing WAV to a pipe can't go back to fill in the sizes, a `data` chunk with a size of 0
+/// or `0xFFFFFFFF` is read until the end of the stream. Integer PCM with 8 to 32 bits and
+/// 32 or 64-bit floats are supported, also in `WAVE_FORMAT_EXTENSIBLE` files.
+///
+/// RF64 and BW64 files, which exceed the 4 GB limit of RIFF, are supported as well: their
+/// `ds64` chunk holds the 64-bit size of the `data` chunk. Broadcast Wave (BWF) metadata in
+/// `bext` chunks is skipped like any other chunk.
+///
+/// # Errors
+/// Returns an error if the stream can't be read, is not a WAV file, uses an unsupported
+/// sample format, or is not mono.
+pub fn read_wav<R: Read>(reader: R) -> anyhow::Result<(Array1<f32>, u32)> {
+    let (channels, fs) = read_wav_channels(reader)?;
+    if channels.nrows() != 1 {
+        anyhow::bail!("Can only handle mono files currently. Please convert input audio file to mono.");
+    }
+    Ok((channels.index_axis_move(Axis(0), 0), fs))
+}
+
+/// Reads a WAV file with any number of channels from a stream, like [`read_wav`].
+///
+/// # Returns
+/// - The samples with shape (channels, samples) and the sampling rate
+///
+/// # Errors
+/// Returns an error if the stream can't be read, is not a WAV file, or uses an unsupported
+/// sample format.
+pub fn read_wav_channels<R: Read>(mut reader: R) -> anyhow::Result<(Array2<f32>, u32)> {
+    let (sample_format, info) = read_wav_header(&mut reader)?;
+
+    let mut bytes = Vec::new();
+    match info.frames {
+        Some(frames) => reader.take(frames * info.channels as u64 * sample_format.bytes() as u64).read_to_end(&mut bytes)?,
+        None => reader.read_to_end(&mut bytes)?,
+    };
+    let samples = decode_samples(&bytes, sample_format)?;
+    Ok((deinterleave(samples, info.channels as usize)?, info.fs))
+}
+
+/// Properties of an audio file read from its header, without decoding the samples.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AudioInfo {
+    /// Number of channels
+    pub channels: u16,
+    /// Sampling rate in Hz
+    pub fs: u32,
+    /// Number of samples per channel, if the header states it
+    pub frames: Option<u64>,
+}
+
+impl AudioInfo {
+    /// Duration in seconds, if the number of samples is known.
+    pub fn duration(&self) -> Option<f32> {
+        self.frames.map(|frames| frames as f32 / self.fs as f32)
+    }
+
+    /// Rough estimate of the peak memory in bytes needed to compute a novelty function, if the
+    /// number of samples is known.
+    ///
+    /// This counts the decoded samples of all channels and three buffers of the length of
+    /// the signal for the analyzed signal and intermediate results, all as 32-bit floats.
+    pub fn estimated_memory(&self) -> Option<u64> {
+        self.frames.map(|frames| frames * (self.channels as u64 + 3) * 4)
+    }
+}
+
+/// Reads the header of a WAV file from a stream up to the start of the samples.
+///
+/// The number of samples is unknown for a `data` chunk with a size of 0 or `0xFFFFFFFF`,
+/// unless a preceding `ds64` chunk of an RF64 or BW64 file states it, see [`read_wav`].
+///
+/// # Returns
+/// - The sample format and the properties of the file
+///
+/// # Errors
+/// Returns an error if the stream can't be read, is not a WAV file, has a `fmt ` or `ds64`
+/// chunk larger than 64 KiB, or uses an unsupported format tag.
+pub fn read_wav_header<R: Read>(reader: &mut R) -> anyhow::Result<(SampleFormat, AudioInfo)> {
+    let mut header = [0u8; 12];
+    reader.read_exact(&mut header)?;
+    if !matches!(&header[0..4], b"RIFF" | b"RF64" | b"BW64") || &header[8..12] != b"WAVE" {
+        anyhow::bail!("Input is not a WAV file");
+    }
+
+    let mut format = None;
+    let mut data_size = None;
+    loop {
+        let mut chunk_header = [0u8; 8];
+        reader.read_exact(&mut chunk_header).map_err(|_| anyhow::anyhow!("WAV stream ended before the data chunk"))?;
+        let size = u32::from_le_bytes([chunk_header[4], chunk_header[5], chunk_header[6], chunk_header[7]]);
+
+        match &chunk_header[0..4] {
+            b"fmt " => {
+                let chunk = read_header_chunk(reader, size as u64 + (size as u64 & 1), "WAV fmt")?;
+                format = Some(parse_fmt(&chunk)?);
+            }
+            b"ds64" => {
+                // 64-bit sizes of the RIFF and data chunks, followed by the sample count and
+                // a table of other large chunks
+                let chunk = read_header_chunk(reader, size as u64 + (size as u64 & 1), "WAV ds64")?;
+                if chunk.len() < 16 {
+                    anyhow::bail!("WAV ds64 chunk is too short");
+                }
+                data_size = Some(u64::from_le_bytes(chunk[8..16].try_into().expect("Slice of 8 bytes")));
+            }
+            b"data" => {
+                let (sample_format, channels, fs) = format.ok_or_else(|| anyhow::anyhow!("WAV data chunk precedes the fmt chunk"))?;
+                let frame_bytes = (channels as u64 * sample_format.bytes() as u64).max(1);
+                let size = match data_size {
+                    Some(data_size) if size == u32::MAX => data_size,
+                    _ => size as u64,
+                };
+                let frames = (size != 0 && size != u32::MAX as u64).then(|| size / frame_bytes);
+                return Ok((sample_format, AudioInfo { channels, fs, frames }));
+            }
+            _ => {
+                // skip other chunks, which are padded to an even size
+                let skip = size as u64 + (size as u64 & 1);
+                std::io::copy(&mut (&mut *reader).take(skip), &mut std::io::sink())?;
+            }
+        }
+    }
+}
+
+/// Reads a header chunk of `size` bytes, which must not exceed [`MAX_HEADER_CHUNK_SIZE`].
+pub(crate) fn read_header_chunk<R: Read>(reader: &mut R, size: u64, name: &str) -> anyhow::Result<Vec<u8>> {
+    if size > MAX_HEADER_CHUNK_SIZE {
+        anyhow::bail!("{} chunk of {} bytes is larger than any valid one", name, size);
+    }
+    let mut chunk = vec![0u8; size as usize];
+    reader.read_exact(&mut chunk)?;
+    Ok(chunk)
+}
+
+/// Reads a WAV file with any number of channels from memory, recovering the samples of a
+/// malformed file instead of failing like [`read_wav_channels`].
+///
+/// Field recorders that lose power or have buggy firmware often write slightly broken files.
+/// This works around
+/// - a missing or damaged `RIFF`/`WAVE` header,
+/// - junk between chunks and wrong chunk sizes, by searching for the next `fmt ` or `data`
+///   chunk after the last intact chunk header, which also covers missing pad bytes and
+///   chunks whose size runs past the end of the file,
+/// - a `data` chunk whose size exceeds the file, e.g. after an interrupted recording, by
+///   reading the samples up to the end, and
+/// - an incomplete last frame, which is dropped.
+///
+/// # Returns
+/// - The samples with shape (channels, samples), the sampling rate, and a description of
+///   each malformation that was worked around
+///
+/// # Errors
+/// Returns an error if no `fmt ` chunk precedes a `data` chunk, or for unsupported sample
+/// formats.
+pub fn read_wav_lenient(bytes: &[u8]) -> anyhow::Result<(Array2<f32>, u32, Vec<String>)> {
+    let mut repairs = Vec::new();
+    let mut position = 12;
+    if bytes.len() < 12 || !matches!(&bytes[0..4], b"RIFF" | b"RF64" | b"BW64") || &bytes[8..12] != b"WAVE" {
+        repairs.push("missing RIFF/WAVE header".to_string());
+        position = find_chunk(bytes, 0).ok_or_else(|| anyhow::anyhow!("No fmt chunk found in WAV file"))?;
+    }
+
+    let mut format = None;
+    // start of the body of the last intact chunk, from which to search after a corrupt one
+    let mut body = position;
+    let data = loop {
+        let Some(chunk_header) = bytes.get(position..position + 8) else {
+            anyhow::bail!("No data chunk found in WAV file");
+        };
+        let size = u32::from_le_bytes([chunk_header[4], chunk_header[5], chunk_header[6], chunk_header[7]]);
+        let start = position + 8;
+        let available = bytes.len() - start;
+
+        match &chunk_header[0..4] {
+            b"fmt " => {
+                format = Some(parse_fmt(&bytes[start..start + (size as usize).min(available)])?);
+                (body, position) = (start, next_chunk(bytes, position, size, &mut repairs)?);
+            }
+            b"data" => {
+                let size = match size as usize {
+                    0 | 0xFFFFFFFF => available,
+                    size if size > available => {
+                        repairs.push(format!("data chunk is truncated to {} of {} bytes", available, size));
+                        available
+                    }
+                    size => size,
+                };
+                break &bytes[start..start + size];
+            }
+            id if id.iter().all(|byte| byte.is_ascii_graphic() || *byte == b' ') => {
+                // skip other chunks, which are padded to an even size
+                (body, position) = (start, next_chunk(bytes, position, size, &mut repairs)?);
+            }
+            _ => {
+                let next = find_chunk(bytes, body)
+                    .ok_or_else(|| anyhow::anyhow!("No data chunk found in WAV file after byte {}", position))?;
+                repairs.push(format!("skipped a corrupt chunk at byte {}", position));
+                position = next;
+            }
+        }
+    };
+
+    let (sample_format, channels, fs) = format.ok_or_else(|| anyhow::anyhow!("WAV data chunk precedes the fmt chunk"))?;
+    let frame_bytes = (channels as usize * sample_format.bytes()).max(1);
+    if data.len() % frame_bytes != 0 {
+        repairs.push(format!("dropped an incomplete frame of {} bytes at the end", data.len() % frame_bytes));
+    }
+    let samples = decode_samples(data, sample_format)?;
+    Ok((deinterleave(samples, channels as usize)?, fs, repairs))
+}
+
+/// Returns the position of the chunk header after the chunk at `position` with a body of
+/// `size` bytes, or the next `fmt ` or `data` chunk in its body if the size runs past the end
+/// of the file, e.g. after a damaged size field, since the `data` chunk always comes last.
+fn next_chunk(bytes: &[u8], position: usize, size: u32, repairs: &mut Vec<String>) -> anyhow::Result<usize> {
+    let next = position + 8 + size as usize + (size as usize & 1);
+    if next + 8 <= bytes.len() {
+        return Ok(next);
+    }
+    let id = String::from_utf8_lossy(&bytes[position..position + 4]).into_owned();
+    repairs.push(format!("{} chunk at byte {} runs past the end of the file", id.trim_end(), position));
+    find_chunk(bytes, position + 8).ok_or_else(|| anyhow::anyhow!("No data chunk found in WAV file after byte {}", position))
+}
+
+/// Returns the position of the next `fmt ` or `data` chunk header from `from` on.
+fn find_chunk(bytes: &[u8], from: usize) -> Option<usize> {
+    let index = bytes.get(from..)?.windows(4).position(|id| id == b"fmt " || id == b"data")?;
+    Some(from + index)
+}
+
+/// Splits interleaved samples into channels with shape (channels, samples).
+///
+/// Samples of an incomplete last frame are dropped.
+///
+/// # Errors
+/// Returns an error if the number of channels is zero.
+pub fn deinterleave(mut samples: Vec<f32>, channels: usize) -> anyhow::Result<Array2<f32>> {
+    if channels == 0 {
+        anyhow::bail!("Audio has no channels");
+    }
+    let frames = samples.len() / channels;
+    samples.truncate(frames * channels);
+    Ok(Array2::from_shape_vec((frames, channels), samples)?.reversed_axes().as_standard_layout().into_owned())
+}
+
+/// Parses a `fmt ` chunk into the sample format, number of channels, and sampling rate.
+fn parse_fmt(chunk: &[u8]) -> anyhow::Result<(SampleFormat, u16, u32)> {
+    if chunk.len() < 16 {
+        anyhow::bail!("WAV fmt chunk is too short");
+    }
+    let u16_at = |i: usize| u16::from_le_bytes([chunk[i], chunk[i + 1]]);
+    let mut format_tag = u16_at(0);
+    let channels = u16_at(2);
+    let fs = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+    let bits = u16_at(14);
+
+    // WAVE_FORMAT_EXTENSIBLE stores the actual format in the first bytes of its subformat GUID
+    if format_tag == 0xFFFE && chunk.len() >= 26 {
+        format_tag = u16_at(24);
+    }
+    let sample_format = match format_tag {
+        1 => SampleFormat::Int(bits),
+        3 => SampleFormat::Float(bits),
+        format_tag => anyhow::bail!("Unsupported WAV format tag {:#06x}", format_tag),
+    };
+
+    Ok((sample_format, channels, fs))
+}
@@ -0,0 +1,93 @@
+use std::path::Path;
+
+use ndarray::Array1;
+use rhai::{Array, Dynamic, Engine, Scope, AST, FLOAT};
+
+use crate::onsets::Onset;
+
+/// A post-processing script in the Rhai language, to transform novelty functions or filter
+/// onsets without recompiling.
+///
+/// The script may define either or both of two functions:
+///
+/// ```rhai
+/// // returns the transformed novelty values, an array of the same length
+/// fn novelty(values, fs_feature) { values }
+///
+/// // returns whether to keep an onset at `time` seconds with the novelty value `strength`
+/// fn onset(time, strength) { strength > 0.2 }
+/// ```
+///
+/// Numbers are passed as Rhai floats. A script without one of the functions leaves the
+/// corresponding values unchanged.
+pub struct PostScript {
+    engine: Engine,
+    ast: AST,
+}
+
+impl PostScript {
+    /// Compiles a script from a file.
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be read or doesn't compile.
+    pub fn load(path: &Path) -> anyhow::Result<PostScript> {
+        let source = std::fs::read_to_string(path)?;
+        let engine = Engine::new();
+        let ast = engine.compile(source).map_err(|error| anyhow::anyhow!("Can't compile {}: {}", path.display(), error))?;
+        Ok(PostScript { engine, ast })
+    }
+
+    /// Whether the script defines a function with the given name and number of parameters.
+    fn defines(&self, name: &str, num_params: usize) -> bool {
+        self.ast.iter_functions().any(|function| function.name == name && function.params.len() == num_params)
+    }
+
+    /// Transforms a novelty function with the `novelty` function of the script, if it has one.
+    ///
+    /// # Errors
+    /// Returns an error if the script fails or doesn't return an array of numbers of the same
+    /// length.
+    pub fn transform_novelty(&self, values: &Array1<f32>, fs_feature: f32) -> anyhow::Result<Array1<f32>> {
+        if !self.defines("novelty", 2) {
+            return Ok(values.clone());
+        }
+        let input: Array = values.iter().map(|&v| Dynamic::from_float(v as FLOAT)).collect();
+        let output: Array = self
+            .engine
+            .call_fn(&mut Scope::new(), &self.ast, "novelty", (input, fs_feature as FLOAT))
+            .map_err(|error| anyhow::anyhow!("Script function novelty failed: {}", error))?;
+        if output.len() != values.len() {
+            anyhow::bail!("Script function novelty returned {} values, expected {}", output.len(), values.len());
+        }
+        output
+            .into_iter()
+            .map(|value| {
+                let number = value.as_float().or_else(|_| value.as_int().map(|value| value as FLOAT));
+                number.map(|number| number as f32).map_err(|kind| anyhow::anyhow!("Script function novelty returned a {} instead of a number", kind))
+            })
+            .collect::<anyhow::Result<Vec<f32>>>()
+            .map(Array1::from_vec)
+    }
+
+    /// Keeps the onsets for which the `onset` function of the script returns true, or all
+    /// onsets if it has no such function.
+    ///
+    /// # Errors
+    /// Returns an error if the script fails or doesn't return a boolean.
+    pub fn filter_onsets(&self, onsets: Vec<Onset>) -> anyhow::Result<Vec<Onset>> {
+        if !self.defines("onset", 2) {
+            return Ok(onsets);
+        }
+        let mut kept = Vec::with_capacity(onsets.len());
+        for onset in onsets {
+            let keep: bool = self
+                .engine
+                .call_fn(&mut Scope::new(), &self.ast, "onset", (onset.time as FLOAT, onset.strength as FLOAT))
+                .map_err(|error| anyhow::anyhow!("Script function onset failed: {}", error))?;
+            if keep {
+                kept.push(onset);
+            }
+        }
+        Ok(kept)
+    }
+}
@@ -0,0 +1,38 @@
+use ndarray::{s, Array1};
+
+/// A section of a recording between two boundaries.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Segment {
+    /// Start of the segment in seconds
+    pub start: f32,
+    /// End of the segment in seconds
+    pub end: f32,
+    /// Samples of the segment
+    pub samples: Array1<f32>,
+}
+
+/// Cuts a recording at the given boundary times in seconds.
+///
+/// Each segment starts at a boundary and ends at the next one or at the end of the
+/// recording. Audio before the first boundary is not part of any segment, so boundaries
+/// from onset detection yield one segment per onset, e.g. one sample per drum hit. Include
+/// 0 in the boundaries to keep the beginning. Empty segments are skipped.
+pub fn split_audio(audio_array: &Array1<f32>, fs: u32, boundaries: &[f32]) -> Vec<Segment> {
+    let mut positions: Vec<usize> = boundaries
+        .iter()
+        .map(|time| ((time.max(0.0) * fs as f32).round() as usize).min(audio_array.len()))
+        .collect();
+    positions.sort_unstable();
+    positions.dedup();
+    positions.push(audio_array.len());
+
+    positions
+        .windows(2)
+        .filter(|pair| pair[1] > pair[0])
+        .map(|pair| Segment {
+            start: pair[0] as f32 / fs as f32,
+            end: pair[1] as f32 / fs as f32,
+            samples: audio_array.slice(s![pair[0]..pair[1]]).to_owned(),
+        })
+        .collect()
+}
@@ -0,0 +1,60 @@
+use std::f64::consts::PI;
+
+use ndarray::Array1;
+use ndarray_stats::QuantileExt;
+
+use crate::novelty::NoveltyCurve;
+
+/// Carrier signal that is amplitude-modulated by the novelty function.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Carrier {
+    /// Sine tone with the given frequency in Hz
+    Tone(f32),
+    /// White noise
+    Noise,
+}
+
+/// Renders a novelty function as audio at the sampling rate `fs`.
+///
+/// The novelty function is upsampled by linear interpolation, scaled to a maximum of one,
+/// and used as the amplitude envelope of the carrier. Frame `m` is placed at time
+/// `m / fs_feature`, so the output is aligned with the analyzed audio. Sample positions and
+/// phases are computed in double precision, which keeps them exact for recordings of many hours.
+pub fn sonify_novelty(novelty: &NoveltyCurve, fs: u32, carrier: Carrier) -> Array1<f32> {
+    let values = &novelty.values;
+    let samples_per_frame = fs as f64 / novelty.fs_feature as f64;
+    let num_samples = (values.len() as f64 * samples_per_frame).round() as usize;
+
+    let max_value = values.max().copied().unwrap_or(0.0);
+    let scale = if max_value > 0.0 { 1.0 / max_value } else { 0.0 };
+
+    // xorshift generator, which is plenty for a noise carrier
+    let mut state: u32 = 0x9E37_79B9;
+    let mut next_noise = move || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state as f32 / u32::MAX as f32 * 2.0 - 1.0
+    };
+
+    Array1::from_shape_fn(num_samples, |n| {
+        // linear interpolation of the envelope between neighboring frames
+        let position = n as f64 / samples_per_frame;
+        let frame = position.floor() as usize;
+        let fraction = (position - frame as f64) as f32;
+        let current = values.get(frame).copied().unwrap_or(0.0);
+        let next = values.get(frame + 1).copied().unwrap_or(current);
+        let envelope = (current + fraction * (next - current)) * scale;
+
+        let carrier_value = match carrier {
+            // the number of periods is wrapped to [0, 1) before scaling by 2π, so the phase
+            // doesn't lose precision as n grows
+            Carrier::Tone(frequency) => {
+                let periods = (frequency as f64 * n as f64 / fs as f64).fract();
+                (2.0 * PI * periods).sin() as f32
+            }
+            Carrier::Noise => next_noise(),
+        };
+        envelope * carrier_value
+    })
+}
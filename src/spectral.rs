@@ -0,0 +1,171 @@
+use std::f32::consts::PI;
+
+use hann_rs::get_hann_window;
+use ndarray::{s, Array1, Array2};
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+
+use crate::compression::Compression;
+use crate::novelty::normalize_max;
+
+/// Computes the magnitude spectrogram of a mono audio signal.
+///
+/// Frames are laid out as for the energy-based novelty function: frame `m` is centered on
+/// sample `m * hop_length`, samples outside the signal are zeros, and there are
+/// `ceil(len / hop_length)` frames. Each frame is weighted with a Hann window of
+/// `window_length` samples, which is also the FFT size.
+///
+/// # Returns
+/// - Magnitudes with shape (`window_length / 2 + 1` frequency bins, number of frames)
+pub fn magnitude_spectrogram(audio_array: &Array1<f32>, window_length: usize, hop_length: usize) -> Array2<f32> {
+    let window = get_hann_window(window_length).expect("Failed to get the Hann window");
+    let num_frames = audio_array.len().div_ceil(hop_length);
+    stft_magnitude(audio_array, &window, hop_length, num_frames)
+}
+
+/// Computes the magnitude spectrogram with an arbitrary window and number of frames.
+///
+/// Frame `m` is centered on sample `m * hop_length` and samples outside the signal are
+/// zeros. The FFT size equals the window length.
+///
+/// # Returns
+/// - Magnitudes with shape (`window.len() / 2 + 1` frequency bins, `num_frames`)
+pub fn stft_magnitude(audio_array: &Array1<f32>, window: &[f32], hop_length: usize, num_frames: usize) -> Array2<f32> {
+    let window_length = window.len();
+    let num_bins = window_length / 2 + 1;
+    let half_window = window_length / 2;
+
+    let fft = FftPlanner::<f32>::new().plan_fft_forward(window_length);
+    let mut buffer = vec![Complex::new(0.0, 0.0); window_length];
+    let mut spectrogram = Array2::zeros((num_bins, num_frames));
+
+    for frame in 0..num_frames {
+        let start = frame * hop_length;
+        for (j, (value, w)) in buffer.iter_mut().zip(window.iter()).enumerate() {
+            let sample = (start + j)
+                .checked_sub(half_window)
+                .and_then(|n| audio_array.get(n))
+                .copied()
+                .unwrap_or(0.0);
+            *value = Complex::new(sample * w, 0.0);
+        }
+        fft.process(&mut buffer);
+        for (k, value) in buffer.iter().take(num_bins).enumerate() {
+            spectrogram[[k, frame]] = value.norm();
+        }
+    }
+
+    spectrogram
+}
+
+/// Returns a periodic Hann window, as used by scipy and librosa for spectral analysis.
+pub fn periodic_hann(window_length: usize) -> Vec<f32> {
+    (0..window_length)
+        .map(|n| 0.5 - 0.5 * (2.0 * PI * n as f32 / window_length as f32).cos())
+        .collect()
+}
+
+/// Returns the center frequency in Hz of each bin of a spectrogram with the given FFT size.
+pub fn bin_frequencies(window_length: usize, fs: u32) -> Array1<f32> {
+    Array1::from_shape_fn(window_length / 2 + 1, |k| k as f32 * fs as f32 / window_length as f32)
+}
+
+/// Computes a novelty function from the increase of the log-compressed magnitude spectrum.
+///
+/// The magnitude spectrogram uses the same framing as [`magnitude_spectrogram`]. Each bin is
+/// compressed, differentiated, and half-wave rectified, and the bins are summed. If
+/// `local_average` is positive, a moving average over `2 * local_average + 1` frames is
+/// subtracted and the result is half-wave rectified again to emphasize local peaks.
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples
+/// - `fs`: Sampling rate of the audio
+/// - `window_length`: Size of the analysis window and FFT
+/// - `hop_length`: Step size between successive frames
+/// - `compression`: Compression function applied to the magnitudes
+/// - `gamma`: Parameter of the compression function
+/// - `local_average`: Half length of the local average in frames (0 disables it)
+/// - `norm`: Whether to normalize the output between 0 and 1
+///
+/// # Returns
+/// - A tuple of the novelty function and its effective sampling rate
+///
+/// # Errors
+/// Returns an error if normalization fails.
+#[allow(clippy::too_many_arguments)]
+pub fn novelty_spectrum(audio_array: &Array1<f32>, fs: u32, window_length: u32, hop_length: u32, compression: Compression, gamma: f32, local_average: u32, norm: bool) -> anyhow::Result<(Array1<f32>, f32)> {
+    let fs_feature = (fs as f32) / (hop_length as f32);
+
+    let spectrogram = magnitude_spectrogram(audio_array, window_length as usize, hop_length as usize);
+    let novelty = spectral_flux(spectrogram, compression, gamma, local_average as usize, norm)?;
+
+    Ok((novelty, fs_feature))
+}
+
+/// Computes the spectral novelty function exactly as `libfmp.c6.compute_novelty_spectrum`.
+///
+/// In contrast to [`novelty_spectrum`], the spectrogram follows `librosa.stft` with
+/// `center=True`: a periodic Hann window, zero padding, and `1 + len / hop_length` frames.
+/// The magnitudes are compressed with `log(1 + gamma * |X|)`.
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples
+/// - `fs`: Sampling rate of the audio
+/// - `window_length`: Size of the analysis window and FFT (FMP default: 1024)
+/// - `hop_length`: Step size between successive frames (FMP default: 256)
+/// - `gamma`: Parameter of the logarithmic compression (FMP default: 100.0)
+/// - `local_average`: Half length of the local average in frames (FMP default: 10)
+/// - `norm`: Whether to normalize the output between 0 and 1
+///
+/// # Returns
+/// - A tuple of the novelty function and its effective sampling rate
+///
+/// # Errors
+/// Returns an error if normalization fails.
+pub fn novelty_spectrum_fmp(audio_array: &Array1<f32>, fs: u32, window_length: u32, hop_length: u32, gamma: f32, local_average: u32, norm: bool) -> anyhow::Result<(Array1<f32>, f32)> {
+    let fs_feature = (fs as f32) / (hop_length as f32);
+
+    let num_frames = 1 + audio_array.len() / hop_length as usize;
+    let spectrogram = stft_magnitude(audio_array, &periodic_hann(window_length as usize), hop_length as usize, num_frames);
+    let novelty = spectral_flux(spectrogram, Compression::Log, gamma, local_average as usize, norm)?;
+
+    Ok((novelty, fs_feature))
+}
+
+/// Sums the rectified increase of the compressed spectrogram over all bins, with a trailing
+/// zero, and optionally subtracts the local average.
+fn spectral_flux(mut spectrogram: Array2<f32>, compression: Compression, gamma: f32, local_average: usize, norm: bool) -> anyhow::Result<Array1<f32>> {
+    spectrogram.mapv_inplace(|v| compression.apply(v, gamma));
+
+    let num_frames = spectrogram.ncols();
+    let mut novelty = Array1::zeros(num_frames);
+    for m in 0..num_frames.saturating_sub(1) {
+        let diff = &spectrogram.column(m + 1) - &spectrogram.column(m);
+        novelty[m] = diff.mapv(|v| v.max(0.0)).sum();
+    }
+
+    if local_average > 0 {
+        let average = compute_local_average(&novelty, local_average);
+        novelty = (&novelty - &average).mapv(|v| v.max(0.0));
+    }
+
+    if norm {
+        normalize_max(&mut novelty)?;
+    }
+
+    Ok(novelty)
+}
+
+/// Computes the moving average over `2 * m + 1` values around each position.
+///
+/// As in the FMP notebooks, values outside the array count as zeros, so the average
+/// decreases towards the edges.
+fn compute_local_average(values: &Array1<f32>, m: usize) -> Array1<f32> {
+    let len = values.len();
+    let divisor = (2 * m + 1) as f32;
+    Array1::from_shape_fn(len, |n| {
+        let start = n.saturating_sub(m);
+        let end = (n + m + 1).min(len);
+        values.slice(s![start..end]).sum() / divisor
+    })
+}
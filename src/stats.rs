@@ -0,0 +1,36 @@
+use ndarray::Array1;
+
+/// Basic statistics of an audio signal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SignalStats {
+    /// Sampling rate in Hz
+    pub sample_rate: u32,
+    /// Number of samples
+    pub num_samples: usize,
+    /// Duration in seconds
+    pub duration: f32,
+    /// Maximum absolute sample value
+    pub peak: f32,
+    /// Root mean square of the samples
+    pub rms: f32,
+    /// Mean of the samples
+    pub dc_offset: f32,
+}
+
+/// Computes basic statistics of a mono audio signal.
+pub fn signal_stats(audio_array: &Array1<f32>, fs: u32) -> SignalStats {
+    let num_samples = audio_array.len();
+    let peak = audio_array.iter().fold(0.0_f32, |acc, v| acc.max(v.abs()));
+    let rms = audio_array.mapv(|v| v * v).mean().unwrap_or(0.0).sqrt();
+    let dc_offset = audio_array.mean().unwrap_or(0.0);
+
+    SignalStats {
+        sample_rate: fs,
+        num_samples,
+        duration: num_samples as f32 / fs as f32,
+        peak,
+        rms,
+        dc_offset,
+    }
+}
@@ -0,0 +1,202 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Number of temporary files staged by this process, to give each a unique name.
+static STAGED_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether a path is an object storage URL, i.e. starts with `s3://` (Amazon S3 and
+/// compatible stores) or `gs://` (Google Cloud Storage).
+pub fn is_object_url(path: &str) -> bool {
+    path.starts_with("s3://") || path.starts_with("gs://")
+}
+
+/// Whether a local file or an object exists.
+///
+/// # Errors
+/// Returns an error if the object store can't be reached, or for object storage URLs
+/// without the `cloud` feature.
+pub fn path_exists(path: &str) -> anyhow::Result<bool> {
+    if is_object_url(path) {
+        cloud::head(path)
+    } else {
+        Ok(Path::new(path).exists())
+    }
+}
+
+/// Reads the contents of a local file or an object.
+///
+/// # Errors
+/// Returns an error if reading fails, or for object storage URLs without the `cloud` feature.
+pub fn read_path(path: &str) -> anyhow::Result<Vec<u8>> {
+    if is_object_url(path) {
+        cloud::get(path)
+    } else {
+        Ok(std::fs::read(path)?)
+    }
+}
+
+/// Writes the contents of a local file or an object, replacing it if it exists.
+///
+/// # Errors
+/// Returns an error if writing fails, or for object storage URLs without the `cloud` feature.
+pub fn write_path(path: &str, contents: &[u8]) -> anyhow::Result<()> {
+    if is_object_url(path) {
+        cloud::put(path, contents.to_vec())
+    } else {
+        Ok(std::fs::write(path, contents)?)
+    }
+}
+
+/// Returns a unique path in the temporary directory that keeps the file name of `path`, so
+/// formats can still be told by their extension.
+fn temporary_path(path: &str) -> PathBuf {
+    let file_name = Path::new(path).file_name().unwrap_or_default().to_string_lossy();
+    let index = STAGED_FILES.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!("novelty_rust-{}-{}-{}", std::process::id(), index, file_name))
+}
+
+/// An input available as local file: the path itself, or a temporary copy of an object that
+/// is removed when this is dropped.
+pub struct StagedInput {
+    path: String,
+    temporary: bool,
+}
+
+impl StagedInput {
+    /// Downloads an object to a temporary file, or uses a local path as is.
+    ///
+    /// # Errors
+    /// Returns an error if the download fails, or for object storage URLs without the
+    /// `cloud` feature.
+    pub fn fetch(path: &str) -> anyhow::Result<StagedInput> {
+        if !is_object_url(path) {
+            return Ok(StagedInput { path: path.to_string(), temporary: false });
+        }
+        let local = temporary_path(path);
+        std::fs::write(&local, cloud::get(path)?)?;
+        Ok(StagedInput { path: local.to_string_lossy().into_owned(), temporary: true })
+    }
+
+    /// Path of the local file.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl Drop for StagedInput {
+    fn drop(&mut self) {
+        if self.temporary {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// An output written to a local file: the path itself, or a temporary file that
+/// [`StagedOutput::upload`] copies to an object. The temporary file is removed when this is
+/// dropped.
+pub struct StagedOutput {
+    path: String,
+    url: Option<String>,
+}
+
+impl StagedOutput {
+    /// Prepares a temporary file for an object that doesn't exist yet, or uses a local path
+    /// as is.
+    ///
+    /// # Errors
+    /// Returns an error if the object already exists or the store can't be reached, or for
+    /// object storage URLs without the `cloud` feature.
+    pub fn create(path: &str) -> anyhow::Result<StagedOutput> {
+        if !is_object_url(path) {
+            return Ok(StagedOutput { path: path.to_string(), url: None });
+        }
+        if cloud::head(path)? {
+            anyhow::bail!("Output path must not already exist!");
+        }
+        let local = temporary_path(path);
+        Ok(StagedOutput { path: local.to_string_lossy().into_owned(), url: Some(path.to_string()) })
+    }
+
+    /// Path of the local file to write.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Uploads the written file to its object, if it has one.
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be read or the upload fails.
+    pub fn upload(self) -> anyhow::Result<()> {
+        match &self.url {
+            Some(url) => cloud::put(url, std::fs::read(&self.path)?),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for StagedOutput {
+    fn drop(&mut self) {
+        if self.url.is_some() {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Object store operations, blocking on a single-threaded runtime per call. Credentials and
+/// regions are taken from the usual environment variables, e.g. `AWS_ACCESS_KEY_ID`,
+/// `AWS_REGION`, or `GOOGLE_APPLICATION_CREDENTIALS`.
+#[cfg(feature = "cloud")]
+mod cloud {
+    use object_store::path::Path as ObjectPath;
+    use object_store::{parse_url_opts, ObjectStore, PutPayload};
+
+    /// Opens the store of a URL and returns it with the path of the object.
+    fn open(url: &str) -> anyhow::Result<(Box<dyn ObjectStore>, ObjectPath)> {
+        let url = url::Url::parse(url)?;
+        let options = std::env::vars().map(|(key, value)| (key.to_ascii_lowercase(), value));
+        Ok(parse_url_opts(&url, options)?)
+    }
+
+    fn block_on<F: std::future::Future>(future: F) -> anyhow::Result<F::Output> {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+        Ok(runtime.block_on(future))
+    }
+
+    pub(super) fn head(url: &str) -> anyhow::Result<bool> {
+        let (store, path) = open(url)?;
+        match block_on(store.head(&path))? {
+            Ok(_) => Ok(true),
+            Err(object_store::Error::NotFound { .. }) => Ok(false),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub(super) fn get(url: &str) -> anyhow::Result<Vec<u8>> {
+        let (store, path) = open(url)?;
+        let bytes = block_on(async { store.get(&path).await?.bytes().await })??;
+        Ok(bytes.to_vec())
+    }
+
+    pub(super) fn put(url: &str, contents: Vec<u8>) -> anyhow::Result<()> {
+        let (store, path) = open(url)?;
+        block_on(store.put(&path, PutPayload::from(contents)))??;
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "cloud"))]
+mod cloud {
+    const REQUIRES_FEATURE: &str = "Object storage URLs require the cloud feature!";
+
+    pub(super) fn head(_url: &str) -> anyhow::Result<bool> {
+        anyhow::bail!(REQUIRES_FEATURE)
+    }
+
+    pub(super) fn get(_url: &str) -> anyhow::Result<Vec<u8>> {
+        anyhow::bail!(REQUIRES_FEATURE)
+    }
+
+    pub(super) fn put(_url: &str, _contents: Vec<u8>) -> anyhow::Result<()> {
+        anyhow::bail!(REQUIRES_FEATURE)
+    }
+}
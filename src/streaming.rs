@@ -0,0 +1,201 @@
+use std::collections::VecDeque;
+
+use hann_rs::get_hann_window;
+
+use crate::compression::Compression;
+use crate::config::{Method, NoveltyConfig};
+use crate::novelty::Aggregation;
+use crate::postprocess::AttackRelease;
+use crate::weighting::Weighting;
+
+/// Computes a novelty function incrementally from blocks of audio as they arrive.
+///
+/// Frames are laid out exactly as in [`crate::novelty_energy`]: frame `m` is centered on
+/// sample `m * hop_length`, and samples before the start of the stream are treated as zeros.
+/// Since the novelty value of a frame depends on the following frame, values are returned
+/// one frame (plus half a window) behind the most recent input.
+///
+/// Normalization needs the maximum over the whole signal and is therefore not applied,
+/// regardless of [`NoveltyConfig::norm`]. Attack/release smoothing is causal and applied
+/// as in the batch computation.
+///
+/// ```no_run
+/// use novelty_rust::{NoveltyConfig, StreamingNovelty};
+///
+/// let config = NoveltyConfig::energy().window(1024).hop(256).build()?;
+/// let mut streaming = StreamingNovelty::new(&config, 22050)?;
+/// for block in [[0.0_f32; 512], [0.5_f32; 512]] {
+///     for (time, value) in streaming.push_samples(&block) {
+///         println!("{time:.05},{value:.05}");
+///     }
+/// }
+/// let remaining = streaming.finish();
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+///
+/// With the `serde` feature, the detector can be serialized between blocks to checkpoint
+/// long computations and resume them later.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StreamingNovelty {
+    /// Squared analysis window
+    kernel: Vec<f32>,
+    hop_length: usize,
+    compression: Compression,
+    gamma: f32,
+    fs: u32,
+    /// Offset of the window center within the kernel
+    center: usize,
+    /// Squared input samples that are still needed for upcoming frames
+    buffer: VecDeque<f32>,
+    /// Absolute sample index of `buffer[0]`
+    buffer_start: usize,
+    /// Total number of samples pushed so far
+    num_samples: usize,
+    /// Index of the next frame whose energy is computed
+    next_frame: usize,
+    /// Compressed energy of the previous frame
+    prev_energy: Option<f32>,
+    /// Attack/release smoothing of the novelty values, if any
+    smoothing: Option<AttackRelease>,
+    /// Previous smoothed novelty value
+    smoothed: f32,
+}
+
+impl StreamingNovelty {
+    /// Creates a streaming novelty detector for audio with sampling rate `fs`.
+    ///
+    /// # Errors
+    /// Returns an error if the configuration is invalid, or if the configured method,
+    /// aggregation, weighting, noise gate, harmonic-percussive separation, resolutions,
+    /// median filter, or post-processing script can't be computed incrementally.
+    pub fn new(config: &NoveltyConfig, fs: u32) -> anyhow::Result<Self> {
+        config.validate()?;
+        if config.method != Method::Energy {
+            anyhow::bail!("Only energy-based novelty can be computed incrementally!");
+        }
+        if config.aggregation != Aggregation::Sum {
+            anyhow::bail!("Only the windowed sum of the energy can be computed incrementally!");
+        }
+        if config.weighting != Weighting::None {
+            anyhow::bail!("Frequency weighting can't be applied incrementally!");
+        }
+        if config.noise_gate.is_some() {
+            anyhow::bail!("The noise gate needs the whole signal and can't be applied incrementally!");
+        }
+        if config.hpss.is_some() {
+            anyhow::bail!("Harmonic-percussive separation needs the whole signal and can't be applied incrementally!");
+        }
+        if !config.resolutions.is_empty() {
+            anyhow::bail!("Several resolutions can't be computed incrementally!");
+        }
+        if config.median_filter > 1 {
+            anyhow::bail!("The median filter looks ahead and can't be applied incrementally!");
+        }
+        if config.post_script.is_some() {
+            anyhow::bail!("Post-processing scripts need the whole novelty function and can't be applied incrementally!");
+        }
+
+        let hann_window = get_hann_window(config.window_length as usize).expect("Failed to get the Hann window");
+        let kernel: Vec<f32> = hann_window.iter().map(|w| w * w).collect();
+        let center = (kernel.len() - 1) / 2;
+
+        Ok(StreamingNovelty {
+            kernel,
+            hop_length: config.hop_length as usize,
+            compression: config.compression,
+            gamma: config.gamma,
+            fs,
+            center,
+            buffer: VecDeque::new(),
+            buffer_start: 0,
+            num_samples: 0,
+            next_frame: 0,
+            prev_energy: None,
+            smoothing: config.smoothing,
+            smoothed: 0.0,
+        })
+    }
+
+    /// Effective sampling rate of the novelty function.
+    pub fn fs_feature(&self) -> f32 {
+        (self.fs as f32) / (self.hop_length as f32)
+    }
+
+    /// Feeds a block of mono samples and returns all novelty values that became available,
+    /// as pairs of frame time in seconds and novelty value.
+    pub fn push_samples(&mut self, samples: &[f32]) -> Vec<(f32, f32)> {
+        self.buffer.extend(samples.iter().map(|v| v * v));
+        self.num_samples += samples.len();
+
+        let mut output = Vec::new();
+        while self.next_frame * self.hop_length + self.center < self.num_samples {
+            self.process_frame(&mut output);
+        }
+        output
+    }
+
+    /// Signals the end of the stream and returns the remaining novelty values.
+    ///
+    /// Samples after the end of the stream are treated as zeros, and the last frame gets a
+    /// novelty value of zero, matching the batch computation.
+    pub fn finish(mut self) -> Vec<(f32, f32)> {
+        let mut output = Vec::new();
+        while self.next_frame * self.hop_length < self.num_samples {
+            self.process_frame(&mut output);
+        }
+        if self.prev_energy.is_some() {
+            let value = self.smooth(0.0);
+            output.push((self.frame_time(self.next_frame - 1), value));
+        }
+        output
+    }
+
+    /// Computes the energy of the next frame and emits the novelty value of the previous one.
+    fn process_frame(&mut self, output: &mut Vec<(f32, f32)>) {
+        let energy = self.compression.apply(self.frame_energy(self.next_frame * self.hop_length), self.gamma);
+
+        // Half-wave rectified difference to the previous frame
+        if let Some(prev_energy) = self.prev_energy {
+            let value = self.smooth((energy - prev_energy).max(0.0));
+            output.push((self.frame_time(self.next_frame - 1), value));
+        }
+        self.prev_energy = Some(energy);
+        self.next_frame += 1;
+
+        // Drop samples that no upcoming frame needs anymore
+        let first_needed = (self.next_frame * self.hop_length + self.center).saturating_sub(self.kernel.len() - 1);
+        while self.buffer_start < first_needed && self.buffer.pop_front().is_some() {
+            self.buffer_start += 1;
+        }
+    }
+
+    /// Local energy of the frame centered on sample `position`, with zeros outside the stream.
+    fn frame_energy(&self, position: usize) -> f32 {
+        let last = position + self.center;
+        self.kernel
+            .iter()
+            .enumerate()
+            .filter_map(|(k, w)| {
+                let index = last.checked_sub(k)?;
+                if index < self.buffer_start || index >= self.num_samples {
+                    return None;
+                }
+                Some(w * self.buffer[index - self.buffer_start])
+            })
+            .sum()
+    }
+
+    /// Applies the attack/release smoothing, if any, to the next novelty value.
+    fn smooth(&mut self, value: f32) -> f32 {
+        if let Some(smoothing) = self.smoothing {
+            self.smoothed = smoothing.step(self.smoothed, value, self.fs_feature());
+            return self.smoothed;
+        }
+        value
+    }
+
+    fn frame_time(&self, frame: usize) -> f32 {
+        (frame * self.hop_length) as f32 / (self.fs as f32)
+    }
+}
@@ -0,0 +1,109 @@
+use ndarray::{Array1, Array2};
+
+use crate::config::NoveltyConfig;
+use crate::mel::normalized_mel_features;
+use crate::novelty::{normalize_max, NoveltyCurve};
+use crate::onsets::detect_onsets;
+
+/// Default half length of the checkerboard kernel in seconds.
+pub const DEFAULT_KERNEL_SECONDS: f32 = 3.0;
+/// Approximate frame rate of the self-similarity analysis in frames per second.
+const STRUCTURE_RATE: f32 = 10.0;
+/// Standard deviation of the Gaussian taper relative to the kernel half length.
+const KERNEL_VARIANCE: f32 = 0.5;
+
+/// Computes a structural novelty function by correlating a checkerboard kernel along the
+/// diagonal of a self-similarity matrix, following Foote (2000) and the FMP notebooks.
+///
+/// The self-similarity matrix compares compressed mel spectra by cosine similarity. To keep
+/// the cost manageable for long recordings, the spectra are averaged to about 10 frames per
+/// second. The Gaussian-tapered kernel has a half length of `kernel_seconds`, so the peaks
+/// mark changes between sections that last at least this long.
+///
+/// # Errors
+/// Returns an error if normalization fails.
+pub fn structure_novelty(audio_array: &Array1<f32>, fs: u32, config: &NoveltyConfig, kernel_seconds: f32) -> anyhow::Result<NoveltyCurve> {
+    let fs_frames = fs as f32 / config.hop_length as f32;
+    let group = ((fs_frames / STRUCTURE_RATE).round() as usize).max(1);
+    let fs_feature = fs_frames / group as f32;
+
+    let features = downsample(&normalized_mel_features(audio_array, fs, config), group);
+    let num_frames = features.ncols();
+    let half = ((kernel_seconds * fs_feature).round() as usize).max(1);
+    let kernel = checkerboard_kernel(half);
+
+    // similarities S(a, a + d) for the offsets d covered by the kernel
+    let width = 2 * half + 1;
+    let band = Array2::from_shape_fn((num_frames, width), |(a, d)| {
+        if a + d < num_frames {
+            features.column(a).dot(&features.column(a + d))
+        } else {
+            0.0
+        }
+    });
+
+    let mut values = Array1::zeros(num_frames);
+    for m in 0..num_frames {
+        let mut sum = 0.0;
+        for i in 0..width {
+            for j in 0..width {
+                // frames m + i - half and m + j - half, zero outside the recording
+                let (low, high) = (i.min(j), i.max(j));
+                let Some(a) = (m + low).checked_sub(half) else {
+                    continue;
+                };
+                if a + (high - low) < num_frames {
+                    sum += kernel[[i, j]] * band[[a, high - low]];
+                }
+            }
+        }
+        values[m] = sum.max(0.0);
+    }
+    if num_frames > 0 {
+        normalize_max(&mut values)?;
+    }
+
+    Ok(NoveltyCurve { values, fs_feature })
+}
+
+/// Detects structural boundaries as the peaks of [`structure_novelty`] and returns their
+/// times in seconds.
+///
+/// # Errors
+/// Returns an error if the structural novelty computation fails.
+pub fn structure_boundaries(audio_array: &Array1<f32>, fs: u32, config: &NoveltyConfig, kernel_seconds: f32, threshold: f32) -> anyhow::Result<Vec<f32>> {
+    let novelty = structure_novelty(audio_array, fs, config, kernel_seconds)?;
+    Ok(detect_onsets(&novelty, threshold).iter().map(|onset| onset.time).collect())
+}
+
+/// Averages groups of `group` consecutive frames and normalizes the results to unit length.
+fn downsample(features: &Array2<f32>, group: usize) -> Array2<f32> {
+    let num_frames = features.ncols().div_ceil(group);
+    let mut result = Array2::zeros((features.nrows(), num_frames));
+    for (m, mut column) in result.columns_mut().into_iter().enumerate() {
+        for n in m * group..((m + 1) * group).min(features.ncols()) {
+            column += &features.column(n);
+        }
+        let norm = column.dot(&column).sqrt();
+        if norm > 0.0 {
+            column.mapv_inplace(|v| v / norm);
+        }
+    }
+    result
+}
+
+/// Returns a Gaussian-tapered checkerboard kernel of size `2 * half + 1`, normalized to a
+/// total absolute weight of one.
+fn checkerboard_kernel(half: usize) -> Array2<f32> {
+    let width = 2 * half + 1;
+    let offset = |i: usize| i as isize - half as isize;
+    let gaussian = |i: usize| (-0.5 * (offset(i) as f32 / (KERNEL_VARIANCE * half as f32)).powi(2)).exp();
+    let mut kernel = Array2::from_shape_fn((width, width), |(i, j)| {
+        gaussian(i) * gaussian(j) * (offset(i).signum() * offset(j).signum()) as f32
+    });
+    let total: f32 = kernel.iter().map(|v| v.abs()).sum();
+    if total > 0.0 {
+        kernel.mapv_inplace(|v| v / total);
+    }
+    kernel
+}
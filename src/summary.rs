@@ -0,0 +1,53 @@
+use crate::artifacts::{Artifact, ArtifactKind};
+use crate::novelty::NoveltyCurve;
+use crate::onsets::Onset;
+
+/// Aggregate statistics of a novelty function and its onsets, e.g. to catalog a corpus.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Summary {
+    /// Duration covered by the novelty function in seconds
+    pub duration: f32,
+    /// Number of onsets
+    pub onset_count: usize,
+    /// Number of onsets per minute
+    pub onset_density: f32,
+    /// Mean of the novelty function
+    pub mean_novelty: f32,
+    /// Maximum of the novelty function
+    pub max_novelty: f32,
+    /// Estimated global tempo in BPM
+    pub tempo: Option<f32>,
+    /// Total duration of clipped regions in seconds
+    pub clipping: f32,
+    /// Total duration of digital dropouts in seconds
+    pub dropouts: f32,
+}
+
+impl Summary {
+    /// Adds the total durations of clipped regions and dropouts found in the decoded audio,
+    /// see [`crate::detect_artifacts`].
+    pub fn with_artifacts(mut self, artifacts: &[Artifact]) -> Self {
+        let total = |kind: ArtifactKind| artifacts.iter().filter(|artifact| artifact.kind == kind).map(Artifact::duration).sum();
+        self.clipping = total(ArtifactKind::Clipping);
+        self.dropouts = total(ArtifactKind::Dropout);
+        self
+    }
+}
+
+/// Summarizes a novelty function together with its detected onsets and tempo estimate.
+pub fn summarize(novelty: &NoveltyCurve, onsets: &[Onset], tempo: Option<f32>) -> Summary {
+    let duration = novelty.values.len() as f32 / novelty.fs_feature;
+    let onset_density = if duration > 0.0 { onsets.len() as f32 * 60.0 / duration } else { 0.0 };
+
+    Summary {
+        duration,
+        onset_count: onsets.len(),
+        onset_density,
+        mean_novelty: novelty.values.mean().unwrap_or(0.0),
+        max_novelty: novelty.values.fold(0.0_f32, |acc, &v| acc.max(v)),
+        tempo,
+        clipping: 0.0,
+        dropouts: 0.0,
+    }
+}
@@ -0,0 +1,93 @@
+use ndarray::Array1;
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+
+use crate::multires::resample_novelty;
+use crate::novelty::NoveltyCurve;
+
+/// Time offset between two recordings of the same event, see [`novelty_lag`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Lag {
+    /// Time in seconds of the start of the other recording within the reference, negative if
+    /// the other recording started earlier. Subtracting it from a time in the reference gives
+    /// the time of the same event in the other recording.
+    pub lag: f32,
+    /// Cross-correlation at the lag, normalized to -1 to 1, as confidence of the match
+    pub correlation: f32,
+}
+
+/// Estimates the time offset between two recordings of the same event, e.g. from several
+/// cameras or recorders, by cross-correlating their novelty functions.
+///
+/// Novelty functions capture when things happen rather than how they sound, so they match
+/// across microphones with different positions and frequency responses where the signals
+/// themselves don't. The other novelty function is resampled to the feature rate of the
+/// reference, both are made zero-mean, and their cross-correlation is computed with an FFT.
+/// The best lag is refined below one frame by quadratic interpolation.
+///
+/// # Arguments
+/// - `reference`: Novelty function of the reference recording
+/// - `other`: Novelty function of the recording to align
+/// - `max_lag`: Largest offset in seconds to consider in either direction, or `None` for any
+///   offset with some overlap
+///
+/// # Errors
+/// Returns an error if a novelty function is empty or constant, or if `max_lag` is negative.
+pub fn novelty_lag(reference: &NoveltyCurve, other: &NoveltyCurve, max_lag: Option<f32>) -> anyhow::Result<Lag> {
+    if max_lag.is_some_and(|max_lag| max_lag < 0.0) {
+        anyhow::bail!("Maximum lag must not be negative!");
+    }
+    let fs_feature = reference.fs_feature;
+    let length = (other.values.len() as f32 * fs_feature / other.fs_feature).round() as usize;
+    let a = zero_mean(&reference.values);
+    let b = zero_mean(&resample_novelty(other, fs_feature, length));
+    let norm = (a.dot(&a) * b.dot(&b)).sqrt();
+    if a.is_empty() || b.is_empty() || norm == 0.0 {
+        anyhow::bail!("Can't align empty or constant novelty functions");
+    }
+
+    let correlation = cross_correlation(&a, &b);
+    let at = |k: isize| correlation[k.rem_euclid(correlation.len() as isize) as usize] / norm;
+
+    let max_frames = max_lag.map_or(isize::MAX, |max_lag| (max_lag * fs_feature).floor() as isize);
+    let lags = (-(b.len() as isize - 1)).max(-max_frames)..=(a.len() as isize - 1).min(max_frames);
+    let best = lags.clone().max_by(|&k, &l| at(k).total_cmp(&at(l))).unwrap_or(0);
+
+    // vertex of the parabola through the peak and its neighbors
+    let mut offset = 0.0;
+    if lags.contains(&(best - 1)) && lags.contains(&(best + 1)) {
+        let (left, center, right) = (at(best - 1), at(best), at(best + 1));
+        let denominator = left - 2.0 * center + right;
+        if denominator < 0.0 {
+            offset = (0.5 * (left - right) / denominator).clamp(-0.5, 0.5);
+        }
+    }
+    Ok(Lag {
+        lag: (best as f32 + offset) / fs_feature,
+        correlation: at(best),
+    })
+}
+
+/// Computes the cross-correlation `c[k] = sum_n a[n + k] b[n]` of two non-empty signals with
+/// an FFT, for lags `k` from `-(b.len() - 1)` to `a.len() - 1`. Negative lags are at the end
+/// of the result, which has `a.len() + b.len() - 1` values.
+pub(crate) fn cross_correlation(a: &Array1<f32>, b: &Array1<f32>) -> Vec<f32> {
+    let size = a.len() + b.len() - 1;
+    let mut planner = FftPlanner::<f32>::new();
+    let forward = planner.plan_fft_forward(size);
+    let spectrum = |values: &Array1<f32>| {
+        let mut buffer: Vec<Complex<f32>> = values.iter().map(|&v| Complex::new(v, 0.0)).collect();
+        buffer.resize(size, Complex::new(0.0, 0.0));
+        forward.process(&mut buffer);
+        buffer
+    };
+    let mut correlation: Vec<Complex<f32>> = spectrum(a).iter().zip(spectrum(b)).map(|(x, y)| x * y.conj()).collect();
+    planner.plan_fft_inverse(size).process(&mut correlation);
+    correlation.iter().map(|value| value.re / size as f32).collect()
+}
+
+pub(crate) fn zero_mean(values: &Array1<f32>) -> Array1<f32> {
+    let mean = values.mean().unwrap_or(0.0);
+    values - mean
+}
@@ -0,0 +1,225 @@
+use std::f32::consts::PI;
+
+use hann_rs::get_hann_window;
+use ndarray::{Array, Array1, Array2, Axis};
+use ndarray_stats::QuantileExt;
+
+use crate::novelty::NoveltyCurve;
+
+/// Default tempogram window length in seconds.
+pub const DEFAULT_WINDOW_SECONDS: f32 = 5.0;
+/// Default hop between tempogram frames in seconds.
+pub const DEFAULT_HOP_SECONDS: f32 = 0.5;
+/// Default lower end of the tempo axis in BPM.
+pub const DEFAULT_MIN_BPM: f32 = 30.0;
+/// Default upper end of the tempo axis in BPM.
+pub const DEFAULT_MAX_BPM: f32 = 300.0;
+/// Default number of tempo candidates to report.
+pub const DEFAULT_NUM_CANDIDATES: usize = 3;
+/// Default number of bins per tempo octave of the cyclic tempogram.
+pub const DEFAULT_CYCLIC_BINS: usize = 60;
+
+/// A time-tempo representation of a novelty function.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tempogram {
+    /// Magnitudes with shape (number of tempi, number of frames)
+    pub values: Array2<f32>,
+    /// Tempo axis in BPM
+    pub bpm: Array1<f32>,
+    /// Sampling rate of the tempogram frames
+    pub fs_tempogram: f32,
+}
+
+/// A tempo hypothesis with its confidence.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TempoCandidate {
+    /// Tempo in BPM
+    pub bpm: f32,
+    /// Salience of the tempo relative to all salience peaks, between 0 and 1
+    pub confidence: f32,
+}
+
+/// Returns a tempo axis from `min_bpm` to `max_bpm` in steps of one BPM.
+pub fn bpm_axis(min_bpm: f32, max_bpm: f32) -> Array1<f32> {
+    Array::range(min_bpm, max_bpm + 1.0, 1.0)
+}
+
+/// Computes a Fourier tempogram of a novelty function.
+///
+/// Each frame compares a Hann-windowed section of the novelty function with complex
+/// sinusoids at the tempi in `bpm`. Frame `m` is centered on novelty frame `m * hop_length`,
+/// and the novelty function is zero-padded by half a window on both sides.
+///
+/// # Arguments
+/// - `novelty`: Novelty function to analyze
+/// - `window_length`: Size of the analysis window in novelty frames
+/// - `hop_length`: Step size between tempogram frames in novelty frames
+/// - `bpm`: Tempo axis in BPM
+pub fn fourier_tempogram(novelty: &NoveltyCurve, window_length: usize, hop_length: usize, bpm: Array1<f32>) -> Tempogram {
+    let window = get_hann_window(window_length).expect("Failed to get the Hann window");
+    let half_window = window_length / 2;
+    let num_novelty = novelty.values.len();
+    let num_frames = num_novelty.div_ceil(hop_length);
+
+    let mut values = Array2::zeros((bpm.len(), num_frames));
+    for (k, &tempo) in bpm.iter().enumerate() {
+        // Precompute the complex sinusoid for this tempo over the whole novelty function
+        let omega = 2.0 * PI * tempo / 60.0 / novelty.fs_feature;
+        let (sin, cos): (Vec<f32>, Vec<f32>) = (0..num_novelty).map(|n| (omega * n as f32).sin_cos()).unzip();
+
+        for frame in 0..num_frames {
+            let start = frame * hop_length;
+            let mut real = 0.0;
+            let mut imag = 0.0;
+            for (j, w) in window.iter().enumerate() {
+                let Some(n) = (start + j).checked_sub(half_window) else {
+                    continue;
+                };
+                if n >= num_novelty {
+                    break;
+                }
+                let v = novelty.values[n] * w;
+                real += v * cos[n];
+                imag -= v * sin[n];
+            }
+            values[[k, frame]] = (real * real + imag * imag).sqrt();
+        }
+    }
+
+    Tempogram {
+        values,
+        bpm,
+        fs_tempogram: novelty.fs_feature / hop_length as f32,
+    }
+}
+
+/// Computes a Fourier tempogram with the default window, hop, and tempo axis.
+pub fn default_tempogram(novelty: &NoveltyCurve) -> Tempogram {
+    let window_length = ((DEFAULT_WINDOW_SECONDS * novelty.fs_feature).round() as usize).max(1);
+    let hop_length = ((DEFAULT_HOP_SECONDS * novelty.fs_feature).round() as usize).max(1);
+    fourier_tempogram(novelty, window_length, hop_length, bpm_axis(DEFAULT_MIN_BPM, DEFAULT_MAX_BPM))
+}
+
+/// Computes a cyclic tempogram, which identifies tempi that differ by a power of two.
+///
+/// Tempi are grouped into octave equivalence classes: bin `i` holds the tempo
+/// `reference_bpm * 2^(i / num_bins)` of the reference octave and sums the tempogram
+/// magnitudes at this tempo and all its multiples and fractions by powers of two within the
+/// tempo axis, interpolated linearly between neighboring tempi. Octave errors then don't
+/// matter, which makes the cyclic tempogram a robust mid-level representation for
+/// tempo-based structure analysis. The result is returned as a [`Tempogram`] whose tempo
+/// axis holds the tempi of the reference octave.
+///
+/// # Arguments
+/// - `tempogram`: Tempogram with an increasing tempo axis
+/// - `reference_bpm`: Lowest tempo of the reference octave, e.g. [`DEFAULT_MIN_BPM`]
+/// - `num_bins`: Number of bins per octave
+pub fn cyclic_tempogram(tempogram: &Tempogram, reference_bpm: f32, num_bins: usize) -> Tempogram {
+    let bpm = Array1::from_shape_fn(num_bins, |i| reference_bpm * 2.0_f32.powf(i as f32 / num_bins as f32));
+    let (Some(&min_bpm), Some(&max_bpm)) = (tempogram.bpm.first(), tempogram.bpm.last()) else {
+        let values = Array2::zeros((num_bins, tempogram.values.ncols()));
+        return Tempogram { values, bpm, fs_tempogram: tempogram.fs_tempogram };
+    };
+
+    // fractional indices into the tempo axis of all tempi of each equivalence class
+    let classes: Vec<Vec<(usize, f32)>> = bpm
+        .iter()
+        .map(|&tempo| {
+            let mut tempo = tempo;
+            while tempo < min_bpm {
+                tempo *= 2.0;
+            }
+            while tempo / 2.0 >= min_bpm {
+                tempo /= 2.0;
+            }
+            let mut positions = Vec::new();
+            while tempo <= max_bpm {
+                let index = tempogram.bpm.iter().rposition(|&b| b <= tempo).unwrap_or(0);
+                let fraction = if index + 1 < tempogram.bpm.len() {
+                    (tempo - tempogram.bpm[index]) / (tempogram.bpm[index + 1] - tempogram.bpm[index])
+                } else {
+                    0.0
+                };
+                positions.push((index, fraction));
+                tempo *= 2.0;
+            }
+            positions
+        })
+        .collect();
+
+    let values = Array2::from_shape_fn((num_bins, tempogram.values.ncols()), |(i, frame)| {
+        classes[i]
+            .iter()
+            .map(|&(index, fraction)| {
+                let low = tempogram.values[[index, frame]];
+                let high = if fraction > 0.0 { tempogram.values[[index + 1, frame]] } else { low };
+                low + fraction * (high - low)
+            })
+            .sum()
+    });
+
+    Tempogram {
+        values,
+        bpm,
+        fs_tempogram: tempogram.fs_tempogram,
+    }
+}
+
+/// Aggregates a tempogram over time into a tempo histogram.
+///
+/// The salience of a tempo is its mean tempogram magnitude, normalized so that the
+/// saliences sum to 1. The histograms of recordings of different lengths and levels are
+/// thus comparable and can be averaged into a tempo distribution of a corpus.
+///
+/// Returns `None` if the tempogram has no frames.
+pub fn tempo_salience(tempogram: &Tempogram) -> Option<Array1<f32>> {
+    let mut salience = tempogram.values.mean_axis(Axis(1))?;
+    let total = salience.sum();
+    if total > 0.0 {
+        salience /= total;
+    }
+    Some(salience)
+}
+
+/// Estimates the global tempo in BPM as the tempo with the largest mean tempogram magnitude.
+///
+/// Returns `None` if the tempogram has no frames.
+pub fn estimate_tempo(tempogram: &Tempogram) -> Option<f32> {
+    let salience = tempo_salience(tempogram)?;
+    let index = salience.argmax().ok()?;
+    Some(tempogram.bpm[index])
+}
+
+/// Returns up to `k` tempo candidates, ordered by descending confidence.
+///
+/// The candidates are the peaks of the mean tempogram magnitude over the tempo axis. The
+/// confidence of a candidate is its salience divided by the summed salience of all peaks,
+/// so candidates related by octave errors, such as 60 and 120 BPM, show up with comparable
+/// confidences if the tempo is ambiguous.
+pub fn tempo_candidates(tempogram: &Tempogram, k: usize) -> Vec<TempoCandidate> {
+    let Some(salience) = tempogram.values.mean_axis(Axis(1)) else {
+        return Vec::new();
+    };
+
+    let num_tempi = salience.len();
+    let mut peaks: Vec<(usize, f32)> = (0..num_tempi)
+        .filter(|&i| (i == 0 || salience[i] > salience[i - 1]) && (i + 1 == num_tempi || salience[i] >= salience[i + 1]))
+        .map(|i| (i, salience[i]))
+        .collect();
+    let total: f32 = peaks.iter().map(|&(_, value)| value).sum();
+    if total <= 0.0 {
+        return Vec::new();
+    }
+
+    peaks.sort_by(|a, b| b.1.total_cmp(&a.1));
+    peaks
+        .into_iter()
+        .take(k)
+        .map(|(i, value)| TempoCandidate {
+            bpm: tempogram.bpm[i],
+            confidence: value / total,
+        })
+        .collect()
+}
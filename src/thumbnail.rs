@@ -0,0 +1,89 @@
+use ndarray::{s, Array1, Axis};
+
+use crate::config::NoveltyConfig;
+use crate::mel::normalized_mel_features;
+
+/// Criterion by which [`thumbnail`] selects an excerpt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum ThumbnailCriterion {
+    /// The excerpt with the largest sum of the novelty function
+    Eventful,
+    /// The excerpt whose spectra are most similar to the whole recording on average
+    Representative,
+}
+
+/// An excerpt of a recording selected by [`thumbnail`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Thumbnail {
+    /// Start of the excerpt in seconds
+    pub start: f32,
+    /// End of the excerpt in seconds
+    pub end: f32,
+    /// Mean per-frame score of the excerpt under the selection criterion
+    pub score: f32,
+}
+
+impl Thumbnail {
+    /// Returns the samples of the excerpt from the recording it was selected from.
+    pub fn excerpt(&self, audio_array: &Array1<f32>, fs: u32) -> Array1<f32> {
+        let start = ((self.start * fs as f32).round() as usize).min(audio_array.len());
+        let end = ((self.end * fs as f32).round() as usize).clamp(start, audio_array.len());
+        audio_array.slice(s![start..end]).to_owned()
+    }
+}
+
+/// Selects the excerpt of `duration` seconds that best satisfies the given criterion.
+///
+/// For [`ThumbnailCriterion::Eventful`], each frame is scored by the configured novelty
+/// function. For [`ThumbnailCriterion::Representative`], each frame is scored by the cosine
+/// similarity of its compressed mel spectrum to all frames of the recording, i.e. the mean
+/// of its row in the self-similarity matrix. Both use the window and hop length of `config`.
+/// If the recording is shorter than `duration`, the whole recording is returned.
+///
+/// # Errors
+/// Returns an error if the novelty computation fails.
+pub fn thumbnail(audio_array: Array1<f32>, fs: u32, config: &NoveltyConfig, duration: f32, criterion: ThumbnailCriterion) -> anyhow::Result<Thumbnail> {
+    let audio_duration = audio_array.len() as f32 / fs as f32;
+    let fs_feature = fs as f32 / config.hop_length as f32;
+
+    let scores = match criterion {
+        ThumbnailCriterion::Eventful => config.compute(audio_array, fs)?.values,
+        ThumbnailCriterion::Representative => representativeness(&audio_array, fs, config),
+    };
+
+    let num_frames = scores.len();
+    let length = ((duration * fs_feature).round() as usize).clamp(1, num_frames.max(1));
+
+    // sliding sum over `length` frames
+    let mut best = (0, f32::NEG_INFINITY);
+    let mut sum: f32 = scores.iter().take(length).sum();
+    for start in 0..=num_frames.saturating_sub(length) {
+        if start > 0 {
+            sum += scores[start + length - 1] - scores[start - 1];
+        }
+        if sum > best.1 {
+            best = (start, sum);
+        }
+    }
+
+    let start = best.0 as f32 / fs_feature;
+    Ok(Thumbnail {
+        start,
+        end: (start + duration).min(audio_duration),
+        score: if num_frames > 0 { best.1 / length as f32 } else { 0.0 },
+    })
+}
+
+/// Returns the mean cosine similarity of each frame's compressed mel spectrum to all frames.
+fn representativeness(audio_array: &Array1<f32>, fs: u32, config: &NoveltyConfig) -> Array1<f32> {
+    let features = normalized_mel_features(audio_array, fs, config);
+
+    // the mean of a row of the self-similarity matrix is the similarity to the mean feature
+    match features.mean_axis(Axis(1)) {
+        Some(mean_feature) => features.t().dot(&mean_feature),
+        None => Array1::zeros(0),
+    }
+}
@@ -0,0 +1,111 @@
+/// SMPTE timecode at a video frame rate, starting at a given timecode, e.g. to express
+/// times of a recording that accompanies broadcast video.
+///
+/// NTSC rates (29.97 and 59.94 frames per second) use drop-frame timecode, which skips frame
+/// numbers so the timecode stays in step with the wall clock, and are written with `;` before
+/// the frames. All other rates, including 23.976, count frames without dropping.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Timecode {
+    /// Numerator of the frame rate in frames per second
+    pub rate_numerator: u32,
+    /// Denominator of the frame rate, 1001 for NTSC rates
+    pub rate_denominator: u32,
+    /// Frame count of the timecode at time 0
+    pub start: u64,
+}
+
+impl Timecode {
+    /// Creates a timecode from a frame rate and a start timecode `HH:MM:SS:FF`, or
+    /// `HH:MM:SS;FF` for drop-frame rates.
+    ///
+    /// # Errors
+    /// Returns an error if the frame rate is neither 23.976, 29.97, 59.94, nor a whole number
+    /// from 1 to 120, or if the start timecode is invalid at that rate.
+    pub fn new(fps: f32, start: &str) -> anyhow::Result<Self> {
+        let (rate_numerator, rate_denominator) = [(23.976, 24000), (29.97, 30000), (59.94, 60000)]
+            .iter()
+            .find(|&&(rate, _)| (fps - rate).abs() < 0.005)
+            .map(|&(_, numerator)| (numerator, 1001))
+            .or_else(|| (fps.fract() == 0.0 && (1.0..=120.0).contains(&fps)).then_some((fps as u32, 1)))
+            .ok_or_else(|| anyhow::anyhow!("Unsupported timecode frame rate {}", fps))?;
+        let mut timecode = Timecode { rate_numerator, rate_denominator, start: 0 };
+        timecode.start = timecode.parse(start)?;
+        Ok(timecode)
+    }
+
+    /// Number of frame numbers per second, e.g. 30 for 29.97 frames per second.
+    pub fn nominal_fps(&self) -> u32 {
+        self.rate_numerator.div_ceil(self.rate_denominator)
+    }
+
+    /// Whether frame numbers are dropped, i.e. for 29.97 and 59.94 frames per second.
+    pub fn drop_frame(&self) -> bool {
+        self.rate_denominator == 1001 && self.nominal_fps() % 30 == 0
+    }
+
+    /// Returns the frame count of a timecode `HH:MM:SS:FF` (or with `;` before the frames).
+    ///
+    /// # Errors
+    /// Returns an error if the timecode doesn't have four fields, if a field is out of
+    /// range, or if it names a frame number that drop-frame timecode skips.
+    pub fn parse(&self, timecode: &str) -> anyhow::Result<u64> {
+        let fields: Vec<u64> = timecode
+            .split([':', ';'])
+            .map(|field| field.trim().parse::<u64>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| anyhow::anyhow!("Invalid timecode {}", timecode))?;
+        let &[hours, minutes, seconds, frames] = fields.as_slice() else {
+            anyhow::bail!("Invalid timecode {}, expected HH:MM:SS:FF", timecode);
+        };
+        let nominal = self.nominal_fps() as u64;
+        if hours >= 24 || minutes >= 60 || seconds >= 60 || frames >= nominal {
+            anyhow::bail!("Invalid timecode {} at {} frames per second", timecode, nominal);
+        }
+        let count = ((hours * 60 + minutes) * 60 + seconds) * nominal + frames;
+        if !self.drop_frame() {
+            return Ok(count);
+        }
+        let drop = nominal / 15;
+        if seconds == 0 && frames < drop && minutes % 10 != 0 {
+            anyhow::bail!("Invalid timecode {}, drop-frame timecode skips frames 0 to {} of this minute", timecode, drop - 1);
+        }
+        let total_minutes = hours * 60 + minutes;
+        Ok(count - drop * (total_minutes - total_minutes / 10))
+    }
+
+    /// Returns the frame count of the timecode at a time in seconds, rounded to the nearest
+    /// frame. Times before the start are counted as the start.
+    pub fn frame(&self, seconds: f64) -> u64 {
+        self.start + (seconds * self.rate_numerator as f64 / self.rate_denominator as f64).round().max(0.0) as u64
+    }
+
+    /// Formats a time in seconds as timecode, rounded to the nearest frame and wrapped at 24
+    /// hours. Times before the start timecode are written as the start timecode.
+    pub fn format(&self, seconds: f64) -> String {
+        self.format_frame(self.frame(seconds))
+    }
+
+    /// Formats a frame count as timecode, wrapped at 24 hours.
+    pub fn format_frame(&self, frame: u64) -> String {
+        let nominal = self.nominal_fps() as u64;
+        let mut count = frame;
+        if self.drop_frame() {
+            // add back the frame numbers skipped in all minutes but every tenth
+            let drop = nominal / 15;
+            let per_ten_minutes = nominal * 600 - 9 * drop;
+            let per_minute = nominal * 60 - drop;
+            let (tens, rest) = (count / per_ten_minutes, count % per_ten_minutes);
+            count += 9 * drop * tens + if rest > drop { drop * ((rest - drop) / per_minute) } else { 0 };
+        }
+        let separator = if self.drop_frame() { ';' } else { ':' };
+        format!(
+            "{:02}:{:02}:{:02}{}{:02}",
+            count / (nominal * 3600) % 24,
+            count / (nominal * 60) % 60,
+            count / nominal % 60,
+            separator,
+            count % nominal
+        )
+    }
+}
@@ -0,0 +1,79 @@
+use std::io::Write;
+
+use crate::novelty::NoveltyCurve;
+use crate::onsets::Onset;
+
+/// Version of the Vega-Lite schema the specifications of [`vega_lite_spec`] follow.
+pub const VEGA_LITE_SCHEMA: &str = "https://vega.github.io/schema/vega-lite/v5.json";
+
+/// Where a Vega-Lite specification gets its data from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum VegaLiteData {
+    /// The novelty function and onsets are embedded in the specification, so it renders on
+    /// its own
+    Inline,
+    /// The specification references CSV files with `time,novelty` and `time,strength` columns
+    /// by URL, which keeps it small for long recordings. Relative URLs are resolved against
+    /// the page the chart is rendered in.
+    Reference {
+        /// URL of the novelty CSV file
+        novelty: String,
+        /// URL of the onsets CSV file
+        onsets: String,
+    },
+}
+
+/// Returns a Vega-Lite specification that plots the novelty function as a line and the
+/// onsets as vertical rules, e.g. to display results in Jupyter or Observable without
+/// plotting code.
+///
+/// The chart can be zoomed and panned along the time axis, and the onsets show their time and
+/// strength as tooltip.
+pub fn vega_lite_spec(novelty: &NoveltyCurve, onsets: &[Onset], data: &VegaLiteData) -> String {
+    // JSON has no NaN or infinity, so non-finite values are written as null
+    let number = |v: f32| if v.is_finite() { format!("{:.05}", v) } else { "null".to_string() };
+    let (novelty_data, onsets_data) = match data {
+        VegaLiteData::Inline => {
+            let values: Vec<String> = novelty
+                .values
+                .iter()
+                .enumerate()
+                .map(|(n, &v)| format!("{{\"time\":{},\"novelty\":{}}}", number(n as f32 / novelty.fs_feature), number(v)))
+                .collect();
+            let onsets: Vec<String> = onsets
+                .iter()
+                .map(|onset| format!("{{\"time\":{},\"strength\":{}}}", number(onset.time), number(onset.strength)))
+                .collect();
+            (format!("{{\"values\":[{}]}}", values.join(",")), format!("{{\"values\":[{}]}}", onsets.join(",")))
+        }
+        VegaLiteData::Reference { novelty, onsets } => {
+            let url = |url: &str| format!("{{\"url\":\"{}\",\"format\":{{\"type\":\"csv\"}}}}", url.replace('\\', "\\\\").replace('"', "\\\""));
+            (url(novelty), url(onsets))
+        }
+    };
+
+    let x = "\"x\":{\"field\":\"time\",\"type\":\"quantitative\",\"title\":\"Time (s)\"}";
+    format!(
+        concat!(
+            "{{\"$schema\":\"{}\",\"width\":800,\"height\":200,\"layer\":[",
+            "{{\"data\":{},\"mark\":{{\"type\":\"line\",\"strokeWidth\":1}},",
+            "\"params\":[{{\"name\":\"zoom\",\"select\":\"interval\",\"bind\":\"scales\",\"encodings\":[\"x\"]}}],",
+            "\"encoding\":{{{},\"y\":{{\"field\":\"novelty\",\"type\":\"quantitative\",\"title\":\"Novelty\"}}}}}},",
+            "{{\"data\":{},\"mark\":{{\"type\":\"rule\",\"color\":\"#d62728\"}},",
+            "\"encoding\":{{{},\"tooltip\":[{{\"field\":\"time\",\"type\":\"quantitative\"}},{{\"field\":\"strength\",\"type\":\"quantitative\"}}]}}}}",
+            "]}}"
+        ),
+        VEGA_LITE_SCHEMA, novelty_data, x, onsets_data, x
+    )
+}
+
+/// Writes a Vega-Lite specification of the novelty function and onsets, see
+/// [`vega_lite_spec`].
+///
+/// # Errors
+/// Returns an error if writing to the file fails.
+pub fn write_vega_lite(path: &str, novelty: &NoveltyCurve, onsets: &[Onset], data: &VegaLiteData) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", vega_lite_spec(novelty, onsets, data))?;
+    Ok(())
+}
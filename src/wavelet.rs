@@ -0,0 +1,120 @@
+use ndarray::{Array1, Array2};
+
+use crate::compression::Compression;
+use crate::mel::band_power_flux;
+use crate::novelty::normalize_max;
+
+/// Lowpass decomposition filter of the Daubechies wavelet with two vanishing moments (db2).
+const DB2_LOWPASS: [f32; 4] = [0.482_962_9, 0.836_516_3, 0.224_143_87, -0.129_409_52];
+
+/// Computes one level of the discrete wavelet transform with the db2 wavelet.
+///
+/// The signal is extended periodically, so both outputs have `ceil(len / 2)` coefficients.
+///
+/// # Returns
+/// - A tuple of the approximation and detail coefficients
+pub fn dwt_step(signal: &[f32]) -> (Vec<f32>, Vec<f32>) {
+    let n = signal.len();
+    let num_coefficients = n.div_ceil(2);
+    let mut approximation = Vec::with_capacity(num_coefficients);
+    let mut detail = Vec::with_capacity(num_coefficients);
+    for i in 0..num_coefficients {
+        let (mut a, mut d) = (0.0, 0.0);
+        for (k, &h) in DB2_LOWPASS.iter().enumerate() {
+            let x = signal[(2 * i + k) % n];
+            // quadrature mirror highpass filter g[k] = (-1)^k h[3 - k]
+            let g = if k % 2 == 0 { DB2_LOWPASS[3 - k] } else { -DB2_LOWPASS[3 - k] };
+            a += h * x;
+            d += g * x;
+        }
+        approximation.push(a);
+        detail.push(d);
+    }
+    (approximation, detail)
+}
+
+/// Decomposes a signal into octave bands with the discrete wavelet transform.
+///
+/// The decomposition stops early once the approximation has fewer than two coefficients.
+///
+/// # Arguments
+/// - `signal`: 1D array of samples
+/// - `levels`: Number of decomposition levels
+///
+/// # Returns
+/// - The detail coefficients of each level from the finest to the coarsest scale, followed
+///   by the approximation coefficients of the last level. Coefficients of level `j`
+///   (one-based) are spaced `2^j` samples apart.
+pub fn wavelet_decomposition(signal: &Array1<f32>, levels: usize) -> Vec<Array1<f32>> {
+    let mut approximation = signal.to_vec();
+    let mut bands = Vec::with_capacity(levels + 1);
+    for _ in 0..levels {
+        if approximation.len() < 2 {
+            break;
+        }
+        let (next, detail) = dwt_step(&approximation);
+        bands.push(Array1::from_vec(detail));
+        approximation = next;
+    }
+    bands.push(Array1::from_vec(approximation));
+    bands
+}
+
+/// Computes a novelty function from the increase of wavelet band energies over time.
+///
+/// The signal is decomposed into octave bands by the discrete wavelet transform, see
+/// [`wavelet_decomposition`]. Each frame sums the squared coefficients of each band within
+/// the window, so the time resolution of the fine bands isn't limited by an FFT frame and
+/// short transients aren't smeared by spectral leakage. The band energies are compressed
+/// and the half-wave rectified increase is averaged over the bands, as for
+/// [`crate::novelty_bands`].
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples
+/// - `fs`: Sampling rate of the audio
+/// - `window_length`: Length of the window over which band energies are summed
+/// - `hop_length`: Step size between successive frames
+/// - `levels`: Number of decomposition levels, each halving the frequency range
+/// - `compression`: Compression function applied to the band energies
+/// - `gamma`: Parameter of the compression function
+/// - `norm`: Whether to normalize the output between 0 and 1
+///
+/// # Returns
+/// - A tuple of the novelty function and its effective sampling rate
+///
+/// # Errors
+/// Returns an error if normalization fails.
+#[allow(clippy::too_many_arguments)]
+pub fn novelty_wavelet(audio_array: &Array1<f32>, fs: u32, window_length: u32, hop_length: u32, levels: u32, compression: Compression, gamma: f32, norm: bool) -> anyhow::Result<(Array1<f32>, f32)> {
+    let fs_feature = (fs as f32) / (hop_length as f32);
+    let (window_length, hop_length) = (window_length as usize, hop_length as usize);
+    let num_frames = audio_array.len().div_ceil(hop_length);
+
+    let bands = wavelet_decomposition(audio_array, levels as usize);
+    let mut band_power = Array2::zeros((bands.len(), num_frames));
+    for (b, coefficients) in bands.iter().enumerate() {
+        // the approximation has the spacing of the coarsest detail band
+        let level = if b + 1 < bands.len() { b + 1 } else { b };
+        let spacing = 1usize << level;
+        let mut cumulative = vec![0.0f64; coefficients.len() + 1];
+        for (i, c) in coefficients.iter().enumerate() {
+            cumulative[i + 1] = cumulative[i] + (c * c) as f64;
+        }
+        // coefficient i is centered on sample i * spacing + delay within the filter support
+        let delay = 3 * (spacing - 1) / 2;
+        let coefficient_at = |sample: usize| sample.saturating_sub(delay).div_ceil(spacing).min(coefficients.len());
+        for m in 0..num_frames {
+            // frame m covers the window centered on sample m * hop_length
+            let start = coefficient_at((m * hop_length).saturating_sub(window_length / 2));
+            let end = coefficient_at(m * hop_length + window_length.div_ceil(2)).max((start + 1).min(coefficients.len()));
+            band_power[[b, m]] = (cumulative[end] - cumulative[start]) as f32;
+        }
+    }
+
+    let mut novelty = band_power_flux(band_power, compression, gamma);
+    if norm {
+        normalize_max(&mut novelty)?;
+    }
+
+    Ok((novelty, fs_feature))
+}
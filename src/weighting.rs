@@ -0,0 +1,76 @@
+use ndarray::Array1;
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+
+/// Frequency weighting applied to the signal before the novelty computation, so that the
+/// novelty function follows perceived loudness rather than raw energy.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Weighting {
+    /// No weighting
+    #[default]
+    None,
+    /// A-weighting per IEC 61672, which strongly attenuates low frequencies as the ear does
+    /// at moderate levels
+    A,
+    /// C-weighting per IEC 61672, which is nearly flat and only attenuates the extremes, as
+    /// the ear does at high levels
+    C,
+}
+
+impl Weighting {
+    /// Returns the amplitude gain of the weighting at a frequency in Hz, normalized to 1 at
+    /// 1 kHz.
+    pub fn gain(self, frequency: f32) -> f32 {
+        match self {
+            Weighting::None => 1.0,
+            Weighting::A => (a_response(frequency as f64) / a_response(1000.0)) as f32,
+            Weighting::C => (c_response(frequency as f64) / c_response(1000.0)) as f32,
+        }
+    }
+}
+
+/// Unnormalized A-weighting response `R_A(f)` of IEC 61672.
+fn a_response(f: f64) -> f64 {
+    let f2 = f * f;
+    12194.0_f64.powi(2) * f2 * f2
+        / ((f2 + 20.6_f64.powi(2)) * ((f2 + 107.7_f64.powi(2)) * (f2 + 737.9_f64.powi(2))).sqrt() * (f2 + 12194.0_f64.powi(2)))
+}
+
+/// Unnormalized C-weighting response `R_C(f)` of IEC 61672.
+fn c_response(f: f64) -> f64 {
+    let f2 = f * f;
+    12194.0_f64.powi(2) * f2 / ((f2 + 20.6_f64.powi(2)) * (f2 + 12194.0_f64.powi(2)))
+}
+
+/// Applies a frequency weighting to a signal.
+///
+/// The signal is filtered in the frequency domain with the magnitude response of the
+/// weighting over its full length, i.e. with a zero-phase filter, so the weighting doesn't
+/// delay onsets. This holds the whole complex spectrum in memory, which is twice the size
+/// of the signal.
+///
+/// # Arguments
+/// - `audio_array`: 1D array of mono audio samples
+/// - `fs`: Sampling rate of the audio
+/// - `weighting`: Frequency weighting to apply
+pub fn apply_weighting(audio_array: &Array1<f32>, fs: u32, weighting: Weighting) -> Array1<f32> {
+    let length = audio_array.len();
+    if weighting == Weighting::None || length == 0 {
+        return audio_array.clone();
+    }
+
+    let mut planner = FftPlanner::<f32>::new();
+    let mut spectrum: Vec<Complex<f32>> = audio_array.iter().map(|&x| Complex::new(x, 0.0)).collect();
+    planner.plan_fft_forward(length).process(&mut spectrum);
+
+    // bins k and length - k share the frequency k * fs / length, which keeps the signal real
+    for (k, value) in spectrum.iter_mut().enumerate() {
+        let frequency = k.min(length - k) as f32 * fs as f32 / length as f32;
+        *value *= weighting.gain(frequency);
+    }
+
+    planner.plan_fft_inverse(length).process(&mut spectrum);
+    spectrum.iter().map(|value| value.re / length as f32).collect()
+}
@@ -0,0 +1,40 @@
+//! Plugin for the tests of the plugin ABI, see `novelty_rust::Plugin`. Its novelty function
+//! is the magnitude of the sample at the center of each frame.
+
+use std::ffi::c_char;
+
+#[no_mangle]
+pub extern "C" fn novelty_plugin_abi_version() -> u32 {
+    1
+}
+
+#[no_mangle]
+pub extern "C" fn novelty_plugin_name() -> *const c_char {
+    c"novelty_test_plugin".as_ptr()
+}
+
+/// Writes the magnitude of sample `m * hop_length` to frame `m`, and fails for a wrong
+/// number of frames.
+///
+/// # Safety
+/// `samples` and `novelty` must be valid for `num_samples` and `num_frames` values.
+#[no_mangle]
+pub unsafe extern "C" fn novelty_plugin_compute(
+    samples: *const f32,
+    num_samples: usize,
+    _fs: u32,
+    _window_length: u32,
+    hop_length: u32,
+    novelty: *mut f32,
+    num_frames: usize,
+) -> i32 {
+    if hop_length == 0 || num_frames != num_samples.div_ceil(hop_length as usize) {
+        return 1;
+    }
+    let samples = std::slice::from_raw_parts(samples, num_samples);
+    let novelty = std::slice::from_raw_parts_mut(novelty, num_frames);
+    for (m, value) in novelty.iter_mut().enumerate() {
+        *value = samples[m * hop_length as usize].abs();
+    }
+    0
+}
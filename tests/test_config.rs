@@ -0,0 +1,561 @@
+use novelty_rust::{
+    audio_path_to_array, bark_filterbank, detect_artifacts, dynamics, erb_filterbank, expand_output_template,
+    find_loop_points, hpss, loudness, median_filter, normalize_excluding, novelty_bands, novelty_energy, novelty_mel,
+    wavelet_decomposition, Aggregation, ArtifactKind, BandScale, Component, Compression, Fusion, HPSS_KERNEL,
+    LOUDNESS_FLOOR, LoopParams, MOMENTARY_WINDOW, Method, NoveltyConfig, NoveltyConfigBuilder, NoveltyCurve, Timecode,
+    Weighting,
+};
+use novelty_rust::bands::{bark_to_hz, erb_rate_to_hz, hz_to_bark, hz_to_erb_rate};
+
+
+// tests that the builder matches the positional novelty function
+#[test]
+fn test_builder_matches_novelty_energy() {
+    let (audio_array, fs) = audio_path_to_array("assets/LJ037-0171.wav").expect("Failed to read audio");
+
+    let config = NoveltyConfig::energy()
+        .window(2048)
+        .hop(128)
+        .gamma(10.0)
+        .norm(true)
+        .build()
+        .expect("Failed to build config");
+    assert_eq!(config.method, Method::Energy);
+
+    let novelty = config.compute(audio_array.clone(), fs).expect("Failed to compute novelty");
+    let (novelty_direct, fs_direct) = novelty_energy(audio_array, fs, 2048, 128, 10.0, true).expect("Failed to compute novelty");
+
+    assert_eq!(novelty.fs_feature, fs_direct);
+    assert_eq!(novelty.values, novelty_direct);
+}
+
+
+// tests that invalid parameters are rejected when building
+#[test]
+fn test_builder_rejects_invalid_parameters() {
+    assert!(NoveltyConfig::energy().hop(0).build().is_err());
+    assert!(NoveltyConfig::energy().window(0).build().is_err());
+    assert!(NoveltyConfig::energy().gamma(-1.0).build().is_err());
+    assert!(NoveltyConfig::energy().overlap(1.0).build().is_err());
+}
+
+
+// tests that the overlap is resolved against the final window length
+#[test]
+fn test_overlap_sets_hop_length() {
+    let config = NoveltyConfig::energy().overlap(0.75).window(2048).build().expect("Failed to build config");
+    assert_eq!(config.hop_length, 512);
+
+    let config = NoveltyConfig::energy().overlap(0.5).hop(100).build().expect("Failed to build config");
+    assert_eq!(config.hop_length, 100);
+}
+
+
+// tests that the RMS envelope of a constant signal equals its amplitude away from the edges
+#[test]
+fn test_rms_envelope_of_constant_signal() {
+    let audio_array = ndarray::Array1::from_elem(8192, 0.5_f32);
+    let config = NoveltyConfig::rms().window(1024).hop(256).norm(false).build().expect("Failed to build config");
+    assert_eq!(config.method, Method::Rms);
+
+    let envelope = config.compute(audio_array, 22050).expect("Failed to compute RMS envelope");
+    assert_eq!(envelope.values.len(), 32);
+    for value in envelope.values.slice(ndarray::s![4..28]).iter() {
+        assert!((value - 0.5).abs() < 1e-3, "Unexpected RMS value {}", value);
+    }
+}
+
+
+// tests that the compression functions behave as documented
+#[test]
+fn test_compression_functions() {
+    let (audio_array, fs) = audio_path_to_array("assets/LJ037-0171.wav").expect("Failed to read audio");
+
+    // disabled log compression equals no compression
+    let none = NoveltyConfig::energy().compression(Compression::None).gamma(10.0).build().unwrap();
+    let log_disabled = NoveltyConfig::energy().compression(Compression::Log).gamma(0.0).build().unwrap();
+    assert_eq!(
+        none.compute(audio_array.clone(), fs).unwrap(),
+        log_disabled.compute(audio_array.clone(), fs).unwrap()
+    );
+
+    // tanh compression saturates, so unnormalized novelty stays below one
+    let tanh = NoveltyConfig::energy().compression(Compression::Tanh).gamma(10.0).norm(false).build().unwrap();
+    let novelty = tanh.compute(audio_array, fs).unwrap();
+    assert!(novelty.values.iter().all(|v| (0.0..=1.0).contains(v)));
+
+    assert_eq!(Compression::Sqrt.apply(4.0, 10.0), 2.0);
+    assert_eq!(Compression::Log.apply(1.0, 1.0), 2.0_f32.ln());
+}
+
+
+// tests that the FMP compatibility mode of the energy method reproduces the positional novelty function
+#[test]
+fn test_fmp_compat_energy_matches_novelty_energy() {
+    let (audio_array, fs) = audio_path_to_array("assets/LJ037-0171.wav").expect("Failed to read audio");
+
+    let config = NoveltyConfig::fmp(Method::Energy).build().expect("Failed to build config");
+    assert_eq!((config.window_length, config.hop_length, config.gamma), (2048, 128, 10.0));
+
+    let novelty = config.compute(audio_array.clone(), fs).expect("Failed to compute novelty");
+    let (novelty_direct, _) = novelty_energy(audio_array, fs, 2048, 128, 10.0, true).expect("Failed to compute novelty");
+    assert_eq!(novelty.values, novelty_direct);
+
+    assert!(NoveltyConfig::fmp(Method::Zcr).build().is_err());
+}
+
+
+// tests that the rectified differences of each aggregation add up to the increase of the
+// aggregated energy at a step from silence to a constant amplitude
+#[test]
+fn test_energy_aggregation_modes() {
+    let amplitude = 0.5_f32;
+    let audio_array = ndarray::Array1::from_shape_fn(16384, |n| if n < 4096 { 0.0 } else { amplitude });
+    let window_energy: f32 = hann_rs::get_hann_window(1024).unwrap().iter().map(|w| w * w).sum();
+
+    for (aggregation, expected) in [
+        (Aggregation::Sum, amplitude.powi(2) * window_energy),
+        (Aggregation::Mean, amplitude.powi(2)),
+        (Aggregation::Rms, amplitude),
+    ] {
+        let config = NoveltyConfig::energy()
+            .aggregation(aggregation)
+            .compression(Compression::None)
+            .norm(false)
+            .build()
+            .expect("Failed to build config");
+        let novelty = config.compute(audio_array.clone(), 22050).expect("Failed to compute novelty");
+
+        let increase = novelty.values.sum();
+        assert!((increase - expected).abs() < 1e-3 * expected, "{:?}: expected {}, got {}", aggregation, expected, increase);
+    }
+}
+
+
+// tests the Bark and ERB scales and that band-wise novelty on the mel scale matches the mel method
+#[test]
+fn test_band_scales() {
+    for frequency in [0.0, 100.0, 1000.0, 8000.0] {
+        assert!((bark_to_hz(hz_to_bark(frequency)) - frequency).abs() < 1e-6);
+        assert!((erb_rate_to_hz(hz_to_erb_rate(frequency)) - frequency).abs() < 1e-6);
+    }
+    assert!((hz_to_erb_rate(1000.0) - 15.62).abs() < 0.01);
+
+    let bark = bark_filterbank(22050, 1024, 24, 0.0, 11025.0);
+    let erb = erb_filterbank(22050, 1024, 40, 0.0, 11025.0);
+    assert_eq!(bark.dim(), (24, 513));
+    assert_eq!(erb.dim(), (40, 513));
+    for filter in erb.rows() {
+        assert!((filter.sum() - 1.0).abs() < 1e-4);
+    }
+
+    let (audio_array, fs) = audio_path_to_array("assets/LJ037-0171.wav").expect("Failed to read audio");
+    let (bands, _) = novelty_bands(&audio_array, fs, 1024, 256, BandScale::Mel, 128, Compression::Log, 10.0, true).unwrap();
+    let (mel, _) = novelty_mel(&audio_array, fs, 1024, 256, 128, Compression::Log, 10.0, true).unwrap();
+    assert_eq!(bands, mel);
+
+    let config = NoveltyConfig::erb().build().expect("Failed to build config");
+    assert_eq!((config.method, config.n_mels), (Method::Erb, 40));
+    let novelty = config.compute(audio_array, fs).expect("Failed to compute novelty");
+    assert_eq!(novelty.values.len(), mel.len());
+}
+
+
+// tests the weighting gains at reference frequencies and that A-weighting removes low-frequency energy
+#[test]
+fn test_frequency_weighting() {
+    let db = |gain: f32| 20.0 * gain.log10();
+    assert!(db(Weighting::A.gain(1000.0)).abs() < 1e-3);
+    assert!((db(Weighting::A.gain(100.0)) - -19.1).abs() < 0.1);
+    assert!((db(Weighting::C.gain(100.0)) - -0.3).abs() < 0.1);
+    assert_eq!(Weighting::None.gain(50.0), 1.0);
+
+    let fs = 22050;
+    let tone = |frequency: f32| ndarray::Array1::from_shape_fn(fs as usize, |n| (2.0 * std::f32::consts::PI * frequency * n as f32 / fs as f32).sin());
+    let rms = |signal: &ndarray::Array1<f32>| (signal.mapv(|v| v * v).mean().unwrap()).sqrt();
+
+    let weighted = novelty_rust::apply_weighting(&tone(1000.0), fs, Weighting::A);
+    assert!((rms(&weighted) / rms(&tone(1000.0)) - 1.0).abs() < 1e-2);
+    let weighted = novelty_rust::apply_weighting(&tone(50.0), fs, Weighting::A);
+    assert!(rms(&weighted) / rms(&tone(50.0)) < 0.05);
+
+    assert!(NoveltyConfig::librosa().weighting(Weighting::A).build().is_err());
+}
+
+
+// tests that the noise gate removes stationary noise and keeps a short tone burst above it
+#[test]
+fn test_noise_gate() {
+    let fs = 22050.0_f32;
+    let mut state = 12345_u32;
+    let audio_array = ndarray::Array1::from_shape_fn(88200, |n| {
+        state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+        let noise = 0.01 * (state as f32 / u32::MAX as f32 - 0.5);
+        let tone = if (22050..27562).contains(&n) { 0.5 * (2.0 * std::f32::consts::PI * 440.0 * n as f32 / fs).sin() } else { 0.0 };
+        noise + tone
+    });
+    let rms = |signal: ndarray::ArrayView1<f32>| (signal.mapv(|v| v * v).mean().unwrap()).sqrt();
+
+    let gated = novelty_rust::spectral_gate(&audio_array, 1024, 90.0);
+    assert_eq!(gated.len(), audio_array.len());
+
+    let noise = ndarray::s![30000..86000];
+    let tone = ndarray::s![23000..26500];
+    assert!(rms(gated.slice(noise)) < 0.3 * rms(audio_array.slice(noise)), "Noise not reduced");
+    assert!((rms(gated.slice(tone)) / rms(audio_array.slice(tone)) - 1.0).abs() < 0.05, "Tone not preserved");
+
+    assert!(NoveltyConfig::energy().noise_gate(Some(150.0)).build().is_err());
+}
+
+
+// tests that an out-of-phase burst over a steady tone only shows up in the side novelty
+#[test]
+fn test_stereo_side_novelty() {
+    let fs = 22050_u32;
+    let frames = 2 * fs as usize;
+    let mut bytes = Vec::new();
+    for n in 0..frames {
+        let tone = 0.3 * (2.0 * std::f32::consts::PI * 220.0 * n as f32 / fs as f32).sin();
+        let burst = if (fs as usize..fs as usize + 2205).contains(&n) { 0.3 * (2.0 * std::f32::consts::PI * 1000.0 * n as f32 / fs as f32).sin() } else { 0.0 };
+        bytes.extend_from_slice(&(tone + burst).to_le_bytes());
+        bytes.extend_from_slice(&(tone - burst).to_le_bytes());
+    }
+
+    // 32-bit float WAV with two channels
+    let mut wav = Vec::new();
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + bytes.len() as u32).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16_u32.to_le_bytes());
+    wav.extend_from_slice(&3_u16.to_le_bytes());
+    wav.extend_from_slice(&2_u16.to_le_bytes());
+    wav.extend_from_slice(&fs.to_le_bytes());
+    wav.extend_from_slice(&(fs * 8).to_le_bytes());
+    wav.extend_from_slice(&8_u16.to_le_bytes());
+    wav.extend_from_slice(&32_u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    wav.extend_from_slice(&bytes);
+
+    assert!(novelty_rust::read_wav(wav.as_slice()).is_err());
+    let (channels, fs) = novelty_rust::read_wav_channels(wav.as_slice()).expect("Failed to read stereo WAV");
+    assert_eq!(channels.dim(), (2, frames));
+    let (mid, side) = novelty_rust::mid_side(&channels).unwrap();
+
+    let config = NoveltyConfig::energy().window(1024).hop(256).build().unwrap();
+    let mid_novelty = config.compute(mid, fs).unwrap();
+    let side_novelty = config.compute(side, fs).unwrap();
+
+    let burst_frame = (fs / 256) as usize;
+    let side_peak = side_novelty.values.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).unwrap().0;
+    assert!(side_peak.abs_diff(burst_frame) <= 4, "Side novelty peaks at frame {}", side_peak);
+    assert!(mid_novelty.values.slice(ndarray::s![burst_frame - 4..burst_frame + 4]).iter().all(|&v| v < 0.1));
+}
+
+// tests that novelty functions at several resolutions are fused at the common frame rate
+#[test]
+fn test_multi_resolution_fusion() {
+    let (audio_array, fs) = audio_path_to_array("assets/LJ037-0171.wav").expect("Failed to read audio");
+    let single = NoveltyConfig::energy().window(2048).build().unwrap().compute(audio_array.clone(), fs).unwrap();
+
+    // a single resolution reproduces the normalized novelty function
+    let config = NoveltyConfig::energy().resolutions(vec![2048]).build().unwrap();
+    let fused = config.compute(audio_array.clone(), fs).unwrap();
+    assert_eq!(fused.fs_feature, single.fs_feature);
+    for (a, b) in fused.values.iter().zip(single.values.iter()) {
+        assert!((a - b).abs() < 1e-6);
+    }
+
+    for fusion in [Fusion::Mean, Fusion::Max] {
+        let config = NoveltyConfig::energy().resolutions(vec![512, 2048, 8192]).fusion(fusion).build().unwrap();
+        let fused = config.compute(audio_array.clone(), fs).unwrap();
+        assert_eq!(fused.values.len(), single.values.len());
+        assert!(fused.values.iter().all(|&v| (0.0..=1.0).contains(&v)));
+    }
+
+    assert!(NoveltyConfig::energy().resolutions(vec![512, 0]).build().is_err());
+    assert!(NoveltyConfig::fmp(Method::Energy).resolutions(vec![512, 2048]).build().is_err());
+}
+
+// tests that the wavelet decomposition preserves the energy and that wavelet novelty peaks at a click
+#[test]
+fn test_wavelet_novelty() {
+    let signal = ndarray::Array1::from_shape_fn(1024, |n| ((n * n) as f32 * 0.37).sin());
+    let bands = wavelet_decomposition(&signal, 5);
+    assert_eq!(bands.len(), 6);
+    assert_eq!(bands[0].len(), 512);
+    let energy: f32 = bands.iter().map(|band| band.mapv(|c| c * c).sum()).sum();
+    assert!((energy - signal.mapv(|v| v * v).sum()).abs() < 1e-2 * energy);
+
+    let fs = 16000;
+    let mut audio_array = ndarray::Array1::zeros(fs as usize);
+    audio_array[8000] = 1.0;
+    let novelty = NoveltyConfig::wavelet().window(512).hop(128).build().unwrap().compute(audio_array, fs).unwrap();
+    let peak = novelty.values.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).unwrap().0;
+    assert!((peak as i32 - 8000 / 128).abs() <= 4, "peak at frame {}", peak);
+}
+
+// tests that a sine crosses zero twice per period and white noise at every other sample, and
+// that ZCR novelty peaks where a tone switches to noise
+#[test]
+fn test_zero_crossing_rate() {
+    let fs = 16000;
+    let mut state = 12345_u32;
+    let audio_array = ndarray::Array1::from_shape_fn(2 * fs as usize, |n| {
+        state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+        if n < fs as usize {
+            (2.0 * std::f32::consts::PI * 1000.0 * n as f32 / fs as f32 + 0.3).sin()
+        } else {
+            state as f32 / u32::MAX as f32 * 2.0 - 1.0
+        }
+    });
+
+    let zcr = novelty_rust::zero_crossing_rate(&audio_array, 1024, 256);
+    assert_eq!(zcr.len(), 125);
+    // 1000 Hz at 16 kHz crosses zero at every eighth sample, away from the edges and the switch
+    assert!(zcr.slice(ndarray::s![2..58]).iter().all(|&v| (v - 0.125).abs() <= 2.0 / 1024.0));
+    assert!(zcr.slice(ndarray::s![68..123]).iter().all(|&v| (v - 0.5).abs() < 0.05));
+
+    let (novelty, fs_feature) = novelty_rust::novelty_zcr(audio_array, fs, 1024, 256, true).unwrap();
+    assert_eq!(novelty.len(), 125);
+    assert_eq!(fs_feature, 62.5);
+    let peak = novelty.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).unwrap().0;
+    assert!(peak.abs_diff(62) <= 2, "peak at frame {}", peak);
+    assert!(novelty.slice(ndarray::s![4..56]).iter().all(|&v| v < 0.05));
+}
+
+// tests that HPSS assigns a steady tone to the harmonic and clicks to the percussive component
+#[test]
+fn test_harmonic_percussive_separation() {
+    let fs = 22050;
+    let audio_array = ndarray::Array1::from_shape_fn(fs as usize, |n| {
+        let tone = 0.3 * (2.0 * std::f32::consts::PI * 440.0 * n as f32 / fs as f32).sin();
+        if n % 5000 == 2500 { tone + 1.0 } else { tone }
+    });
+    let (harmonic, percussive) = hpss(&audio_array, 1024, HPSS_KERNEL);
+    assert_eq!(percussive.len(), audio_array.len());
+
+    // the components add up to the input away from the edges
+    let inner = ndarray::s![1024..fs as usize - 1024];
+    let residual = &audio_array.slice(inner) - &(&harmonic + &percussive).slice(inner);
+    assert!(residual.iter().all(|v| v.abs() < 1e-3));
+
+    // between the clicks, the percussive component holds little of the tone
+    let rms = |signal: ndarray::ArrayView1<f32>| (signal.mapv(|v| v * v).mean().unwrap()).sqrt();
+    let between = ndarray::s![4000..6500];
+    assert!(rms(percussive.slice(between)) < 0.1 * rms(audio_array.slice(between)));
+    assert!(percussive[7500].abs() > 0.3);
+
+    assert!(NoveltyConfig::fmp(Method::Energy).hpss(Some(Component::Percussive)).build().is_err());
+}
+
+// tests that the nn method requires a model
+#[test]
+fn test_nn_method_requires_model() {
+    assert!(NoveltyConfigBuilder::from(Method::Nn).build().is_err());
+    let config = NoveltyConfig::nn("model.onnx").build().unwrap();
+    assert_eq!(config.model.as_deref(), Some(std::path::Path::new("model.onnx")));
+    assert!(NoveltyConfig::fmp(Method::Nn).model(Some("model.onnx".into())).build().is_err());
+}
+
+// tests that the nn method runs an ONNX model, here one that averages the mel bands, and
+// returns one activation per frame at the feature rate of the hop length
+#[cfg(feature = "nn")]
+#[test]
+fn test_nn_method_runs_model() {
+    let (audio_array, fs) = audio_path_to_array("assets/LJ037-0171.wav").expect("Failed to read audio");
+    let config = NoveltyConfig::nn("assets/mel_mean.onnx").norm(false).build().unwrap();
+    let novelty = config.compute(audio_array.clone(), fs).unwrap();
+
+    let mel = novelty_rust::mel_features(&audio_array, fs, config.window_length, config.hop_length, config.n_mels, config.compression, config.gamma);
+    assert_eq!(novelty.values.len(), mel.ncols());
+    assert_eq!(novelty.fs_feature, fs as f32 / config.hop_length as f32);
+    for (value, bands) in novelty.values.iter().zip(mel.columns()) {
+        assert!((value - bands.mean().unwrap()).abs() < 1e-4, "{} != {}", value, bands.mean().unwrap());
+    }
+}
+
+// tests that the plugin method requires a plugin
+#[test]
+fn test_plugin_method_requires_plugin() {
+    assert!(NoveltyConfigBuilder::from(Method::Plugin).build().is_err());
+    let config = NoveltyConfig::plugin("libdetector.so").build().unwrap();
+    assert_eq!(config.plugin.as_deref(), Some(std::path::Path::new("libdetector.so")));
+}
+
+/// Builds the plugin in tests/plugin and returns the path of its shared library.
+#[cfg(feature = "plugins")]
+fn build_test_plugin() -> std::path::PathBuf {
+    let target_dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("plugin");
+    let status = std::process::Command::new(env!("CARGO"))
+        .args(["build", "--quiet", "--manifest-path", "tests/plugin/Cargo.toml", "--target-dir"])
+        .arg(&target_dir)
+        .status()
+        .expect("Failed to run cargo");
+    assert!(status.success());
+    let (prefix, extension) = (std::env::consts::DLL_PREFIX, std::env::consts::DLL_EXTENSION);
+    target_dir.join("debug").join(format!("{}novelty_test_plugin.{}", prefix, extension))
+}
+
+// tests that a plugin is loaded with its name, computes one value per frame, and runs as
+// the plugin method
+#[cfg(feature = "plugins")]
+#[test]
+fn test_plugin_compute() {
+    let path = build_test_plugin();
+    let plugin = novelty_rust::Plugin::load(&path).unwrap();
+    assert_eq!(plugin.name(), "novelty_test_plugin");
+    assert_eq!(plugin.path(), path.as_path());
+
+    // the test plugin returns the magnitude of the sample at the center of each frame
+    let samples = ndarray::Array1::from_iter((0..1000).map(|n| (n % 7) as f32 - 3.0));
+    let values = plugin.compute(&samples, 22050, 64, 100).unwrap();
+    assert_eq!(values.len(), 10);
+    for (m, value) in values.iter().enumerate() {
+        assert_eq!(*value, samples[m * 100].abs());
+    }
+
+    let (audio_array, fs) = audio_path_to_array("assets/LJ037-0171.wav").expect("Failed to read audio");
+    let novelty = NoveltyConfig::plugin(&path).hop(256).build().unwrap().compute(audio_array, fs).unwrap();
+    assert_eq!(novelty.values.len(), 167226_usize.div_ceil(256));
+    assert_eq!(novelty.fs_feature, fs as f32 / 256.0);
+    assert_eq!(novelty.values.iter().copied().fold(0.0, f32::max), 1.0);
+}
+
+// tests that a plugin is resolved by name only from the library named after it
+#[cfg(feature = "plugins")]
+#[test]
+fn test_resolve_plugin() {
+    let library = build_test_plugin();
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("plugins");
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    std::fs::create_dir_all(&dir).unwrap();
+    let (prefix, extension) = (std::env::consts::DLL_PREFIX, std::env::consts::DLL_EXTENSION);
+    // the copy named other reports the name of the test plugin, but isn't named after it
+    for stem in ["novelty_test_plugin", "other"] {
+        std::fs::copy(&library, dir.join(format!("{}{}.{}", prefix, stem, extension))).unwrap();
+    }
+    let dirs = vec![dir.clone()];
+
+    let expected = dir.join(format!("{}novelty_test_plugin.{}", prefix, extension));
+    assert_eq!(novelty_rust::resolve_plugin("novelty_test_plugin", &dirs).unwrap(), expected);
+    assert!(novelty_rust::resolve_plugin("other", &dirs).is_err());
+    assert!(novelty_rust::resolve_plugin("missing", &dirs).is_err());
+    let path = library.to_str().unwrap();
+    assert_eq!(novelty_rust::resolve_plugin(path, &[]).unwrap(), library);
+}
+
+// tests that the median filter removes an isolated spike and keeps a longer plateau
+#[test]
+fn test_median_filter() {
+    let values = ndarray::Array1::from_vec(vec![0.0, 0.0, 1.0, 0.0, 0.0, 0.5, 0.5, 0.5, 0.5, 0.0]);
+    let filtered = median_filter(&values, 3);
+    assert_eq!(filtered.to_vec(), vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.5, 0.5, 0.5, 0.5, 0.0]);
+    assert_eq!(median_filter(&values, 1), values);
+
+    let (audio_array, fs) = audio_path_to_array("assets/LJ037-0171.wav").expect("Failed to read audio");
+    let novelty = NoveltyConfig::energy().median_filter(5).build().unwrap().compute(audio_array, fs).unwrap();
+    assert_eq!(novelty.values.iter().copied().fold(0.0, f32::max), 1.0);
+}
+
+
+// tests that a full-scale 997 Hz sine has a loudness of -3.01 LUFS, as specified by ITU-R BS.1770
+#[test]
+fn test_loudness_of_sine() {
+    let fs = 48000;
+    let sine = ndarray::Array1::from_shape_fn(4 * fs as usize, |n| (2.0 * std::f32::consts::PI * 997.0 * n as f32 / fs as f32).sin());
+    let momentary = loudness(&sine, fs, 4800, MOMENTARY_WINDOW);
+    assert_eq!(momentary.len(), 40);
+    assert!((momentary[20] + 3.01).abs() < 0.05, "Unexpected loudness {}", momentary[20]);
+    assert!(momentary[0] < momentary[20]);
+
+    let silence = ndarray::Array1::zeros(fs as usize);
+    assert!(loudness(&silence, fs, 4800, MOMENTARY_WINDOW).iter().all(|&v| v == LOUDNESS_FLOOR));
+}
+
+
+// tests the dynamics of a sine that jumps by 20 dB, whose quiet half is gated from the integrated loudness
+#[test]
+fn test_dynamics_of_sine() {
+    let fs = 48000;
+    let sine = ndarray::Array1::from_shape_fn(20 * fs as usize, |n| {
+        let amplitude = if n < 10 * fs as usize { 0.1 } else { 1.0 };
+        amplitude * (2.0 * std::f32::consts::PI * 997.0 * n as f32 / fs as f32).sin()
+    });
+    let profile = dynamics(&sine, fs, 0);
+
+    assert!(profile.peak.abs() < 0.01, "Unexpected peak {}", profile.peak);
+    assert!((profile.rms + 5.98).abs() < 0.05, "Unexpected RMS {}", profile.rms);
+    assert!((profile.crest_factor - 5.98).abs() < 0.05);
+    assert!((profile.integrated_loudness.unwrap() + 3.01).abs() < 0.1, "{:?}", profile);
+    assert!((profile.loudness_range.unwrap() - 20.0).abs() < 0.5, "{:?}", profile);
+    assert_eq!(dynamics(&ndarray::Array1::zeros(fs as usize), fs, 0).integrated_loudness, None);
+}
+
+
+// tests that clipping and dropouts are found, but not digital silence at the edges, and excluded from normalization
+#[test]
+fn test_detect_artifacts() {
+    let fs = 8000;
+    let mut audio_array = ndarray::Array1::from_shape_fn(fs as usize, |n| 0.5 * (2.0 * std::f32::consts::PI * 440.0 * n as f32 / fs as f32).sin());
+    audio_array.slice_mut(ndarray::s![..80]).fill(0.0);
+    audio_array.slice_mut(ndarray::s![2000..2010]).fill(1.0);
+    audio_array.slice_mut(ndarray::s![4000..4160]).fill(0.0);
+
+    let artifacts = detect_artifacts(&audio_array, fs);
+    assert_eq!(artifacts.len(), 2, "{:?}", artifacts);
+    assert_eq!((artifacts[0].kind, artifacts[0].start, artifacts[0].end), (ArtifactKind::Clipping, 0.25, 0.25125));
+    assert_eq!((artifacts[1].kind, artifacts[1].start, artifacts[1].end), (ArtifactKind::Dropout, 0.5, 0.52));
+
+    let mut novelty = NoveltyCurve { values: ndarray::Array1::from_vec(vec![0.2, 1.0, 0.5, 0.1]), fs_feature: 4.0 };
+    normalize_excluding(&mut novelty, &artifacts[..1], 0.0);
+    assert_eq!(novelty.values.to_vec(), [0.4, 1.0, 1.0, 0.2]);
+}
+
+
+// tests that output templates are expanded with the input stem and the parameters
+#[test]
+fn test_output_template() {
+    let config = NoveltyConfig::energy().window(2048).hop(512).build().unwrap();
+    let name = expand_output_template("{stem}_{method}_w{window}_h{hop}.csv", "corpus/take 1.wav", &config).unwrap();
+    assert_eq!(name, "take 1_energy_w2048_h512.csv");
+    assert!(expand_output_template("{stem}_{size}.csv", "a.wav", &config).is_err());
+    assert!(expand_output_template("{stem.csv", "a.wav", &config).is_err());
+}
+
+// tests that times are formatted as non-drop and drop-frame timecode
+#[test]
+fn test_timecode() {
+    let timecode = Timecode::new(25.0, "10:00:00:00").unwrap();
+    assert_eq!(timecode.format(0.0), "10:00:00:00");
+    assert_eq!(timecode.format(61.52), "10:01:01:13");
+
+    let timecode = Timecode::new(29.97, "00:00:00;00").unwrap();
+    assert!(timecode.drop_frame());
+    assert_eq!(timecode.format(1800.0 * 1001.0 / 30000.0), "00:01:00;02");
+    assert_eq!(timecode.format(600.0), "00:10:00;00");
+    assert!(Timecode::new(29.97, "00:01:00;00").is_err());
+    assert!(Timecode::new(24.5, "00:00:00:00").is_err());
+}
+
+// tests that loops in a sustained sine after a silent start continue the waveform
+#[test]
+fn test_loop_points_of_sine() {
+    let fs = 22050;
+    let sine = ndarray::Array1::from_shape_fn(3 * fs as usize, |n| {
+        if n < fs as usize / 2 {
+            0.0
+        } else {
+            0.5 * (2.0 * std::f32::consts::PI * 220.0 * n as f32 / fs as f32).sin()
+        }
+    });
+    let novelty = NoveltyConfig::energy().build().unwrap().compute(sine.clone(), fs).unwrap();
+    let loops = find_loop_points(&sine, fs, &novelty, &LoopParams::default());
+    assert!(!loops.is_empty());
+    for point in &loops {
+        assert!(point.start >= fs as usize / 2 && point.end - point.start >= fs as usize / 2, "Unexpected loop {:?}", point);
+    }
+    assert!(loops[0].score > 0.99, "Unexpected score {}", loops[0].score);
+}
@@ -0,0 +1,30 @@
+use std::fs;
+use std::path::Path;
+
+use ndarray::array;
+use novelty_rust::{detect_onsets, NoveltyCurve, ResultsDatabase};
+
+
+// tests that results of several files are collected in linked tables across connections
+#[test]
+fn test_results_are_appended() {
+    let path = "test_results.sqlite";
+    if Path::new(path).exists() {
+        fs::remove_file(path).unwrap();
+    }
+
+    let novelty = NoveltyCurve { values: array![0.0, 1.0, 0.0, 0.0, 0.5, 0.0], fs_feature: 100.0 };
+    let onsets = detect_onsets(&novelty, 0.1);
+    assert_eq!(onsets.len(), 2);
+
+    let first = ResultsDatabase::open(path).unwrap().insert("a.wav", "default", &novelty, &onsets, Some(120.0)).unwrap();
+    let second = ResultsDatabase::open(path).unwrap().insert("b.wav", "default", &novelty, &onsets, None).unwrap();
+    assert_ne!(first, second);
+
+    let connection = rusqlite::Connection::open(path).unwrap();
+    let count = |sql: &str| connection.query_row(sql, [], |row| row.get::<_, i64>(0)).unwrap();
+    assert_eq!(count("SELECT COUNT(*) FROM files"), 2);
+    assert_eq!(count("SELECT COUNT(*) FROM frames"), 12);
+    assert_eq!(count(&format!("SELECT COUNT(*) FROM onsets WHERE file_id = {}", second)), 2);
+    assert_eq!(count("SELECT COUNT(*) FROM files WHERE tempo IS NULL"), 1);
+}
@@ -0,0 +1,315 @@
+use novelty_rust::{
+    is_object_url, path_exists, read_aiff, read_audio_channels, read_broadcast_info, read_caf, read_path,
+    read_wav_channels, read_wav_header, read_wav_lenient, write_path, StagedInput, StagedOutput,
+};
+
+
+/// Interleaved stereo test samples that are exactly representable with 16 bits.
+fn samples() -> Vec<f32> {
+    (0..200).map(|n| ((n % 50) as f32 - 25.0) / 32.0 * if n % 2 == 0 { 1.0 } else { -0.5 }).collect()
+}
+
+
+// tests that lenient WAV reading skips a junk chunk and reads truncated data up to the end
+#[test]
+fn test_read_wav_lenient() {
+    let data: Vec<u8> = samples().iter().flat_map(|v| ((v * 32768.0) as i16).to_le_bytes()).collect();
+
+    let mut wav = Vec::new();
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&9999_u32.to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16_u32.to_le_bytes());
+    for value in [1_u16, 2] {
+        wav.extend_from_slice(&value.to_le_bytes());
+    }
+    wav.extend_from_slice(&44100_u32.to_le_bytes());
+    wav.extend_from_slice(&(44100_u32 * 4).to_le_bytes());
+    for value in [4_u16, 16] {
+        wav.extend_from_slice(&value.to_le_bytes());
+    }
+    // junk without a valid chunk header, then a data chunk larger than the file that ends
+    // within a frame
+    wav.extend_from_slice(b"\x00\xff\x13junkjunk");
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&1000_u32.to_le_bytes());
+    wav.extend_from_slice(&data);
+    wav.extend_from_slice(&[1, 2]);
+
+    assert!(read_wav_channels(wav.as_slice()).is_err());
+    let (channels, fs, repairs) = read_wav_lenient(&wav).unwrap();
+    assert_eq!(fs, 44100);
+    assert_eq!(channels.dim(), (2, 100));
+    for (n, value) in samples().iter().enumerate() {
+        assert_eq!(channels[[n % 2, n / 2]], *value);
+    }
+    assert_eq!(repairs.len(), 3);
+}
+
+
+// tests that lenient WAV reading finds the data chunk after a fmt or LIST chunk whose size
+// runs past the end of the file
+#[test]
+fn test_read_wav_lenient_oversized_chunks() {
+    let data: Vec<u8> = samples().iter().flat_map(|v| ((v * 32768.0) as i16).to_le_bytes()).collect();
+    let fmt = |size: u32| {
+        let mut chunk = b"fmt ".to_vec();
+        chunk.extend_from_slice(&size.to_le_bytes());
+        for value in [1_u16, 2] {
+            chunk.extend_from_slice(&value.to_le_bytes());
+        }
+        chunk.extend_from_slice(&44100_u32.to_le_bytes());
+        chunk.extend_from_slice(&(44100_u32 * 4).to_le_bytes());
+        for value in [4_u16, 16] {
+            chunk.extend_from_slice(&value.to_le_bytes());
+        }
+        chunk
+    };
+
+    // the LIST chunk ends 4 bytes before the end of the file, so no chunk header fits after it
+    let list_size = (4 + 8 + data.len() - 4) as u32;
+    for (fmt_size, list_size, repair) in [(1_000_000, None, "fmt chunk at byte 12"), (16, Some(list_size), "LIST chunk at byte 36")] {
+        let mut wav = b"RIFF".to_vec();
+        wav.extend_from_slice(&0_u32.to_le_bytes());
+        wav.extend_from_slice(b"WAVE");
+        wav.extend_from_slice(&fmt(fmt_size));
+        if let Some(list_size) = list_size {
+            wav.extend_from_slice(b"LIST");
+            wav.extend_from_slice(&list_size.to_le_bytes());
+            wav.extend_from_slice(b"INFO");
+        }
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        wav.extend_from_slice(&data);
+
+        let (channels, fs, repairs) = read_wav_lenient(&wav).unwrap();
+        assert_eq!(fs, 44100);
+        assert_eq!(channels.dim(), (2, 100));
+        for (n, value) in samples().iter().enumerate() {
+            assert_eq!(channels[[n % 2, n / 2]], *value);
+        }
+        assert_eq!(repairs, [format!("{} runs past the end of the file", repair)]);
+    }
+}
+
+
+// tests that the ds64 chunk of RF64 gives the size of the data chunk, so following chunks
+// aren't read as samples
+#[test]
+fn test_read_rf64() {
+    let data: Vec<u8> = samples().iter().flat_map(|v| ((v * 32768.0) as i16).to_le_bytes()).collect();
+
+    let mut rf64 = Vec::new();
+    rf64.extend_from_slice(b"RF64");
+    rf64.extend_from_slice(&u32::MAX.to_le_bytes());
+    rf64.extend_from_slice(b"WAVEds64");
+    rf64.extend_from_slice(&28_u32.to_le_bytes());
+    for size in [0_u64, data.len() as u64, 100] {
+        rf64.extend_from_slice(&size.to_le_bytes());
+    }
+    rf64.extend_from_slice(&0_u32.to_le_bytes());
+    rf64.extend_from_slice(b"fmt ");
+    rf64.extend_from_slice(&16_u32.to_le_bytes());
+    for value in [1_u16, 2] {
+        rf64.extend_from_slice(&value.to_le_bytes());
+    }
+    rf64.extend_from_slice(&48000_u32.to_le_bytes());
+    rf64.extend_from_slice(&(48000_u32 * 4).to_le_bytes());
+    for value in [4_u16, 16] {
+        rf64.extend_from_slice(&value.to_le_bytes());
+    }
+    rf64.extend_from_slice(b"data");
+    rf64.extend_from_slice(&u32::MAX.to_le_bytes());
+    rf64.extend_from_slice(&data);
+    rf64.extend_from_slice(b"LIST");
+    rf64.extend_from_slice(&4_u32.to_le_bytes());
+    rf64.extend_from_slice(b"INFO");
+
+    let (_, info) = read_wav_header(&mut rf64.as_slice()).unwrap();
+    assert_eq!(info.frames, Some(100));
+    let (channels, fs) = read_audio_channels(rf64.as_slice()).unwrap();
+    assert_eq!(fs, 48000);
+    assert_eq!(channels.dim(), (2, 100));
+    for (n, value) in samples().iter().enumerate() {
+        assert_eq!(channels[[n % 2, n / 2]], *value);
+    }
+}
+
+
+// tests that a fmt chunk with a corrupt size fails instead of allocating its claimed size
+#[test]
+fn test_read_wav_oversized_fmt() {
+    let mut wav = Vec::new();
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&36_u32.to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&0xFFFF_FFF0_u32.to_le_bytes());
+    wav.extend_from_slice(&[0; 16]);
+
+    let error = read_wav_header(&mut wav.as_slice()).unwrap_err();
+    assert!(error.to_string().contains("fmt"), "{}", error);
+}
+
+
+// tests that the bext time reference and iXML elements are found around the samples
+#[test]
+fn test_read_broadcast_info() {
+    let mut bext = vec![0u8; 602];
+    bext[..9].copy_from_slice(b"Scene 12A");
+    bext[256..264].copy_from_slice(b"Recorder");
+    bext[320..330].copy_from_slice(b"2024-05-01");
+    bext[330..338].copy_from_slice(b"10:00:00");
+    bext[338..346].copy_from_slice(&(48000_u64 * 36000).to_le_bytes());
+    let ixml = b"<BWFXML><PROJECT>Feature</PROJECT><SCENE>12A</SCENE><TAKE>3</TAKE>\
+        <SPEED><TIMECODE_RATE>25/1</TIMECODE_RATE></SPEED></BWFXML>";
+
+    let mut wav = Vec::new();
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&0_u32.to_le_bytes());
+    wav.extend_from_slice(b"WAVEbext");
+    wav.extend_from_slice(&(bext.len() as u32).to_le_bytes());
+    wav.extend_from_slice(&bext);
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&3_u32.to_le_bytes());
+    wav.extend_from_slice(&[1, 2, 3, 0]);
+    wav.extend_from_slice(b"iXML");
+    wav.extend_from_slice(&(ixml.len() as u32).to_le_bytes());
+    wav.extend_from_slice(ixml);
+
+    let info = read_broadcast_info(&mut std::io::Cursor::new(&wav)).unwrap().unwrap();
+    assert_eq!(info.description, "Scene 12A");
+    assert_eq!(info.originator, "Recorder");
+    assert_eq!(info.origination_date, "2024-05-01");
+    assert_eq!(info.origination_time, "10:00:00");
+    assert_eq!(info.timecode_offset(48000), Some(36000.0));
+    assert_eq!(info.timecode_rate.as_deref(), Some("25/1"));
+    assert_eq!(info.project.as_deref(), Some("Feature"));
+    assert_eq!(info.scene.as_deref(), Some("12A"));
+    assert_eq!(info.take.as_deref(), Some("3"));
+
+    // files without metadata and other formats have none
+    assert_eq!(read_broadcast_info(&mut std::io::Cursor::new(&wav[..12])).unwrap(), None);
+    assert_eq!(read_broadcast_info(&mut std::io::Cursor::new(b"FORM")).unwrap(), None);
+}
+
+
+// tests that 16-bit big-endian AIFF and 80-bit sampling rates are decoded
+#[test]
+fn test_read_aiff() {
+    let data: Vec<u8> = samples().iter().flat_map(|v| ((v * 32768.0) as i16).to_be_bytes()).collect();
+
+    let mut comm = Vec::new();
+    comm.extend_from_slice(&2_u16.to_be_bytes());
+    comm.extend_from_slice(&100_u32.to_be_bytes());
+    comm.extend_from_slice(&16_u16.to_be_bytes());
+    // 44100 Hz as 80-bit extended: 44100 = 0xAC44 * 2^0, normalized to the top of the mantissa
+    comm.extend_from_slice(&[0x40, 0x0E, 0xAC, 0x44, 0, 0, 0, 0, 0, 0]);
+
+    let mut aiff = Vec::new();
+    aiff.extend_from_slice(b"FORM");
+    aiff.extend_from_slice(&(4 + 8 + comm.len() as u32 + 16 + data.len() as u32).to_be_bytes());
+    aiff.extend_from_slice(b"AIFFCOMM");
+    aiff.extend_from_slice(&(comm.len() as u32).to_be_bytes());
+    aiff.extend_from_slice(&comm);
+    aiff.extend_from_slice(b"SSND");
+    aiff.extend_from_slice(&(8 + data.len() as u32).to_be_bytes());
+    aiff.extend_from_slice(&[0; 8]);
+    aiff.extend_from_slice(&data);
+
+    let (channels, fs) = read_aiff(aiff.as_slice()).expect("Failed to read AIFF");
+    assert_eq!(fs, 44100);
+    assert_eq!(channels.dim(), (2, 100));
+    let expected = samples();
+    assert_eq!(channels[[0, 3]], expected[6]);
+    assert_eq!(channels[[1, 3]], expected[7]);
+
+    let (detected, _) = read_audio_channels(aiff.as_slice()).expect("Failed to detect AIFF");
+    assert_eq!(detected, channels);
+}
+
+
+// tests that little-endian float CAF is decoded, including a data chunk of unknown size
+#[test]
+fn test_read_caf() {
+    let data: Vec<u8> = samples().iter().flat_map(|v| v.to_le_bytes()).collect();
+
+    let mut desc = Vec::new();
+    desc.extend_from_slice(&48000.0_f64.to_be_bytes());
+    desc.extend_from_slice(b"lpcm");
+    desc.extend_from_slice(&3_u32.to_be_bytes());
+    desc.extend_from_slice(&8_u32.to_be_bytes());
+    desc.extend_from_slice(&1_u32.to_be_bytes());
+    desc.extend_from_slice(&2_u32.to_be_bytes());
+    desc.extend_from_slice(&32_u32.to_be_bytes());
+
+    let mut caf = Vec::new();
+    caf.extend_from_slice(b"caff");
+    caf.extend_from_slice(&1_u16.to_be_bytes());
+    caf.extend_from_slice(&0_u16.to_be_bytes());
+    caf.extend_from_slice(b"desc");
+    caf.extend_from_slice(&(desc.len() as i64).to_be_bytes());
+    caf.extend_from_slice(&desc);
+    caf.extend_from_slice(b"data");
+    caf.extend_from_slice(&(-1_i64).to_be_bytes());
+    caf.extend_from_slice(&[0; 4]);
+    caf.extend_from_slice(&data);
+
+    let (channels, fs) = read_caf(caf.as_slice()).expect("Failed to read CAF");
+    assert_eq!(fs, 48000);
+    assert_eq!(channels.dim(), (2, 100));
+    assert_eq!(channels.row(1).to_vec(), samples().into_iter().skip(1).step_by(2).collect::<Vec<f32>>());
+}
+
+
+// tests that an ADTS stream of 22 silent AAC frames followed by 22 frames of perceptual noise
+// substitution is decoded frame by frame, and that the noise is detected as an onset
+#[cfg(feature = "aac")]
+#[test]
+fn test_read_aac() {
+    let path = "assets/pns_burst.aac";
+    let info = novelty_rust::read_aac_info(std::path::Path::new(path)).expect("Failed to read AAC properties");
+    assert_eq!((info.channels, info.fs), (1, 22050));
+
+    let (channels, fs) = novelty_rust::read_aac(std::path::Path::new(path)).expect("Failed to read AAC");
+    assert_eq!(fs, 22050);
+    assert_eq!(channels.dim(), (1, 44 * 1024));
+    let samples = channels.row(0);
+    // the noise of frame 22 overlaps only into the output of frame 22
+    assert!(samples.slice(ndarray::s![..22 * 1024]).iter().all(|&v| v.abs() < 1e-6));
+    let rms = |frame: usize| samples.slice(ndarray::s![frame * 1024..(frame + 1) * 1024]).mapv(|v| v * v).mean().unwrap().sqrt();
+    let mean_rms = (23..44).map(rms).sum::<f32>() / 21.0;
+    assert!(mean_rms > 1e-4 && mean_rms.is_finite(), "noise RMS {}", mean_rms);
+    assert!((23..44).all(|frame| (rms(frame) / mean_rms - 1.0).abs() < 0.5));
+
+    let (audio_array, fs) = novelty_rust::audio_path_to_array(path).expect("Failed to read AAC");
+    let novelty = novelty_rust::NoveltyConfig::energy().window(1024).hop(256).build().unwrap().compute(audio_array, fs).unwrap();
+    // the noise fades in with the window over the output of frame 22
+    let peak = novelty.values.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).unwrap().0;
+    assert!(peak.abs_diff((22 * 1024 + 512) / 256) <= 4, "peak at frame {}", peak);
+}
+
+
+// tests that local paths pass through the object storage staging unchanged
+#[test]
+fn test_storage_local_paths() {
+    let path = "test_storage_local.csv";
+    if std::path::Path::new(path).exists() {
+        std::fs::remove_file(path).unwrap();
+    }
+    assert!(is_object_url("s3://bucket/key.wav") && is_object_url("gs://bucket/key.wav"));
+    assert!(!is_object_url(path));
+
+    let output = StagedOutput::create(path).unwrap();
+    assert_eq!(output.path(), path);
+    write_path(output.path(), b"time,novelty\n").unwrap();
+    output.upload().unwrap();
+    assert!(path_exists(path).unwrap());
+
+    let input = StagedInput::fetch(path).unwrap();
+    assert_eq!(input.path(), path);
+    assert_eq!(read_path(input.path()).unwrap(), b"time,novelty\n");
+    drop(input);
+    // a local input is no temporary copy and is kept
+    assert!(path_exists(path).unwrap());
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,355 @@
+use std::fs;
+use std::path::Path;
+
+use float_cmp::approx_eq;
+use ndarray::Array1;
+use novelty_rust::{
+    align_onsets, classify_drums, classify_onsets, cluster_iois, detect_onsets, fuse_onsets, ioi_histogram, note_name,
+    onset_attacks, onset_pitches, onset_stability, onset_velocities, pick_peaks, read_annotations, refine_onsets,
+    snap_annotations, sonify_novelty, sparkline, write_midi, Annotation, Carrier, DrumVoice, Jitter, NoveltyConfig,
+    NoveltyCurve, Onset, OnsetKind, PeakPicking, PitchParams, VelocityMapping, VelocitySource,
+};
+
+
+// tests that quadratic interpolation recovers the vertex of a sampled parabola
+#[test]
+fn test_refine_onsets_recovers_parabola_vertex() {
+    let vertex = 10.3_f32;
+    let novelty = NoveltyCurve {
+        values: Array1::from_shape_fn(21, |n| 1.0 - (n as f32 - vertex).powi(2) / 100.0),
+        fs_feature: 100.0,
+    };
+
+    let onsets = detect_onsets(&novelty, 0.1);
+    assert_eq!(onsets.len(), 1);
+    assert_eq!(onsets[0].frame, 10);
+
+    let refined = refine_onsets(&novelty, &onsets);
+    assert_eq!(refined[0].frame, 10);
+    assert!((refined[0].time - vertex / 100.0).abs() < 1e-5, "Unexpected onset time {}", refined[0].time);
+    assert!((refined[0].strength - 1.0).abs() < 1e-4, "Unexpected onset strength {}", refined[0].strength);
+}
+
+
+// tests the librosa peak picker on a hand-computed example
+#[test]
+fn test_pick_peaks_matches_librosa_rules() {
+    let novelty = NoveltyCurve {
+        values: Array1::from_vec(vec![0.0, 1.0, 0.0, 0.0, 3.0, 0.0, 2.0, 0.0, 0.0, 0.0]),
+        fs_feature: 10.0,
+    };
+    let params = PeakPicking { pre_max: 1, post_max: 2, pre_avg: 1, post_avg: 2, delta: 0.5, wait: 0 };
+    let frames = |params: PeakPicking| -> Vec<usize> {
+        pick_peaks(&novelty, &params).expect("Failed to pick peaks").iter().map(|onset| onset.frame).collect()
+    };
+
+    assert_eq!(frames(params), vec![1, 4, 6]);
+    assert_eq!(frames(PeakPicking { wait: 2, ..params }), vec![1, 4]);
+    assert_eq!(frames(PeakPicking { delta: 0.8, ..params }), vec![4, 6]);
+
+    assert!(pick_peaks(&novelty, &PeakPicking { post_max: 0, ..params }).is_err());
+}
+
+
+// tests that a regular onset pattern yields its period as the best IOI cluster
+#[test]
+fn test_cluster_iois_finds_period() {
+    // onsets every 0.5 s with one missing, i.e. 120 BPM
+    let onsets: Vec<Onset> = [0.0, 0.5, 1.0, 2.0, 2.5, 3.0, 3.5]
+        .iter()
+        .enumerate()
+        .map(|(frame, &time)| Onset { frame, time, strength: 1.0 })
+        .collect();
+
+    let clusters = cluster_iois(&onsets, 0.025, 2.5);
+    assert!((clusters[0].bpm - 120.0).abs() < 1e-3, "Unexpected best tempo {}", clusters[0].bpm);
+
+    let histogram = ioi_histogram(&onsets, 0.1, 2.5);
+    assert_eq!(histogram.len(), 25);
+    assert_eq!(histogram[5], 5);
+    assert_eq!(histogram[10], 1);
+}
+
+
+// tests that the preview keeps short peaks and marks the onset columns
+#[test]
+fn test_sparkline_preview() {
+    let mut values = Array1::zeros(100);
+    values[25] = 1.0;
+    values[76] = 0.5;
+    let novelty = NoveltyCurve { values, fs_feature: 100.0 };
+    let onsets = detect_onsets(&novelty, 0.1);
+
+    let preview = sparkline(&novelty, &onsets, 10);
+    let lines: Vec<&str> = preview.lines().collect();
+    assert_eq!(lines[0], "▁▁█▁▁▁▁▅▁▁");
+    assert_eq!(lines[1], "  ^    ^");
+}
+
+
+// tests that detections within the tolerance are merged at the earliest time with their channels
+#[test]
+fn test_fuse_channel_onsets() {
+    let onset = |time: f32, strength: f32| Onset { frame: (time * 100.0).round() as usize, time, strength };
+    let per_channel = vec![
+        vec![onset(0.50, 0.4), onset(1.00, 1.0)],
+        vec![onset(0.51, 0.8)],
+        vec![onset(0.52, 0.2), onset(0.60, 0.3)],
+    ];
+
+    let fused = fuse_onsets(&per_channel, 0.03);
+    assert_eq!(fused.len(), 3);
+    assert_eq!(fused[0].time, 0.50);
+    assert_eq!(fused[0].strength, 0.8);
+    assert_eq!(fused[0].channels, vec![0, 1, 2]);
+    assert_eq!(fused[1].channels, vec![2]);
+    assert_eq!(fused[2].channels, vec![0]);
+}
+
+
+// tests that a click is classified as hard and a faded-in tone as soft
+#[test]
+fn test_classify_onsets() {
+    let fs = 22050;
+    let audio_array = Array1::from_shape_fn(fs as usize, |n| {
+        let t = n as f32 / fs as f32;
+        let click = if n == 5000 { 1.0 } else { 0.0 };
+        let fade = ((t - 0.5) / 0.1).clamp(0.0, 1.0);
+        click + fade * 0.5 * (2.0 * std::f32::consts::PI * 440.0 * t).sin()
+    });
+    let onsets = [5000.0 / fs as f32, 0.5].map(|time| Onset { frame: 0, time, strength: 1.0 });
+
+    let characters = classify_onsets(&audio_array, fs, &onsets, 1024);
+    assert_eq!(characters[0].kind, OnsetKind::Hard);
+    assert_eq!(characters[1].kind, OnsetKind::Soft);
+    assert!(characters[0].brightness > characters[1].brightness);
+    assert!(characters[1].attack_time > 0.05);
+}
+
+
+// tests that the attack of a click is short and the attack of a faded-in tone spans the fade
+#[test]
+fn test_onset_attacks() {
+    let fs = 22050;
+    let audio_array = Array1::from_shape_fn(fs as usize, |n| {
+        let t = n as f32 / fs as f32;
+        let click = if n == 5000 { 1.0 } else { 0.0 };
+        let fade = ((t - 0.5) / 0.1).clamp(0.0, 1.0);
+        click + fade * 0.5 * (2.0 * std::f32::consts::PI * 440.0 * t).sin()
+    });
+    let onsets = [5000.0 / fs as f32, 0.5].map(|time| Onset { frame: 0, time, strength: 1.0 });
+
+    let attacks = onset_attacks(&audio_array, fs, &onsets);
+    assert!(attacks[0].duration < 0.005, "Unexpected attack {:?}", attacks[0]);
+    assert!((attacks[1].start - 0.5).abs() < 0.01, "Unexpected attack {:?}", attacks[1]);
+    assert!(attacks[1].duration > 0.08, "Unexpected attack {:?}", attacks[1]);
+}
+
+
+// tests that low, mid, and high bursts are classified as kick, snare, and hat
+#[test]
+fn test_classify_drums() {
+    let fs = 22050;
+    let voices = [[60.0, 60.0], [1000.0, 2500.0], [7000.0, 9000.0]];
+    let audio_array = Array1::from_shape_fn(fs as usize * 3 / 2, |n| {
+        let t = n as f32 / fs as f32;
+        let (k, offset) = ((t / 0.5) as usize, t % 0.5 - 0.25);
+        if offset < 0.0 || k >= voices.len() {
+            return 0.0;
+        }
+        let burst = |frequency: f32| (2.0 * std::f32::consts::PI * frequency * offset).sin();
+        (-offset / 0.05).exp() * 0.5 * (burst(voices[k][0]) + burst(voices[k][1]))
+    });
+    let onsets = [0.25, 0.75, 1.25].map(|time| Onset { frame: 0, time, strength: 1.0 });
+
+    let hits = classify_drums(&audio_array, fs, &onsets, 1024);
+    assert_eq!(hits.iter().map(|hit| hit.voice).collect::<Vec<_>>(), [DrumVoice::Kick, DrumVoice::Snare, DrumVoice::Hat]);
+    assert!(hits.iter().all(|hit| (hit.low + hit.mid + hit.high - 1.0).abs() < 1e-4));
+}
+
+
+// tests that the pitches of harmonic notes are found and silence is unvoiced
+#[test]
+fn test_onset_pitches() {
+    let fs = 22050;
+    let frequencies = [220.0, 440.0, 659.26];
+    let audio_array = Array1::from_shape_fn(fs as usize * 6 / 5, |n| {
+        let t = n as f32 / fs as f32;
+        let Some(&frequency) = frequencies.get((t / 0.3) as usize) else {
+            return 0.0;
+        };
+        (1..=3).map(|k| (2.0 * std::f32::consts::PI * k as f32 * frequency * t).sin() / k as f32).sum()
+    });
+    let onsets = [0.0, 0.3, 0.6, 0.9].map(|time| Onset { frame: 0, time, strength: 1.0 });
+
+    let pitches = onset_pitches(&audio_array, fs, &onsets, &PitchParams::default()).unwrap();
+    assert_eq!(pitches.iter().map(|pitch| pitch.midi_note()).collect::<Vec<_>>(), [Some(57), Some(69), Some(76), None]);
+    assert!((pitches[1].frequency.unwrap() - 440.0).abs() < 2.0, "Unexpected pitch {:?}", pitches[1]);
+    assert_eq!(note_name(69), "A4");
+    assert_eq!(note_name(61), "C#4");
+    assert!(onset_pitches(&audio_array, fs, &onsets, &PitchParams { window: 0.01, ..PitchParams::default() }).is_err());
+}
+
+
+// tests that peak heights are mapped to velocities along the curve and written as MIDI notes
+#[test]
+fn test_midi_velocities() {
+    let path = "midi_velocities.mid";
+    if Path::new(path).exists() {
+        fs::remove_file(path).unwrap();
+    }
+    let onsets = [(0.0, 0.5), (0.5, 1.0)].map(|(time, strength)| Onset { frame: 0, time, strength });
+
+    assert_eq!(onset_velocities(&onsets, None, &VelocityMapping::default()).unwrap(), [64, 127]);
+    let curved = VelocityMapping { curve: 2.0, ..VelocityMapping::default() };
+    assert_eq!(onset_velocities(&onsets, None, &curved).unwrap(), [33, 127]);
+    let loudness = VelocityMapping { source: VelocitySource::Loudness, ..VelocityMapping::default() };
+    assert!(onset_velocities(&onsets, None, &loudness).is_err());
+    assert!(onset_velocities(&onsets, None, &VelocityMapping { min: 100, max: 50, ..VelocityMapping::default() }).is_err());
+
+    write_midi(path, &onsets, &[64, 127], 36, 10).unwrap();
+    let bytes = fs::read(path).unwrap();
+    assert_eq!(&bytes[..4], b"MThd");
+    assert_eq!(&bytes[14..18], b"MTrk");
+    assert_eq!(bytes.len(), 22 + 28);
+    // the second note on 384 ticks after the first note off
+    assert!(bytes.windows(5).any(|window| window == [0x83, 0x00, 0x99, 36, 127]));
+    fs::remove_file(path).unwrap();
+}
+
+
+// tests that clear clicks are detected by all jittered runs
+#[test]
+fn test_onset_stability() {
+    let fs = 22050;
+    let audio_array = Array1::from_shape_fn(fs as usize * 2, |n| if n % 11025 == 5000 { 1.0 } else { 0.0 });
+    let config = NoveltyConfig::energy().build().unwrap();
+
+    let onsets = onset_stability(&audio_array, fs, &config, 0.1, &Jitter::default(), 0.03).unwrap();
+    assert_eq!(onsets.len(), 4);
+    assert!(onsets.iter().all(|onset| onset.stability == 1.0));
+
+    let no_runs = Jitter { window_factors: vec![], ..Jitter::default() };
+    assert!(onset_stability(&audio_array, fs, &config, 0.1, &no_runs, 0.03).is_err());
+}
+
+
+// tests that Audacity labels are read and aligned with detections as matches, misses, and extras
+#[test]
+fn test_align_annotations() {
+    let path = "test_align_annotations.txt";
+    if Path::new(path).exists() {
+        fs::remove_file(path).unwrap();
+    }
+    fs::write(path, "0.500000\t0.500000\tkick\n\\\t100.0\t200.0\n1.000000\t1.200000\tsnare\n2.000000\t2.000000\n").unwrap();
+    let annotations = read_annotations(path).unwrap();
+    fs::remove_file(path).unwrap();
+
+    assert_eq!(annotations.len(), 3);
+    assert_eq!(annotations[0].label.as_deref(), Some("kick"));
+    assert_eq!(annotations[1].time, 1.0);
+    assert_eq!(annotations[2].label, None);
+
+    let alignments = align_onsets(&[0.52, 0.98, 1.03, 3.0], &annotations, 0.05);
+    assert_eq!(alignments.len(), 5);
+    assert!((alignments[0].deviation().unwrap() - 0.02).abs() < 1e-6);
+    // the closer detection wins the annotation, the other one is extra
+    assert_eq!(alignments[1].detection, Some(0.98));
+    assert!(alignments[1].annotation.is_some());
+    assert!(alignments.iter().any(|alignment| alignment.detection == Some(1.03) && alignment.annotation.is_none()));
+    assert!(alignments.iter().any(|alignment| alignment.time() == 2.0 && alignment.detection.is_none()));
+    assert_eq!(alignments.iter().filter(|alignment| alignment.deviation().is_some()).count(), 2);
+}
+
+
+// tests that annotations snap to the nearest peak within the tolerance and keep their time otherwise
+#[test]
+fn test_snap_annotations() {
+    let annotations: Vec<Annotation> = [0.48, 0.9, 2.0]
+        .iter()
+        .map(|&time| Annotation { time, label: Some("x".to_string()) })
+        .collect();
+
+    let snapped = snap_annotations(&[0.5, 0.55, 0.93, 1.0], &annotations, 0.05);
+    assert_eq!(snapped.len(), 3);
+    assert_eq!(snapped[0].detection, Some(0.5));
+    assert_eq!(snapped[1].detection, Some(0.93));
+    assert_eq!(snapped[2].detection, None);
+    assert_eq!(snapped[2].time(), 2.0);
+}
+
+
+// tests that a post-processing script transforms the novelty function and filters onsets
+#[cfg(all(feature = "wav", feature = "scripting"))]
+#[test]
+fn test_post_script() {
+    use novelty_rust::{audio_path_to_array, PostScript};
+
+    let path = "test_post_script.rhai";
+    if Path::new(path).exists() {
+        fs::remove_file(path).unwrap();
+    }
+    fs::write(path, "fn novelty(values, fs_feature) { values.map(|v| v * 2.0) }\nfn onset(time, strength) { time >= 1.0 }\n").unwrap();
+    let script = PostScript::load(Path::new(path));
+    let config = NoveltyConfig::energy().norm(false).post_script(Some(path.into())).build().unwrap();
+    let (audio_array, fs) = audio_path_to_array("assets/LJ037-0171.wav").expect("Failed to read audio");
+    let scripted = config.compute(audio_array, fs);
+    fs::remove_file(path).unwrap();
+    let script = script.unwrap();
+
+    let values = Array1::from_vec(vec![0.0, 0.5, 1.0]);
+    assert_eq!(script.transform_novelty(&values, 100.0).unwrap().to_vec(), vec![0.0, 1.0, 2.0]);
+    let (audio_array, fs) = audio_path_to_array("assets/LJ037-0171.wav").expect("Failed to read audio");
+    let plain = NoveltyConfig::energy().norm(false).build().unwrap().compute(audio_array, fs).unwrap();
+    assert_eq!(scripted.unwrap().values, plain.values.mapv(|v| v * 2.0));
+
+    let onsets = vec![Onset { frame: 43, time: 0.5, strength: 1.0 }, Onset { frame: 129, time: 1.5, strength: 0.5 }];
+    let kept = script.filter_onsets(onsets).unwrap();
+    assert_eq!(kept.len(), 1);
+    assert_eq!(kept[0].time, 1.5);
+}
+
+
+// tests that the sonification has the length of the audio, follows the novelty function as
+// envelope of the carrier, peaks where the novelty function peaks, and stays in phase on long inputs
+#[test]
+fn test_sonify() {
+    let fs = 8000;
+    // 80 samples per frame, and a tone with a period of 4 samples whose maxima are at 1 mod 4
+    let tone = |n: usize| (2.0 * std::f64::consts::PI * 2000.0 * n as f64 / fs as f64).sin() as f32;
+
+    let mut values = Array1::zeros(50);
+    values[20] = 2.0;
+    let novelty = NoveltyCurve { values, fs_feature: 100.0 };
+    let audio = sonify_novelty(&novelty, fs, Carrier::Tone(2000.0));
+    assert_eq!(audio.len(), 50 * 80);
+    assert!(audio.iter().take(19 * 80).chain(audio.iter().skip(21 * 80)).all(|&value| value == 0.0));
+    // the envelope rises linearly from frame 19 to 1 at frame 20
+    for n in [1521, 1561, 1601, 1641] {
+        let envelope = 1.0 - (n as f32 - 1600.0).abs() / 80.0;
+        assert!(approx_eq!(f32, audio[n], envelope * tone(n), epsilon = 1e-3), "sample {}: {}", n, audio[n]);
+    }
+    let peak = (0..audio.len()).max_by(|&a, &b| audio[a].abs().total_cmp(&audio[b].abs())).unwrap();
+    assert!(peak.abs_diff(20 * 80) <= 1, "peak at sample {}", peak);
+
+    // a constant novelty function leaves the carrier unchanged
+    let novelty = NoveltyCurve { values: Array1::from_elem(50, 0.5), fs_feature: 100.0 };
+    let audio = sonify_novelty(&novelty, fs, Carrier::Tone(2000.0));
+    assert!(audio.iter().enumerate().all(|(n, &value)| approx_eq!(f32, value, tone(n), epsilon = 2e-3)));
+    let audio = sonify_novelty(&novelty, fs, Carrier::Noise);
+    assert!(audio.iter().all(|value| value.abs() <= 1.0));
+    let mean = audio.sum() / audio.len() as f32;
+    let mean_square = audio.mapv(|value| value * value).sum() / audio.len() as f32;
+    assert!(mean.abs() < 0.05 && (mean_square - 1.0 / 3.0).abs() < 0.05, "mean {}, mean square {}", mean, mean_square);
+
+    // the frame rate of the CLI isn't a divisor of the sampling rate
+    let novelty = NoveltyCurve { values: Array1::zeros(1307), fs_feature: 22050.0 / 256.0 };
+    assert_eq!(sonify_novelty(&novelty, 22050, Carrier::Noise).len(), 1307 * 256);
+
+    // the carrier keeps its phase beyond 2^24 samples, which single precision can't count
+    let novelty = NoveltyCurve { values: Array1::from_elem(210_000, 0.5), fs_feature: 100.0 };
+    let audio = sonify_novelty(&novelty, fs, Carrier::Tone(2000.0));
+    for n in [(1 << 24) + 1, (1 << 24) + 2, audio.len() - 3] {
+        assert!(approx_eq!(f32, audio[n], tone(n), epsilon = 2e-3), "sample {}: {}", n, audio[n]);
+    }
+}
@@ -4,6 +4,7 @@ use std::path::Path;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use float_cmp::approx_eq;
+use novelty_rust::{write_markers, Marker, MarkerFormat, Timecode};
 
 
 /// Reads the `time` and `novelty` columns from a CSV file.
@@ -29,6 +30,238 @@ fn load_csv(path: &str) -> Vec<(f32, f32)> {
         .collect()
 }
 
+/// Sends an HTTP GET request and reads the response until the server closes the connection.
+///
+/// # Returns
+/// - The status line and headers, and the body, or `None` if the server can't be reached
+fn http_get(address: &str, path: &str) -> Option<(String, Vec<u8>)> {
+    use std::io::{Read, Write};
+
+    let mut stream = std::net::TcpStream::connect(address).ok()?;
+    write!(stream, "GET {} HTTP/1.1\r\nHost: {}\r\n\r\n", path, address).ok()?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).ok()?;
+    let split = response.windows(4).position(|window| window == b"\r\n\r\n")?;
+    Some((String::from_utf8_lossy(&response[..split]).into_owned(), response[split + 4..].to_vec()))
+}
+
+/// Object read back from an HDF5 file by [`read_hdf5`], either a group with links to other
+/// objects or a `float32` dataset, with its attributes formatted as text.
+#[derive(Debug, Default)]
+struct Hdf5Object {
+    attributes: Vec<(String, String)>,
+    links: Vec<(String, Hdf5Object)>,
+    shape: Vec<u64>,
+    values: Vec<f32>,
+}
+
+impl Hdf5Object {
+    /// Returns the value of an attribute.
+    fn attribute(&self, name: &str) -> &str {
+        let attribute = self.attributes.iter().find(|(other, _)| other == name);
+        attribute.map(|(_, value)| value.as_str()).unwrap_or_else(|| panic!("Missing attribute {}", name))
+    }
+
+    /// Returns the object a link points to.
+    fn link(&self, name: &str) -> &Hdf5Object {
+        let link = self.links.iter().find(|(other, _)| other == name);
+        link.map(|(_, object)| object).unwrap_or_else(|| panic!("Missing link {}", name))
+    }
+}
+
+/// Returns a little-endian unsigned integer of up to 8 bytes.
+fn le(bytes: &[u8]) -> u64 {
+    bytes.iter().rev().fold(0, |acc, &b| (acc << 8) | b as u64)
+}
+
+/// Jenkins' lookup3 `hashlittle` with an initial value of 0, the checksum of HDF5 metadata,
+/// written after the reference implementation to check the writer independently.
+fn hashlittle(key: &[u8]) -> u32 {
+    let [mut a, mut b, mut c] = [0xdeadbeef_u32.wrapping_add(key.len() as u32); 3];
+    let mut blocks = key.chunks(12).peekable();
+    while let Some(block) = blocks.next() {
+        let mut words = [0_u8; 12];
+        words[..block.len()].copy_from_slice(block);
+        a = a.wrapping_add(le(&words[0..4]) as u32);
+        b = b.wrapping_add(le(&words[4..8]) as u32);
+        c = c.wrapping_add(le(&words[8..12]) as u32);
+        if blocks.peek().is_some() {
+            // mix
+            a = a.wrapping_sub(c) ^ c.rotate_left(4);
+            c = c.wrapping_add(b);
+            b = b.wrapping_sub(a) ^ a.rotate_left(6);
+            a = a.wrapping_add(c);
+            c = c.wrapping_sub(b) ^ b.rotate_left(8);
+            b = b.wrapping_add(a);
+            a = a.wrapping_sub(c) ^ c.rotate_left(16);
+            c = c.wrapping_add(b);
+            b = b.wrapping_sub(a) ^ a.rotate_left(19);
+            a = a.wrapping_add(c);
+            c = c.wrapping_sub(b) ^ b.rotate_left(4);
+            b = b.wrapping_add(a);
+        } else {
+            // final
+            c = (c ^ b).wrapping_sub(b.rotate_left(14));
+            a = (a ^ c).wrapping_sub(c.rotate_left(11));
+            b = (b ^ a).wrapping_sub(a.rotate_left(25));
+            c = (c ^ b).wrapping_sub(b.rotate_left(16));
+            a = (a ^ c).wrapping_sub(c.rotate_left(4));
+            b = (b ^ a).wrapping_sub(a.rotate_left(14));
+            c = (c ^ b).wrapping_sub(b.rotate_left(24));
+        }
+    }
+    c
+}
+
+/// Reads the groups, `float32` datasets, and scalar attributes of an HDF5 file with a version 2
+/// superblock and version 2 object headers, following the HDF5 file format specification and
+/// checking every checksum.
+fn read_hdf5(path: &str) -> Hdf5Object {
+    let bytes = fs::read(path).unwrap();
+    assert_eq!(&bytes[..8], b"\x89HDF\r\n\x1a\n");
+    // version 2 with 8-byte addresses and lengths, no base address and no extension
+    assert_eq!(&bytes[8..11], &[2, 8, 8]);
+    assert_eq!(le(&bytes[12..20]), 0);
+    assert_eq!(le(&bytes[20..28]), u64::MAX);
+    assert_eq!(le(&bytes[28..36]), bytes.len() as u64, "End of file address");
+    assert_eq!(hashlittle(&bytes[..44]), le(&bytes[44..48]) as u32, "Superblock checksum");
+    read_hdf5_object(&bytes, le(&bytes[36..44]) as usize)
+}
+
+/// Reads the object whose header is at `address`, and the objects it links to.
+fn read_hdf5_object(bytes: &[u8], address: usize) -> Hdf5Object {
+    assert_eq!(&bytes[address..address + 5], b"OHDR\x02", "Object header at {}", address);
+    let flags = bytes[address + 5];
+    // optional times and attribute phase change values, then the size of the first chunk
+    let mut position = address + 6 + if flags & 0x20 != 0 { 16 } else { 0 } + if flags & 0x10 != 0 { 4 } else { 0 };
+    let size_length = 1 << (flags & 0x03);
+    let end = position + size_length + le(&bytes[position..position + size_length]) as usize;
+    position += size_length;
+    assert_eq!(hashlittle(&bytes[address..end]), le(&bytes[end..end + 4]) as u32, "Object header checksum at {}", address);
+
+    let mut object = Hdf5Object::default();
+    let mut layout = None;
+    // a gap shorter than a message header may follow the messages
+    while position + 4 <= end {
+        let (kind, size) = (bytes[position], le(&bytes[position + 1..position + 3]) as usize);
+        position += 4 + if flags & 0x04 != 0 { 2 } else { 0 };
+        let data = &bytes[position..position + size];
+        position += size;
+        match kind {
+            // dataspace
+            0x01 => object.shape = read_hdf5_dataspace(data),
+            // datatype of a dataset, little-endian IEEE float32
+            0x03 => assert_eq!(read_hdf5_value(data, &[0; 4]).0, "float32"),
+            // hard link
+            0x06 => {
+                let (name, target) = read_hdf5_link(data);
+                object.links.push((name, read_hdf5_object(bytes, target)));
+            }
+            // contiguous data layout, version 3
+            0x08 => {
+                assert_eq!(&data[..2], &[3, 1]);
+                layout = Some((le(&data[2..10]), le(&data[10..18]) as usize));
+            }
+            // attribute, version 3
+            0x0C => object.attributes.push(read_hdf5_attribute(data)),
+            // NIL, link info, fill value, and group info
+            0x00 | 0x02 | 0x05 | 0x0A => {}
+            _ => panic!("Unexpected message type {} at {}", kind, position),
+        }
+    }
+    assert!(position <= end);
+
+    if let Some((data_address, size)) = layout {
+        assert_eq!(size as u64, 4 * object.shape.iter().product::<u64>());
+        if data_address != u64::MAX {
+            let data = &bytes[data_address as usize..data_address as usize + size];
+            object.values = data.chunks(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect();
+        }
+    }
+    object
+}
+
+/// Returns the dimensions of a dataspace message, empty for a scalar.
+fn read_hdf5_dataspace(data: &[u8]) -> Vec<u64> {
+    let (version, rank) = (data[0], data[1] as usize);
+    let start = match version {
+        1 => 8,
+        2 => {
+            assert!(data[3] < 2, "Null dataspace");
+            4
+        }
+        _ => panic!("Unexpected dataspace version {}", version),
+    };
+    (0..rank).map(|k| le(&data[start + 8 * k..start + 8 * k + 8])).collect()
+}
+
+/// Returns the name and address of a hard link message.
+fn read_hdf5_link(data: &[u8]) -> (String, usize) {
+    assert_eq!(data[0], 1);
+    let flags = data[1];
+    let mut position = 2;
+    if flags & 0x08 != 0 {
+        assert_eq!(data[position], 0, "Only hard links are expected");
+        position += 1;
+    }
+    if flags & 0x04 != 0 {
+        position += 8;
+    }
+    if flags & 0x10 != 0 {
+        position += 1;
+    }
+    let size_length = 1 << (flags & 0x03);
+    let length = le(&data[position..position + size_length]) as usize;
+    position += size_length;
+    let name = String::from_utf8(data[position..position + length].to_vec()).unwrap();
+    (name, le(&data[position + length..position + length + 8]) as usize)
+}
+
+/// Returns the name and the value of a scalar attribute message.
+fn read_hdf5_attribute(data: &[u8]) -> (String, String) {
+    assert_eq!(data[0], 3);
+    let (name_size, datatype_size, dataspace_size) = (le(&data[2..4]) as usize, le(&data[4..6]) as usize, le(&data[6..8]) as usize);
+    let name = String::from_utf8(data[9..9 + name_size - 1].to_vec()).unwrap();
+    let datatype = &data[9 + name_size..9 + name_size + datatype_size];
+    let dataspace = &data[9 + name_size + datatype_size..9 + name_size + datatype_size + dataspace_size];
+    assert!(read_hdf5_dataspace(dataspace).is_empty(), "Attribute {} isn't scalar", name);
+    let value = &data[9 + name_size + datatype_size + dataspace_size..];
+    let (text, size) = read_hdf5_value(datatype, value);
+    assert_eq!(value.len(), size, "Size of attribute {}", name);
+    (name, text)
+}
+
+/// Returns a value of a little-endian integer, float, or string datatype message formatted
+/// as text, `float32` for the type of a 4-byte float, and the size of the type.
+fn read_hdf5_value(datatype: &[u8], value: &[u8]) -> (String, usize) {
+    let (class, version, size) = (datatype[0] & 0x0f, datatype[0] >> 4, le(&datatype[4..8]) as usize);
+    assert_eq!(version, 1);
+    assert_eq!(datatype[1] & 0x01, 0, "Big-endian type");
+    let text = match (class, size) {
+        // signed 64-bit integer
+        (0, 8) => {
+            assert_ne!(datatype[1] & 0x08, 0);
+            (le(&value[..8]) as i64).to_string()
+        }
+        // IEEE float with sign, exponent, and mantissa at their standard positions
+        (1, 4 | 8) => {
+            let (exponent_size, mantissa_size, bias) = if size == 4 { (8, 23, 127) } else { (11, 52, 1023) };
+            assert_eq!(&datatype[1..4], &[0x20, 8 * size as u8 - 1, 0]);
+            assert_eq!(&datatype[8..12], &[0, 0, 8 * size as u8, 0]);
+            assert_eq!(&datatype[12..16], &[mantissa_size, exponent_size, 0, mantissa_size]);
+            assert_eq!(le(&datatype[16..20]), bias);
+            match size {
+                4 => "float32".to_string(),
+                _ => f64::from_le_bytes(value[..8].try_into().unwrap()).to_string(),
+            }
+        }
+        // null-terminated string
+        (3, _) => String::from_utf8(value[..size].split(|&b| b == 0).next().unwrap().to_vec()).unwrap(),
+        _ => panic!("Unexpected datatype class {} of {} bytes", class, size),
+    };
+    (text, size)
+}
+
 
 // tests a computed CSV file against a reference CSV file
 #[test]
@@ -85,4 +318,1728 @@ fn test_novelty_output_against_reference() {
             n_act
         );
     }
-}
\ No newline at end of file
+}
+
+// tests that the analyze subcommand writes a complete JSON report
+#[test]
+fn test_analyze_report() {
+    let test_audio = "assets/LJ037-0171.wav";
+    let generated_json = "LJ037-0171_analyze.json";
+
+    if Path::new(generated_json).exists() {
+        fs::remove_file(generated_json).unwrap();
+    }
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args([
+            "analyze",
+            test_audio,
+            generated_json,
+            "--window-length", "2048",
+            "--hop-length", "128",
+        ])
+        .status()
+        .expect("Failed to execute program");
+
+    assert!(status.success());
+
+    let report: serde_json::Value = serde_json::from_reader(File::open(generated_json).unwrap()).expect("Failed to parse report");
+    let ref_data = load_csv("reference/LJ037-0171.csv");
+
+    assert_eq!(report["path"], test_audio);
+    assert_eq!(report["stats"]["sample_rate"], 22050);
+    assert!(report["tempo"].is_number(), "Missing tempo estimate");
+    assert!(!report["onsets"].as_array().unwrap().is_empty(), "No onsets detected");
+    assert_eq!(report["novelty"]["values"]["data"].as_array().unwrap().len(), ref_data.len());
+}
+
+
+// tests that the downstream stages accept a precomputed novelty CSV as input
+#[test]
+fn test_stages_accept_novelty_csv() {
+    let reference_csv = "reference/LJ037-0171.csv";
+    let feature_rate = (22050.0 / 128.0).to_string();
+
+    for stage in ["onsets", "tempo", "beats"] {
+        let generated_csv = format!("LJ037-0171_{}.csv", stage);
+        if Path::new(&generated_csv).exists() {
+            fs::remove_file(&generated_csv).unwrap();
+        }
+
+        let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+            .args([stage, reference_csv, &generated_csv, "--feature-rate", &feature_rate])
+            .status()
+            .expect("Failed to execute program");
+        assert!(status.success(), "Stage {} failed", stage);
+
+        let rows = BufReader::new(File::open(&generated_csv).unwrap()).lines().count();
+        assert!(rows > 1, "Stage {} wrote no results", stage);
+    }
+}
+
+
+// tests that the tempogram is exported as CSV, NumPy array, and PNG heatmap
+#[test]
+fn test_tempogram_export() {
+    let test_audio = "assets/LJ037-0171.wav";
+    let outputs = ["LJ037-0171_tempo_export.csv", "LJ037-0171_tempogram.csv", "LJ037-0171_tempogram.npy", "LJ037-0171_tempogram.png"];
+    for path in outputs {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["tempo", test_audio, outputs[0], "--tempogram", outputs[1], "--tempogram", outputs[2], "--tempogram", outputs[3]])
+        .status()
+        .expect("Failed to execute program");
+
+    assert!(status.success());
+
+    let lines: Vec<String> = BufReader::new(File::open(outputs[1]).unwrap()).lines().map(|l| l.unwrap()).collect();
+    assert!(lines[0].starts_with("time,30,31,"), "Unexpected header: {}", &lines[0][..20]);
+    assert_eq!(lines[1].split(',').count(), 272);
+    assert!(fs::read(outputs[2]).unwrap().starts_with(b"\x93NUMPY"));
+    let png = fs::read(outputs[3]).unwrap();
+    assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+    // the image is one pixel per frame wide and one pixel per tempo high
+    assert_eq!(u32::from_be_bytes(png[16..20].try_into().unwrap()) as usize, lines.len() - 1);
+    assert_eq!(u32::from_be_bytes(png[20..24].try_into().unwrap()), 271);
+}
+
+
+// tests that onsets are exported as REAPER markers and segments as regions, and beats as
+// Ardour locations
+#[test]
+fn test_daw_markers() {
+    let test_audio = "assets/LJ037-0171.wav";
+    let outputs = [
+        "LJ037-0171_marker_onsets.csv",
+        "LJ037-0171_markers.csv",
+        "LJ037-0171_region_onsets.csv",
+        "LJ037-0171_regions.csv",
+        "LJ037-0171_marker_beats.csv",
+        "LJ037-0171_locations.xml",
+    ];
+    for path in outputs {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    for (path_out, path_markers, regions) in [(outputs[0], outputs[1], false), (outputs[2], outputs[3], true)] {
+        let mut args = vec!["onsets", test_audio, path_out, "--markers", path_markers];
+        if regions {
+            args.push("--marker-regions");
+        }
+        let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust")).args(&args).status().expect("Failed to execute program");
+        assert!(status.success());
+    }
+
+    let onsets = BufReader::new(File::open(outputs[0]).unwrap()).lines().count() - 1;
+    let markers: Vec<String> = BufReader::new(File::open(outputs[1]).unwrap()).lines().map(|l| l.unwrap()).collect();
+    assert_eq!(markers[0], "#,Name,Start,End,Length");
+    assert_eq!(markers.len(), onsets + 1);
+    assert!(markers[1].starts_with("M1,\"Onset 1\",0:0") && markers[1].ends_with(",,"), "Unexpected marker {}", markers[1]);
+
+    let regions: Vec<String> = BufReader::new(File::open(outputs[3]).unwrap()).lines().map(|l| l.unwrap()).collect();
+    assert_eq!(regions.len(), onsets + 1);
+    assert!(regions[1].starts_with("R1,\"Segment 1\","), "Unexpected region {}", regions[1]);
+    assert_eq!(regions[1].split(',').count(), 5);
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["beats", test_audio, outputs[4], "--markers", outputs[5], "--marker-format", "ardour"])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+
+    let beats = BufReader::new(File::open(outputs[4]).unwrap()).lines().count() - 1;
+    let locations = fs::read_to_string(outputs[5]).unwrap();
+    assert!(locations.starts_with("<Locations>\n") && locations.ends_with("</Locations>\n"));
+    assert_eq!(locations.matches("flags=\"IsMark\"").count(), beats);
+    assert!(locations.contains("name=\"Beat 1\""));
+}
+
+// tests that markers and regions are written as EDL events and FCPXML markers on frames
+#[test]
+fn test_video_markers() {
+    let (path_edl, path_fcpxml) = ("video_markers.edl", "video_markers.fcpxml");
+    for path in [path_edl, path_fcpxml] {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+    let markers = [
+        Marker { name: "Onset 1".to_string(), start: 0.52, end: None },
+        Marker { name: "Segment <1>".to_string(), start: 1.0, end: Some(2.0) },
+    ];
+    let timecode = Timecode::new(25.0, "10:00:00:00").unwrap();
+    assert!(write_markers(path_edl, &markers, MarkerFormat::Edl, None, None).is_err());
+
+    write_markers(path_edl, &markers, MarkerFormat::Edl, None, Some(timecode)).unwrap();
+    let edl: Vec<String> = fs::read_to_string(path_edl).unwrap().lines().map(String::from).collect();
+    assert_eq!(edl[1], "FCM: NON-DROP FRAME");
+    assert_eq!(edl[3], "001  001      V     C        10:00:00:13 10:00:00:14 10:00:00:13 10:00:00:14");
+    assert_eq!(edl[4], " |C:ResolveColorBlue |M:Onset 1 |D:1");
+    assert_eq!(edl[7], " |C:ResolveColorBlue |M:Segment <1> |D:25");
+
+    write_markers(path_fcpxml, &markers, MarkerFormat::Fcpxml, None, Some(timecode)).unwrap();
+    let fcpxml = fs::read_to_string(path_fcpxml).unwrap();
+    assert!(fcpxml.contains("frameDuration=\"1/25s\""));
+    assert!(fcpxml.contains("tcStart=\"36000s\" tcFormat=\"NDF\""));
+    assert!(fcpxml.contains("<marker start=\"900013/25s\" duration=\"1/25s\" value=\"Onset 1\"/>"));
+    assert!(fcpxml.contains("<marker start=\"36001s\" duration=\"1s\" value=\"Segment &lt;1&gt;\"/>"));
+    for path in [path_edl, path_fcpxml] {
+        fs::remove_file(path).unwrap();
+    }
+
+    // EDL and FCPXML markers of the CLI take the frame rate of the timecode options
+    let test_audio = "assets/LJ037-0171.wav";
+    let (path_onsets, path_beats) = ("LJ037-0171_video_onsets.csv", "LJ037-0171_video_beats.csv");
+    for path in [path_onsets, path_beats] {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+    let output = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["onsets", test_audio, path_onsets, "--markers", path_edl, "--marker-format", "edl"])
+        .output()
+        .expect("Failed to execute program");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--timecode-fps"));
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["onsets", test_audio, path_onsets, "--markers", path_edl, "--marker-format", "edl", "--timecode-fps", "25", "--timecode-start", "10:00:00:00"])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+    let onsets = BufReader::new(File::open(path_onsets).unwrap()).lines().count() - 1;
+    let edl = fs::read_to_string(path_edl).unwrap();
+    assert_eq!(edl.matches("|M:Onset ").count(), onsets);
+    assert!(edl.contains(" 10:00:0"));
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["beats", test_audio, path_beats, "--markers", path_fcpxml, "--marker-format", "fcpxml", "--timecode-fps", "25"])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+    let beats: Vec<String> = BufReader::new(File::open(path_beats).unwrap()).lines().map(|l| l.unwrap()).collect();
+    assert!(beats[1].starts_with("00:00:0"), "Unexpected beat {}", beats[1]);
+    let fcpxml = fs::read_to_string(path_fcpxml).unwrap();
+    assert_eq!(fcpxml.matches("<marker ").count(), beats.len() - 1);
+    for path in [path_onsets, path_beats, path_edl, path_fcpxml] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+
+// tests that requested descriptors are appended as CSV columns
+#[test]
+fn test_descriptor_columns() {
+    let test_audio = "assets/LJ037-0171.wav";
+    let generated_csv = "LJ037-0171_descriptors.csv";
+
+    if Path::new(generated_csv).exists() {
+        fs::remove_file(generated_csv).unwrap();
+    }
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args([
+            test_audio,
+            generated_csv,
+            "--window-length", "2048",
+            "--hop-length", "128",
+            "--descriptors", "centroid,rolloff,flatness",
+        ])
+        .status()
+        .expect("Failed to execute program");
+
+    assert!(status.success());
+
+    let lines: Vec<String> = BufReader::new(File::open(generated_csv).unwrap()).lines().map(|l| l.unwrap()).collect();
+    assert_eq!(lines[0], "time,novelty,centroid,rolloff,flatness");
+    assert_eq!(lines.len(), load_csv("reference/LJ037-0171.csv").len() + 1);
+    for line in &lines[1..] {
+        let values: Vec<f32> = line.split(',').map(|v| v.parse().unwrap()).collect();
+        assert_eq!(values.len(), 5);
+        assert!(values[2] >= 0.0 && values[2] <= 11025.0, "Centroid out of range: {}", line);
+        assert!(values[3] >= 0.0 && values[3] <= 11025.0, "Rolloff out of range: {}", line);
+        assert!(values[4] >= 0.0 && values[4] <= 1.0 + 1e-3, "Flatness out of range: {}", line);
+    }
+}
+
+
+// tests the librosa compatibility mode against a reference CSV file created by librosa
+#[test]
+#[ignore = "requires reference/LJ037-0171_librosa.csv, see reference/README.md"]
+fn test_librosa_compat_against_reference() {
+    let test_audio = "assets/LJ037-0171.wav";
+    let generated_csv = "LJ037-0171_librosa.csv";
+    let reference_csv = "reference/LJ037-0171_librosa.csv";
+
+    if Path::new(generated_csv).exists() {
+        fs::remove_file(generated_csv).unwrap();
+    }
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args([test_audio, generated_csv, "--compat", "librosa"])
+        .status()
+        .expect("Failed to execute program");
+
+    assert!(status.success());
+
+    let ref_data = load_csv(reference_csv);
+    let act_data = load_csv(generated_csv);
+
+    assert_eq!(
+        ref_data.len(),
+        act_data.len(),
+        "CSV files have different number of rows"
+    );
+
+    // onset strength is in dB, so allow a slightly larger absolute deviation
+    let tol = 1e-2;
+
+    for (i, ((_, n_ref), (_, n_act))) in ref_data.iter().zip(act_data.iter()).enumerate() {
+        assert!(
+            approx_eq!(f32, *n_ref, *n_act, epsilon = tol),
+            "Novelty mismatch at index {}: expected {}, got {}",
+            i,
+            n_ref,
+            n_act
+        );
+    }
+}
+
+
+// tests the FMP compatibility mode of the spectral method against a reference CSV file created by libfmp
+#[test]
+#[ignore = "requires reference/LJ037-0171_fmp_spectrum.csv, see reference/README.md"]
+fn test_fmp_spectrum_compat_against_reference() {
+    let test_audio = "assets/LJ037-0171.wav";
+    let generated_csv = "LJ037-0171_fmp_spectrum.csv";
+    let reference_csv = "reference/LJ037-0171_fmp_spectrum.csv";
+
+    if Path::new(generated_csv).exists() {
+        fs::remove_file(generated_csv).unwrap();
+    }
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args([test_audio, generated_csv, "--compat", "fmp", "--method", "spectral"])
+        .status()
+        .expect("Failed to execute program");
+
+    assert!(status.success());
+
+    let ref_data = load_csv(reference_csv);
+    let act_data = load_csv(generated_csv);
+
+    assert_eq!(
+        ref_data.len(),
+        act_data.len(),
+        "CSV files have different number of rows"
+    );
+
+    let tol = 1e-3;
+
+    for (i, ((_, n_ref), (_, n_act))) in ref_data.iter().zip(act_data.iter()).enumerate() {
+        assert!(
+            approx_eq!(f32, *n_ref, *n_act, epsilon = tol),
+            "Novelty mismatch at index {}: expected {}, got {}",
+            i,
+            n_ref,
+            n_act
+        );
+    }
+}
+
+
+// tests that summaries of several runs are collected in one CSV file
+#[test]
+fn test_summary_is_appended() {
+    let test_audio = "assets/LJ037-0171.wav";
+    let summary_csv = "LJ037-0171_summary.csv";
+
+    if Path::new(summary_csv).exists() {
+        fs::remove_file(summary_csv).unwrap();
+    }
+
+    for run in 0..2 {
+        let generated_csv = format!("LJ037-0171_summary_{}.csv", run);
+        if Path::new(&generated_csv).exists() {
+            fs::remove_file(&generated_csv).unwrap();
+        }
+
+        let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+            .args([test_audio, &generated_csv, "--summary", summary_csv])
+            .status()
+            .expect("Failed to execute program");
+        assert!(status.success());
+    }
+
+    let lines: Vec<String> = BufReader::new(File::open(summary_csv).unwrap()).lines().map(|l| l.unwrap()).collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], "path,duration,onset_count,onset_density,mean_novelty,max_novelty,tempo,clipping,dropouts");
+    assert_eq!(lines[1], lines[2]);
+
+    let values: Vec<&str> = lines[1].split(',').collect();
+    assert_eq!(values[0], test_audio);
+    assert!(values[2].parse::<usize>().unwrap() > 0, "No onsets counted");
+    assert!(!values[6].is_empty(), "Missing tempo estimate");
+}
+
+
+// tests that a batch manifest applies its per-file outputs and parameter overrides, and
+// that a failing file doesn't abort the others
+#[test]
+fn test_batch_manifest() {
+    let test_audio = "assets/LJ037-0171.wav";
+    let manifest = "LJ037-0171_manifest.csv";
+    let report = "LJ037-0171_batch_report.json";
+    let outputs = ["LJ037-0171_batch_reference.csv", "LJ037-0171_batch_default.csv"];
+
+    for path in outputs.iter().chain([&manifest, &report]) {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+    fs::write(
+        manifest,
+        format!(
+            "path,output,window_length,hop_length\nassets/missing.wav,missing.csv,,\n{},{},2048,128\n{},{},,\n",
+            test_audio, outputs[0], test_audio, outputs[1]
+        ),
+    )
+    .unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["batch", "--manifest-in", manifest, "--jobs", "2", "--report", report])
+        .status()
+        .expect("Failed to execute program");
+    assert!(!status.success(), "Missing input should fail the batch");
+
+    // the report lists all files in manifest order, including the failure
+    let report: serde_json::Value = serde_json::from_reader(File::open(report).unwrap()).expect("Failed to parse report");
+    assert_eq!(report["processed"], 2);
+    assert_eq!(report["failed"], 1);
+    let files = report["files"].as_array().unwrap();
+    assert_eq!(files.len(), 3);
+    assert_eq!(files[0]["status"], "failed");
+    assert!(files[0]["error"].is_string());
+    assert_eq!(files[1]["output"], outputs[0]);
+    assert!(files[1]["summary"]["onset_count"].as_u64().unwrap() > 0, "No onsets counted");
+    assert!(files[2]["summary"]["duration"].as_f64().unwrap() > 7.0);
+
+    // the overrides reproduce the reference, the defaults use a larger hop
+    let ref_data = load_csv("reference/LJ037-0171.csv");
+    assert_eq!(load_csv(outputs[0]).len(), ref_data.len());
+    assert_eq!(load_csv(outputs[1]).len(), 167226_usize.div_ceil(256));
+}
+
+
+// tests that a batch whose entries share an output path is rejected before anything is written
+#[test]
+fn test_batch_colliding_outputs() {
+    let test_audio = "assets/LJ037-0171.wav";
+    let manifest = "LJ037-0171_colliding_manifest.csv";
+    let output = "LJ037-0171_colliding.csv";
+
+    for path in [manifest, output] {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+    fs::write(manifest, format!("path,output,hop_length\n{},{},128\n{},{},512\n", test_audio, output, test_audio, output)).unwrap();
+
+    let result = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["batch", "--manifest-in", manifest, "--jobs", "2"])
+        .output()
+        .expect("Failed to execute program");
+    fs::remove_file(manifest).unwrap();
+    assert!(!result.status.success(), "Colliding outputs should fail the batch");
+    assert!(String::from_utf8_lossy(&result.stderr).contains(output));
+    assert!(!Path::new(output).exists());
+}
+
+
+// tests that a directory input is searched recursively and mirrored under the output directory
+#[test]
+fn test_batch_recursive_directory() {
+    let input_dir = Path::new("LJ037-0171_corpus");
+    let output_dir = Path::new("LJ037-0171_corpus_out");
+
+    for dir in [input_dir, output_dir] {
+        if dir.exists() {
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+    fs::create_dir_all(input_dir.join("nested")).unwrap();
+    fs::copy("assets/LJ037-0171.wav", input_dir.join("top.wav")).unwrap();
+    fs::copy("assets/LJ037-0171.wav", input_dir.join("nested/deep.wav")).unwrap();
+    fs::write(input_dir.join("notes.txt"), "not audio").unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["batch", input_dir.to_str().unwrap(), "--recursive", "--output-dir", output_dir.to_str().unwrap()])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+
+    let num_frames = 167226_usize.div_ceil(256);
+    assert_eq!(load_csv(output_dir.join("top.csv").to_str().unwrap()).len(), num_frames);
+    assert_eq!(load_csv(output_dir.join("nested/deep.csv").to_str().unwrap()).len(), num_frames);
+    assert!(!output_dir.join("notes.csv").exists());
+}
+
+
+// tests that existing batch outputs are skipped, and rejected on a parameter mismatch
+#[test]
+fn test_batch_skip_existing() {
+    let input_dir = Path::new("LJ037-0171_resume");
+    let output_dir = Path::new("LJ037-0171_resume_out");
+
+    for dir in [input_dir, output_dir] {
+        if dir.exists() {
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+    fs::create_dir_all(input_dir).unwrap();
+    fs::copy("assets/LJ037-0171.wav", input_dir.join("a.wav")).unwrap();
+    fs::copy("assets/LJ037-0171.wav", input_dir.join("b.wav")).unwrap();
+
+    let run = |extra: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+            .args(["batch", input_dir.to_str().unwrap(), "--output-dir", output_dir.to_str().unwrap()])
+            .args(extra)
+            .status()
+            .expect("Failed to execute program")
+    };
+
+    assert!(run(&[]).success());
+    assert!(!run(&[]).success(), "Existing outputs should fail without --skip-existing");
+
+    // a removed output is recomputed, the other one is kept
+    fs::remove_file(output_dir.join("b.csv")).unwrap();
+    assert!(run(&["--skip-existing", "--check-params"]).success());
+    assert!(output_dir.join("b.csv").exists());
+
+    assert!(run(&["--skip-existing", "--hop-length", "128"]).success());
+    assert!(!run(&["--skip-existing", "--check-params", "--hop-length", "128"]).success());
+}
+
+
+// tests that a WAV stream on stdin with unknown chunk sizes, as written by ffmpeg to a pipe,
+// gives the same result as the file
+#[test]
+fn test_wav_from_stdin() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let test_audio = "assets/LJ037-0171.wav";
+    let generated_csv = "LJ037-0171_stdin.csv";
+    let reference_csv = "LJ037-0171_stdin_reference.csv";
+
+    for path in [generated_csv, reference_csv] {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    // mark the RIFF and data chunk sizes as unknown
+    let mut bytes = fs::read(test_audio).unwrap();
+    bytes[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+    let data = bytes.windows(4).position(|w| w == b"data").expect("Missing data chunk");
+    bytes[data + 4..data + 8].copy_from_slice(&u32::MAX.to_le_bytes());
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["-", generated_csv])
+        .stdin(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute program");
+    child.stdin.take().unwrap().write_all(&bytes).unwrap();
+    assert!(child.wait().unwrap().success());
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args([test_audio, reference_csv])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+
+    let ref_data = load_csv(reference_csv);
+    let act_data = load_csv(generated_csv);
+    assert_eq!(ref_data.len(), act_data.len());
+    for ((t_ref, n_ref), (t_act, n_act)) in ref_data.iter().zip(act_data.iter()) {
+        assert!(approx_eq!(f32, *t_ref, *t_act, epsilon = 1e-3));
+        assert!(approx_eq!(f32, *n_ref, *n_act, epsilon = 1e-3));
+    }
+}
+
+
+// tests that the novelty function piped from stdout into onsets on stdin gives the same
+// onsets as the audio file
+#[test]
+fn test_novelty_pipe() {
+    use std::process::Stdio;
+
+    let test_audio = "assets/LJ037-0171.wav";
+    let generated_csv = "LJ037-0171_pipe_onsets.csv";
+    let reference_csv = "LJ037-0171_pipe_onsets_reference.csv";
+
+    for path in [generated_csv, reference_csv] {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    let novelty = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args([test_audio, "-"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute program");
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["onsets", "-", generated_csv])
+        .stdin(novelty.stdout.unwrap())
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["onsets", test_audio, reference_csv])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+
+    let ref_data = load_csv(reference_csv);
+    let act_data = load_csv(generated_csv);
+    assert!(!ref_data.is_empty());
+    assert_eq!(ref_data.len(), act_data.len());
+    for ((t_ref, s_ref), (t_act, s_act)) in ref_data.iter().zip(act_data.iter()) {
+        assert!(approx_eq!(f32, *t_ref, *t_act, epsilon = 1e-3));
+        assert!(approx_eq!(f32, *s_ref, *s_act, epsilon = 1e-3));
+    }
+}
+
+// tests that the output format follows the extension, with --format as override
+#[test]
+fn test_output_format_by_extension() {
+    let test_audio = "assets/LJ037-0171.wav";
+    let outputs = ["LJ037-0171_format.json", "LJ037-0171_format.npy", "LJ037-0171_format.png", "LJ037-0171_format.out", "LJ037-0171_format.parquet"];
+    for path in outputs {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+            .arg(test_audio)
+            .args(args)
+            .status()
+            .expect("Failed to execute program")
+    };
+
+    assert!(run(&[outputs[0]]).success());
+    let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(outputs[0]).unwrap()).unwrap();
+    let num_frames = json["novelty"].as_array().unwrap().len();
+    assert!(num_frames > 0);
+    assert_eq!(json["time"].as_array().unwrap().len(), num_frames);
+
+    assert!(run(&[outputs[1]]).success());
+    assert!(fs::read(outputs[1]).unwrap().starts_with(b"\x93NUMPY"));
+
+    assert!(run(&[outputs[2]]).success());
+    let png = fs::read(outputs[2]).unwrap();
+    assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+    assert_eq!(u32::from_be_bytes(png[16..20].try_into().unwrap()) as usize, num_frames);
+
+    assert!(run(&[outputs[3], "--format", "json"]).success());
+    assert!(fs::read_to_string(outputs[3]).unwrap().starts_with("{\"fs_feature\":"));
+
+    #[cfg(feature = "parquet")]
+    {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+        use parquet::record::RowAccessor;
+
+        assert!(run(&[outputs[4]]).success());
+        let reader = SerializedFileReader::new(File::open(outputs[4]).unwrap()).expect("Failed to read Parquet");
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows() as usize, num_frames);
+        let names: Vec<&str> = metadata.schema_descr().columns().iter().map(|column| column.name()).collect();
+        assert_eq!(names, ["time", "novelty"]);
+        let fs_feature = metadata.key_value_metadata().unwrap().iter().find(|entry| entry.key == "fs_feature").unwrap();
+        assert_eq!(fs_feature.value.as_deref(), Some(json["fs_feature"].to_string().as_str()));
+        for (row, (time, novelty)) in reader.get_row_iter(None).unwrap().zip(json["time"].as_array().unwrap().iter().zip(json["novelty"].as_array().unwrap())) {
+            let row = row.unwrap();
+            assert!(approx_eq!(f64, row.get_double(0).unwrap(), time.as_f64().unwrap(), epsilon = 1e-5));
+            assert!(approx_eq!(f64, row.get_float(1).unwrap() as f64, novelty.as_f64().unwrap(), epsilon = 1e-5));
+        }
+    }
+    #[cfg(not(feature = "parquet"))]
+    assert!(!run(&[outputs[4]]).success());
+
+    for path in outputs {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
+
+// tests that start timing shifts the time column by half a window
+#[test]
+fn test_frame_timing_start() {
+    let test_audio = "assets/LJ037-0171.wav";
+    let generated_csv = "LJ037-0171_frame_start.csv";
+
+    if Path::new(generated_csv).exists() {
+        fs::remove_file(generated_csv).unwrap();
+    }
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args([test_audio, generated_csv, "--window-length", "2048", "--hop-length", "128", "--frame-timing", "start"])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+
+    let ref_data = load_csv("reference/LJ037-0171.csv");
+    let act_data = load_csv(generated_csv);
+    assert_eq!(ref_data.len(), act_data.len());
+    for ((t_ref, _), (t_act, _)) in ref_data.iter().zip(act_data.iter()) {
+        assert!(approx_eq!(f32, *t_ref - 1024.0 / 22050.0, *t_act, epsilon = 1e-3));
+    }
+}
+
+
+// tests the time column in samples, frames, and timestamps, and that reading it back infers
+// the feature rate from timestamps but not from indices
+#[test]
+fn test_time_unit() {
+    let test_audio = "assets/LJ037-0171.wav";
+    for (unit, header, second_row, readable) in [
+        ("samples", "sample,novelty", "256,", false),
+        ("frames", "frame,novelty", "1,", false),
+        ("timestamp", "time,novelty", "00:00:00.012,", true),
+    ] {
+        let generated_csv = format!("LJ037-0171_time_{}.csv", unit);
+        if Path::new(&generated_csv).exists() {
+            fs::remove_file(&generated_csv).unwrap();
+        }
+
+        let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+            .args([test_audio, &generated_csv, "--time-unit", unit])
+            .status()
+            .expect("Failed to execute program");
+        assert!(status.success());
+
+        let lines: Vec<String> = BufReader::new(File::open(&generated_csv).unwrap()).lines().map(|line| line.unwrap()).collect();
+        assert_eq!(lines[0], header);
+        assert!(lines[2].starts_with(second_row), "{}", lines[2]);
+        assert_eq!(lines.len(), 1 + 167226_usize.div_ceil(256));
+
+        let onsets_csv = format!("LJ037-0171_time_{}_onsets.csv", unit);
+        if Path::new(&onsets_csv).exists() {
+            fs::remove_file(&onsets_csv).unwrap();
+        }
+        let output = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+            .args(["onsets", &generated_csv, &onsets_csv])
+            .output()
+            .expect("Failed to execute program");
+        assert_eq!(output.status.success(), readable, "{}", String::from_utf8_lossy(&output.stderr));
+        if !readable {
+            assert!(String::from_utf8_lossy(&output.stderr).contains("Can't infer the feature rate"));
+            let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+                .args(["onsets", &generated_csv, &onsets_csv, "--feature-rate", "86.1328125"])
+                .status()
+                .expect("Failed to execute program");
+            assert!(status.success());
+        }
+        fs::remove_file(&onsets_csv).unwrap();
+        fs::remove_file(&generated_csv).unwrap();
+    }
+}
+
+// tests that --timecode-offset shifts the time column by the BWF time reference to the exact sample
+#[test]
+fn test_timecode_offset() {
+    let bwf_audio = "LJ037-0171_bwf.wav";
+    let generated_csv = "LJ037-0171_bwf.csv";
+    for path in [bwf_audio, generated_csv] {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    // a bext chunk between the fmt and data chunks, with the take at 10:00:00.5
+    let time_reference = 22050_u64 * 36000 + 11025;
+    let mut bext = vec![0u8; 602];
+    bext[338..346].copy_from_slice(&time_reference.to_le_bytes());
+    let audio = fs::read("assets/LJ037-0171.wav").unwrap();
+    let mut wav = audio[..36].to_vec();
+    wav.extend_from_slice(b"bext");
+    wav.extend_from_slice(&(bext.len() as u32).to_le_bytes());
+    wav.extend_from_slice(&bext);
+    wav.extend_from_slice(&audio[36..]);
+    wav[4..8].copy_from_slice(&(wav.len() as u32 - 8).to_le_bytes());
+    fs::write(bwf_audio, &wav).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args([bwf_audio, generated_csv, "--timecode-offset", "--time-unit", "samples"])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+
+    let lines: Vec<String> = BufReader::new(File::open(generated_csv).unwrap()).lines().map(|line| line.unwrap()).collect();
+    assert_eq!(lines[0], "sample,novelty");
+    assert_eq!(lines.len(), 1 + 167226_usize.div_ceil(256));
+    for (i, line) in lines[1..].iter().enumerate() {
+        let sample: u64 = line.split(',').next().unwrap().parse().unwrap();
+        assert_eq!(sample, time_reference + 256 * i as u64, "{}", line);
+    }
+
+    // without a time reference, the offset is rejected
+    fs::remove_file(generated_csv).unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["assets/LJ037-0171.wav", generated_csv, "--timecode-offset"])
+        .status()
+        .expect("Failed to execute program");
+    assert!(!status.success());
+
+    for path in [bwf_audio, generated_csv] {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
+
+// tests that an input over --max-duration is rejected, or streamed with the same result
+#[test]
+fn test_max_duration_guard() {
+    let test_audio = "assets/LJ037-0171.wav";
+    let rejected_csv = "LJ037-0171_rejected.csv";
+    let streamed_csv = "LJ037-0171_streamed.csv";
+
+    for path in [rejected_csv, streamed_csv] {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args([test_audio, rejected_csv, "--max-duration", "1"])
+        .status()
+        .expect("Failed to execute program");
+    assert!(!status.success());
+    assert!(!Path::new(rejected_csv).exists());
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args([test_audio, streamed_csv, "--window-length", "2048", "--hop-length", "128", "--max-memory", "64K", "--on-limit", "stream"])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+
+    let ref_data = load_csv("reference/LJ037-0171.csv");
+    let act_data = load_csv(streamed_csv);
+    assert_eq!(ref_data.len(), act_data.len());
+    for ((t_ref, n_ref), (t_act, n_act)) in ref_data.iter().zip(act_data.iter()) {
+        assert!(approx_eq!(f32, *t_ref, *t_act, epsilon = 1e-3));
+        assert!(approx_eq!(f32, *n_ref, *n_act, epsilon = 1e-3));
+    }
+}
+
+
+// tests that an interrupted run with --checkpoint resumes to the same result, dropping values
+// written after the last checkpoint, and that resuming with other parameters is rejected
+#[cfg(unix)]
+#[test]
+fn test_checkpoint_resume() {
+    use std::io::Write;
+
+    let test_audio = "assets/LJ037-0171.wav";
+    let pipe_audio = "LJ037-0171_checkpoint.wav";
+    let resumed_csv = "LJ037-0171_resumed.csv";
+    let path_checkpoint = format!("{}.checkpoint", resumed_csv);
+    let path_partial = format!("{}.partial", resumed_csv);
+
+    for path in [pipe_audio, resumed_csv, &path_checkpoint, &path_partial] {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    // the first run reads a pipe that stalls after the first block of 65536 samples, and is
+    // killed once it has saved a checkpoint
+    assert!(Command::new("mkfifo").arg(pipe_audio).status().expect("Failed to create pipe").success());
+    let args = ["--window-length", "2048", "--hop-length", "128", "--checkpoint", "--checkpoint-interval", "1"];
+    let mut interrupted = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args([pipe_audio, resumed_csv])
+        .args(args)
+        .spawn()
+        .expect("Failed to execute program");
+    let audio = fs::read(test_audio).unwrap();
+    let mut pipe = fs::OpenOptions::new().write(true).open(pipe_audio).unwrap();
+    pipe.write_all(&audio[..44 + 2 * 65536]).unwrap();
+    let saved = (0..50).any(|_| {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        Path::new(&path_checkpoint).exists()
+    });
+    interrupted.kill().unwrap();
+    interrupted.wait().unwrap();
+    drop(pipe);
+    fs::remove_file(pipe_audio).unwrap();
+    assert!(saved);
+    assert!(!Path::new(resumed_csv).exists());
+
+    // values flushed after the checkpoint must be dropped when resuming
+    let mut partial = fs::OpenOptions::new().append(true).open(&path_partial).unwrap();
+    writeln!(partial, "1000").unwrap();
+    drop(partial);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args([test_audio, resumed_csv, "--window-length", "1024", "--hop-length", "128", "--checkpoint", "--checkpoint-interval", "1"])
+        .output()
+        .expect("Failed to execute program");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("different parameters"));
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args([test_audio, resumed_csv])
+        .args(args)
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+    assert!(!Path::new(&path_checkpoint).exists());
+    assert!(!Path::new(&path_partial).exists());
+
+    let ref_data = load_csv("reference/LJ037-0171.csv");
+    let act_data = load_csv(resumed_csv);
+    assert_eq!(ref_data.len(), act_data.len());
+    for ((t_ref, n_ref), (t_act, n_act)) in ref_data.iter().zip(act_data.iter()) {
+        assert!(approx_eq!(f32, *t_ref, *t_act, epsilon = 1e-3));
+        assert!(approx_eq!(f32, *n_ref, *n_act, epsilon = 1e-3));
+    }
+    fs::remove_file(resumed_csv).unwrap();
+}
+
+
+// tests that the transient component is written alongside the onsets
+#[test]
+fn test_export_transients() {
+    let test_audio = "assets/LJ037-0171.wav";
+    let onsets_csv = "LJ037-0171_transient_onsets.csv";
+    let transients_wav = "LJ037-0171_transients.wav";
+
+    for path in [onsets_csv, transients_wav] {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["onsets", test_audio, onsets_csv, "--export-transients", transients_wav])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+
+    let (audio_array, _) = novelty_rust::audio_path_to_array(test_audio).unwrap();
+    let (transients, _) = novelty_rust::audio_path_to_array(transients_wav).unwrap();
+    assert_eq!(transients.len(), audio_array.len());
+    let energy = |signal: &ndarray::Array1<f32>| signal.mapv(|v| v * v).sum();
+    assert!(energy(&transients) > 0.0 && energy(&transients) < energy(&audio_array));
+}
+
+
+// tests that the per-onset column sets are combined in one pass and match the onsets alone
+#[test]
+fn test_onset_columns() {
+    let test_audio = "assets/LJ037-0171.wav";
+    let (plain_csv, columns_csv) = ("LJ037-0171_plain_onsets.csv", "LJ037-0171_column_onsets.csv");
+    for path in [plain_csv, columns_csv] {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    let plain_args = vec!["onsets", test_audio, plain_csv, "--window-length", "1024"];
+    let mut column_args = vec!["onsets", test_audio, columns_csv, "--window-length", "1024"];
+    column_args.extend(["--classify", "--attack-times", "--drums", "--pitch"]);
+    for args in [plain_args, column_args] {
+        let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust")).args(&args).status().expect("Failed to execute program");
+        assert!(status.success());
+    }
+
+    let plain: Vec<String> = BufReader::new(File::open(plain_csv).unwrap()).lines().map(|l| l.unwrap()).collect();
+    let columns: Vec<String> = BufReader::new(File::open(columns_csv).unwrap()).lines().map(|l| l.unwrap()).collect();
+    assert_eq!(
+        columns[0],
+        "time,strength,kind,attack_time,brightness,attack_start,attack_duration,voice,low,mid,high,frequency,midi_note,note,confidence"
+    );
+    assert_eq!(columns.len(), plain.len());
+    for (row, onset) in columns.iter().zip(&plain).skip(1) {
+        assert!(row.starts_with(&format!("{},", onset)), "Unexpected row {}", row);
+        assert_eq!(row.split(',').count(), 15);
+    }
+    for path in [plain_csv, columns_csv] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+
+// tests that the features subcommand writes an NPZ archive with aligned arrays and the annotated labels
+#[test]
+fn test_features_npz() {
+    let test_audio = "assets/LJ037-0171.wav";
+    let annotations = "LJ037-0171_annotations.csv";
+    let features_npz = "LJ037-0171_features.npz";
+
+    if Path::new(features_npz).exists() {
+        fs::remove_file(features_npz).unwrap();
+    }
+    fs::write(annotations, "time\n0.5\n1.0\n2.25\n").unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["features", test_audio, features_npz, "--annotations", annotations, "--n-mels", "40"])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+
+    // walk the local file headers of the stored members
+    let archive = fs::read(features_npz).unwrap();
+    let mut members = std::collections::HashMap::new();
+    let mut offset = 0;
+    while archive[offset..offset + 4] == [0x50, 0x4b, 0x03, 0x04] {
+        let u32_at = |i: usize| u32::from_le_bytes(archive[i..i + 4].try_into().unwrap()) as usize;
+        let size = u32_at(offset + 18);
+        let name_length = u16::from_le_bytes([archive[offset + 26], archive[offset + 27]]) as usize;
+        let name = String::from_utf8(archive[offset + 30..offset + 30 + name_length].to_vec()).unwrap();
+        let data = &archive[offset + 30 + name_length..offset + 30 + name_length + size];
+        assert_eq!(&data[..6], b"\x93NUMPY");
+        members.insert(name, data.to_vec());
+        offset += 30 + name_length + size;
+    }
+
+    let (audio_array, _) = novelty_rust::audio_path_to_array(test_audio).unwrap();
+    let num_frames = audio_array.len().div_ceil(256);
+    let header = |name: &str| {
+        let data = &members[name];
+        let length = u16::from_le_bytes([data[8], data[9]]) as usize;
+        (String::from_utf8(data[10..10 + length].to_vec()).unwrap(), data[10 + length..].to_vec())
+    };
+    assert!(header("mel.npy").0.contains(&format!("'shape': ({}, 40)", num_frames)));
+    assert!(header("novelty.npy").0.contains(&format!("'shape': ({},)", num_frames)));
+    let (labels_header, labels) = header("labels.npy");
+    assert!(labels_header.contains("'descr': '|u1'"));
+    assert_eq!(labels.iter().map(|&label| label as usize).sum::<usize>(), 3);
+    assert!(members.contains_key("times.npy"));
+
+    fs::remove_file(annotations).unwrap();
+}
+
+// tests that a pipeline specification gives the same novelty function and onsets as the
+// equivalent command line, and that stages out of order are rejected
+#[test]
+fn test_pipeline() {
+    let test_audio = "assets/LJ037-0171.wav";
+    let spec = "test_pipeline.toml";
+    let unordered_spec = "test_pipeline_unordered.toml";
+    let novelty_csv = "LJ037-0171_pipeline_novelty.csv";
+    let onsets_csv = "LJ037-0171_pipeline_onsets.csv";
+    let reference_novelty_csv = "LJ037-0171_pipeline_novelty_reference.csv";
+    let reference_onsets_csv = "LJ037-0171_pipeline_onsets_reference.csv";
+    for path in [spec, unordered_spec, novelty_csv, onsets_csv, reference_novelty_csv, reference_onsets_csv] {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    fs::write(
+        spec,
+        "[[stage]]\nstage = \"detector\"\nmethod = \"spectral\"\nwindow_length = 2048\nhop_length = 512\n\n\
+         [[stage]]\nstage = \"smooth\"\nmedian_filter = 5\n\n\
+         [[stage]]\nstage = \"peak-pick\"\nthreshold = 0.2\n\n\
+         [[stage]]\nstage = \"export\"\nnovelty = \"{stem}_pipeline_novelty.csv\"\nonsets = \"{stem}_pipeline_onsets.csv\"\n",
+    )
+    .unwrap();
+    fs::write(unordered_spec, "[[stage]]\nstage = \"smooth\"\n\n[[stage]]\nstage = \"detector\"\nmethod = \"energy\"\n").unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["pipeline", spec, test_audio])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["pipeline", unordered_spec, test_audio])
+        .status()
+        .expect("Failed to execute program");
+    assert!(!status.success());
+
+    let parameters = ["--method", "spectral", "--window-length", "2048", "--hop-length", "512", "--median-filter", "5"];
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args([test_audio, reference_novelty_csv])
+        .args(parameters)
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["onsets", test_audio, reference_onsets_csv, "--onset-threshold", "0.2"])
+        .args(parameters)
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+
+    for (generated, reference) in [(novelty_csv, reference_novelty_csv), (onsets_csv, reference_onsets_csv)] {
+        let ref_data = load_csv(reference);
+        let act_data = load_csv(generated);
+        assert!(!ref_data.is_empty());
+        assert_eq!(ref_data, act_data);
+    }
+
+    // run as a library, the pipeline doesn't overwrite the exports either
+    let pipeline = novelty_rust::Pipeline::load(Path::new(spec)).unwrap();
+    let (audio_array, fs) = novelty_rust::audio_path_to_array(test_audio).unwrap();
+    let novelty_before = fs::read(novelty_csv).unwrap();
+    assert!(pipeline.check_outputs([test_audio]).is_err());
+    assert!(pipeline.run(audio_array, fs, test_audio).is_err());
+    assert_eq!(fs::read(novelty_csv).unwrap(), novelty_before);
+    assert!(pipeline.check_outputs(["other.wav", "nested/other.wav"]).is_err());
+    assert!(pipeline.check_outputs(["other.wav"]).is_ok());
+
+    for path in [spec, unordered_spec, novelty_csv, onsets_csv, reference_novelty_csv, reference_onsets_csv] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+// tests that a coordinator hands out the files of a batch to workers and reports their
+// results, and hands out the file of a hung worker again
+#[test]
+fn test_batch_distributed() {
+    use std::io::Read;
+
+    let input_dir = Path::new("LJ037-0171_distributed");
+    let output_dir = Path::new("LJ037-0171_distributed_out");
+    let report = "LJ037-0171_distributed_report.json";
+
+    for dir in [input_dir, output_dir] {
+        if dir.exists() {
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+    if Path::new(report).exists() {
+        fs::remove_file(report).unwrap();
+    }
+    fs::create_dir_all(input_dir).unwrap();
+    for name in ["first.wav", "second.wav", "third.wav"] {
+        fs::copy("assets/LJ037-0171.wav", input_dir.join(name)).unwrap();
+    }
+
+    let mut coordinator = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["batch", input_dir.to_str().unwrap(), "--output-dir", output_dir.to_str().unwrap()])
+        .args(["--report", report, "--serve", "127.0.0.1:0", "--worker-timeout", "1"])
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("Failed to execute program");
+    // the coordinator announces the port it was given, and its later messages are drained
+    let mut messages = BufReader::new(coordinator.stderr.take().unwrap()).lines();
+    let address = messages
+        .by_ref()
+        .map(|line| line.unwrap())
+        .find_map(|line| line.strip_prefix("Waiting for workers on ").map(str::to_string))
+        .expect("Coordinator didn't announce its address");
+    let drain = std::thread::spawn(move || messages.count());
+
+    // a worker that takes a file and never replies
+    let mut hung = std::net::TcpStream::connect(&address).unwrap();
+    let mut received = Vec::new();
+    while received.iter().filter(|&&byte| byte == b'\n').count() < 2 {
+        let mut buffer = [0u8; 4096];
+        let length = hung.read(&mut buffer).unwrap();
+        assert!(length > 0);
+        received.extend_from_slice(&buffer[..length]);
+    }
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["worker", &address, "--jobs", "2"])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+    assert!(coordinator.wait().unwrap().success());
+    drop(hung);
+    drain.join().unwrap();
+
+    let report_json: serde_json::Value = serde_json::from_reader(File::open(report).unwrap()).expect("Failed to parse report");
+    assert_eq!(report_json["processed"], 3);
+    let num_frames = 167226_usize.div_ceil(256);
+    for name in ["first.csv", "second.csv", "third.csv"] {
+        assert_eq!(load_csv(output_dir.join(name).to_str().unwrap()).len(), num_frames);
+    }
+
+    for dir in [input_dir, output_dir] {
+        fs::remove_dir_all(dir).unwrap();
+    }
+    fs::remove_file(report).unwrap();
+}
+
+
+// tests that a file is recorded as failed after three workers took it and disconnected
+#[test]
+fn test_batch_distributed_gives_up() {
+    use std::io::Read;
+
+    let manifest = "LJ037-0171_gives_up_manifest.csv";
+    let report = "LJ037-0171_gives_up_report.json";
+    for path in [manifest, report] {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+    fs::write(manifest, "path,output\nassets/LJ037-0171.wav,LJ037-0171_gives_up.csv\n").unwrap();
+
+    let mut coordinator = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["batch", "--manifest-in", manifest, "--report", report, "--serve", "127.0.0.1:0"])
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("Failed to execute program");
+    let mut messages = BufReader::new(coordinator.stderr.take().unwrap()).lines();
+    let address = messages
+        .by_ref()
+        .map(|line| line.unwrap())
+        .find_map(|line| line.strip_prefix("Waiting for workers on ").map(str::to_string))
+        .expect("Coordinator didn't announce its address");
+    let drain = std::thread::spawn(move || messages.count());
+
+    // workers that crash on the file: each reads the command line and the entry and disconnects
+    for _ in 0..3 {
+        let mut crashing = std::net::TcpStream::connect(&address).unwrap();
+        let mut received = Vec::new();
+        while received.iter().filter(|&&byte| byte == b'\n').count() < 2 {
+            let mut buffer = [0u8; 4096];
+            let length = crashing.read(&mut buffer).unwrap();
+            assert!(length > 0);
+            received.extend_from_slice(&buffer[..length]);
+        }
+    }
+    assert!(!coordinator.wait().unwrap().success(), "The file without a reply should fail the batch");
+    drain.join().unwrap();
+
+    let report_json: serde_json::Value = serde_json::from_reader(File::open(report).unwrap()).expect("Failed to parse report");
+    assert_eq!(report_json["failed"], 1);
+    assert!(report_json["files"][0]["error"].as_str().unwrap().contains("3 attempts"));
+    assert!(!Path::new("LJ037-0171_gives_up.csv").exists());
+    for path in [manifest, report] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+
+// tests that the provenance sidecar hashes input and output, and that verify detects a
+// changed output
+#[test]
+fn test_provenance() {
+    use novelty_rust::{format_timestamp, sha256_file};
+
+    assert_eq!(format_timestamp(std::time::UNIX_EPOCH + std::time::Duration::from_secs(951782400)), "2000-02-29T00:00:00Z");
+
+    let test_audio = "assets/LJ037-0171.wav";
+    let generated_csv = "LJ037-0171_provenance.csv";
+    let sidecar = "LJ037-0171_provenance.csv.prov.json";
+    for path in [generated_csv, sidecar] {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args([test_audio, generated_csv, "--provenance"])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+    let provenance: serde_json::Value = serde_json::from_reader(File::open(sidecar).unwrap()).expect("Failed to parse sidecar");
+    assert_eq!(provenance["input_sha256"], sha256_file(test_audio).unwrap());
+    assert_eq!(provenance["output_sha256"], sha256_file(generated_csv).unwrap());
+    assert_eq!(provenance["parameters"]["window_length"], 1024);
+
+    let verify = |expected: bool| {
+        let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+            .args(["verify", generated_csv])
+            .status()
+            .expect("Failed to execute program");
+        assert_eq!(status.success(), expected);
+    };
+    verify(true);
+    fs::write(generated_csv, "time,novelty\n").unwrap();
+    verify(false);
+
+    for path in [generated_csv, sidecar] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+// tests that the JSON log format writes one event object per line to standard error
+#[test]
+fn test_json_log_format() {
+    let test_audio = "assets/LJ037-0171.wav";
+    let generated_csv = "LJ037-0171_json_log.csv";
+    if Path::new(generated_csv).exists() {
+        fs::remove_file(generated_csv).unwrap();
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args([test_audio, generated_csv, "--log-format", "json"])
+        .output()
+        .expect("Failed to execute program");
+    assert!(output.status.success());
+    let events: Vec<serde_json::Value> = String::from_utf8(output.stderr)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("Failed to parse log event"))
+        .collect();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0]["event"], "file_started");
+    assert_eq!(events[1]["event"], "file_finished");
+    assert_eq!(events[1]["frames"], 167226_usize.div_ceil(256));
+    assert!(events[1]["seconds"].as_f64().unwrap() >= 0.0);
+
+    // a failure is an event too
+    let output = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args([test_audio, generated_csv, "--log-format", "json"])
+        .output()
+        .expect("Failed to execute program");
+    assert!(!output.status.success());
+    let event: serde_json::Value = serde_json::from_slice(&output.stderr).expect("Failed to parse log event");
+    assert_eq!(event["level"], "error");
+
+    fs::remove_file(generated_csv).unwrap();
+}
+
+// tests the Prometheus rendering of the batch metrics
+#[test]
+fn test_metrics() {
+    use novelty_rust::{BatchStatus, Metrics};
+
+    let metrics = Metrics::default();
+    metrics.record(BatchStatus::Processed, 0.3);
+    metrics.record(BatchStatus::Processed, 7.0);
+    metrics.record(BatchStatus::Skipped, 0.0);
+    metrics.record(BatchStatus::Failed, 1.0);
+    let text = metrics.render();
+    let lines: Vec<&str> = text.lines().collect();
+    for line in [
+        "novelty_rust_files_total{status=\"processed\"} 2",
+        "novelty_rust_files_total{status=\"skipped\"} 1",
+        "novelty_rust_files_total{status=\"failed\"} 1",
+        "novelty_rust_processing_seconds_bucket{le=\"0.25\"} 0",
+        "novelty_rust_processing_seconds_bucket{le=\"0.5\"} 1",
+        "novelty_rust_processing_seconds_bucket{le=\"10\"} 2",
+        "novelty_rust_processing_seconds_bucket{le=\"+Inf\"} 2",
+        "novelty_rust_processing_seconds_sum 7.3",
+        "novelty_rust_processing_seconds_count 2",
+    ] {
+        assert!(lines.contains(&line), "missing {}", line);
+    }
+}
+
+// tests that a coordinator serves the metrics of the files processed so far while the batch
+// runs, and only at /metrics
+#[test]
+fn test_batch_metrics() {
+    use std::io::Read;
+
+    let input_dir = Path::new("LJ037-0171_metrics");
+    let output_dir = Path::new("LJ037-0171_metrics_out");
+    for dir in [input_dir, output_dir] {
+        if dir.exists() {
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+    fs::create_dir_all(input_dir).unwrap();
+    for name in ["first.wav", "second.wav"] {
+        fs::copy("assets/LJ037-0171.wav", input_dir.join(name)).unwrap();
+    }
+
+    let mut coordinator = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["batch", input_dir.to_str().unwrap(), "--output-dir", output_dir.to_str().unwrap()])
+        .args(["--serve", "127.0.0.1:0", "--metrics", "127.0.0.1:0", "--worker-timeout", "60"])
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("Failed to execute program");
+    // the metrics and the coordinator announce the ports they were given
+    let mut messages = BufReader::new(coordinator.stderr.take().unwrap()).lines();
+    let metrics_address = messages
+        .by_ref()
+        .map(|line| line.unwrap())
+        .find_map(|line| line.strip_prefix("Serving metrics at http://")?.strip_suffix("/metrics").map(str::to_string))
+        .expect("Coordinator didn't announce the metrics address");
+    let address = messages
+        .by_ref()
+        .map(|line| line.unwrap())
+        .find_map(|line| line.strip_prefix("Waiting for workers on ").map(str::to_string))
+        .expect("Coordinator didn't announce its address");
+    let drain = std::thread::spawn(move || messages.count());
+
+    let (header, body) = http_get(&metrics_address, "/metrics").expect("Failed to scrape the metrics");
+    assert!(header.starts_with("HTTP/1.1 200 OK\r\n"), "{}", header);
+    assert!(header.contains("Content-Type: text/plain; version=0.0.4"), "{}", header);
+    let text = String::from_utf8(body).unwrap();
+    assert!(text.lines().any(|line| line == "novelty_rust_files_total{status=\"processed\"} 0"), "{}", text);
+    let (header, _) = http_get(&metrics_address, "/").expect("Failed to reach the metrics server");
+    assert!(header.starts_with("HTTP/1.1 404 Not Found\r\n"), "{}", header);
+
+    // a worker holds the first file, so the batch keeps running after the second is processed
+    let mut held = std::net::TcpStream::connect(&address).unwrap();
+    let mut received = Vec::new();
+    while received.iter().filter(|&&byte| byte == b'\n').count() < 2 {
+        let mut buffer = [0u8; 4096];
+        let length = held.read(&mut buffer).unwrap();
+        assert!(length > 0);
+        received.extend_from_slice(&buffer[..length]);
+    }
+    let mut worker = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["worker", &address, "--jobs", "1"])
+        .spawn()
+        .expect("Failed to execute program");
+    let text = (0..600)
+        .find_map(|_| {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+            let (_, body) = http_get(&metrics_address, "/metrics")?;
+            let text = String::from_utf8(body).ok()?;
+            text.lines().any(|line| line == "novelty_rust_files_total{status=\"processed\"} 1").then_some(text)
+        })
+        .expect("The processed file wasn't counted");
+    assert!(text.lines().any(|line| line == "novelty_rust_processing_seconds_count 1"), "{}", text);
+
+    // the held file is handed out again once its worker disconnects
+    drop(held);
+    assert!(worker.wait().unwrap().success());
+    assert!(coordinator.wait().unwrap().success());
+    drain.join().unwrap();
+    for name in ["first.csv", "second.csv"] {
+        assert!(output_dir.join(name).exists());
+    }
+
+    for dir in [input_dir, output_dir] {
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
+
+// tests that sync finds the offset of an excerpt that starts one second into the recording
+#[test]
+fn test_sync() {
+    let test_audio = "assets/LJ037-0171.wav";
+    let excerpt_wav = "LJ037-0171_sync_excerpt.wav";
+    let generated_csv = "LJ037-0171_sync.csv";
+    for path in [excerpt_wav, generated_csv] {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    let (audio_array, fs) = novelty_rust::audio_path_to_array(test_audio).unwrap();
+    let excerpt = audio_array.slice(ndarray::s![fs as usize..]).to_owned();
+    novelty_rust::write_wav(excerpt_wav, &excerpt, fs).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["sync", test_audio, excerpt_wav, generated_csv])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+
+    let file = File::open(generated_csv).unwrap();
+    let line = BufReader::new(file).lines().nth(1).unwrap().unwrap();
+    let values: Vec<f32> = line.split(',').map(|v| v.parse().unwrap()).collect();
+    assert!((values[0] - 1.0).abs() < 256.0 / fs as f32, "lag {}", values[0]);
+    assert!(values[1] > 0.5);
+
+    for path in [excerpt_wav, generated_csv] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+// tests that a query clip is found in the index at the time it was taken from
+#[test]
+fn test_index_query() {
+    let test_audio = "assets/LJ037-0171.wav";
+    let input_dir = Path::new("LJ037-0171_index");
+    let index = "LJ037-0171_index.jsonl";
+    let query_wav = "LJ037-0171_query.wav";
+    let generated_csv = "LJ037-0171_query.csv";
+    if input_dir.exists() {
+        fs::remove_dir_all(input_dir).unwrap();
+    }
+    for path in [index, query_wav, generated_csv] {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    // the recording, its time reversal as a distractor, and a clip from 2 to 5 seconds
+    let (audio_array, fs) = novelty_rust::audio_path_to_array(test_audio).unwrap();
+    fs::create_dir_all(input_dir).unwrap();
+    fs::copy(test_audio, input_dir.join("original.wav")).unwrap();
+    let reversed = audio_array.slice(ndarray::s![..;-1]).to_owned();
+    novelty_rust::write_wav(input_dir.join("reversed.wav").to_str().unwrap(), &reversed, fs).unwrap();
+    let clip = audio_array.slice(ndarray::s![2 * fs as usize..5 * fs as usize]).to_owned();
+    novelty_rust::write_wav(query_wav, &clip, fs).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["index", input_dir.to_str().unwrap(), index])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+    assert_eq!(BufReader::new(File::open(index).unwrap()).lines().count(), 2);
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["query", index, query_wav, generated_csv])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+
+    let lines: Vec<String> = BufReader::new(File::open(generated_csv).unwrap()).lines().map(|line| line.unwrap()).collect();
+    assert_eq!(lines.len(), 3);
+    let best: Vec<&str> = lines[1].split(',').collect();
+    assert!(best[0].ends_with("original.wav"));
+    assert!((best[1].parse::<f32>().unwrap() - 2.0).abs() <= 0.1);
+    assert!(best[2].parse::<f32>().unwrap() > 0.8);
+
+    fs::remove_dir_all(input_dir).unwrap();
+    for path in [index, query_wav, generated_csv] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+// tests that an excerpt of a recording in the index is reported as its duplicate with the
+// offset of the excerpt, and a time-reversed copy is not
+#[test]
+fn test_duplicates() {
+    let test_audio = "assets/LJ037-0171.wav";
+    let input_dir = Path::new("LJ037-0171_duplicates");
+    let index = "LJ037-0171_duplicates.jsonl";
+    let generated_csv = "LJ037-0171_duplicates.csv";
+    if input_dir.exists() {
+        fs::remove_dir_all(input_dir).unwrap();
+    }
+    for path in [index, generated_csv] {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    // the recording, an excerpt from 1 second to the end, and its time reversal
+    let (audio_array, fs) = novelty_rust::audio_path_to_array(test_audio).unwrap();
+    fs::create_dir_all(input_dir).unwrap();
+    fs::copy(test_audio, input_dir.join("a_original.wav")).unwrap();
+    let excerpt = audio_array.slice(ndarray::s![fs as usize..]).to_owned();
+    novelty_rust::write_wav(input_dir.join("b_excerpt.wav").to_str().unwrap(), &excerpt, fs).unwrap();
+    let reversed = audio_array.slice(ndarray::s![..;-1]).to_owned();
+    novelty_rust::write_wav(input_dir.join("c_reversed.wav").to_str().unwrap(), &reversed, fs).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["index", input_dir.to_str().unwrap(), index])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["duplicates", index, generated_csv])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+
+    let lines: Vec<String> = BufReader::new(File::open(generated_csv).unwrap()).lines().map(|line| line.unwrap()).collect();
+    assert_eq!(lines[0], "first,second,offset,overlap,score");
+    assert_eq!(lines.len(), 2);
+    let pair: Vec<&str> = lines[1].split(',').collect();
+    let mut paths = [pair[0], pair[1]];
+    paths.sort();
+    assert!(paths[0].ends_with("a_original.wav") && paths[1].ends_with("b_excerpt.wav"));
+    assert!((pair[2].parse::<f32>().unwrap().abs() - 1.0).abs() <= 0.1);
+    assert!(pair[4].parse::<f32>().unwrap() > 0.8);
+
+    fs::remove_dir_all(input_dir).unwrap();
+    for path in [index, generated_csv] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+
+// tests that qc passes a clean file and fails a clipped file and a file that isn't audio
+#[test]
+fn test_qc() {
+    let clean_wav = "qc_clean.wav";
+    let clipped_wav = "qc_clipped.wav";
+    let invalid_wav = "qc_invalid.wav";
+    let report = "qc_report.csv";
+    for path in [clean_wav, clipped_wav, invalid_wav, report] {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    let fs = 16000;
+    let sine = ndarray::Array1::from_shape_fn(2 * fs as usize, |n| 0.5 * (2.0 * std::f32::consts::PI * 440.0 * n as f32 / fs as f32).sin());
+    novelty_rust::write_wav(clean_wav, &sine, fs).unwrap();
+    novelty_rust::write_wav(clipped_wav, &sine.mapv(|v| (4.0 * v).clamp(-1.0, 1.0)), fs).unwrap();
+    fs::write(invalid_wav, b"not a wave file").unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["qc", clean_wav])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["qc", clean_wav, clipped_wav, invalid_wav, "--report", report])
+        .output()
+        .expect("Failed to execute program");
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("qc_clean.wav\tpass"), "{}", stdout);
+    assert!(stdout.contains("qc_clipped.wav\tfail: clipping"), "{}", stdout);
+    assert!(stdout.contains("qc_invalid.wav\tfail: invalid format"), "{}", stdout);
+
+    let lines: Vec<String> = BufReader::new(File::open(report).unwrap()).lines().map(|line| line.unwrap()).collect();
+    assert_eq!(lines[0], "path,status,duration,clipping,dropouts,dc_offset,silence,failures");
+    assert_eq!(lines.len(), 4);
+    assert!(lines[1].starts_with("qc_clean.wav,pass,2.00000,"));
+    assert!(lines[2].starts_with("qc_clipped.wav,fail,"));
+
+    for path in [clean_wav, clipped_wav, invalid_wav, report] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+// tests that view serves the analysis and audio of a recording
+#[test]
+fn test_view() {
+    let mut server = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["view", "assets/LJ037-0171.wav", "--address", "127.0.0.1:0"])
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("Failed to execute program");
+    // the server announces the port it was given once the audio is analyzed, and its later
+    // messages are drained
+    let mut messages = BufReader::new(server.stderr.take().unwrap()).lines();
+    let address = messages
+        .by_ref()
+        .map(|line| line.unwrap())
+        .find_map(|line| line.strip_prefix("Serving 1 tracks on http://")?.strip_suffix('/').map(str::to_string))
+        .expect("Server didn't announce its address");
+    let drain = std::thread::spawn(move || messages.count());
+    let track = http_get(&address, "/tracks/0.json");
+    let wav = http_get(&address, "/tracks/0.wav");
+    let missing = http_get(&address, "/tracks/1.json");
+    server.kill().unwrap();
+    server.wait().unwrap();
+    drain.join().unwrap();
+
+    let (header, body) = track.expect("Failed to connect to the server");
+    assert!(header.starts_with("HTTP/1.1 200 OK"), "{}", header);
+    let track: serde_json::Value = serde_json::from_slice(&body).expect("Failed to parse track");
+    assert_eq!(track["novelty"].as_array().unwrap().len(), 167226_usize.div_ceil(256));
+    assert!(!track["onsets"].as_array().unwrap().is_empty());
+    let (header, body) = wav.unwrap();
+    assert!(header.contains("audio/wav"), "{}", header);
+    assert_eq!(body.len(), 44 + 2 * 167226);
+    assert!(missing.unwrap().0.starts_with("HTTP/1.1 404"));
+}
+
+// tests that the Vega-Lite specification embeds or references the novelty function and onsets
+#[test]
+fn test_vega_lite() {
+    let novelty_csv = "LJ037-0171_vega_novelty.csv";
+    let onsets_csv = "LJ037-0171_vega_onsets.csv";
+    let inline_spec = "LJ037-0171_vega_inline.vl.json";
+    let reference_spec = "LJ037-0171_vega_reference.vl.json";
+    for path in [novelty_csv, onsets_csv, inline_spec, reference_spec] {
+        if Path::new(path).exists() {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["assets/LJ037-0171.wav", novelty_csv])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["onsets", "assets/LJ037-0171.wav", onsets_csv, "--vega-lite", inline_spec])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+    let num_onsets = BufReader::new(File::open(onsets_csv).unwrap()).lines().count() - 1;
+    fs::remove_file(onsets_csv).unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["onsets", novelty_csv, onsets_csv, "--vega-lite", reference_spec, "--vega-data", "reference"])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+
+    let spec: serde_json::Value = serde_json::from_reader(File::open(inline_spec).unwrap()).expect("Failed to parse spec");
+    assert!(spec["$schema"].as_str().unwrap().contains("vega-lite"));
+    assert_eq!(spec["layer"][0]["data"]["values"].as_array().unwrap().len(), 167226_usize.div_ceil(256));
+    assert_eq!(spec["layer"][1]["data"]["values"].as_array().unwrap().len(), num_onsets);
+
+    let spec: serde_json::Value = serde_json::from_reader(File::open(reference_spec).unwrap()).expect("Failed to parse spec");
+    assert_eq!(spec["layer"][0]["data"]["url"], novelty_csv);
+    assert_eq!(spec["layer"][1]["data"]["url"], onsets_csv);
+
+    for path in [novelty_csv, onsets_csv, inline_spec, reference_spec] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+// tests that the HDF5 file of a batch reads back with one group per file, whose attributes and
+// datasets match the inputs and the novelty functions, and keeps similar paths apart
+#[test]
+fn test_batch_hdf5() {
+    let input_dir = Path::new("LJ037-0171_hdf5");
+    let output_dir = Path::new("LJ037-0171_hdf5_out");
+    let path_hdf5 = "LJ037-0171_batch.h5";
+    for dir in [input_dir, output_dir] {
+        if dir.exists() {
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+    if Path::new(path_hdf5).exists() {
+        fs::remove_file(path_hdf5).unwrap();
+    }
+    // the paths only differ in where the separator is
+    let names = ["first.wav", "a_b/c.wav", "a/b_c.wav"];
+    for name in names {
+        fs::create_dir_all(input_dir.join(name).parent().unwrap()).unwrap();
+        fs::copy("assets/LJ037-0171.wav", input_dir.join(name)).unwrap();
+    }
+
+    let status = Command::new(env!("CARGO_BIN_EXE_novelty_rust"))
+        .args(["batch", input_dir.to_str().unwrap(), "--output-dir", output_dir.to_str().unwrap(), "--recursive", "--hdf5", path_hdf5])
+        .status()
+        .expect("Failed to execute program");
+    assert!(status.success());
+
+    assert_eq!(hashlittle(b""), 0xdeadbeef);
+    assert_eq!(hashlittle(b"Four score and seven years ago"), 0x17770551);
+    let root = read_hdf5(path_hdf5);
+    assert!(root.attribute("tool").starts_with("novelty_rust "));
+    assert_eq!(root.links.len(), names.len());
+
+    // the novelty function is stored as it is written to the CSV output, up to rounding
+    let novelty = load_csv(output_dir.join("first.csv").to_str().unwrap());
+    for name in names {
+        let path = input_dir.join(name).to_str().unwrap().to_string();
+        let group = root.link(&path.replace('/', "%2F"));
+        assert_eq!(group.attribute("path"), path);
+        assert_eq!(group.attribute("fs"), "22050");
+        let fs_feature: f64 = group.attribute("fs_feature").parse().unwrap();
+        let duration = (novelty[novelty.len() - 1].0 - novelty[0].0) as f64;
+        assert!(approx_eq!(f64, (novelty.len() - 1) as f64 / fs_feature, duration, epsilon = 1e-3));
+        let parameters: serde_json::Value = serde_json::from_str(group.attribute("parameters")).unwrap();
+        assert_eq!(parameters["n_mels"], 128);
+
+        let values = &group.link("novelty").values;
+        assert_eq!(group.link("novelty").shape, [novelty.len() as u64]);
+        for ((_, expected), actual) in novelty.iter().zip(values) {
+            assert!(approx_eq!(f32, *expected, *actual, epsilon = 1e-4));
+        }
+        let mel = group.link("mel");
+        assert_eq!(mel.shape, [novelty.len() as u64, 128]);
+        assert!(mel.values.iter().all(|v| v.is_finite()) && mel.values.iter().any(|&v| v > 0.0));
+    }
+
+    fs::remove_dir_all(input_dir).unwrap();
+    fs::remove_dir_all(output_dir).unwrap();
+    fs::remove_file(path_hdf5).unwrap();
+}
@@ -0,0 +1,35 @@
+use novelty_rust::{sha256_hex, Sha256};
+
+
+// tests that SHA-256 matches the NIST vectors, including messages over several blocks, and
+// that hashing in chunks across block boundaries gives the same digest as hashing at once
+#[test]
+fn test_sha256() {
+    assert_eq!(sha256_hex(b""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+    assert_eq!(sha256_hex(b"abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    assert_eq!(
+        sha256_hex(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+        "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+    );
+    assert_eq!(sha256_hex(&[b'a'; 1_000_000]), "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0");
+
+    let message: Vec<u8> = (0..1000_u32).map(|i| (i * 31 % 251) as u8).collect();
+    assert_eq!(sha256_hex(&message), "f3f55c45264850b8475533289ff43ab81fa1eb3bf781267db645e1ce0c193379");
+    let mut hasher = Sha256::default();
+    hasher.update(&message);
+    let expected = hasher.finalize();
+    for chunk_size in [1, 3, 55, 56, 63, 64, 65, 127, 999] {
+        let mut hasher = Sha256::default();
+        for chunk in message.chunks(chunk_size) {
+            hasher.update(chunk);
+        }
+        assert_eq!(hasher.finalize(), expected, "chunks of {} bytes", chunk_size);
+    }
+
+    // an empty update between chunks, and a split right before the length padding
+    let mut hasher = Sha256::default();
+    hasher.update(&message[..55]);
+    hasher.update(&[]);
+    hasher.update(&message[55..]);
+    assert_eq!(hasher.finalize(), expected);
+}
@@ -0,0 +1,75 @@
+use std::f32::consts::PI;
+
+use ndarray::Array1;
+use novelty_rust::{
+    audio_path_to_array, classify_content, detect_activity, split_audio, structure_boundaries, ActivityThresholds, ContentClass,
+    NoveltyConfig,
+};
+
+
+// tests that audio is cut at the boundaries and the beginning is dropped
+#[test]
+fn test_split_audio_at_boundaries() {
+    let audio_array = Array1::from_shape_fn(1000, |n| n as f32);
+    let segments = split_audio(&audio_array, 100, &[7.5, 2.0, 2.0, 5.0]);
+
+    assert_eq!(segments.len(), 3);
+    assert_eq!((segments[0].start, segments[0].end), (2.0, 5.0));
+    assert_eq!(segments[0].samples[0], 200.0);
+    assert_eq!(segments[1].samples.len(), 250);
+    assert_eq!((segments[2].start, segments[2].end), (7.5, 10.0));
+}
+
+
+// tests that the change between two tones is found as a structural boundary
+#[test]
+fn test_structure_boundary_between_tones() {
+    let fs = 8000;
+    let audio_array = Array1::from_shape_fn(20 * fs, |n| {
+        let frequency = if n < 10 * fs { 440.0 } else { 2000.0 };
+        0.5 * (2.0 * PI * frequency * n as f32 / fs as f32).sin()
+    });
+    let config = NoveltyConfig::energy().build().expect("Failed to build config");
+
+    let boundaries = structure_boundaries(&audio_array, fs as u32, &config, 3.0, 0.5).expect("Failed to compute boundaries");
+    assert!(boundaries.iter().any(|time| (time - 10.0).abs() < 0.5), "No boundary near 10 s: {:?}", boundaries);
+}
+
+
+// tests that two tones with a short pause form one active segment between silence
+#[test]
+fn test_detect_activity() {
+    let fs = 8000;
+    let audio_array = Array1::from_shape_fn(5 * fs + fs / 8, |n| {
+        let time = n as f32 / fs as f32;
+        let tone = (1.0..2.0).contains(&time) || (2.125..3.125).contains(&time);
+        if tone { 0.5 * (2.0 * PI * 440.0 * time).sin() } else { 0.0 }
+    });
+
+    let segments = detect_activity(audio_array, fs as u32, 1024, 256, &ActivityThresholds::default()).expect("Failed to detect activity");
+    assert_eq!(segments.iter().map(|segment| segment.active).collect::<Vec<_>>(), [false, true, false], "{:?}", segments);
+    assert_eq!((segments[0].start, segments[2].end), (0.0, 5.125));
+    assert!((segments[1].start - 1.0).abs() < 0.1, "{:?}", segments[1]);
+    assert!(segments[1].end > 3.1 && segments[1].end < 3.8, "{:?}", segments[1]);
+}
+
+
+// tests that speech, a sustained chord, and noise are told apart
+#[test]
+fn test_classify_content() {
+    let (speech, _) = audio_path_to_array("assets/LJ037-0171.wav").expect("Failed to read audio");
+    assert_eq!(classify_content(&speech, 1024, 256).class, ContentClass::Speech);
+
+    let fs = 22050.0;
+    let chord = Array1::from_shape_fn(5 * fs as usize, |n| {
+        [220.0, 277.2, 329.6].iter().map(|frequency| 0.2 * (2.0 * PI * frequency * n as f32 / fs).sin()).sum::<f32>()
+    });
+    assert_eq!(classify_content(&chord, 1024, 256).class, ContentClass::Music);
+
+    let mut state = 1_u32;
+    let noise = Array1::from_shape_fn(5 * fs as usize, |_| {
+        state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+        state as f32 / u32::MAX as f32 - 0.5
+    });
+    assert_eq!(classify_content(&noise, 1024, 256).class, ContentClass::Other);
+}
@@ -0,0 +1,59 @@
+use ndarray::{array, Array1};
+use novelty_rust::{Method, NoveltyConfig, NoveltyCurve, StreamingNovelty};
+
+
+// tests that configuration and result types round-trip through JSON
+#[test]
+fn test_json_round_trip() {
+    let config = NoveltyConfig::energy().window(2048).hop(128).gamma(5.0).build().expect("Failed to build config");
+    let json = serde_json::to_string(&config).expect("Failed to serialize config");
+    let restored: NoveltyConfig = serde_json::from_str(&json).expect("Failed to deserialize config");
+    assert_eq!(config, restored);
+
+    let curve = NoveltyCurve { values: array![0.0, 0.5, 1.0, 0.0], fs_feature: 172.265625 };
+    let json = serde_json::to_string(&curve).expect("Failed to serialize curve");
+    let restored: NoveltyCurve = serde_json::from_str(&json).expect("Failed to deserialize curve");
+    assert_eq!(curve, restored);
+}
+
+
+// tests that missing fields fall back to their defaults
+#[test]
+fn test_partial_config_uses_defaults() {
+    let config: NoveltyConfig = serde_json::from_str(r#"{"method": "energy", "hop_length": 128}"#).expect("Failed to deserialize config");
+    assert_eq!(config.method, Method::Energy);
+    assert_eq!(config.hop_length, 128);
+    assert_eq!(config.window_length, NoveltyConfig::default().window_length);
+}
+
+
+// tests that a deserialized configuration with invalid parameters is rejected before computing
+#[test]
+fn test_invalid_config_is_rejected() {
+    let config: NoveltyConfig = serde_json::from_str(r#"{"hop_length": 0}"#).expect("Failed to deserialize config");
+    assert!(config.validate().is_err());
+    let signal = Array1::from_shape_fn(4096, |n| (n as f32 * 0.05).sin());
+    assert!(config.compute(signal.clone(), 22050).is_err());
+    assert!(StreamingNovelty::new(&config, 22050).is_err());
+}
+
+
+// tests that a streaming detector restored from a checkpoint continues as if uninterrupted
+#[test]
+fn test_streaming_checkpoint_resumes() {
+    let config = NoveltyConfig::energy().window(512).hop(128).build().expect("Failed to build config");
+    let signal: Vec<f32> = (0..8000).map(|n| (if (n / 1000) % 2 == 0 { 0.1 } else { 0.8 }) * (n as f32 * 0.05).sin()).collect();
+
+    let mut uninterrupted = StreamingNovelty::new(&config, 22050).unwrap();
+    let mut expected = uninterrupted.push_samples(&signal);
+    expected.extend(uninterrupted.finish());
+
+    let mut first = StreamingNovelty::new(&config, 22050).unwrap();
+    let mut actual = first.push_samples(&signal[..3333]);
+    let checkpoint = serde_json::to_string(&first).expect("Failed to serialize detector");
+    let mut resumed: StreamingNovelty = serde_json::from_str(&checkpoint).expect("Failed to deserialize detector");
+    actual.extend(resumed.push_samples(&signal[3333..]));
+    actual.extend(resumed.finish());
+
+    assert_eq!(expected, actual);
+}
@@ -0,0 +1,63 @@
+use float_cmp::approx_eq;
+use novelty_rust::{attack_release, audio_path_to_array, AttackRelease, NoveltyConfig, StreamingNovelty};
+
+
+/// Feeds the signal to a streaming detector in blocks of the given size.
+fn stream_in_blocks(config: &NoveltyConfig, samples: &[f32], fs: u32, block_size: usize) -> Vec<(f32, f32)> {
+    let mut streaming = StreamingNovelty::new(config, fs).expect("Failed to create streaming novelty");
+    let mut output = Vec::new();
+    for block in samples.chunks(block_size) {
+        output.extend(streaming.push_samples(block));
+    }
+    output.extend(streaming.finish());
+    output
+}
+
+
+// tests that streaming output does not depend on the block size and matches the batch novelty
+#[test]
+fn test_streaming_is_block_size_invariant() {
+    let (audio_array, fs) = audio_path_to_array("assets/LJ037-0171.wav").expect("Failed to read audio");
+    let config = NoveltyConfig::energy().window(2048).hop(128).norm(false).build().expect("Failed to build config");
+
+    let novelty_batch = config.compute(audio_array.clone(), fs).expect("Failed to compute novelty").values;
+
+    let samples = audio_array.to_vec();
+    let reference = stream_in_blocks(&config, &samples, fs, samples.len());
+    assert_eq!(reference.len(), novelty_batch.len());
+    for (i, ((_, value), expected)) in reference.iter().zip(novelty_batch.iter()).enumerate() {
+        assert!(approx_eq!(f32, *value, *expected, epsilon = 1e-4), "Batch mismatch at index {}", i);
+    }
+
+    for block_size in [1, 100, 4096] {
+        let actual = stream_in_blocks(&config, &samples, fs, block_size);
+        assert_eq!(reference.len(), actual.len());
+        for (i, ((t_ref, n_ref), (t_act, n_act))) in reference.iter().zip(actual.iter()).enumerate() {
+            assert_eq!(t_ref, t_act, "Time mismatch at index {}", i);
+            assert!(approx_eq!(f32, *n_ref, *n_act, epsilon = 1e-5), "Novelty mismatch at index {}", i);
+        }
+    }
+}
+
+
+// tests that attack/release smoothing follows rises and falls with its time constants and
+// matches between streaming and batch computation
+#[test]
+fn test_streaming_attack_release() {
+    let values = ndarray::Array1::from_vec(vec![0.0, 1.0, 0.0, 0.0]);
+    let instant = attack_release(&values, 10.0, AttackRelease { attack: 0.0, release: 0.0 });
+    assert_eq!(instant, values);
+    let smoothed = attack_release(&values, 10.0, AttackRelease { attack: 0.0, release: 0.5 });
+    assert_eq!(smoothed[1], 1.0);
+    assert!(smoothed[2] > 0.8 && smoothed[3] > 0.6 && smoothed[3] < smoothed[2]);
+
+    let (audio_array, fs) = audio_path_to_array("assets/LJ037-0171.wav").expect("Failed to read audio");
+    let smoothing = Some(AttackRelease { attack: 0.01, release: 0.2 });
+    let config = NoveltyConfig::energy().norm(false).smoothing(smoothing).build().expect("Failed to build config");
+    let novelty_batch = config.compute(audio_array.clone(), fs).expect("Failed to compute novelty").values;
+    let actual = stream_in_blocks(&config, &audio_array.to_vec(), fs, 1000);
+    assert_eq!(actual.len(), novelty_batch.len());
+    for (i, ((_, value), expected)) in actual.iter().zip(novelty_batch.iter()).enumerate() {
+        assert!(approx_eq!(f32, *value, *expected, epsilon = 1e-4), "Novelty mismatch at index {}", i);
+    }
+}
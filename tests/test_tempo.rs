@@ -0,0 +1,109 @@
+use std::f32::consts::PI;
+
+use ndarray::Array1;
+use novelty_rust::{
+    beat_grid_stats, cyclic_tempogram, default_tempogram, estimate_meter, estimate_tempo, tempo_candidates,
+    tempo_salience, track_downbeats, Beat, NoveltyCurve, Tempogram,
+};
+
+
+// tests that the best tempo candidate of a periodic novelty function is its period
+#[test]
+fn test_tempo_candidates_of_periodic_novelty() {
+    let fs_feature = 100.0;
+    let novelty = NoveltyCurve {
+        values: Array1::from_shape_fn(2000, |n| 1.0 + (2.0 * PI * 2.0 * n as f32 / fs_feature).cos()),
+        fs_feature,
+    };
+
+    let tempogram = default_tempogram(&novelty);
+    let candidates = tempo_candidates(&tempogram, 3);
+
+    assert!(!candidates.is_empty() && candidates.len() <= 3);
+    assert_eq!(candidates[0].bpm, 120.0);
+    assert_eq!(Some(candidates[0].bpm), estimate_tempo(&tempogram));
+    let salience = tempo_salience(&tempogram).unwrap();
+    assert!((salience.sum() - 1.0).abs() < 1e-4);
+    for pair in candidates.windows(2) {
+        assert!(pair[0].confidence >= pair[1].confidence);
+    }
+    assert!(candidates.iter().map(|c| c.confidence).sum::<f32>() <= 1.0 + 1e-6);
+}
+
+
+// tests that the meter and downbeats are recognized from the accentuation of a beat grid
+#[test]
+fn test_meter_and_downbeats_from_accents() {
+    let period = 50;
+    for (pattern, beats_per_bar) in [(vec![1.0, 0.3, 0.6, 0.3], 4), (vec![1.0, 0.3, 0.3], 3)] {
+        let num_beats = 8 * pattern.len();
+        let mut values = Array1::zeros(num_beats * period);
+        let beats: Vec<Beat> = (0..num_beats)
+            .map(|i| {
+                // the pattern starts with the second beat, so the first downbeat is beat 1
+                values[i * period] = pattern[(i + 1) % pattern.len()];
+                Beat { frame: i * period, time: (i * period) as f32 / 100.0, position_in_bar: None }
+            })
+            .collect();
+        let novelty = NoveltyCurve { values, fs_feature: 100.0 };
+
+        let candidates = estimate_meter(&novelty, &beats);
+        assert_eq!(candidates[0].beats_per_bar, beats_per_bar);
+        assert_eq!(candidates[0].time_signature(), format!("{}/4", beats_per_bar));
+        assert_eq!(candidates[0].phase, pattern.len() - 1);
+
+        let mut beats = beats;
+        track_downbeats(&novelty, &mut beats).expect("Failed to track downbeats");
+        assert_eq!(beats[1].position_in_bar, Some(3));
+        assert_eq!(beats[beats_per_bar - 1].position_in_bar, Some(1));
+        assert_eq!(beats[beats_per_bar].position_in_bar, Some(2));
+    }
+}
+
+
+// tests that the cyclic tempogram sums tempi an octave apart into one bin
+#[test]
+fn test_cyclic_tempogram_octave_equivalence() {
+    let bpm = Array1::range(30.0, 301.0, 1.0);
+    let values = ndarray::Array2::from_shape_fn((bpm.len(), 2), |(i, frame)| match (bpm[i] as u32, frame) {
+        (60, 0) | (120, 0) => 1.0,
+        (90, 1) => 2.0,
+        _ => 0.0,
+    });
+    let tempogram = Tempogram { values, bpm, fs_tempogram: 2.0 };
+
+    let cyclic = cyclic_tempogram(&tempogram, 30.0, 60);
+    assert_eq!(cyclic.values.dim(), (60, 2));
+    assert_eq!(cyclic.bpm[0], 30.0);
+    assert_eq!(cyclic.values[[0, 0]], 2.0);
+    assert!(cyclic.values.column(0).iter().all(|&v| v <= 2.0));
+    // 90 BPM lies between two bins of the reference octave at 45 BPM
+    let index = cyclic.values.column(1).iter().position(|&v| v > 0.0).unwrap();
+    assert!((cyclic.bpm[index] - 45.0).abs() < 0.6, "Unexpected tempo {}", cyclic.bpm[index]);
+}
+
+
+// tests that quantized beats match a constant grid and drifting beats don't
+#[test]
+fn test_beat_grid_stats() {
+    let beat = |time: f32| Beat { frame: 0, time, position_in_bar: None };
+    let quantized: Vec<Beat> = (0..16).map(|k| beat(0.1 + 0.5 * k as f32)).collect();
+    let stats = beat_grid_stats(&quantized, None, 0.02).unwrap();
+    assert!((stats.bpm - 120.0).abs() < 1e-3);
+    assert!(stats.ibi_std < 1e-5 && stats.max_drift < 1e-5);
+    assert_eq!(stats.matching, 1.0);
+
+    // the tempo slows down from 120 to about 100 BPM
+    let mut time = 0.0;
+    let drifting: Vec<Beat> = (0..16)
+        .map(|k| {
+            time += 0.5 + 0.01 * k as f32;
+            beat(time)
+        })
+        .collect();
+    let stats = beat_grid_stats(&drifting, None, 0.02).unwrap();
+    assert!(stats.ibi_std > 0.03 && stats.max_drift > 0.1);
+    assert!(stats.matching < 0.5);
+    assert!(beat_grid_stats(&drifting, Some(120.0), 0.02).unwrap().max_drift > stats.max_drift);
+    assert!(beat_grid_stats(&drifting[..2], None, 0.02).is_none());
+}
@@ -0,0 +1,17 @@
+use ndarray::Array1;
+use novelty_rust::{thumbnail, NoveltyConfig, ThumbnailCriterion};
+
+
+// tests that the eventful thumbnail covers the only section with onsets
+#[test]
+fn test_eventful_thumbnail_finds_clicks() {
+    let fs = 8000;
+    // 20 seconds of silence with a click every 100 ms between 12 and 14 seconds
+    let audio_array = Array1::from_shape_fn(20 * fs, |n| if (12 * fs..14 * fs).contains(&n) && n % 800 == 0 { 1.0 } else { 0.0 });
+    let config = NoveltyConfig::energy().build().expect("Failed to build config");
+
+    let excerpt = thumbnail(audio_array.clone(), fs as u32, &config, 2.0, ThumbnailCriterion::Eventful).expect("Failed to compute thumbnail");
+    assert!((excerpt.start - 12.0).abs() < 0.2, "Unexpected start {}", excerpt.start);
+    assert!((excerpt.end - excerpt.start - 2.0).abs() < 1e-3);
+    assert_eq!(excerpt.excerpt(&audio_array, fs as u32).len(), 2 * fs);
+}